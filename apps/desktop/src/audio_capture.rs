@@ -0,0 +1,852 @@
+//! Microphone and system audio capture for DailyDoco Pro
+//!
+//! The microphone is opened through cpal; system audio is read from the
+//! PulseAudio/PipeWire monitor of the default sink (or a named source) by
+//! running `parec`, falling back to `pw-record`. Each source is its own
+//! track so narration and desktop sound can be mixed, ducked or dropped
+//! independently at export.
+//!
+//! Audio callbacks only write into a lock-free single-producer ring buffer.
+//! A drain thread cuts the samples into fixed-length `AudioData` chunks and
+//! hands them to the sink, so a slow disk delays the drain instead of the
+//! device. Chunks are timestamped on the same capture clock as video frames:
+//! the first callback anchors the track, later chunks follow by sample count.
+//! Callbacks also note how many frames had arrived by when, about once a
+//! second, so `av_sync` can measure how far the device clock drifts, and
+//! fold every sample into `audio_meter` blocks for the live level meters.
+
+use std::io::Read;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use aegnt_27::audio::AudioData;
+use anyhow::{anyhow, bail, Context, Result};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, Sample, SizedSample};
+use parking_lot::Mutex;
+use rtrb::{Consumer, Producer, RingBuffer};
+use serde::{Deserialize, Serialize};
+
+use crate::audio_meter::{level_channel, AudioMeter, LevelReading, LevelTap, MeterConfig, TrackMeter};
+use crate::av_sync::{ClockPoint, SyncConfig};
+
+/// Monitor of the default output, understood by PulseAudio and pipewire-pulse
+const DEFAULT_MONITOR: &str = "@DEFAULT_MONITOR@";
+const DRAIN_INTERVAL: Duration = Duration::from_millis(20);
+const NOT_ANCHORED: u64 = u64::MAX;
+const CLOCK_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioCaptureConfig {
+    pub microphone: bool,
+    /// Input device name, `None` uses the default input
+    pub microphone_device: Option<String>,
+    pub system_audio: bool,
+    /// Pulse/PipeWire source to record, `None` uses the default sink's monitor
+    pub monitor_source: Option<String>,
+    pub system_sample_rate: u32,
+    pub system_channels: u8,
+    /// Length of each `AudioData` chunk handed to the sink
+    pub chunk_duration: Duration,
+    /// Ring buffer capacity per track; the sink may stall this long without loss
+    pub buffer_duration: Duration,
+    /// Drift correction applied to the tracks after recording
+    #[serde(default)]
+    pub sync: SyncConfig,
+    /// Live levels and the silent or clipping mic alerts
+    #[serde(default)]
+    pub meter: MeterConfig,
+}
+
+impl Default for AudioCaptureConfig {
+    fn default() -> Self {
+        Self {
+            microphone: true,
+            microphone_device: None,
+            system_audio: true,
+            monitor_source: None,
+            system_sample_rate: 48_000,
+            system_channels: 2,
+            chunk_duration: Duration::from_secs(1),
+            buffer_duration: Duration::from_secs(10),
+            sync: SyncConfig::default(),
+            meter: MeterConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AudioTrack {
+    Microphone,
+    System,
+}
+
+impl AudioTrack {
+    pub fn label(self) -> &'static str {
+        match self {
+            AudioTrack::Microphone => "Microphone",
+            AudioTrack::System => "System audio",
+        }
+    }
+}
+
+/// A stretch of one track placed on the capture clock
+#[derive(Debug, Clone)]
+pub struct AudioChunk {
+    pub track: AudioTrack,
+    /// Capture-clock time of the chunk's first frame
+    pub timestamp: Duration,
+    pub audio: AudioData,
+}
+
+impl AudioChunk {
+    pub fn end(&self) -> Duration {
+        self.timestamp + self.audio.duration
+    }
+}
+
+/// What happened to one track over the recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackStats {
+    pub track: AudioTrack,
+    pub sample_rate: u32,
+    pub channels: u8,
+    /// Capture-clock time of the first frame, `None` if nothing arrived
+    pub started: Option<Duration>,
+    pub frames: u64,
+    /// Frames lost because the ring buffer was full, replaced by silence
+    pub dropped_frames: u64,
+    /// Device frames received by each observed capture-clock time
+    #[serde(default)]
+    pub clock: Vec<ClockPoint>,
+}
+
+/// A finished recording; `chunks` is empty when a custom sink took them
+#[derive(Debug, Clone, Default)]
+pub struct AudioRecording {
+    pub chunks: Vec<AudioChunk>,
+    pub stats: Vec<TrackStats>,
+}
+
+impl AudioRecording {
+    pub fn track(&self, track: AudioTrack) -> impl Iterator<Item = &AudioChunk> {
+        self.chunks.iter().filter(move |chunk| chunk.track == track)
+    }
+
+    /// One track as a single buffer, with the capture-clock time it starts at
+    pub fn merged(&self, track: AudioTrack) -> Option<(Duration, AudioData)> {
+        let mut chunks = self.track(track);
+        let first = chunks.next()?;
+        let mut samples = first.audio.samples.clone();
+        for chunk in chunks {
+            samples.extend_from_slice(&chunk.audio.samples);
+        }
+        Some((first.timestamp, AudioData::new(samples, first.audio.sample_rate, first.audio.channels)))
+    }
+}
+
+/// Real-time side of a track: owned by the audio callback, never blocks
+struct TrackWriter {
+    producer: Producer<f32>,
+    clock: Producer<ClockPoint>,
+    levels: LevelTap,
+    shared: Arc<TrackShared>,
+    capture_origin: Instant,
+    /// Every frame the device delivered, including dropped ones
+    frames_received: u64,
+    last_clock_point: Option<Duration>,
+}
+
+/// State both sides of a track's ring buffer read
+struct TrackShared {
+    track: AudioTrack,
+    sample_rate: u32,
+    channels: u8,
+    /// Nanoseconds on the capture clock of the first frame
+    anchor: AtomicU64,
+    /// Frames dropped since the drain last looked
+    dropped: AtomicU64,
+}
+
+impl TrackShared {
+    fn frames_to_duration(&self, frames: u64) -> Duration {
+        Duration::from_secs_f64(frames as f64 / self.sample_rate.max(1) as f64)
+    }
+}
+
+impl TrackWriter {
+    /// Copy one callback's interleaved samples into the ring; drops whole
+    /// frames rather than blocking when the drain has fallen behind, though
+    /// the meter still hears them
+    fn write(&mut self, samples: impl ExactSizeIterator<Item = f32>) {
+        let channels = self.shared.channels.max(1) as usize;
+        let frames = samples.len() / channels;
+        if frames == 0 {
+            return;
+        }
+
+        let now = self.capture_origin.elapsed();
+        if self.shared.anchor.load(Ordering::Relaxed) == NOT_ANCHORED {
+            // The callback runs once its buffer is full, so the first frame is older than now
+            let anchor = now.saturating_sub(self.shared.frames_to_duration(frames as u64));
+            self.shared.anchor.store(anchor.as_nanos() as u64, Ordering::Release);
+        }
+        self.frames_received += frames as u64;
+        self.observe_clock(now);
+
+        let levels = &mut self.levels;
+        let mut samples = samples.inspect(|&sample| levels.add(sample));
+        let writable = (self.producer.slots() / channels).min(frames);
+        if writable > 0 {
+            if let Ok(chunk) = self.producer.write_chunk_uninit(writable * channels) {
+                chunk.fill_from_iter(&mut samples);
+            }
+        }
+        samples.for_each(drop);
+        if writable < frames {
+            self.shared.dropped.fetch_add((frames - writable) as u64, Ordering::Relaxed);
+        }
+    }
+
+    fn observe_clock(&mut self, now: Duration) {
+        if self.last_clock_point.is_some_and(|last| now < last + CLOCK_INTERVAL) {
+            return;
+        }
+        let point = ClockPoint {
+            capture_time: now,
+            frames: self.frames_received,
+        };
+        // A missed observation only thins the fit
+        if self.clock.push(point).is_ok() {
+            self.last_clock_point = Some(now);
+        }
+    }
+}
+
+/// Cuts a track's sample stream into fixed-length chunks on the capture clock
+struct Chunker {
+    track: AudioTrack,
+    sample_rate: u32,
+    channels: u8,
+    chunk_frames: usize,
+    start: Option<Duration>,
+    frames_emitted: u64,
+    pending: Vec<f32>,
+    stats: TrackStats,
+}
+
+impl Chunker {
+    fn new(track: AudioTrack, sample_rate: u32, channels: u8, chunk_duration: Duration) -> Self {
+        let chunk_frames = ((chunk_duration.as_secs_f64() * sample_rate as f64).round() as usize).max(1);
+        Self {
+            track,
+            sample_rate,
+            channels,
+            chunk_frames,
+            start: None,
+            frames_emitted: 0,
+            pending: Vec::with_capacity(chunk_frames * channels.max(1) as usize),
+            stats: TrackStats {
+                track,
+                sample_rate,
+                channels,
+                started: None,
+                frames: 0,
+                dropped_frames: 0,
+                clock: Vec::new(),
+            },
+        }
+    }
+
+    /// Append samples; `start` is the capture-clock time of the track's first frame
+    fn push(&mut self, start: Duration, samples: &[f32], emit: &mut dyn FnMut(AudioChunk)) {
+        self.start.get_or_insert(start);
+        self.stats.started.get_or_insert(start);
+        self.stats.frames += (samples.len() / self.channels.max(1) as usize) as u64;
+
+        let chunk_len = self.chunk_frames * self.channels.max(1) as usize;
+        let mut rest = samples;
+        while !rest.is_empty() {
+            let take = (chunk_len - self.pending.len()).min(rest.len());
+            self.pending.extend_from_slice(&rest[..take]);
+            rest = &rest[take..];
+            if self.pending.len() == chunk_len {
+                emit(self.take_chunk());
+            }
+        }
+    }
+
+    /// Keep the timeline intact across an overrun by filling the gap with silence
+    fn push_silence(&mut self, frames: u64, emit: &mut dyn FnMut(AudioChunk)) {
+        let Some(start) = self.start else { return };
+        self.stats.dropped_frames += frames;
+        let silence = vec![0.0; frames as usize * self.channels.max(1) as usize];
+        self.push(start, &silence, emit);
+    }
+
+    fn flush(&mut self, emit: &mut dyn FnMut(AudioChunk)) {
+        if !self.pending.is_empty() {
+            emit(self.take_chunk());
+        }
+    }
+
+    fn take_chunk(&mut self) -> AudioChunk {
+        let capacity = self.chunk_frames * self.channels.max(1) as usize;
+        let samples = std::mem::replace(&mut self.pending, Vec::with_capacity(capacity));
+        let offset = Duration::from_secs_f64(self.frames_emitted as f64 / self.sample_rate.max(1) as f64);
+        let audio = AudioData::new(samples, self.sample_rate, self.channels);
+        self.frames_emitted += audio.frame_count() as u64;
+        AudioChunk {
+            track: self.track,
+            timestamp: self.start.unwrap_or_default() + offset,
+            audio,
+        }
+    }
+}
+
+/// Drain side of a track's ring buffer
+struct TrackReader {
+    consumer: Consumer<f32>,
+    clock: Consumer<ClockPoint>,
+    levels: Consumer<LevelReading>,
+    meter: TrackMeter,
+    shared: Arc<TrackShared>,
+    chunker: Chunker,
+}
+
+impl TrackReader {
+    /// Move everything currently buffered into chunks; returns whether anything was read
+    fn drain(&mut self, emit: &mut dyn FnMut(AudioChunk)) -> bool {
+        let anchor = self.shared.anchor.load(Ordering::Acquire);
+        if anchor == NOT_ANCHORED {
+            return false;
+        }
+        let start = Duration::from_nanos(anchor);
+
+        let available = self.consumer.slots();
+        let mut read = false;
+        if available > 0 {
+            if let Ok(chunk) = self.consumer.read_chunk(available) {
+                let (first, second) = chunk.as_slices();
+                self.chunker.push(start, first, emit);
+                self.chunker.push(start, second, emit);
+                chunk.commit_all();
+                read = true;
+            }
+        }
+
+        while let Ok(point) = self.clock.pop() {
+            self.chunker.stats.clock.push(point);
+        }
+        while let Ok(reading) = self.levels.pop() {
+            self.meter.update(reading);
+        }
+
+        let dropped = self.shared.dropped.swap(0, Ordering::Relaxed);
+        if dropped > 0 {
+            log::warn!("🎙️ {} fell behind, {} frames replaced by silence", self.shared.track.label(), dropped);
+            self.chunker.push_silence(dropped, emit);
+        }
+        read
+    }
+}
+
+fn track_pair(
+    track: AudioTrack,
+    sample_rate: u32,
+    channels: u8,
+    config: &AudioCaptureConfig,
+    capture_origin: Instant,
+    meter: &AudioMeter,
+) -> (TrackWriter, TrackReader) {
+    let capacity = (config.buffer_duration.as_secs_f64() * sample_rate as f64) as usize * channels.max(1) as usize;
+    let (producer, consumer) = RingBuffer::new(capacity.max(channels.max(1) as usize));
+    let (clock_producer, clock_consumer) = RingBuffer::new(64);
+    let (level_tap, level_consumer) = level_channel(sample_rate, channels, &config.meter);
+    let shared = Arc::new(TrackShared {
+        track,
+        sample_rate,
+        channels,
+        anchor: AtomicU64::new(NOT_ANCHORED),
+        dropped: AtomicU64::new(0),
+    });
+
+    let writer = TrackWriter {
+        producer,
+        clock: clock_producer,
+        levels: level_tap,
+        shared: Arc::clone(&shared),
+        capture_origin,
+        frames_received: 0,
+        last_clock_point: None,
+    };
+    let reader = TrackReader {
+        consumer,
+        clock: clock_consumer,
+        levels: level_consumer,
+        meter: TrackMeter::new(track, sample_rate, config.meter.clone(), meter.clone()),
+        chunker: Chunker::new(track, sample_rate, channels, config.chunk_duration),
+        shared,
+    };
+    (writer, reader)
+}
+
+/// Records the microphone and system audio as separate tracks
+pub struct AudioRecorder {
+    running: Arc<AtomicBool>,
+    /// Set once every source has stopped writing
+    sources_done: Arc<AtomicBool>,
+    microphone: Option<JoinHandle<()>>,
+    system: Option<(Child, JoinHandle<()>)>,
+    drain: Option<JoinHandle<Vec<TrackStats>>>,
+    chunks: Arc<Mutex<Vec<AudioChunk>>>,
+    meter: AudioMeter,
+}
+
+impl AudioRecorder {
+    /// Start recording and keep the chunks in memory until `stop`
+    pub fn start(config: &AudioCaptureConfig, capture_origin: Instant) -> Result<Self> {
+        let chunks = Arc::new(Mutex::new(Vec::new()));
+        let sink = {
+            let chunks = Arc::clone(&chunks);
+            move |chunk| chunks.lock().push(chunk)
+        };
+        let mut recorder = Self::start_with_sink(config, capture_origin, sink)?;
+        recorder.chunks = chunks;
+        Ok(recorder)
+    }
+
+    /// Start recording and hand each chunk to `sink` on the drain thread,
+    /// e.g. to stream it to disk
+    pub fn start_with_sink(
+        config: &AudioCaptureConfig,
+        capture_origin: Instant,
+        sink: impl FnMut(AudioChunk) + Send + 'static,
+    ) -> Result<Self> {
+        if !config.microphone && !config.system_audio {
+            bail!("No audio track enabled");
+        }
+
+        let running = Arc::new(AtomicBool::new(true));
+        let meter = AudioMeter::default();
+        let mut readers = Vec::new();
+
+        let microphone = if config.microphone {
+            let (handle, reader) = spawn_microphone(config, capture_origin, &meter, Arc::clone(&running))?;
+            readers.push(reader);
+            Some(handle)
+        } else {
+            None
+        };
+
+        let system = if config.system_audio {
+            match spawn_system_audio(config, capture_origin, &meter) {
+                Ok((child, handle, reader)) => {
+                    readers.push(reader);
+                    Some((child, handle))
+                }
+                // Narration alone is still worth recording
+                Err(e) if microphone.is_some() => {
+                    log::warn!("🔇 System audio unavailable: {}", e);
+                    None
+                }
+                Err(e) => return Err(e),
+            }
+        } else {
+            None
+        };
+
+        let sources_done = Arc::new(AtomicBool::new(false));
+        let drain = std::thread::spawn({
+            let sources_done = Arc::clone(&sources_done);
+            move || drain_tracks(readers, sources_done, sink)
+        });
+
+        Ok(Self {
+            running,
+            sources_done,
+            microphone,
+            system,
+            drain: Some(drain),
+            chunks: Arc::new(Mutex::new(Vec::new())),
+            meter,
+        })
+    }
+
+    /// Live levels of every track and the alerts they raise, for the tray and UI
+    pub fn meter(&self) -> AudioMeter {
+        self.meter.clone()
+    }
+
+    /// Stop every source, drain what is left and return the recording
+    pub fn stop(mut self) -> Result<AudioRecording> {
+        let stats = self.shut_down()?;
+        let mut chunks = std::mem::take(&mut *self.chunks.lock());
+        chunks.sort_by_key(|chunk| chunk.timestamp);
+        Ok(AudioRecording { chunks, stats })
+    }
+
+    /// Stop the sources, then join every thread once nothing is left to
+    /// drain. Whatever is already shut down is skipped.
+    fn shut_down(&mut self) -> Result<Vec<TrackStats>> {
+        self.running.store(false, Ordering::SeqCst);
+
+        if let Some(microphone) = self.microphone.take() {
+            microphone.thread().unpark();
+            microphone.join().map_err(|_| anyhow!("Microphone thread panicked"))?;
+        }
+        if let Some((mut child, reader)) = self.system.take() {
+            let _ = child.kill();
+            child.wait().context("Failed to reap system audio recorder")?;
+            reader.join().map_err(|_| anyhow!("System audio reader thread panicked"))?;
+        }
+        self.sources_done.store(true, Ordering::SeqCst);
+
+        let stats = match self.drain.take() {
+            Some(drain) => drain.join().map_err(|_| anyhow!("Audio drain thread panicked"))?,
+            None => Vec::new(),
+        };
+        Ok(stats)
+    }
+}
+
+/// A recorder dropped without `stop`, e.g. when capture fails, must not
+/// leave the system audio recorder or any thread running
+impl Drop for AudioRecorder {
+    fn drop(&mut self) {
+        if let Err(e) = self.shut_down() {
+            log::warn!("🎙️ Audio recorder didn't shut down cleanly: {}", e);
+        }
+    }
+}
+
+/// Drain thread: poll every ring until the sources stop, then flush
+fn drain_tracks(
+    mut readers: Vec<TrackReader>,
+    sources_done: Arc<AtomicBool>,
+    mut sink: impl FnMut(AudioChunk),
+) -> Vec<TrackStats> {
+    loop {
+        // Read the flag first so the pass after it sees every last sample
+        let done = sources_done.load(Ordering::SeqCst);
+        let mut read = false;
+        for reader in readers.iter_mut() {
+            read |= reader.drain(&mut sink);
+        }
+        if done && !read {
+            break;
+        }
+        if !read {
+            std::thread::sleep(DRAIN_INTERVAL);
+        }
+    }
+
+    readers
+        .into_iter()
+        .map(|mut reader| {
+            reader.chunker.flush(&mut sink);
+            reader.chunker.stats
+        })
+        .collect()
+}
+
+/// Microphone thread: cpal streams can't move between threads, so the
+/// stream lives here until the recorder stops
+fn spawn_microphone(
+    config: &AudioCaptureConfig,
+    capture_origin: Instant,
+    meter: &AudioMeter,
+    running: Arc<AtomicBool>,
+) -> Result<(JoinHandle<()>, TrackReader)> {
+    let config = config.clone();
+    let meter = meter.clone();
+    let (ready_tx, ready_rx) = mpsc::channel();
+
+    let handle = std::thread::spawn(move || {
+        let stream = match open_microphone(&config, capture_origin, &meter) {
+            Ok((stream, reader)) => {
+                let _ = ready_tx.send(Ok(reader));
+                stream
+            }
+            Err(e) => {
+                let _ = ready_tx.send(Err(e));
+                return;
+            }
+        };
+        while running.load(Ordering::SeqCst) {
+            std::thread::park_timeout(Duration::from_millis(200));
+        }
+        drop(stream);
+    });
+
+    match ready_rx.recv() {
+        Ok(Ok(reader)) => Ok((handle, reader)),
+        Ok(Err(e)) => {
+            let _ = handle.join();
+            Err(e)
+        }
+        Err(_) => bail!("Microphone thread exited before opening the device"),
+    }
+}
+
+fn open_microphone(
+    config: &AudioCaptureConfig,
+    capture_origin: Instant,
+    meter: &AudioMeter,
+) -> Result<(cpal::Stream, TrackReader)> {
+    let host = cpal::default_host();
+    let device = match config.microphone_device.as_deref() {
+        Some(name) => host
+            .input_devices()?
+            .find(|device| device.name().map(|n| n == name).unwrap_or(false))
+            .ok_or_else(|| anyhow!("No input device named {:?}", name))?,
+        None => host.default_input_device().ok_or_else(|| anyhow!("No default input device"))?,
+    };
+
+    let supported = device.default_input_config().context("Failed to query input format")?;
+    let sample_format = supported.sample_format();
+    let stream_config: cpal::StreamConfig = supported.into();
+    let channels = u8::try_from(stream_config.channels).context("Too many input channels")?;
+    let (writer, reader) =
+        track_pair(AudioTrack::Microphone, stream_config.sample_rate.0, channels, config, capture_origin, meter);
+
+    let stream = match sample_format {
+        cpal::SampleFormat::F32 => build_input::<f32>(&device, &stream_config, writer)?,
+        cpal::SampleFormat::I16 => build_input::<i16>(&device, &stream_config, writer)?,
+        cpal::SampleFormat::I32 => build_input::<i32>(&device, &stream_config, writer)?,
+        cpal::SampleFormat::U16 => build_input::<u16>(&device, &stream_config, writer)?,
+        other => bail!("Unsupported input sample format {:?}", other),
+    };
+    stream.play().context("Failed to start microphone")?;
+
+    log::info!(
+        "🎙️ Recording {} ({} Hz, {} ch)",
+        device.name().unwrap_or_else(|_| "microphone".to_string()),
+        stream_config.sample_rate.0,
+        channels
+    );
+    Ok((stream, reader))
+}
+
+fn build_input<T>(device: &cpal::Device, config: &cpal::StreamConfig, mut writer: TrackWriter) -> Result<cpal::Stream>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], _: &cpal::InputCallbackInfo| writer.write(data.iter().map(|&s| f32::from_sample(s))),
+            |e| log::error!("🎙️ Microphone stream error: {}", e),
+            None,
+        )
+        .context("Failed to open microphone stream")
+}
+
+/// Start `parec` (or `pw-record`) on the monitor source and a thread reading its raw float output
+fn spawn_system_audio(
+    config: &AudioCaptureConfig,
+    capture_origin: Instant,
+    meter: &AudioMeter,
+) -> Result<(Child, JoinHandle<()>, TrackReader)> {
+    let mut child = monitor_command(config, "parec")
+        .spawn()
+        .or_else(|_| monitor_command(config, "pw-record").spawn())
+        .context("Neither parec nor pw-record could be started")?;
+    let stdout = child.stdout.take().ok_or_else(|| anyhow!("System audio recorder has no stdout"))?;
+
+    let (writer, reader) = track_pair(
+        AudioTrack::System,
+        config.system_sample_rate,
+        config.system_channels,
+        config,
+        capture_origin,
+        meter,
+    );
+    let handle = std::thread::spawn(move || read_raw_f32(stdout, writer));
+
+    log::info!(
+        "🔊 Recording system audio from {}",
+        config.monitor_source.as_deref().unwrap_or(DEFAULT_MONITOR)
+    );
+    Ok((child, handle, reader))
+}
+
+fn monitor_command(config: &AudioCaptureConfig, program: &str) -> Command {
+    let mut command = Command::new(program);
+    let rate = config.system_sample_rate.to_string();
+    let channels = config.system_channels.to_string();
+
+    if program == "parec" {
+        let source = config.monitor_source.as_deref().unwrap_or(DEFAULT_MONITOR);
+        command.args(["--raw", "--format=float32le", "--latency-msec=20"]);
+        command.arg(format!("--device={}", source));
+        command.arg(format!("--rate={}", rate));
+        command.arg(format!("--channels={}", channels));
+    } else {
+        // Capturing from a sink records what it plays, i.e. its monitor
+        command.args(["--format", "f32", "--rate", &rate, "--channels", &channels]);
+        command.args(["-P", "{ stream.capture.sink = true }"]);
+        if let Some(source) = &config.monitor_source {
+            command.args(["--target", source.trim_end_matches(".monitor")]);
+        }
+        command.arg("-");
+    }
+
+    command.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::null());
+    command
+}
+
+/// Reader thread: decode little-endian f32 samples until the recorder exits.
+/// Reads can end anywhere, so only whole frames are written and the rest is
+/// carried over; a split frame would shift every later sample to the next channel.
+fn read_raw_f32(mut stdout: impl Read, mut writer: TrackWriter) {
+    let frame_bytes = 4 * writer.shared.channels.max(1) as usize;
+    let mut buffer = [0u8; 8192];
+    let mut pending: Vec<u8> = Vec::new();
+
+    loop {
+        let read = match stdout.read(&mut buffer) {
+            Ok(0) | Err(_) => break,
+            Ok(read) => read,
+        };
+        pending.extend_from_slice(&buffer[..read]);
+
+        let whole = pending.len() - pending.len() % frame_bytes;
+        writer.write(
+            pending[..whole]
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+        );
+        pending.drain(..whole);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect(reader: &mut TrackReader) -> Vec<AudioChunk> {
+        let mut chunks = Vec::new();
+        reader.drain(&mut |chunk| chunks.push(chunk));
+        chunks
+    }
+
+    #[test]
+    fn test_chunks_follow_the_first_callback_on_the_capture_clock() {
+        let config = AudioCaptureConfig {
+            chunk_duration: Duration::from_millis(100),
+            ..Default::default()
+        };
+        let origin = Instant::now() - Duration::from_secs(5);
+        let meter = AudioMeter::default();
+        let (mut writer, mut reader) = track_pair(AudioTrack::Microphone, 1_000, 2, &config, origin, &meter);
+
+        assert!(collect(&mut reader).is_empty());
+        // 250 stereo frames at 1 kHz: two full chunks and half of a third
+        writer.write(vec![0.25; 500].into_iter());
+        let chunks = collect(&mut reader);
+
+        assert_eq!(chunks.len(), 2);
+        let first = chunks[0].timestamp;
+        assert!(first >= Duration::from_millis(4_700) && first <= Duration::from_secs(6), "{:?}", first);
+        assert_eq!(chunks[1].timestamp - first, Duration::from_millis(100));
+        assert_eq!(chunks[1].audio.frame_count(), 100);
+
+        let mut rest = Vec::new();
+        reader.chunker.flush(&mut |chunk| rest.push(chunk));
+        assert_eq!(rest[0].audio.frame_count(), 50);
+        assert_eq!(rest[0].timestamp - first, Duration::from_millis(200));
+
+        // Five 50 ms meter blocks went by, all at -12 dBFS
+        let level = meter.level(AudioTrack::Microphone).unwrap();
+        assert!((level.rms_dbfs + 12.04).abs() < 0.01 && !level.clipping);
+    }
+
+    #[test]
+    fn test_overrun_drops_whole_frames_and_keeps_the_timeline() {
+        let config = AudioCaptureConfig {
+            chunk_duration: Duration::from_millis(100),
+            buffer_duration: Duration::from_millis(50),
+            ..Default::default()
+        };
+        let meter = AudioMeter::default();
+        let (mut writer, mut reader) = track_pair(AudioTrack::System, 1_000, 2, &config, Instant::now(), &meter);
+
+        // The ring holds 50 frames; the drain is "stuck" while 80 arrive
+        writer.write(vec![0.5; 160].into_iter());
+        writer.write(vec![0.5; 40].into_iter());
+        let mut chunks = collect(&mut reader);
+        reader.chunker.flush(&mut |chunk| chunks.push(chunk));
+
+        let stats = &reader.chunker.stats;
+        assert_eq!(stats.dropped_frames, 50);
+        assert_eq!(stats.frames, 100);
+        // Both callbacks fell within one clock interval; dropped frames still count
+        assert_eq!(stats.clock.len(), 1);
+        assert_eq!(stats.clock[0].frames, 80);
+        let samples: Vec<f32> = chunks.iter().flat_map(|c| c.audio.samples.iter().copied()).collect();
+        assert_eq!(samples.len(), 200);
+        assert!(samples[..100].iter().all(|&s| s == 0.5));
+        assert!(samples[100..].iter().all(|&s| s == 0.0));
+        // The meter heard the dropped frames, not the silence standing in for them
+        assert!((meter.level(AudioTrack::System).unwrap().rms_dbfs + 6.02).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_raw_reads_split_mid_frame_keep_channels_apart() {
+        /// Hands out a few bytes per read, like a pipe under load
+        struct Trickle(std::io::Cursor<Vec<u8>>);
+        impl Read for Trickle {
+            fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+                let len = buffer.len().min(6);
+                self.0.read(&mut buffer[..len])
+            }
+        }
+
+        let config = AudioCaptureConfig {
+            chunk_duration: Duration::from_millis(100),
+            ..Default::default()
+        };
+        let meter = AudioMeter::default();
+        let (writer, mut reader) = track_pair(AudioTrack::System, 1_000, 2, &config, Instant::now(), &meter);
+        let bytes: Vec<u8> = [0.25f32, -0.5].repeat(100).iter().flat_map(|s| s.to_le_bytes()).collect();
+
+        read_raw_f32(Trickle(std::io::Cursor::new(bytes)), writer);
+        let chunks = collect(&mut reader);
+
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].audio.samples, [0.25, -0.5].repeat(100));
+    }
+
+    #[test]
+    fn test_recording_keeps_tracks_apart() {
+        let chunk = |track, timestamp_ms, value| AudioChunk {
+            track,
+            timestamp: Duration::from_millis(timestamp_ms),
+            audio: AudioData::new(vec![value; 4], 4, 1),
+        };
+        let recording = AudioRecording {
+            chunks: vec![
+                chunk(AudioTrack::Microphone, 0, 0.1),
+                chunk(AudioTrack::System, 250, 0.9),
+                chunk(AudioTrack::Microphone, 1_000, 0.2),
+            ],
+            stats: Vec::new(),
+        };
+
+        let (start, microphone) = recording.merged(AudioTrack::Microphone).unwrap();
+        assert_eq!(start, Duration::ZERO);
+        assert_eq!(microphone.samples, vec![0.1, 0.1, 0.1, 0.1, 0.2, 0.2, 0.2, 0.2]);
+        assert_eq!(microphone.duration, Duration::from_secs(2));
+
+        let (start, system) = recording.merged(AudioTrack::System).unwrap();
+        assert_eq!(start, Duration::from_millis(250));
+        assert_eq!(system.samples.len(), 4);
+        assert_eq!(recording.chunks[2].end(), Duration::from_secs(2));
+    }
+}
@@ -0,0 +1,250 @@
+//! Export-time audio processing for DailyDoco Pro
+//!
+//! Renders the timeline's audio across its cuts, optionally gates out
+//! steady background noise learned from the recording and lays a ducked
+//! music bed under it, then brings the result to the loudness its
+//! destination expects (EBU R128 measurement, gain to the target, true peaks
+//! limited under the ceiling) before writing it out. The final measurement
+//! travels with the audio in its metadata.
+
+use std::path::Path;
+use std::time::Duration;
+
+use aegnt_27::audio::AudioData;
+use aegnt_27::audio_denoise::{DenoiseConfig, NoiseProfile, SpectralDenoiser};
+use aegnt_27::audio_io::SampleEncoding;
+use aegnt_27::audio_loudness::{LoudnessNormalizer, LoudnessTarget};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::music_bed::{MusicBed, MusicBedConfig};
+use crate::timeline::{AudioCutConfig, Timeline, TimelineAudioRenderer};
+
+/// Stretch of the source recording the noise profile is learned from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoiseSample {
+    /// The quietest frames, normally the pauses between sentences
+    Silence,
+    /// A range the user marked as noise only
+    Range { start: Duration, end: Duration },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoiseReductionConfig {
+    pub sample: NoiseSample,
+    #[serde(default)]
+    pub denoise: DenoiseConfig,
+}
+
+impl Default for NoiseReductionConfig {
+    fn default() -> Self {
+        Self {
+            sample: NoiseSample::Silence,
+            denoise: DenoiseConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioExportConfig {
+    /// How the audio is joined at each cut
+    #[serde(default)]
+    pub cuts: AudioCutConfig,
+    /// Spectral gating of background noise, off when `None`
+    #[serde(default)]
+    pub noise_reduction: Option<NoiseReductionConfig>,
+    /// Background music ducked under the narration, none when `None`
+    #[serde(default)]
+    pub music: Option<MusicBedConfig>,
+    /// Loudness to normalise to, or `None` to keep the recorded level
+    pub loudness: Option<LoudnessTarget>,
+    /// Sample format of exported WAV or FLAC files
+    pub encoding: SampleEncoding,
+}
+
+impl Default for AudioExportConfig {
+    fn default() -> Self {
+        Self {
+            cuts: AudioCutConfig::default(),
+            noise_reduction: None,
+            music: None,
+            loudness: Some(LoudnessTarget::YouTube),
+            encoding: SampleEncoding::Int24,
+        }
+    }
+}
+
+/// Produces the final audio of an export
+pub struct AudioExporter {
+    config: AudioExportConfig,
+    renderer: TimelineAudioRenderer,
+}
+
+impl AudioExporter {
+    pub fn new(config: AudioExportConfig) -> Self {
+        let renderer = TimelineAudioRenderer::new(config.cuts.clone());
+        Self { config, renderer }
+    }
+
+    /// Cut, denoise, add music to and normalise the timeline's audio
+    pub fn render(&self, timeline: &Timeline, source: &AudioData) -> Result<AudioData> {
+        let mut audio = self.renderer.render(timeline, source)?;
+        if let Some(noise_reduction) = &self.config.noise_reduction {
+            audio = Self::reduce_noise(noise_reduction, source, &audio)?;
+        }
+        if let Some(music) = &self.config.music {
+            audio = MusicBed::load(music.clone())?.apply(&audio)?;
+        }
+        let measured = audio.analyze_loudness().map_err(|e| anyhow!("Loudness measurement failed: {}", e))?;
+
+        let Some(target) = self.config.loudness else {
+            return Ok(audio);
+        };
+        let normalized = LoudnessNormalizer::new(target)
+            .normalize(&audio)
+            .map_err(|e| anyhow!("Loudness normalisation failed: {}", e))?;
+
+        if let Some(result) = normalized.metadata.loudness {
+            log::info!(
+                "🔊 Loudness {:.1} LUFS → {:.1} LUFS ({:.1} dBTP, LRA {:.1} LU) for {:?}",
+                measured.integrated_lufs,
+                result.integrated_lufs,
+                result.true_peak_dbtp,
+                result.loudness_range_lu,
+                target
+            );
+        }
+        Ok(normalized)
+    }
+
+    /// The noise profile comes from the whole source recording, which has
+    /// more pauses to learn from than the cut
+    fn reduce_noise(config: &NoiseReductionConfig, source: &AudioData, audio: &AudioData) -> Result<AudioData> {
+        let fft_size = config.denoise.fft_size;
+        let profile = match &config.sample {
+            NoiseSample::Silence => NoiseProfile::from_silence(source, fft_size),
+            NoiseSample::Range { start, end } => NoiseProfile::from_range(source, *start..*end, fft_size),
+        }
+        .map_err(|e| anyhow!("Failed to learn noise profile: {}", e))?;
+
+        SpectralDenoiser::new(config.denoise.clone(), profile)
+            .and_then(|denoiser| denoiser.process(audio))
+            .map_err(|e| anyhow!("Noise reduction failed: {}", e))
+    }
+
+    /// Render the timeline's audio and write it as `.wav` or `.flac`
+    pub fn export(&self, timeline: &Timeline, source: &AudioData, path: &Path) -> Result<AudioData> {
+        let audio = self.render(timeline, source)?;
+        audio
+            .save(path, self.config.encoding)
+            .map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))?;
+        Ok(audio)
+    }
+}
+
+impl Default for AudioExporter {
+    fn default() -> Self {
+        Self::new(AudioExportConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    use crate::timeline::TimelineClip;
+
+    fn sine(seconds: f32, amplitude: f32, sample_rate: u32) -> AudioData {
+        let frames = (seconds * sample_rate as f32) as usize;
+        let samples = (0..frames)
+            .map(|n| amplitude * (2.0 * std::f32::consts::PI * 1000.0 * n as f32 / sample_rate as f32).sin())
+            .collect();
+        AudioData::new(samples, sample_rate, 1)
+    }
+
+    fn timeline() -> Timeline {
+        Timeline::new(vec![
+            TimelineClip::new(Duration::ZERO, Duration::from_secs(3)),
+            TimelineClip::new(Duration::from_secs(4), Duration::from_secs(8)),
+        ])
+    }
+
+    #[test]
+    fn test_render_reaches_platform_target() {
+        let source = sine(8.0, 0.05, 48000);
+        for target in [LoudnessTarget::YouTube, LoudnessTarget::Podcast] {
+            let config = AudioExportConfig {
+                loudness: Some(target),
+                ..AudioExportConfig::default()
+            };
+            let audio = AudioExporter::new(config).render(&timeline(), &source).unwrap();
+            let loudness = audio.metadata.loudness.unwrap();
+
+            assert_eq!(audio.frame_count(), 7 * 48000);
+            assert!((loudness.integrated_lufs - target.integrated_lufs()).abs() < 0.5);
+            assert!(loudness.true_peak_dbtp <= target.true_peak_dbtp() + 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_render_without_target_keeps_level_but_measures() {
+        let source = sine(8.0, 0.05, 48000);
+        let config = AudioExportConfig {
+            loudness: None,
+            ..AudioExportConfig::default()
+        };
+        let audio = AudioExporter::new(config).render(&timeline(), &source).unwrap();
+
+        // Away from the cut the samples are the source's own
+        assert_eq!(audio.samples[48000], source.samples[48000]);
+        // 1 kHz mono sine: amplitude in dBFS minus 3 dB
+        let loudness = audio.metadata.loudness.unwrap();
+        assert!((loudness.integrated_lufs - (20.0 * 0.05f64.log10() - 3.01)).abs() < 0.2);
+    }
+
+    #[test]
+    fn test_export_writes_normalized_file() {
+        let path = std::env::temp_dir().join(format!("dailydoco-audio-{}.flac", uuid::Uuid::new_v4()));
+        let source = sine(8.0, 0.05, 48000);
+
+        let exported = AudioExporter::default().export(&timeline(), &source, &path).unwrap();
+        let reloaded = AudioData::load(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(reloaded.frame_count(), exported.frame_count());
+        let loudness = reloaded.measure_loudness().unwrap();
+        assert!((loudness.integrated_lufs - LoudnessTarget::YouTube.integrated_lufs()).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_noise_reduction_learns_from_marked_range() {
+        // Fan-like hiss over the whole recording, speech stand-in after 2 s
+        let mut state = 1u32;
+        let mut source = sine(8.0, 0.2, 48000);
+        for (n, sample) in source.samples.iter_mut().enumerate() {
+            state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+            let hiss = 0.01 * (state as f32 / u32::MAX as f32 * 2.0 - 1.0);
+            *sample = if n < 2 * 48000 { hiss } else { *sample + hiss };
+        }
+        let config = AudioExportConfig {
+            noise_reduction: Some(NoiseReductionConfig {
+                sample: NoiseSample::Range {
+                    start: Duration::ZERO,
+                    end: Duration::from_secs(2),
+                },
+                ..NoiseReductionConfig::default()
+            }),
+            loudness: None,
+            ..AudioExportConfig::default()
+        };
+
+        let audio = AudioExporter::new(config).render(&timeline(), &source).unwrap();
+        let rms = |samples: &[f32]| (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt();
+        // The first clip opens with a second and a half of hiss only
+        assert!(rms(&audio.samples[4800..67200]) < rms(&source.samples[4800..67200]) / 2.0);
+        let speech = 4 * 48000..6 * 48000;
+        assert!((rms(&audio.samples[speech.clone()]) / rms(&source.samples[speech]) - 1.0).abs() < 0.1);
+    }
+}
@@ -0,0 +1,394 @@
+//! Live audio levels and microphone alerts for DailyDoco Pro
+//!
+//! The audio callback folds every sample into short meter blocks and pushes
+//! each block's RMS and peak through a small ring, the same way it reports
+//! clock points. The drain thread feeds the blocks to a `TrackMeter` per
+//! track, which publishes the latest levels on the shared `AudioMeter` for
+//! the tray and UI to poll. A microphone that stays silent, or keeps
+//! clipping, queues an alert there too; the tray owner shows it, so neither
+//! the callback nor the drain ever waits on a notification.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use rtrb::{Consumer, Producer, RingBuffer};
+use serde::{Deserialize, Serialize};
+
+use crate::audio_capture::AudioTrack;
+
+/// Meter blocks buffered between the callback and the drain
+const READING_CAPACITY: usize = 256;
+/// Level reported for digital silence
+const FLOOR_DBFS: f32 = -120.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MeterConfig {
+    /// Length of each RMS/peak measurement
+    pub block: Duration,
+    /// Blocks quieter than this, RMS in dBFS, count as silence
+    pub silence_threshold_dbfs: f32,
+    /// Silence this long on the microphone raises an alert
+    pub silence_alert: Duration,
+    /// Blocks peaking at or above this, dBFS, count as clipped
+    pub clip_threshold_dbfs: f32,
+    /// Clipped blocks within `clip_window` that raise an alert
+    pub clip_alert_blocks: usize,
+    pub clip_window: Duration,
+    /// Minimum time between two clipping alerts
+    pub clip_alert_cooldown: Duration,
+}
+
+impl Default for MeterConfig {
+    fn default() -> Self {
+        Self {
+            block: Duration::from_millis(50),
+            silence_threshold_dbfs: -60.0,
+            silence_alert: Duration::from_secs(10),
+            clip_threshold_dbfs: -0.1,
+            clip_alert_blocks: 8,
+            clip_window: Duration::from_secs(5),
+            clip_alert_cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+/// RMS and peak of one meter block
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LevelReading {
+    /// Track frames received by the end of the block
+    pub frames: u64,
+    pub rms: f32,
+    pub peak: f32,
+}
+
+/// Latest levels of one track, as shown by the tray and UI
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackLevel {
+    pub track: AudioTrack,
+    pub rms_dbfs: f32,
+    pub peak_dbfs: f32,
+    /// Whether the track clipped within the last clip window
+    pub clipping: bool,
+    /// How long the track has been below the silence threshold
+    pub silent_for: Duration,
+}
+
+/// Something wrong with the microphone worth interrupting the user for
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LevelAlert {
+    Silent { track: AudioTrack, duration: Duration },
+    Clipping { track: AudioTrack, clipped_blocks: usize, window: Duration },
+}
+
+impl LevelAlert {
+    pub fn title(&self) -> String {
+        match self {
+            LevelAlert::Silent { track, .. } => format!("{} is silent", track.label()),
+            LevelAlert::Clipping { track, .. } => format!("{} is clipping", track.label()),
+        }
+    }
+
+    /// A silent microphone is ruining the recording; clipping only hurts it
+    pub fn is_urgent(&self) -> bool {
+        matches!(self, LevelAlert::Silent { .. })
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            LevelAlert::Silent { duration, .. } => format!(
+                "Nothing has been heard for {} s. Check the mic isn't muted or unplugged.",
+                duration.as_secs()
+            ),
+            LevelAlert::Clipping {
+                clipped_blocks, window, ..
+            } => format!(
+                "The signal hit full scale {} times in {} s. Turn the input gain down.",
+                clipped_blocks,
+                window.as_secs()
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct MeterState {
+    levels: Vec<TrackLevel>,
+    alerts: Vec<LevelAlert>,
+}
+
+/// Levels and pending alerts of every track being recorded; cheap to clone
+#[derive(Debug, Clone, Default)]
+pub struct AudioMeter {
+    state: Arc<Mutex<MeterState>>,
+}
+
+impl AudioMeter {
+    /// Latest level of each track that has delivered audio
+    pub fn levels(&self) -> Vec<TrackLevel> {
+        self.state.lock().levels.clone()
+    }
+
+    pub fn level(&self, track: AudioTrack) -> Option<TrackLevel> {
+        self.state.lock().levels.iter().find(|level| level.track == track).cloned()
+    }
+
+    /// Alerts raised since the last call
+    pub fn take_alerts(&self) -> Vec<LevelAlert> {
+        std::mem::take(&mut self.state.lock().alerts)
+    }
+
+    fn publish(&self, level: TrackLevel) {
+        let mut state = self.state.lock();
+        match state.levels.iter_mut().find(|existing| existing.track == level.track) {
+            Some(existing) => *existing = level,
+            None => state.levels.push(level),
+        }
+    }
+
+    fn raise(&self, alert: LevelAlert) {
+        self.state.lock().alerts.push(alert);
+    }
+}
+
+/// Callback side of a track's meter: sums samples into blocks and pushes
+/// each finished one, never blocking or allocating
+pub struct LevelTap {
+    producer: Producer<LevelReading>,
+    channels: u64,
+    block_samples: usize,
+    samples: usize,
+    sum_squares: f32,
+    peak: f32,
+    samples_total: u64,
+}
+
+impl LevelTap {
+    pub fn add(&mut self, sample: f32) {
+        self.sum_squares += sample * sample;
+        self.peak = self.peak.max(sample.abs());
+        self.samples += 1;
+        if self.samples == self.block_samples {
+            self.samples_total += self.samples as u64;
+            let reading = LevelReading {
+                frames: self.samples_total / self.channels,
+                rms: (self.sum_squares / self.samples as f32).sqrt(),
+                peak: self.peak,
+            };
+            // A missed block only leaves the meter a moment behind
+            let _ = self.producer.push(reading);
+            self.samples = 0;
+            self.sum_squares = 0.0;
+            self.peak = 0.0;
+        }
+    }
+}
+
+/// A track's meter ring: the tap for the callback, the reading end for the drain
+pub fn level_channel(sample_rate: u32, channels: u8, config: &MeterConfig) -> (LevelTap, Consumer<LevelReading>) {
+    let (producer, consumer) = RingBuffer::new(READING_CAPACITY);
+    let block_frames = ((config.block.as_secs_f64() * sample_rate as f64).round() as usize).max(1);
+    let tap = LevelTap {
+        producer,
+        channels: channels.max(1) as u64,
+        block_samples: block_frames * channels.max(1) as usize,
+        samples: 0,
+        sum_squares: 0.0,
+        peak: 0.0,
+        samples_total: 0,
+    };
+    (tap, consumer)
+}
+
+/// Drain side of a track's meter: tracks silence and clipping across blocks
+pub struct TrackMeter {
+    track: AudioTrack,
+    sample_rate: u32,
+    config: MeterConfig,
+    meter: AudioMeter,
+    /// Frame the current silent stretch began at
+    silent_since: Option<u64>,
+    silence_alerted: bool,
+    /// End frames of recently clipped blocks
+    clipped: VecDeque<u64>,
+    last_clip_alert: Option<u64>,
+}
+
+impl TrackMeter {
+    pub fn new(track: AudioTrack, sample_rate: u32, config: MeterConfig, meter: AudioMeter) -> Self {
+        Self {
+            track,
+            sample_rate,
+            config,
+            meter,
+            silent_since: None,
+            silence_alerted: false,
+            clipped: VecDeque::new(),
+            last_clip_alert: None,
+        }
+    }
+
+    /// Publish a block's levels; only the microphone raises alerts, since
+    /// silent or loud system audio is usually intended
+    pub fn update(&mut self, reading: LevelReading) -> Option<LevelAlert> {
+        let rms_dbfs = dbfs(reading.rms);
+        let peak_dbfs = dbfs(reading.peak);
+        let frames_of = |duration: Duration| (duration.as_secs_f64() * self.sample_rate as f64) as u64;
+
+        let block_start = reading.frames.saturating_sub(frames_of(self.config.block));
+        if rms_dbfs < self.config.silence_threshold_dbfs {
+            self.silent_since.get_or_insert(block_start);
+        } else {
+            self.silent_since = None;
+            self.silence_alerted = false;
+        }
+        let silent_frames = self.silent_since.map_or(0, |since| reading.frames - since);
+        let silent_for = Duration::from_secs_f64(silent_frames as f64 / self.sample_rate.max(1) as f64);
+
+        if peak_dbfs >= self.config.clip_threshold_dbfs {
+            self.clipped.push_back(reading.frames);
+        }
+        let window = frames_of(self.config.clip_window);
+        while self.clipped.front().is_some_and(|&end| end + window <= reading.frames) {
+            self.clipped.pop_front();
+        }
+
+        self.meter.publish(TrackLevel {
+            track: self.track,
+            rms_dbfs,
+            peak_dbfs,
+            clipping: !self.clipped.is_empty(),
+            silent_for,
+        });
+
+        if self.track != AudioTrack::Microphone {
+            return None;
+        }
+        let alert = if !self.silence_alerted && silent_for >= self.config.silence_alert {
+            self.silence_alerted = true;
+            Some(LevelAlert::Silent {
+                track: self.track,
+                duration: silent_for,
+            })
+        } else if self.clipped.len() >= self.config.clip_alert_blocks.max(1)
+            && self
+                .last_clip_alert
+                .is_none_or(|last| reading.frames >= last + frames_of(self.config.clip_alert_cooldown))
+        {
+            self.last_clip_alert = Some(reading.frames);
+            Some(LevelAlert::Clipping {
+                track: self.track,
+                clipped_blocks: self.clipped.len(),
+                window: self.config.clip_window,
+            })
+        } else {
+            None
+        };
+
+        if let Some(alert) = &alert {
+            log::warn!("🎚️ {}: {}", alert.title(), alert.message());
+            self.meter.raise(alert.clone());
+        }
+        alert
+    }
+}
+
+fn dbfs(linear: f32) -> f32 {
+    (20.0 * linear.log10()).max(FLOOR_DBFS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 1_000;
+
+    fn feed(tap: &mut LevelTap, seconds: f32, value: impl Fn(usize) -> f32) {
+        (0..(seconds * RATE as f32) as usize).for_each(|n| tap.add(value(n)));
+    }
+
+    fn run(track: AudioTrack, seconds: f32, value: impl Fn(usize) -> f32) -> (AudioMeter, Vec<LevelAlert>) {
+        let config = MeterConfig::default();
+        let meter = AudioMeter::default();
+        let (mut tap, mut readings) = level_channel(RATE, 1, &config);
+        let mut track_meter = TrackMeter::new(track, RATE, config, meter.clone());
+        let mut alerts = Vec::new();
+        // Drain a second at a time, as a busy drain thread would
+        for second in 0..seconds.ceil() as usize {
+            feed(&mut tap, 1.0f32.min(seconds - second as f32), |n| value(second * RATE as usize + n));
+            while let Ok(reading) = readings.pop() {
+                alerts.extend(track_meter.update(reading));
+            }
+        }
+        (meter, alerts)
+    }
+
+    #[test]
+    fn test_tap_measures_whole_blocks_across_callbacks() {
+        let (mut tap, mut readings) = level_channel(RATE, 2, &MeterConfig::default());
+        // 50 ms blocks are 100 stereo samples; 260 samples make two and a bit
+        feed(&mut tap, 0.07, |_| 0.5);
+        feed(&mut tap, 0.19, |n| if n == 100 { -1.0 } else { 0.25 });
+
+        let first = readings.pop().unwrap();
+        assert_eq!(first.frames, 50);
+        assert!((first.rms - ((70.0 * 0.25 + 30.0 * 0.0625) / 100.0f32).sqrt()).abs() < 1e-6);
+        assert_eq!(first.peak, 0.5);
+        let second = readings.pop().unwrap();
+        assert_eq!(second.frames, 100);
+        assert!((second.rms - ((99.0 * 0.0625 + 1.0) / 100.0f32).sqrt()).abs() < 1e-6);
+        assert_eq!(second.peak, 1.0);
+        // The last 60 samples wait for the rest of their block
+        assert!(readings.pop().is_err());
+    }
+
+    #[test]
+    fn test_silent_microphone_alerts_once_per_silence() {
+        // Talking for 2 s, muted for 12 s, talking again, muted for 4 s
+        let voice = |n: usize| {
+            let second = n / RATE as usize;
+            if second < 2 || (14..16).contains(&second) {
+                0.1 * (n as f32 * 0.3).sin()
+            } else {
+                0.0
+            }
+        };
+        let (meter, alerts) = run(AudioTrack::Microphone, 20.0, voice);
+        assert_eq!(
+            alerts,
+            vec![LevelAlert::Silent {
+                track: AudioTrack::Microphone,
+                duration: Duration::from_secs(10),
+            }]
+        );
+        let level = meter.level(AudioTrack::Microphone).unwrap();
+        assert_eq!(level.silent_for, Duration::from_secs(4));
+        assert_eq!(level.rms_dbfs, -120.0);
+        assert_eq!(meter.take_alerts(), alerts);
+        assert!(meter.take_alerts().is_empty());
+
+        // System audio is silent more often than not
+        let (meter, alerts) = run(AudioTrack::System, 20.0, voice);
+        assert!(alerts.is_empty());
+        assert_eq!(meter.levels()[0].track, AudioTrack::System);
+    }
+
+    #[test]
+    fn test_only_persistent_clipping_alerts() {
+        // One full-scale sample a second never adds up to 8 clipped blocks in 5 s
+        let occasional = |n: usize| if n % RATE as usize == 500 { 1.0 } else { 0.3 };
+        let (meter, alerts) = run(AudioTrack::Microphone, 20.0, occasional);
+        assert!(alerts.is_empty());
+        let level = meter.level(AudioTrack::Microphone).unwrap();
+        assert!(level.clipping);
+        assert!((level.peak_dbfs - dbfs(0.3)).abs() < 1e-4);
+
+        // A hot mic clips every block; one alert per 30 s cooldown
+        let (_, alerts) = run(AudioTrack::Microphone, 40.0, |n| if n % 10 == 0 { 1.0 } else { 0.5 });
+        assert_eq!(alerts.len(), 2);
+        assert_eq!(alerts[0].title(), "Microphone is clipping");
+        assert!(alerts[0].message().contains("8 times in 5 s"));
+    }
+}
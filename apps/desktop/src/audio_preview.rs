@@ -0,0 +1,514 @@
+//! Waveform peaks and spectrograms for the timeline UI of DailyDoco Pro
+//!
+//! Drawing an hour of audio from raw samples is far too slow for the editor,
+//! so each track gets two precomputed previews, cached next to the session:
+//!
+//! - A peak file holding the minimum and maximum sample of every bucket at
+//!   several zoom levels, each level merging `zoom_factor` buckets of the one
+//!   before, so the frontend picks the level closest to its pixels-per-second
+//!   and draws one column per bucket.
+//! - A spectrogram PNG, time left to right and frequency on a log scale
+//!   bottom to top, for spotting noise, clipping and silence at a glance.
+//!
+//! Peak file layout, all little-endian:
+//!
+//! ```text
+//! magic "DDPK" | version u16 | level count u16 | sample rate u32 | channels u16 | frames u64
+//! per level:   samples per bucket u32 | bucket count u32
+//! per level:   bucket count × (min i16, max i16), all channels combined
+//! ```
+//!
+//! Cached files are named after a fingerprint of the audio, so a re-recorded
+//! or re-edited track never shows stale previews.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use aegnt_27::audio::AudioData;
+use anyhow::{bail, Context, Result};
+use rustfft::num_complex::Complex;
+use rustfft::FftPlanner;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::session::SessionRecord;
+
+const PEAKS_MAGIC: &[u8; 4] = b"DDPK";
+const PEAKS_VERSION: u16 = 1;
+/// Lowest frequency on the spectrogram's log scale
+const SPECTROGRAM_LOW_HZ: f32 = 20.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreviewConfig {
+    /// Samples per bucket at the finest zoom level
+    pub base_bucket: u32,
+    /// How many buckets of one level merge into a bucket of the next
+    pub zoom_factor: u32,
+    pub levels: usize,
+    /// Widest spectrogram drawn, in pixels; long tracks get fewer columns per second
+    pub spectrogram_width: u32,
+    pub spectrogram_height: u32,
+    pub fft_size: usize,
+    /// Level drawn black, dBFS
+    pub floor_db: f32,
+}
+
+impl Default for PreviewConfig {
+    fn default() -> Self {
+        Self {
+            base_bucket: 256,
+            zoom_factor: 4,
+            levels: 5,
+            spectrogram_width: 4096,
+            spectrogram_height: 256,
+            fft_size: 2048,
+            floor_db: -96.0,
+        }
+    }
+}
+
+/// Min/max buckets at one zoom level
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeakLevel {
+    pub samples_per_bucket: u32,
+    /// (min, max) of each bucket, full scale at ±32767
+    pub buckets: Vec<(i16, i16)>,
+}
+
+/// Multi-resolution waveform of one track
+#[derive(Debug, Clone, PartialEq)]
+pub struct WaveformPeaks {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub frames: u64,
+    /// Finest level first
+    pub levels: Vec<PeakLevel>,
+}
+
+impl WaveformPeaks {
+    pub fn from_audio(audio: &AudioData, config: &PreviewConfig) -> Result<Self> {
+        if config.base_bucket == 0 || config.zoom_factor < 2 || config.levels == 0 {
+            bail!("Peaks need a bucket size, a zoom factor of at least 2 and one level");
+        }
+        let channels = audio.channels.max(1) as usize;
+        let to_i16 = |sample: f32| (sample.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16;
+
+        let finest = audio
+            .samples
+            .chunks(config.base_bucket as usize * channels)
+            .map(|chunk| {
+                let (min, max) = chunk
+                    .iter()
+                    .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), &s| (min.min(s), max.max(s)));
+                (to_i16(min), to_i16(max))
+            })
+            .collect();
+
+        let mut levels = vec![PeakLevel {
+            samples_per_bucket: config.base_bucket,
+            buckets: finest,
+        }];
+        while levels.len() < config.levels {
+            let previous = levels.last().unwrap();
+            let buckets = previous
+                .buckets
+                .chunks(config.zoom_factor as usize)
+                .map(|group| {
+                    group.iter().fold((i16::MAX, i16::MIN), |(min, max), &(lo, hi)| (min.min(lo), max.max(hi)))
+                })
+                .collect();
+            levels.push(PeakLevel {
+                samples_per_bucket: previous.samples_per_bucket * config.zoom_factor,
+                buckets,
+            });
+        }
+
+        Ok(Self {
+            sample_rate: audio.sample_rate,
+            channels: channels as u16,
+            frames: audio.frame_count() as u64,
+            levels,
+        })
+    }
+
+    /// Coarsest level that still has at least one bucket per pixel
+    pub fn level_for(&self, samples_per_pixel: f64) -> &PeakLevel {
+        self.levels
+            .iter()
+            .rev()
+            .find(|level| level.samples_per_bucket as f64 <= samples_per_pixel)
+            .unwrap_or(&self.levels[0])
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let data: usize = self.levels.iter().map(|level| 8 + level.buckets.len() * 4).sum();
+        let mut bytes = Vec::with_capacity(22 + data);
+        bytes.extend_from_slice(PEAKS_MAGIC);
+        bytes.extend_from_slice(&PEAKS_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(self.levels.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(&self.sample_rate.to_le_bytes());
+        bytes.extend_from_slice(&self.channels.to_le_bytes());
+        bytes.extend_from_slice(&self.frames.to_le_bytes());
+        for level in &self.levels {
+            bytes.extend_from_slice(&level.samples_per_bucket.to_le_bytes());
+            bytes.extend_from_slice(&(level.buckets.len() as u32).to_le_bytes());
+        }
+        for level in &self.levels {
+            for (min, max) in &level.buckets {
+                bytes.extend_from_slice(&min.to_le_bytes());
+                bytes.extend_from_slice(&max.to_le_bytes());
+            }
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = ByteReader { bytes, position: 0 };
+        if reader.take(4)? != PEAKS_MAGIC {
+            bail!("Not a peak file");
+        }
+        let version = reader.u16()?;
+        if version != PEAKS_VERSION {
+            bail!("Unsupported peak file version {}", version);
+        }
+        let level_count = reader.u16()? as usize;
+        let sample_rate = reader.u32()?;
+        let channels = reader.u16()?;
+        let frames = reader.u64()?;
+
+        let mut headers = Vec::with_capacity(level_count);
+        for _ in 0..level_count {
+            headers.push((reader.u32()?, reader.u32()? as usize));
+        }
+        let mut levels = Vec::with_capacity(level_count);
+        for (samples_per_bucket, count) in headers {
+            let mut buckets = Vec::with_capacity(count);
+            for _ in 0..count {
+                buckets.push((reader.u16()? as i16, reader.u16()? as i16));
+            }
+            levels.push(PeakLevel {
+                samples_per_bucket,
+                buckets,
+            });
+        }
+
+        Ok(Self {
+            sample_rate,
+            channels,
+            frames,
+            levels,
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, self.to_bytes()).with_context(|| format!("Writing {}", path.display()))
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes = std::fs::read(path).with_context(|| format!("Reading {}", path.display()))?;
+        Self::from_bytes(&bytes).with_context(|| format!("Parsing peaks {}", path.display()))
+    }
+}
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8]> {
+        let Some(slice) = self.bytes.get(self.position..self.position + count) else {
+            bail!("Peak file is truncated");
+        };
+        self.position += count;
+        Ok(slice)
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into()?))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into()?))
+    }
+}
+
+/// Log-frequency spectrogram of the audio, one FFT per column
+pub fn render_spectrogram(audio: &AudioData, config: &PreviewConfig) -> Result<image::RgbImage> {
+    let fft_size = config.fft_size;
+    if fft_size < 64 || !fft_size.is_power_of_two() || config.spectrogram_width == 0 || config.spectrogram_height == 0 {
+        bail!("Spectrogram needs a power-of-two FFT size of at least 64 and a non-empty image");
+    }
+    let mono = audio.to_mono();
+    let frames = mono.samples.len();
+    let width = ((frames / (fft_size / 2)).max(1) as u32).min(config.spectrogram_width);
+    let height = config.spectrogram_height;
+
+    let fft = FftPlanner::<f32>::new().plan_fft_forward(fft_size);
+    let window: Vec<f32> = (0..fft_size)
+        .map(|n| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * n as f32 / fft_size as f32).cos())
+        .collect();
+    // Hann window halves the amplitude of a bin-centred sine
+    let full_scale = fft_size as f32 / 4.0;
+
+    // Fractional FFT bin shown by each row, bottom row lowest
+    let nyquist = audio.sample_rate as f32 / 2.0;
+    let bin_hz = audio.sample_rate as f32 / fft_size as f32;
+    let low = SPECTROGRAM_LOW_HZ.min(nyquist / 2.0);
+    let row_bins: Vec<f32> = (0..height)
+        .map(|row| {
+            let position = (height - 1 - row) as f32 / (height - 1).max(1) as f32;
+            low * (nyquist / low).powf(position) / bin_hz
+        })
+        .collect();
+
+    let mut image = image::RgbImage::new(width, height);
+    let mut buffer = vec![Complex::new(0.0f32, 0.0); fft_size];
+    for column in 0..width {
+        let centre = (column as f64 + 0.5) * frames as f64 / width as f64;
+        let start = (centre as usize).saturating_sub(fft_size / 2);
+        for (n, value) in buffer.iter_mut().enumerate() {
+            let sample = mono.samples.get(start + n).copied().unwrap_or(0.0);
+            *value = Complex::new(sample * window[n], 0.0);
+        }
+        fft.process(&mut buffer);
+
+        for (row, &bin) in row_bins.iter().enumerate() {
+            // Interpolate between neighbouring bins at the low end, where
+            // several rows share one bin
+            let (below, fraction) = (bin.floor() as usize, bin.fract());
+            let above = (below + 1).min(fft_size / 2);
+            let magnitude = buffer[below].norm() * (1.0 - fraction) + buffer[above].norm() * fraction;
+            let db = 20.0 * (magnitude / full_scale).max(1e-9).log10();
+            let level = ((db - config.floor_db) / -config.floor_db).clamp(0.0, 1.0);
+            image.put_pixel(column, row as u32, image::Rgb(heat(level)));
+        }
+    }
+    Ok(image)
+}
+
+/// Black through purple, red and orange to pale yellow
+fn heat(level: f32) -> [u8; 3] {
+    const STOPS: [[f32; 3]; 5] = [
+        [0.0, 0.0, 0.0],
+        [80.0, 18.0, 123.0],
+        [221.0, 49.0, 85.0],
+        [252.0, 140.0, 40.0],
+        [252.0, 253.0, 191.0],
+    ];
+    let position = level * (STOPS.len() - 1) as f32;
+    let index = (position.floor() as usize).min(STOPS.len() - 2);
+    let fraction = position - index as f32;
+    let (a, b) = (STOPS[index], STOPS[index + 1]);
+    [0, 1, 2].map(|c| (a[c] + (b[c] - a[c]) * fraction).round() as u8)
+}
+
+/// Identity of the audio: a SHA-256 of its format and every sample, stable
+/// across builds so cached previews stay valid
+pub fn fingerprint(audio: &AudioData) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(audio.sample_rate.to_le_bytes());
+    hasher.update(audio.channels.to_le_bytes());
+    for sample in &audio.samples {
+        hasher.update(sample.to_le_bytes());
+    }
+    hasher.finalize()[..8].iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Previews of one track, paths relative to the session directory
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AudioPreview {
+    pub track: String,
+    pub fingerprint: String,
+    pub duration: Duration,
+    pub peaks: PathBuf,
+    pub spectrogram: PathBuf,
+}
+
+/// Preview files kept in a session's `previews` directory
+pub struct AudioPreviewCache {
+    session_dir: PathBuf,
+    config: PreviewConfig,
+}
+
+impl AudioPreviewCache {
+    pub fn new(session_dir: impl Into<PathBuf>, config: PreviewConfig) -> Self {
+        Self {
+            session_dir: session_dir.into(),
+            config,
+        }
+    }
+
+    pub fn dir(&self) -> PathBuf {
+        self.session_dir.join("previews")
+    }
+
+    /// Previews of `audio` for `track`, built only when the cache has none
+    /// for this exact audio; stale previews of the track are removed
+    pub fn preview(&self, track: &str, audio: &AudioData) -> Result<AudioPreview> {
+        if track.is_empty() || track.contains(['/', '\\', '.']) {
+            bail!("Track name {:?} can't be used as a file name", track);
+        }
+        let fingerprint = fingerprint(audio);
+        let peaks = Path::new("previews").join(format!("{}.{}.peaks", track, fingerprint));
+        let spectrogram = Path::new("previews").join(format!("{}.{}.png", track, fingerprint));
+        let preview = AudioPreview {
+            track: track.to_string(),
+            fingerprint,
+            duration: audio.duration,
+            peaks,
+            spectrogram,
+        };
+        if self.session_dir.join(&preview.peaks).is_file() && self.session_dir.join(&preview.spectrogram).is_file() {
+            return Ok(preview);
+        }
+
+        let dir = self.dir();
+        std::fs::create_dir_all(&dir).with_context(|| format!("Creating {}", dir.display()))?;
+        self.remove_stale(track, &preview.fingerprint)?;
+
+        WaveformPeaks::from_audio(audio, &self.config)?.save(&self.session_dir.join(&preview.peaks))?;
+        let spectrogram_path = self.session_dir.join(&preview.spectrogram);
+        render_spectrogram(audio, &self.config)?
+            .save(&spectrogram_path)
+            .with_context(|| format!("Writing {}", spectrogram_path.display()))?;
+
+        log::info!("🌊 Built audio previews for {} ({})", track, preview.fingerprint);
+        Ok(preview)
+    }
+
+    /// Build or reuse the track's previews and list them in the session record
+    pub fn update_record(&self, record: &mut SessionRecord, track: &str, audio: &AudioData) -> Result<AudioPreview> {
+        let preview = self.preview(track, audio)?;
+        record.audio_previews.retain(|p| p.track != track);
+        record.audio_previews.push(preview.clone());
+        Ok(preview)
+    }
+
+    fn remove_stale(&self, track: &str, fingerprint: &str) -> Result<()> {
+        let prefix = format!("{}.", track);
+        for entry in std::fs::read_dir(self.dir())? {
+            let path = entry?.path();
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if let Some(rest) = name.strip_prefix(&prefix) {
+                if !rest.starts_with(fingerprint) {
+                    std::fs::remove_file(&path).with_context(|| format!("Removing {}", path.display()))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared_types::CaptureSession;
+    use uuid::Uuid;
+
+    fn tone(seconds: f32, frequency: f32, amplitude: f32, sample_rate: u32) -> AudioData {
+        let frames = (seconds * sample_rate as f32) as usize;
+        let samples = (0..frames)
+            .map(|n| amplitude * (2.0 * std::f32::consts::PI * frequency * n as f32 / sample_rate as f32).sin())
+            .collect();
+        AudioData::new(samples, sample_rate, 1)
+    }
+
+    #[test]
+    fn test_peaks_at_every_zoom_level_round_trip() {
+        let mut samples = vec![0.0f32; 2 * 10_000];
+        samples[2 * 300] = 0.5;
+        samples[2 * 300 + 1] = -1.0;
+        samples[2 * 9_999] = 0.25;
+        let audio = AudioData::new(samples, 48_000, 2);
+        let config = PreviewConfig {
+            base_bucket: 100,
+            levels: 3,
+            ..PreviewConfig::default()
+        };
+
+        let peaks = WaveformPeaks::from_audio(&audio, &config).unwrap();
+        let sizes: Vec<_> = peaks.levels.iter().map(|l| (l.samples_per_bucket, l.buckets.len())).collect();
+        assert_eq!(sizes, vec![(100, 100), (400, 25), (1600, 7)]);
+        assert_eq!(peaks.levels[0].buckets[3], (-32767, 16384));
+        assert_eq!(peaks.levels[1].buckets[0], (-32767, 16384));
+        assert_eq!(peaks.levels[2].buckets[6], (0, 8192));
+        assert_eq!(peaks.levels[0].buckets[4], (0, 0));
+
+        assert_eq!(peaks.level_for(50.0).samples_per_bucket, 100);
+        assert_eq!(peaks.level_for(1000.0).samples_per_bucket, 400);
+        assert_eq!(peaks.level_for(1e6).samples_per_bucket, 1600);
+
+        let bytes = peaks.to_bytes();
+        assert_eq!(&bytes[..4], b"DDPK");
+        assert_eq!(WaveformPeaks::from_bytes(&bytes).unwrap(), peaks);
+        assert!(WaveformPeaks::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_spectrogram_puts_a_tone_on_its_row() {
+        let config = PreviewConfig {
+            spectrogram_width: 64,
+            spectrogram_height: 128,
+            ..PreviewConfig::default()
+        };
+        let image = render_spectrogram(&tone(8.0, 1_000.0, 0.5, 16_000), &config).unwrap();
+        assert_eq!(image.dimensions(), (64, 128));
+
+        // 1 kHz on a 20 Hz to 8 kHz log scale, counted from the bottom row
+        let expected = 127 - ((1_000f32 / 20.0).ln() / (8_000f32 / 20.0).ln() * 127.0).round() as u32;
+        let brightness = |row: u32| image.get_pixel(32, row).0.iter().map(|&c| c as u32).sum::<u32>();
+        let brightest = (0..128).max_by_key(|&row| brightness(row)).unwrap();
+        assert!(brightest.abs_diff(expected) <= 1, "row {} not {}", brightest, expected);
+        assert!(brightness(10) < brightness(expected) / 4);
+
+        // Short audio gets one column per half frame, not stretched pixels
+        let short = render_spectrogram(&tone(0.256, 1_000.0, 0.5, 16_000), &config).unwrap();
+        assert_eq!(short.width(), 4);
+    }
+
+    #[test]
+    fn test_cache_reuses_and_replaces_previews() {
+        let session_dir = std::env::temp_dir().join(format!("dailydoco-preview-{}", Uuid::new_v4()));
+        let cache = AudioPreviewCache::new(&session_dir, PreviewConfig::default());
+        let narration = tone(2.0, 440.0, 0.3, 48_000);
+
+        let first = cache.preview("microphone", &narration).unwrap();
+        assert!(session_dir.join(&first.peaks).is_file());
+        assert!(session_dir.join(&first.spectrogram).is_file());
+        let written = std::fs::metadata(session_dir.join(&first.peaks)).unwrap().modified().unwrap();
+        let loaded = WaveformPeaks::load(&session_dir.join(&first.peaks)).unwrap();
+        assert_eq!(loaded.frames, 96_000);
+
+        let session = CaptureSession {
+            id: Uuid::new_v4(),
+            project_id: Uuid::new_v4(),
+            started_at: chrono::Utc::now(),
+            duration: None,
+        };
+        let mut record = SessionRecord::new(session, "Previews", session_dir.join("video.mp4"));
+        let again = cache.update_record(&mut record, "microphone", &narration).unwrap();
+        assert_eq!(again, first);
+        assert_eq!(std::fs::metadata(session_dir.join(&again.peaks)).unwrap().modified().unwrap(), written);
+        // Every sample counts, not just a sparse sample of them
+        let mut touched = narration.clone();
+        touched.samples[1] += 0.001;
+        assert_ne!(fingerprint(&touched), first.fingerprint);
+
+        let edited = tone(2.0, 440.0, 0.6, 48_000);
+        let replaced = cache.update_record(&mut record, "microphone", &edited).unwrap();
+        assert_ne!(replaced.fingerprint, first.fingerprint);
+        assert_eq!(record.audio_previews, vec![replaced.clone()]);
+        assert!(!session_dir.join(&first.peaks).exists());
+        assert_eq!(std::fs::read_dir(cache.dir()).unwrap().count(), 2);
+
+        assert!(cache.preview("../escape", &narration).is_err());
+        std::fs::remove_dir_all(&session_dir).ok();
+    }
+}
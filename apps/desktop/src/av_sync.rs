@@ -0,0 +1,566 @@
+//! Audio/video sync drift detection and correction for DailyDoco Pro
+//!
+//! Audio chunks are placed on the capture clock by counting samples at the
+//! device's nominal rate, while video frames carry capture-clock timestamps.
+//! Sound cards run a little fast or slow (tens to hundreds of ppm), so over
+//! an hour the two drift apart by a noticeable fraction of a second.
+//!
+//! During capture each track notes how many frames had arrived by when. A
+//! least-squares fit of those points gives the device's real rate; when the
+//! drift it implies passes the threshold, either the audio is resampled onto
+//! the capture clock, or the video is retimed to follow the
+//! audio by dropping and duplicating frames.
+
+use std::time::Duration;
+
+use aegnt_27::audio::AudioData;
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::audio_capture::{AudioRecording, AudioTrack, TrackStats};
+
+/// Frames a track had received by a moment on the capture clock
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ClockPoint {
+    pub capture_time: Duration,
+    pub frames: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncCorrection {
+    /// Resample the audio so it runs at the capture clock's pace
+    ResampleAudio,
+    /// Keep the audio and drop or duplicate video frames to follow it
+    RetimeVideo,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncConfig {
+    /// Drift at the end of the recording that triggers a correction
+    pub threshold: Duration,
+    pub correction: SyncCorrection,
+    /// Shorter observation spans can't tell drift from callback jitter
+    pub min_span: Duration,
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self {
+            // Viewers start noticing lip-sync errors around 45 ms
+            threshold: Duration::from_millis(40),
+            correction: SyncCorrection::ResampleAudio,
+            min_span: Duration::from_secs(30),
+        }
+    }
+}
+
+/// A track's measured sample clock against the capture clock
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClockFit {
+    pub nominal_rate: u32,
+    /// Frames per second of capture-clock time the device really delivered
+    pub measured_rate: f64,
+    /// Capture-clock time of the track's first frame
+    pub start: Duration,
+    /// Largest distance of an observation from the fit
+    pub jitter: Duration,
+}
+
+impl ClockFit {
+    /// Least-squares line through the observations
+    pub fn from_points(nominal_rate: u32, points: &[ClockPoint]) -> Option<Self> {
+        if points.len() < 2 || nominal_rate == 0 {
+            return None;
+        }
+
+        let n = points.len() as f64;
+        let mean_t = points.iter().map(|p| p.capture_time.as_secs_f64()).sum::<f64>() / n;
+        let mean_f = points.iter().map(|p| p.frames as f64).sum::<f64>() / n;
+        let (mut covariance, mut variance) = (0.0, 0.0);
+        for point in points {
+            let dt = point.capture_time.as_secs_f64() - mean_t;
+            covariance += dt * (point.frames as f64 - mean_f);
+            variance += dt * dt;
+        }
+        if variance <= f64::EPSILON {
+            return None;
+        }
+
+        let measured_rate = covariance / variance;
+        if measured_rate <= 0.0 {
+            return None;
+        }
+        let start = mean_t - mean_f / measured_rate;
+        let jitter = points
+            .iter()
+            .map(|p| (p.frames as f64 - measured_rate * (p.capture_time.as_secs_f64() - start)).abs() / measured_rate)
+            .fold(0.0, f64::max);
+
+        Some(Self {
+            nominal_rate,
+            measured_rate,
+            start: Duration::from_secs_f64(start.max(0.0)),
+            jitter: Duration::from_secs_f64(jitter),
+        })
+    }
+
+    /// How far the device clock runs from its nominal rate, in parts per million
+    pub fn ppm(&self) -> f64 {
+        (self.measured_rate / self.nominal_rate as f64 - 1.0) * 1e6
+    }
+
+    /// Seconds by which sample-counted audio is late (positive) or early at a
+    /// capture-clock time
+    pub fn drift_at(&self, capture_time: Duration) -> f64 {
+        let elapsed = capture_time.as_secs_f64() - self.start.as_secs_f64();
+        elapsed * (self.measured_rate / self.nominal_rate as f64 - 1.0)
+    }
+
+    /// The same clock seen from another track's clock instead of the capture clock
+    fn relative_to(&self, reference: &ClockFit) -> Self {
+        Self {
+            measured_rate: self.measured_rate * reference.nominal_rate as f64 / reference.measured_rate,
+            ..*self
+        }
+    }
+}
+
+/// Drift of one track over a session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriftStats {
+    pub track: AudioTrack,
+    pub nominal_rate: u32,
+    pub measured_rate: f64,
+    pub ppm: f64,
+    /// Span of capture-clock time the observations covered
+    pub observed: Duration,
+    /// Drift at the last observation, positive when audio runs late
+    pub final_drift_ms: f64,
+    pub jitter_ms: f64,
+    /// Correction applied, `None` when drift stayed under the threshold
+    pub correction: Option<SyncCorrection>,
+    #[serde(default)]
+    pub frames_dropped: usize,
+    #[serde(default)]
+    pub frames_duplicated: usize,
+    /// Too little was observed to measure the clock, so the track was taken
+    /// to run at its nominal rate
+    #[serde(default)]
+    pub unmeasured: bool,
+}
+
+/// Which source frame to show for each output frame of a retimed video
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FrameSchedule {
+    pub frames: Vec<usize>,
+    pub dropped: usize,
+    pub duplicated: usize,
+}
+
+/// A track ready for export, starting at `start` on the capture clock
+#[derive(Debug, Clone)]
+pub struct SyncedTrack {
+    pub track: AudioTrack,
+    pub start: Duration,
+    pub audio: AudioData,
+}
+
+/// A recording with its drift corrected
+#[derive(Debug, Clone, Default)]
+pub struct SyncedRecording {
+    pub tracks: Vec<SyncedTrack>,
+    /// Set when the video was retimed to follow the first drifting track
+    pub video: Option<FrameSchedule>,
+    pub drift: Vec<DriftStats>,
+}
+
+/// Measures drift per track and applies the configured correction
+pub struct AvSync {
+    config: SyncConfig,
+}
+
+impl AvSync {
+    pub fn new(config: SyncConfig) -> Self {
+        Self { config }
+    }
+
+    /// Fit a track's clock observations; `None` when they're too few or too short
+    pub fn analyse(&self, stats: &TrackStats) -> Option<(ClockFit, DriftStats)> {
+        let first = stats.clock.first()?;
+        let last = stats.clock.last()?;
+        let observed = last.capture_time.saturating_sub(first.capture_time);
+        if observed < self.config.min_span {
+            return None;
+        }
+
+        let fit = ClockFit::from_points(stats.sample_rate, &stats.clock)?;
+        let final_drift = fit.drift_at(last.capture_time);
+        let correction = (final_drift.abs() > self.config.threshold.as_secs_f64()).then_some(self.config.correction);
+
+        Some((
+            fit,
+            DriftStats {
+                track: stats.track,
+                nominal_rate: stats.sample_rate,
+                measured_rate: fit.measured_rate,
+                ppm: fit.ppm(),
+                observed,
+                final_drift_ms: final_drift * 1000.0,
+                jitter_ms: fit.jitter.as_secs_f64() * 1000.0,
+                correction,
+                frames_dropped: 0,
+                frames_duplicated: 0,
+                unmeasured: false,
+            },
+        ))
+    }
+
+    /// Stand-in for a track `analyse` couldn't measure: its nominal rate,
+    /// starting at `start`, with no drift
+    fn assume_nominal(&self, stats: &TrackStats, start: Duration) -> (ClockFit, DriftStats) {
+        let observed = match (stats.clock.first(), stats.clock.last()) {
+            (Some(first), Some(last)) => last.capture_time.saturating_sub(first.capture_time),
+            _ => Duration::ZERO,
+        };
+        let fit = ClockFit {
+            nominal_rate: stats.sample_rate,
+            measured_rate: stats.sample_rate as f64,
+            start,
+            jitter: Duration::ZERO,
+        };
+        let drift = DriftStats {
+            track: stats.track,
+            nominal_rate: stats.sample_rate,
+            measured_rate: fit.measured_rate,
+            ppm: 0.0,
+            observed,
+            final_drift_ms: 0.0,
+            jitter_ms: 0.0,
+            correction: None,
+            frames_dropped: 0,
+            frames_duplicated: 0,
+            unmeasured: true,
+        };
+        (fit, drift)
+    }
+
+    /// Resample a track recorded at the measured rate back to its nominal
+    /// rate, so each second of output covers one second of capture clock
+    pub fn resample_audio(&self, fit: &ClockFit, audio: &AudioData) -> Result<AudioData> {
+        let ratio = fit.nominal_rate as f64 / fit.measured_rate;
+        if !(0.5..=2.0).contains(&ratio) {
+            bail!("Measured rate {:.1} Hz is implausible for a {} Hz track", fit.measured_rate, fit.nominal_rate);
+        }
+        audio
+            .resample_with_ratio(ratio, fit.nominal_rate)
+            .map_err(|e| anyhow!("Resampling failed: {}", e))
+    }
+
+    /// Pick source frames for a video at `fps` that stays in step with the
+    /// uncorrected audio track; frame timestamps are on the capture clock
+    pub fn retime_video(&self, fit: &ClockFit, frame_timestamps: &[Duration], fps: f64) -> Result<FrameSchedule> {
+        if !(fps.is_finite() && fps > 0.0) {
+            bail!("Can't retime video to {} fps", fps);
+        }
+        let Some(&last) = frame_timestamps.last() else {
+            return Ok(FrameSchedule::default());
+        };
+
+        // Output time runs at the audio's pace: one nominal second of samples
+        // covers nominal / measured seconds of capture clock
+        let pace = fit.nominal_rate as f64 / fit.measured_rate;
+        let mut schedule = FrameSchedule::default();
+        let mut source = 0;
+        for output in 0.. {
+            let target = frame_timestamps[0] + Duration::from_secs_f64(output as f64 / fps * pace);
+            if target > last + Duration::from_secs_f64(0.5 / fps) {
+                break;
+            }
+            while source + 1 < frame_timestamps.len()
+                && frame_timestamps[source + 1].abs_diff(target) <= frame_timestamps[source].abs_diff(target)
+            {
+                source += 1;
+            }
+
+            match schedule.frames.last() {
+                Some(&previous) if previous == source => schedule.duplicated += 1,
+                Some(&previous) => schedule.dropped += source - previous - 1,
+                None => {}
+            }
+            schedule.frames.push(source);
+        }
+        Ok(schedule)
+    }
+
+    /// Analyse every track of a recording and correct the ones that drifted;
+    /// `frame_timestamps` are the video's capture-clock frame times
+    pub fn correct_recording(
+        &self,
+        recording: &AudioRecording,
+        frame_timestamps: &[Duration],
+        fps: f64,
+    ) -> Result<SyncedRecording> {
+        let tracks: Vec<_> = recording
+            .stats
+            .iter()
+            .filter_map(|stats| {
+                let (start, audio) = recording.merged(stats.track)?;
+                Some((stats, start, audio, self.analyse(stats)))
+            })
+            .collect();
+
+        // Video can only follow one clock: the first drifting track's. Every
+        // other track is then moved onto that clock, drifting or not, since
+        // the capture clock no longer matches the picture.
+        let reference = tracks.iter().enumerate().find_map(|(index, (_, _, _, analysis))| match analysis {
+            Some((fit, drift)) if drift.correction == Some(SyncCorrection::RetimeVideo) => Some((index, *fit)),
+            _ => None,
+        });
+        let mut synced = SyncedRecording::default();
+        if let Some((_, fit)) = reference {
+            synced.video = Some(self.retime_video(&fit, frame_timestamps, fps)?);
+        }
+
+        for (index, (stats, start, audio, analysis)) in tracks.into_iter().enumerate() {
+            // An unmeasured track still has to follow retimed video, as if
+            // its clock were exact
+            let (fit, mut drift) = analysis.unwrap_or_else(|| self.assume_nominal(stats, start));
+
+            let audio = match (reference, &synced.video) {
+                (Some((reference, _)), Some(schedule)) if reference == index => {
+                    drift.frames_dropped = schedule.dropped;
+                    drift.frames_duplicated = schedule.duplicated;
+                    audio
+                }
+                (Some((_, reference)), _) => {
+                    drift.correction = Some(SyncCorrection::ResampleAudio);
+                    self.resample_audio(&fit.relative_to(&reference), &audio)?
+                }
+                // Without a reference only resampling can have been chosen
+                (None, _) if drift.correction.is_some() => self.resample_audio(&fit, &audio)?,
+                (None, _) => audio,
+            };
+            synced.tracks.push(SyncedTrack {
+                track: stats.track,
+                start: fit.start,
+                audio,
+            });
+            synced.drift.push(drift);
+        }
+        Ok(synced)
+    }
+}
+
+/// Log a session's drift, warning for tracks that needed correcting
+pub fn log_drift(session: &str, stats: &[DriftStats]) {
+    for track in stats {
+        if track.unmeasured {
+            let corrected = if track.correction.is_some() { ", resampled onto the retimed video" } else { "" };
+            log::warn!(
+                "⏱️ {} {}: only {}s observed, clock assumed nominal{}",
+                session,
+                track.track.label(),
+                track.observed.as_secs(),
+                corrected
+            );
+            continue;
+        }
+        let summary = format!(
+            "{} {}: {:+.1} ppm, {:+.1} ms after {}s (jitter {:.1} ms)",
+            session,
+            track.track.label(),
+            track.ppm,
+            track.final_drift_ms,
+            track.observed.as_secs(),
+            track.jitter_ms
+        );
+        match track.correction {
+            Some(SyncCorrection::ResampleAudio) => log::warn!("⏱️ {}, audio resampled", summary),
+            Some(SyncCorrection::RetimeVideo) => log::warn!(
+                "⏱️ {}, video retimed ({} dropped, {} duplicated)",
+                summary,
+                track.frames_dropped,
+                track.frames_duplicated
+            ),
+            None => log::info!("⏱️ {}", summary),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio_capture::AudioChunk;
+
+    /// Observations from a device running `ppm` off nominal, with callback jitter
+    fn skewed_stats(rate: u32, ppm: f64, start: f64, seconds: usize) -> TrackStats {
+        let actual = rate as f64 * (1.0 + ppm / 1e6);
+        let clock = (1..=seconds)
+            .map(|second| {
+                // Callbacks run up to 10 ms after their last frame arrived
+                let jitter = ((second * 7919) % 11) as f64 / 1000.0;
+                ClockPoint {
+                    capture_time: Duration::from_secs_f64(start + second as f64 + jitter),
+                    frames: (second as f64 * actual) as u64,
+                }
+            })
+            .collect();
+        TrackStats {
+            track: AudioTrack::Microphone,
+            sample_rate: rate,
+            channels: 1,
+            started: Some(Duration::from_secs_f64(start)),
+            frames: 0,
+            dropped_frames: 0,
+            clock,
+        }
+    }
+
+    #[test]
+    fn test_detects_drift_from_synthetic_clock_skew() {
+        let sync = AvSync::new(SyncConfig::default());
+
+        let (fit, stats) = sync.analyse(&skewed_stats(48_000, 120.0, 2.5, 3_600)).unwrap();
+        assert!((stats.ppm - 120.0).abs() < 1.0, "{}", stats.ppm);
+        assert!(fit.start.abs_diff(Duration::from_secs_f64(2.5)) < Duration::from_millis(10));
+        // 120 ppm over an hour is about 432 ms
+        assert!((stats.final_drift_ms - 432.0).abs() < 5.0, "{}", stats.final_drift_ms);
+        assert!(stats.jitter_ms < 11.0);
+        assert_eq!(stats.correction, Some(SyncCorrection::ResampleAudio));
+
+        let (_, stats) = sync.analyse(&skewed_stats(48_000, 5.0, 0.0, 600)).unwrap();
+        assert_eq!(stats.correction, None);
+        assert!(sync.analyse(&skewed_stats(48_000, 120.0, 0.0, 10)).is_none());
+    }
+
+    #[test]
+    fn test_resampling_puts_audio_back_on_the_capture_clock() {
+        let sync = AvSync::new(SyncConfig::default());
+        let fit = ClockFit {
+            nominal_rate: 8_000,
+            measured_rate: 8_080.0,
+            start: Duration::ZERO,
+            jitter: Duration::ZERO,
+        };
+
+        // Ten real seconds from a device 1% fast, with a click at 5 s
+        let mut samples = vec![0.0f32; 80_800];
+        samples[40_400] = 1.0;
+        let corrected = sync.resample_audio(&fit, &AudioData::new(samples, 8_000, 1)).unwrap();
+
+        assert_eq!(corrected.frame_count(), 80_000);
+        let click = corrected
+            .samples
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.abs().total_cmp(&b.1.abs()))
+            .map(|(index, _)| index)
+            .unwrap();
+        assert!(click.abs_diff(40_000) <= 2, "click at {}", click);
+    }
+
+    #[test]
+    fn test_retiming_video_drops_frames_to_follow_slow_audio() {
+        let sync = AvSync::new(SyncConfig {
+            correction: SyncCorrection::RetimeVideo,
+            ..Default::default()
+        });
+        // Device 1% slow: ten seconds of audio take 10.1 s of capture clock
+        let fit = ClockFit {
+            nominal_rate: 48_000,
+            measured_rate: 48_000.0 / 1.01,
+            start: Duration::ZERO,
+            jitter: Duration::ZERO,
+        };
+        let frames: Vec<Duration> = (0..303).map(|i| Duration::from_secs_f64(i as f64 / 30.0)).collect();
+
+        let schedule = sync.retime_video(&fit, &frames, 30.0).unwrap();
+        assert_eq!(schedule.duplicated, 0);
+        assert_eq!(schedule.dropped, 3);
+        assert_eq!(schedule.frames.len(), 300);
+        assert_eq!(*schedule.frames.last().unwrap(), 302);
+
+        let fit = ClockFit {
+            measured_rate: 48_000.0 * 1.01,
+            ..fit
+        };
+        let schedule = sync.retime_video(&fit, &frames, 30.0).unwrap();
+        assert_eq!(schedule.dropped, 0);
+        assert_eq!(schedule.duplicated, 3);
+        assert!(sync.retime_video(&fit, &frames, 0.0).is_err());
+    }
+
+    #[test]
+    fn test_retimed_video_pulls_every_track_onto_the_reference_clock() {
+        let sync = AvSync::new(SyncConfig {
+            correction: SyncCorrection::RetimeVideo,
+            ..Default::default()
+        });
+        // The microphone stays under the threshold, the system track runs 1% slow
+        let microphone = skewed_stats(1_000, 300.0, 0.0, 60);
+        let system = TrackStats {
+            track: AudioTrack::System,
+            ..skewed_stats(1_000, -10_000.0, 0.0, 60)
+        };
+        let chunk = |stats: &TrackStats| AudioChunk {
+            track: stats.track,
+            timestamp: Duration::ZERO,
+            audio: AudioData::new(vec![0.1; stats.clock.last().unwrap().frames as usize], 1_000, 1),
+        };
+        let recording = AudioRecording {
+            chunks: vec![chunk(&microphone), chunk(&system)],
+            stats: vec![microphone, system],
+        };
+        let frames: Vec<Duration> = (0..1_800).map(|i| Duration::from_secs_f64(i as f64 / 30.0)).collect();
+
+        let synced = sync.correct_recording(&recording, &frames, 30.0).unwrap();
+        assert!(synced.video.is_some());
+        assert_eq!(synced.drift[0].correction, Some(SyncCorrection::ResampleAudio));
+        assert_eq!(synced.drift[1].correction, Some(SyncCorrection::RetimeVideo));
+        // Both tracks now cover the same stretch of the retimed video
+        let (retimed, reference) = (synced.tracks[0].audio.frame_count(), synced.tracks[1].audio.frame_count());
+        assert_eq!(reference, 59_400);
+        assert!(retimed.abs_diff(reference) <= 2, "{} vs {}", retimed, reference);
+        assert!(sync.correct_recording(&recording, &frames, 0.0).is_err());
+    }
+
+    #[test]
+    fn test_unmeasured_track_follows_retimed_video() {
+        let sync = AvSync::new(SyncConfig {
+            correction: SyncCorrection::RetimeVideo,
+            ..Default::default()
+        });
+        // The microphone came in late: ten seconds of observations are too few to fit
+        let microphone = skewed_stats(1_000, 0.0, 50.0, 10);
+        let system = TrackStats {
+            track: AudioTrack::System,
+            ..skewed_stats(1_000, -10_000.0, 0.0, 60)
+        };
+        let recording = AudioRecording {
+            chunks: vec![
+                AudioChunk {
+                    track: AudioTrack::Microphone,
+                    timestamp: Duration::from_secs(50),
+                    audio: AudioData::new(vec![0.1; 10_000], 1_000, 1),
+                },
+                AudioChunk {
+                    track: AudioTrack::System,
+                    timestamp: Duration::ZERO,
+                    audio: AudioData::new(vec![0.1; 59_400], 1_000, 1),
+                },
+            ],
+            stats: vec![microphone, system],
+        };
+        let frames: Vec<Duration> = (0..1_800).map(|i| Duration::from_secs_f64(i as f64 / 30.0)).collect();
+
+        let synced = sync.correct_recording(&recording, &frames, 30.0).unwrap();
+        let drift = &synced.drift[0];
+        assert!(drift.unmeasured);
+        assert_eq!(drift.correction, Some(SyncCorrection::ResampleAudio));
+        assert_eq!(synced.tracks[0].start, Duration::from_secs(50));
+        // Ten capture-clock seconds are 9.9 s of the retimed video
+        assert!(synced.tracks[0].audio.frame_count().abs_diff(9_900) <= 2);
+        assert!(!synced.drift[1].unmeasured);
+    }
+}
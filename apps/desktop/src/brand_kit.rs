@@ -0,0 +1,77 @@
+//! Brand kit for DailyDoco Pro exports
+//!
+//! Colors, fonts and logo applied to generated sites and documents.
+//! Defaults to the Aegntic neural palette.
+
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use anyhow::{Context, Result};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BrandKit {
+    pub name: String,
+    /// CSS colors, usually `#rrggbb`
+    pub primary: String,
+    pub accent: String,
+    pub background: String,
+    pub surface: String,
+    pub text: String,
+    pub muted_text: String,
+    pub border: String,
+    pub font_family: String,
+    pub code_font_family: String,
+    pub logo: Option<PathBuf>,
+}
+
+impl Default for BrandKit {
+    fn default() -> Self {
+        Self {
+            name: "DailyDoco Pro".to_string(),
+            primary: "#0c9ae5".to_string(),
+            accent: "#36b4f4".to_string(),
+            background: "#020617".to_string(),
+            surface: "#0f172a".to_string(),
+            text: "#f1f5f9".to_string(),
+            muted_text: "#94a3b8".to_string(),
+            border: "#1e293b".to_string(),
+            font_family: "Inter, system-ui, -apple-system, sans-serif".to_string(),
+            code_font_family: "'JetBrains Mono', 'DejaVu Sans Mono', monospace".to_string(),
+            logo: None,
+        }
+    }
+}
+
+impl BrandKit {
+    /// Load a brand kit from JSON, filling missing fields with defaults.
+    /// A relative logo path is resolved against the file's directory.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).with_context(|| format!("Reading {}", path.display()))?;
+        let mut kit: BrandKit =
+            serde_json::from_str(&text).with_context(|| format!("Parsing brand kit {}", path.display()))?;
+
+        if let (Some(logo), Some(dir)) = (&kit.logo, path.parent()) {
+            if logo.is_relative() {
+                kit.logo = Some(dir.join(logo));
+            }
+        }
+        Ok(kit)
+    }
+
+    /// `:root` block defining the kit as CSS custom properties
+    pub fn css_variables(&self) -> String {
+        format!(
+            ":root{{--brand-primary:{};--brand-accent:{};--brand-bg:{};--brand-surface:{};--brand-text:{};\
+             --brand-muted:{};--brand-border:{};--brand-font:{};--brand-code-font:{}}}",
+            self.primary,
+            self.accent,
+            self.background,
+            self.surface,
+            self.text,
+            self.muted_text,
+            self.border,
+            self.font_family,
+            self.code_font_family
+        )
+    }
+}
@@ -0,0 +1,562 @@
+//! Browser extension bridge for DailyDoco Pro
+//!
+//! The browser extension connects to a WebSocket on the loopback interface
+//! and reports what happens in the browser while a session is recorded.
+//! The bridge is off until the user turns it on. Only extension origins may
+//! connect, and the first message must carry the pairing token shown by the
+//! desktop app:
+//!
+//! - `{"type":"hello","token":"…","browser":"chrome"}`, answered with
+//!   `{"type":"welcome","protocol":1}` or an error before the socket closes
+//! - `{"type":"tab","tab_id":3,"url":"…","title":"…"}` when the active tab
+//!   or its URL changes
+//! - `{"type":"console_error","tab_id":3,"url":"…","message":"…"}`
+//! - `{"type":"network_failure","tab_id":3,"url":"…","status":502}`
+//!
+//! Events are timestamped on the capture clock. Errors and failures become
+//! importance scores, and tab changes a timeline of page categories that
+//! tells what kind of browser window was on screen.
+
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Context, Result};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tungstenite::handshake::server::{Callback, ErrorResponse, Request, Response};
+use tungstenite::http::StatusCode;
+use tungstenite::{Message, WebSocket};
+
+use crate::intelligent_clip_selector::{EventType, ImportanceScore};
+
+pub const PROTOCOL_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BrowserBridgeConfig {
+    pub enabled: bool,
+    /// Loopback port to listen on, 0 picks a free one
+    pub port: u16,
+    /// Pairing token; `None` generates a new one each time the bridge starts,
+    /// so set one to keep the extension paired across launches
+    pub token: Option<String>,
+    /// `Origin` prefixes allowed to connect; web pages are always refused
+    pub allowed_origins: Vec<String>,
+    /// Connections that don't say hello in time are closed
+    pub hello_timeout: Duration,
+}
+
+impl Default for BrowserBridgeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 47_322,
+            token: None,
+            allowed_origins: vec!["chrome-extension://".to_string(), "moz-extension://".to_string()],
+            hello_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Something the extension saw in the browser
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BrowserEventKind {
+    Tab {
+        tab_id: i64,
+        url: String,
+        #[serde(default)]
+        title: String,
+    },
+    ConsoleError {
+        tab_id: i64,
+        url: String,
+        message: String,
+        #[serde(default)]
+        source: Option<String>,
+        #[serde(default)]
+        line: Option<u32>,
+    },
+    NetworkFailure {
+        tab_id: i64,
+        url: String,
+        #[serde(default)]
+        method: Option<String>,
+        /// HTTP status, `None` when the request never got a response
+        #[serde(default)]
+        status: Option<u16>,
+        #[serde(default)]
+        error: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BrowserEvent {
+    /// Capture-clock time the event arrived
+    pub timestamp: Duration,
+    pub kind: BrowserEventKind,
+}
+
+/// Messages the app sends to the extension
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DesktopMessage {
+    Welcome { protocol: u32 },
+    Error { message: String },
+}
+
+/// First message of every connection
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Handshake {
+    Hello {
+        token: String,
+        #[serde(default)]
+        browser: Option<String>,
+    },
+}
+
+/// What kind of page a browser tab shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PageCategory {
+    /// The app under development, served from this machine
+    LocalApp,
+    Documentation,
+    CodeReview,
+    IssueTracker,
+    Search,
+    Other,
+}
+
+/// The browser tab on screen from `timestamp` until the next classification
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WindowClassification {
+    pub timestamp: Duration,
+    pub tab_id: i64,
+    pub url: String,
+    pub title: String,
+    pub category: PageCategory,
+}
+
+/// Classify a page by its URL
+pub fn classify_url(url: &str) -> PageCategory {
+    let rest = url.split_once("://").map(|(_, rest)| rest).unwrap_or(url);
+    let (authority, path) = rest.split_at(rest.find(['/', '?', '#']).unwrap_or(rest.len()));
+    let host = authority.rsplit('@').next().unwrap_or_default().to_lowercase();
+    let host = match host.strip_prefix('[') {
+        Some(ipv6) => ipv6.split(']').next().unwrap_or_default().to_string(),
+        None => host.split(':').next().unwrap_or_default().to_string(),
+    };
+    let path = path.to_lowercase();
+
+    let local = ["localhost", "127.0.0.1", "0.0.0.0", "::1"];
+    if local.contains(&host.as_str()) || host.ends_with(".localhost") || host.ends_with(".local") {
+        return PageCategory::LocalApp;
+    }
+    let code_host = ["github.com", "gitlab.com", "bitbucket.org"].contains(&host.as_str());
+    if code_host && (path.contains("/pull/") || path.contains("/merge_requests/") || path.contains("/commit/")) {
+        return PageCategory::CodeReview;
+    }
+    if (code_host && path.contains("/issues"))
+        || host.ends_with(".atlassian.net")
+        || host == "linear.app"
+    {
+        return PageCategory::IssueTracker;
+    }
+    let search_host = host.starts_with("www.google.") || host == "www.bing.com";
+    if (search_host && path.starts_with("/search")) || host == "duckduckgo.com" {
+        return PageCategory::Search;
+    }
+    let docs_host = ["developer.mozilla.org", "docs.rs", "doc.rust-lang.org", "stackoverflow.com"];
+    if docs_host.contains(&host.as_str())
+        || host.starts_with("docs.")
+        || host.ends_with(".readthedocs.io")
+        || path.starts_with("/docs")
+    {
+        return PageCategory::Documentation;
+    }
+    PageCategory::Other
+}
+
+/// Tab changes in arrival order, each with its page category
+pub fn window_classifications(events: &[BrowserEvent]) -> Vec<WindowClassification> {
+    events
+        .iter()
+        .filter_map(|event| match &event.kind {
+            BrowserEventKind::Tab { tab_id, url, title } => Some(WindowClassification {
+                timestamp: event.timestamp,
+                tab_id: *tab_id,
+                url: url.clone(),
+                title: title.clone(),
+                category: classify_url(url),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+impl BrowserEvent {
+    /// How much this moment matters to the video, `None` for routine browsing
+    pub fn importance(&self) -> Option<ImportanceScore> {
+        let mut context = HashMap::from([("source".to_string(), "browser".to_string())]);
+        let (event_type, score, confidence) = match &self.kind {
+            BrowserEventKind::ConsoleError { url, message, source, line, .. } => {
+                context.insert("url".to_string(), url.clone());
+                context.insert("message".to_string(), message.clone());
+                if let Some(source) = source {
+                    let location = line.map(|line| format!("{}:{}", source, line)).unwrap_or_else(|| source.clone());
+                    context.insert("location".to_string(), location);
+                }
+                (EventType::Debugging, 0.7, 0.8)
+            }
+            BrowserEventKind::NetworkFailure { url, method, status, error, .. } => {
+                context.insert("url".to_string(), url.clone());
+                if let Some(method) = method {
+                    context.insert("method".to_string(), method.clone());
+                }
+                if let Some(status) = status {
+                    context.insert("status".to_string(), status.to_string());
+                }
+                if let Some(error) = error {
+                    context.insert("error".to_string(), error.clone());
+                }
+                // A request that never got an answer is more likely the bug
+                // being chased than a 404 for a favicon
+                let score = match status {
+                    None => 0.8,
+                    Some(status) if *status >= 500 => 0.75,
+                    Some(_) => 0.55,
+                };
+                (EventType::Debugging, score, 0.7)
+            }
+            BrowserEventKind::Tab { url, title, .. } => {
+                context.insert("url".to_string(), url.clone());
+                context.insert("title".to_string(), title.clone());
+                match classify_url(url) {
+                    PageCategory::LocalApp => (EventType::Testing, 0.5, 0.6),
+                    PageCategory::Documentation => (EventType::LearningMoment, 0.4, 0.6),
+                    PageCategory::CodeReview => (EventType::CollaborativeMoment, 0.5, 0.7),
+                    PageCategory::IssueTracker | PageCategory::Search | PageCategory::Other => return None,
+                }
+            }
+        };
+
+        Some(ImportanceScore {
+            timestamp: self.timestamp,
+            score,
+            confidence,
+            event_type,
+            context,
+        })
+    }
+}
+
+/// Importance scores for every event that has one
+pub fn importance_scores(events: &[BrowserEvent]) -> Vec<ImportanceScore> {
+    events.iter().filter_map(BrowserEvent::importance).collect()
+}
+
+/// Listens for the browser extension on a loopback WebSocket
+pub struct BrowserBridge {
+    address: SocketAddr,
+    token: String,
+    accept: Option<JoinHandle<()>>,
+    /// Open connections by id, kept to close them on stop
+    connections: Arc<Mutex<HashMap<u64, TcpStream>>>,
+    events: Arc<Mutex<Vec<BrowserEvent>>>,
+    running: Arc<AtomicBool>,
+}
+
+impl BrowserBridge {
+    /// Start listening; `capture_origin` is the instant the capture clock reads zero
+    pub fn bind(config: &BrowserBridgeConfig, capture_origin: Instant) -> Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, config.port))
+            .with_context(|| format!("Binding browser bridge to port {}", config.port))?;
+        let address = listener.local_addr()?;
+        let token = config.token.clone().unwrap_or_else(generate_token);
+
+        let connections = Arc::new(Mutex::new(HashMap::new()));
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let running = Arc::new(AtomicBool::new(true));
+        let accept = std::thread::spawn({
+            let (connections, events, running) = (Arc::clone(&connections), Arc::clone(&events), Arc::clone(&running));
+            let (config, token) = (config.clone(), token.clone());
+            move || {
+                for (id, stream) in listener.incoming().enumerate() {
+                    if !running.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let Ok(stream) = stream else { continue };
+                    let Ok(handle) = stream.try_clone() else { continue };
+                    connections.lock().insert(id as u64, handle);
+
+                    let (config, token) = (config.clone(), token.clone());
+                    let (connections, events) = (Arc::clone(&connections), Arc::clone(&events));
+                    std::thread::spawn(move || {
+                        if let Err(e) = serve_extension(stream, &config, &token, capture_origin, events) {
+                            log::warn!("Browser bridge connection closed: {}", e);
+                        }
+                        if let Some(handle) = connections.lock().remove(&(id as u64)) {
+                            let _ = handle.shutdown(Shutdown::Both);
+                        }
+                    });
+                }
+            }
+        });
+
+        log::info!("🌐 Browser bridge listening on ws://{}", address);
+        Ok(Self {
+            address,
+            token,
+            accept: Some(accept),
+            connections,
+            events,
+            running,
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Token the extension must present, shown to the user for pairing and
+    /// never logged
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// Events received so far, in arrival order
+    pub fn events(&self) -> Vec<BrowserEvent> {
+        self.events.lock().clone()
+    }
+
+    /// Stop listening and return every event received
+    pub fn stop(mut self) -> Result<Vec<BrowserEvent>> {
+        self.running.store(false, Ordering::SeqCst);
+        for (_, connection) in self.connections.lock().drain() {
+            let _ = connection.shutdown(Shutdown::Both);
+        }
+        // Wake the accept loop so it sees the flag
+        let _ = TcpStream::connect(self.address);
+        if let Some(accept) = self.accept.take() {
+            accept.join().map_err(|_| anyhow!("Browser bridge thread panicked"))?;
+        }
+        Ok(std::mem::take(&mut *self.events.lock()))
+    }
+}
+
+/// Connection thread: upgrade, check the token, then record events until
+/// the extension disconnects
+fn serve_extension(
+    stream: TcpStream,
+    config: &BrowserBridgeConfig,
+    token: &str,
+    capture_origin: Instant,
+    events: Arc<Mutex<Vec<BrowserEvent>>>,
+) -> Result<()> {
+    stream.set_read_timeout(Some(config.hello_timeout))?;
+    let check_origin = OriginCheck(&config.allowed_origins);
+    let mut socket = tungstenite::accept_hdr(stream, check_origin).map_err(|e| anyhow!("Handshake failed: {}", e))?;
+
+    let hello = read_text(&mut socket)?.ok_or_else(|| anyhow!("Closed before hello"))?;
+    match serde_json::from_str::<Handshake>(&hello) {
+        Ok(Handshake::Hello { token: offered, browser }) if tokens_match(&offered, token) => {
+            log::info!("🌐 {} extension connected", browser.as_deref().unwrap_or("Browser"));
+            send(&mut socket, &DesktopMessage::Welcome { protocol: PROTOCOL_VERSION })?;
+        }
+        _ => {
+            send(&mut socket, &DesktopMessage::Error { message: "Invalid pairing token".to_string() })?;
+            // Reading sends the close frame and waits for the extension's reply
+            let _ = socket.close(None);
+            while socket.read().is_ok() {}
+            bail!("Extension sent an invalid hello");
+        }
+    }
+    socket.get_mut().set_read_timeout(None)?;
+
+    while let Some(text) = read_text(&mut socket)? {
+        match serde_json::from_str::<BrowserEventKind>(&text) {
+            Ok(kind) => events.lock().push(BrowserEvent {
+                timestamp: capture_origin.elapsed(),
+                kind,
+            }),
+            Err(e) => send(&mut socket, &DesktopMessage::Error { message: format!("Invalid message: {}", e) })?,
+        }
+    }
+    Ok(())
+}
+
+/// Refuses upgrades from origins that aren't allowed. Web pages can reach
+/// localhost too, so only extensions and non-browser clients (which send no
+/// origin) are let in.
+struct OriginCheck<'a>(&'a [String]);
+
+impl Callback for OriginCheck<'_> {
+    fn on_request(self, request: &Request, response: Response) -> Result<Response, ErrorResponse> {
+        let origin = request.headers().get("origin").and_then(|origin| origin.to_str().ok());
+        match origin {
+            Some(origin) if !self.0.iter().any(|allowed| origin.starts_with(allowed.as_str())) => {
+                let mut refusal = ErrorResponse::new(Some(format!("Origin {} not allowed", origin)));
+                *refusal.status_mut() = StatusCode::FORBIDDEN;
+                Err(refusal)
+            }
+            _ => Ok(response),
+        }
+    }
+}
+
+/// Next text message, `None` once the connection is closed
+fn read_text(socket: &mut WebSocket<TcpStream>) -> Result<Option<String>> {
+    loop {
+        match socket.read() {
+            Ok(Message::Text(text)) => return Ok(Some(text)),
+            // Pings are answered by tungstenite itself, and after a close
+            // frame the next read sends the reply and reports the close
+            Ok(_) => continue,
+            Err(tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed) => return Ok(None),
+            Err(tungstenite::Error::Io(e)) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                bail!("Timed out waiting for the extension")
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+fn send(socket: &mut WebSocket<TcpStream>, message: &DesktopMessage) -> Result<()> {
+    socket.send(Message::Text(serde_json::to_string(message)?))?;
+    Ok(())
+}
+
+/// Compares tokens in time that depends only on their lengths, so a client
+/// can't learn the token a byte at a time from how fast it's refused
+fn tokens_match(offered: &str, token: &str) -> bool {
+    offered.len() == token.len()
+        && offered.bytes().zip(token.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+fn generate_token() -> String {
+    rand::random::<[u8; 16]>().iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tungstenite::client::IntoClientRequest;
+
+    fn connect(bridge: &BrowserBridge, origin: &str) -> Result<WebSocket<TcpStream>> {
+        let mut request = format!("ws://{}/", bridge.local_addr()).into_client_request()?;
+        request.headers_mut().insert("Origin", origin.parse()?);
+        let stream = TcpStream::connect(bridge.local_addr())?;
+        stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+        let (socket, _) = tungstenite::client(request, stream).map_err(|e| anyhow!("{}", e))?;
+        Ok(socket)
+    }
+
+    fn reply(socket: &mut WebSocket<TcpStream>) -> DesktopMessage {
+        serde_json::from_str(&read_text(socket).unwrap().unwrap()).unwrap()
+    }
+
+    fn bridge() -> BrowserBridge {
+        let config = BrowserBridgeConfig {
+            port: 0,
+            token: Some("secret".to_string()),
+            ..Default::default()
+        };
+        BrowserBridge::bind(&config, Instant::now()).unwrap()
+    }
+
+    #[test]
+    fn test_classify_urls() {
+        assert_eq!(classify_url("http://localhost:3000/login"), PageCategory::LocalApp);
+        assert_eq!(classify_url("http://[::1]:8080/"), PageCategory::LocalApp);
+        assert_eq!(classify_url("https://github.com/acme/app/pull/42/files"), PageCategory::CodeReview);
+        assert_eq!(classify_url("https://github.com/acme/app/issues/7"), PageCategory::IssueTracker);
+        assert_eq!(classify_url("https://docs.rs/serde/latest/serde/"), PageCategory::Documentation);
+        assert_eq!(classify_url("https://www.google.com/search?q=rust"), PageCategory::Search);
+        assert_eq!(classify_url("https://news.ycombinator.com/"), PageCategory::Other);
+    }
+
+    #[test]
+    fn test_events_become_scores_and_classifications() {
+        let events = vec![
+            BrowserEvent {
+                timestamp: Duration::from_secs(3),
+                kind: BrowserEventKind::Tab {
+                    tab_id: 1,
+                    url: "http://localhost:5173/".to_string(),
+                    title: "Vite App".to_string(),
+                },
+            },
+            BrowserEvent {
+                timestamp: Duration::from_secs(5),
+                kind: BrowserEventKind::NetworkFailure {
+                    tab_id: 1,
+                    url: "http://localhost:5173/api/upload".to_string(),
+                    method: Some("POST".to_string()),
+                    status: Some(502),
+                    error: None,
+                },
+            },
+            BrowserEvent {
+                timestamp: Duration::from_secs(9),
+                kind: BrowserEventKind::Tab {
+                    tab_id: 2,
+                    url: "https://news.ycombinator.com/".to_string(),
+                    title: "Hacker News".to_string(),
+                },
+            },
+        ];
+
+        let scores = importance_scores(&events);
+        assert_eq!(scores.len(), 2);
+        assert!(matches!(scores[0].event_type, EventType::Testing));
+        assert!(matches!(scores[1].event_type, EventType::Debugging));
+        assert_eq!(scores[1].score, 0.75);
+        assert_eq!(scores[1].context["status"], "502");
+
+        let windows = window_classifications(&events);
+        let categories: Vec<PageCategory> = windows.iter().map(|window| window.category).collect();
+        assert_eq!(categories, [PageCategory::LocalApp, PageCategory::Other]);
+        assert_eq!(windows[1].title, "Hacker News");
+    }
+
+    #[test]
+    fn test_handshake_and_events() {
+        assert!(!BrowserBridgeConfig::default().enabled, "the bridge is opt-in");
+        let bridge = bridge();
+
+        // Web pages are refused at the upgrade
+        assert!(connect(&bridge, "https://evil.example").is_err());
+
+        let mut intruder = connect(&bridge, "chrome-extension://abcdef").unwrap();
+        intruder.send(Message::Text(r#"{"type":"hello","token":"secreT"}"#.to_string())).unwrap();
+        assert!(matches!(reply(&mut intruder), DesktopMessage::Error { .. }));
+        assert!(read_text(&mut intruder).unwrap().is_none());
+        assert!(!tokens_match("secre", "secret"));
+
+        let mut socket = connect(&bridge, "chrome-extension://abcdef").unwrap();
+        socket.send(Message::Text(r#"{"type":"hello","token":"secret","browser":"chrome"}"#.to_string())).unwrap();
+        assert_eq!(reply(&mut socket), DesktopMessage::Welcome { protocol: PROTOCOL_VERSION });
+        let error = r#"{"type":"console_error","tab_id":4,"url":"http://localhost:3000/","message":"TypeError: x"}"#;
+        socket.send(Message::Text(error.to_string())).unwrap();
+        socket.close(None).unwrap();
+        while read_text(&mut socket).is_ok_and(|text| text.is_some()) {}
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while bridge.events().is_empty() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let events = bridge.stop().unwrap();
+        assert_eq!(events.len(), 1);
+        match &events[0].kind {
+            BrowserEventKind::ConsoleError { tab_id, message, .. } => {
+                assert_eq!(*tab_id, 4);
+                assert_eq!(message, "TypeError: x");
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+}
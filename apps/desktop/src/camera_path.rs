@@ -0,0 +1,396 @@
+//! Cursor-following camera path generation for DailyDoco Pro
+//!
+//! Screencasts recorded at 4K are unreadable on small screens. This module
+//! follows the recorded cursor and focused window to produce eased zoom/pan
+//! keyframes, and hands them to the renderer as `PacingAction::Zoom` decisions.
+
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+
+use aegnt_27::utils::math::{generate_cubic_bezier_points, smooth_values, Point2D};
+
+use crate::dynamic_pacing_engine::{PacingAction, PacingDecision, ZoomTarget};
+use crate::input_log::{InputEventKind, InputEventLog, WindowGeometry};
+
+/// Easing applied between camera keyframes
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum CameraEasing {
+    Linear,
+    EaseInOut,
+    EaseOut,
+    /// CSS-style cubic Bezier with control points (x1, y1, x2, y2)
+    CubicBezier(f64, f64, f64, f64),
+}
+
+impl CameraEasing {
+    /// Map linear progress `t` (0.0 to 1.0) onto the eased curve
+    pub fn apply(&self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match *self {
+            CameraEasing::Linear => t,
+            CameraEasing::EaseInOut => Self::bezier(0.42, 0.0, 0.58, 1.0, t),
+            CameraEasing::EaseOut => Self::bezier(0.0, 0.0, 0.58, 1.0, t),
+            CameraEasing::CubicBezier(x1, y1, x2, y2) => Self::bezier(x1, y1, x2, y2, t),
+        }
+    }
+
+    fn bezier(x1: f64, y1: f64, x2: f64, y2: f64, t: f64) -> f64 {
+        let curve = generate_cubic_bezier_points(
+            Point2D::new(0.0, 0.0),
+            Point2D::new(x1, y1),
+            Point2D::new(x2, y2),
+            Point2D::new(1.0, 1.0),
+            64,
+        );
+
+        // Curve is parameterised by its own t, so look up y for the given x
+        let index = curve.partition_point(|p| p.x < t);
+        if index == 0 {
+            return curve[0].y;
+        }
+        if index >= curve.len() {
+            return 1.0;
+        }
+
+        let (a, b) = (curve[index - 1], curve[index]);
+        let span = b.x - a.x;
+        if span <= f64::EPSILON {
+            return b.y;
+        }
+        a.y + (b.y - a.y) * (t - a.x) / span
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CameraPathConfig {
+    /// Captured frame size in pixels
+    pub frame_width: u32,
+    pub frame_height: u32,
+    /// Largest zoom factor the camera may use (1.0 = full frame)
+    pub max_zoom: f64,
+    /// How long the cursor must stay in one area before zooming in
+    pub dwell_time: Duration,
+    /// Radius, as a fraction of the frame width, that counts as one area
+    pub dwell_radius: f64,
+    /// Time taken to ease between two camera states
+    pub transition_duration: Duration,
+    pub easing: CameraEasing,
+    /// Cursor sampling interval used to build the path
+    pub sample_interval: Duration,
+    /// Moving-average window in samples applied to the cursor track
+    pub smoothing_window: usize,
+    /// Pan only when the target moves this far, as a fraction of the view
+    pub pan_deadzone: f64,
+}
+
+impl Default for CameraPathConfig {
+    fn default() -> Self {
+        Self {
+            frame_width: 3840,
+            frame_height: 2160,
+            max_zoom: 2.0,
+            dwell_time: Duration::from_millis(800),
+            dwell_radius: 0.12,
+            transition_duration: Duration::from_millis(600),
+            easing: CameraEasing::EaseInOut,
+            sample_interval: Duration::from_millis(50),
+            smoothing_window: 9,
+            pan_deadzone: 0.2,
+        }
+    }
+}
+
+/// Camera state at a point in time
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CameraKeyframe {
+    pub timestamp: Duration,
+    pub center_x: f64,
+    pub center_y: f64,
+    pub zoom: f64,
+}
+
+/// Generates smooth zoom/pan camera paths from recorded input
+pub struct CameraPathGenerator {
+    config: CameraPathConfig,
+}
+
+impl CameraPathGenerator {
+    pub fn new(config: CameraPathConfig) -> Self {
+        Self { config }
+    }
+
+    /// Build camera keyframes that follow the action in `log`
+    pub fn generate(&self, log: &InputEventLog) -> Vec<CameraKeyframe> {
+        let samples = self.sample_cursor(log);
+        if samples.is_empty() {
+            return Vec::new();
+        }
+
+        let xs: Vec<f64> = samples.iter().map(|s| s.1).collect();
+        let ys: Vec<f64> = samples.iter().map(|s| s.2).collect();
+        let xs = smooth_values(&xs, self.config.smoothing_window);
+        let ys = smooth_values(&ys, self.config.smoothing_window);
+
+        let full_frame = self.full_frame_state();
+        let dwell_samples = (self.config.dwell_time.as_secs_f64()
+            / self.config.sample_interval.as_secs_f64())
+            .ceil()
+            .max(1.0) as usize;
+        let radius = self.config.dwell_radius * self.config.frame_width as f64;
+
+        let mut keyframes = vec![CameraKeyframe {
+            timestamp: Duration::ZERO,
+            ..full_frame
+        }];
+        let mut current = full_frame;
+        let mut anchor = 0;
+
+        for i in 0..samples.len() {
+            let timestamp = samples[i].0;
+
+            // Restart the dwell window when the cursor leaves the anchor area
+            let anchor_point = Point2D::new(xs[anchor], ys[anchor]);
+            if anchor_point.distance_to(&Point2D::new(xs[i], ys[i])) > radius {
+                anchor = i;
+            }
+
+            let dwelling = i + 1 - anchor >= dwell_samples;
+            let target = if dwelling {
+                let count = (i + 1 - anchor) as f64;
+                let mean_x = xs[anchor..=i].iter().sum::<f64>() / count;
+                let mean_y = ys[anchor..=i].iter().sum::<f64>() / count;
+                self.focus_state(mean_x, mean_y, samples[i].3)
+            } else if current.zoom > 1.0 && anchor == i {
+                full_frame
+            } else {
+                current
+            };
+
+            if self.needs_move(&current, &target) {
+                let last = keyframes.last().map(|k| k.timestamp).unwrap_or(Duration::ZERO);
+                // Hold the old state until the move starts, then ease to the new one
+                if timestamp > last {
+                    keyframes.push(CameraKeyframe { timestamp, ..current });
+                }
+                keyframes.push(CameraKeyframe {
+                    timestamp: timestamp + self.config.transition_duration,
+                    ..target
+                });
+                current = target;
+            }
+        }
+
+        keyframes.dedup_by(|b, a| a.timestamp == b.timestamp);
+        keyframes
+    }
+
+    /// Interpolate the camera at `timestamp` using the configured easing
+    pub fn sample(&self, keyframes: &[CameraKeyframe], timestamp: Duration) -> CameraKeyframe {
+        let Some(first) = keyframes.first() else {
+            return CameraKeyframe { timestamp, ..self.full_frame_state() };
+        };
+
+        let index = keyframes.partition_point(|k| k.timestamp <= timestamp);
+        if index == 0 {
+            return CameraKeyframe { timestamp, ..*first };
+        }
+        if index >= keyframes.len() {
+            return CameraKeyframe { timestamp, ..keyframes[keyframes.len() - 1] };
+        }
+
+        let (a, b) = (keyframes[index - 1], keyframes[index]);
+        let span = b.timestamp.saturating_sub(a.timestamp).as_secs_f64();
+        let t = if span > 0.0 {
+            timestamp.saturating_sub(a.timestamp).as_secs_f64() / span
+        } else {
+            1.0
+        };
+        let eased = self.config.easing.apply(t);
+
+        CameraKeyframe {
+            timestamp,
+            center_x: a.center_x + (b.center_x - a.center_x) * eased,
+            center_y: a.center_y + (b.center_y - a.center_y) * eased,
+            zoom: a.zoom + (b.zoom - a.zoom) * eased,
+        }
+    }
+
+    /// Express each camera move as a zoom decision for the renderer
+    pub fn to_pacing_decisions(&self, keyframes: &[CameraKeyframe]) -> Vec<PacingDecision> {
+        keyframes
+            .windows(2)
+            .filter(|pair| pair[0].center_x != pair[1].center_x
+                || pair[0].center_y != pair[1].center_y
+                || pair[0].zoom != pair[1].zoom)
+            .map(|pair| {
+                let (from, to) = (pair[0], pair[1]);
+                let intensity = if self.config.max_zoom > 1.0 {
+                    ((to.zoom - 1.0) / (self.config.max_zoom - 1.0)).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                let reasoning = if to.zoom > from.zoom {
+                    format!("Zooming to {:.1}x where the cursor settled", to.zoom)
+                } else if to.zoom < from.zoom {
+                    "Zooming out as the cursor left the focus area".to_string()
+                } else {
+                    "Panning to follow the cursor".to_string()
+                };
+
+                PacingDecision {
+                    timestamp: from.timestamp,
+                    action: PacingAction::Zoom {
+                        target: ZoomTarget::Region {
+                            center_x: to.center_x,
+                            center_y: to.center_y,
+                            zoom: to.zoom,
+                        },
+                        duration: to.timestamp.saturating_sub(from.timestamp),
+                    },
+                    intensity,
+                    reasoning,
+                    confidence: 0.8,
+                }
+            })
+            .collect()
+    }
+
+    /// Resample the cursor at a fixed interval: (time, x, y, focused window)
+    fn sample_cursor(&self, log: &InputEventLog) -> Vec<(Duration, f64, f64, Option<WindowGeometry>)> {
+        let interval = self.config.sample_interval.max(Duration::from_millis(1));
+        let end = log.duration();
+        let mut samples = Vec::new();
+
+        let mut position: Option<(f64, f64)> = None;
+        let mut window: Option<WindowGeometry> = None;
+        let mut events = log.events.iter().peekable();
+        let mut timestamp = Duration::ZERO;
+
+        while timestamp <= end {
+            while let Some(event) = events.next_if(|e| e.timestamp <= timestamp) {
+                if let Some((x, y)) = event.position() {
+                    position = Some((x as f64, y as f64));
+                }
+                if let InputEventKind::WindowFocus { geometry, .. } = &event.kind {
+                    window = Some(*geometry);
+                }
+            }
+
+            if let Some((x, y)) = position {
+                samples.push((timestamp, x, y, window));
+            }
+            timestamp += interval;
+        }
+
+        samples
+    }
+
+    /// Camera state that frames the area around (x, y)
+    fn focus_state(&self, x: f64, y: f64, window: Option<WindowGeometry>) -> CameraKeyframe {
+        let (frame_w, frame_h) = (self.config.frame_width as f64, self.config.frame_height as f64);
+
+        // Zoom to fit the focused window when the cursor is inside it
+        let zoom = match window {
+            Some(geometry) if geometry.contains(x, y) && geometry.width > 0 && geometry.height > 0 => {
+                (frame_w / geometry.width as f64).min(frame_h / geometry.height as f64)
+            }
+            _ => self.config.max_zoom,
+        }
+        .clamp(1.0, self.config.max_zoom.max(1.0));
+
+        let (center_x, center_y) = self.clamp_center(x, y, zoom);
+        CameraKeyframe {
+            timestamp: Duration::ZERO,
+            center_x,
+            center_y,
+            zoom,
+        }
+    }
+
+    /// Keep the zoomed viewport inside the captured frame
+    fn clamp_center(&self, x: f64, y: f64, zoom: f64) -> (f64, f64) {
+        let (frame_w, frame_h) = (self.config.frame_width as f64, self.config.frame_height as f64);
+        let half_w = frame_w / zoom / 2.0;
+        let half_h = frame_h / zoom / 2.0;
+        (x.clamp(half_w, frame_w - half_w), y.clamp(half_h, frame_h - half_h))
+    }
+
+    fn needs_move(&self, current: &CameraKeyframe, target: &CameraKeyframe) -> bool {
+        if (current.zoom - target.zoom).abs() > 0.05 {
+            return true;
+        }
+
+        let view_width = self.config.frame_width as f64 / current.zoom;
+        let distance = Point2D::new(current.center_x, current.center_y)
+            .distance_to(&Point2D::new(target.center_x, target.center_y));
+        distance > view_width * self.config.pan_deadzone
+    }
+
+    fn full_frame_state(&self) -> CameraKeyframe {
+        CameraKeyframe {
+            timestamp: Duration::ZERO,
+            center_x: self.config.frame_width as f64 / 2.0,
+            center_y: self.config.frame_height as f64 / 2.0,
+            zoom: 1.0,
+        }
+    }
+}
+
+impl Default for CameraPathGenerator {
+    fn default() -> Self {
+        Self::new(CameraPathConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input_log::InputEvent;
+
+    fn still_cursor_log(x: i32, y: i32, seconds: u64) -> InputEventLog {
+        let mut log = InputEventLog::new();
+        for ms in (0..seconds * 1000).step_by(20) {
+            log.record(InputEvent::new(
+                Duration::from_millis(ms),
+                InputEventKind::PointerMove { x, y },
+            ));
+        }
+        log
+    }
+
+    #[test]
+    fn test_dwell_zooms_in() {
+        let generator = CameraPathGenerator::default();
+        let keyframes = generator.generate(&still_cursor_log(800, 600, 3));
+
+        let max_zoom = keyframes.iter().map(|k| k.zoom).fold(0.0, f64::max);
+        assert!((max_zoom - 2.0).abs() < 1e-9);
+
+        // The viewport must stay inside the frame
+        let zoomed = keyframes.iter().find(|k| k.zoom > 1.0).unwrap();
+        assert!(zoomed.center_x >= 3840.0 / 4.0);
+        assert!(zoomed.center_y >= 2160.0 / 4.0);
+    }
+
+    #[test]
+    fn test_easing_endpoints() {
+        for easing in [CameraEasing::Linear, CameraEasing::EaseInOut, CameraEasing::EaseOut] {
+            assert!(easing.apply(0.0).abs() < 1e-6);
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-6);
+        }
+        assert!(CameraEasing::EaseInOut.apply(0.25) < 0.25);
+    }
+
+    #[test]
+    fn test_pacing_decisions_use_region_zoom() {
+        let generator = CameraPathGenerator::default();
+        let keyframes = generator.generate(&still_cursor_log(2000, 1000, 3));
+        let decisions = generator.to_pacing_decisions(&keyframes);
+
+        assert!(!decisions.is_empty());
+        assert!(decisions.iter().all(|d| matches!(
+            d.action,
+            PacingAction::Zoom { target: ZoomTarget::Region { .. }, .. }
+        )));
+    }
+}
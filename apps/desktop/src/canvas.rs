@@ -0,0 +1,369 @@
+//! Software drawing primitives for DailyDoco Pro overlays
+//!
+//! Anti-aliased shapes blended directly into RGB `VideoFrame` data. Kept
+//! deliberately small: overlays draw a handful of shapes per frame, so a
+//! coverage-per-pixel approach is fast enough and has no GPU dependency.
+
+use ab_glyph::{point, Font, FontRef, PxScale, ScaleFont};
+use anyhow::{bail, Result};
+
+use aegnt_27::visual::{ColorSpace, VideoFrame};
+
+/// DejaVu Sans Mono, bundled so rendering never depends on system fonts
+static MONOSPACE_FONT: &[u8] = include_bytes!("../assets/fonts/DejaVuSansMono.ttf");
+
+/// The bundled monospace font
+pub fn monospace_font() -> FontRef<'static> {
+    FontRef::try_from_slice(MONOSPACE_FONT).expect("bundled font is a valid TrueType file")
+}
+
+/// Horizontal advance of `text` at `size` pixels
+pub fn text_width(font: &impl Font, text: &str, size: f32) -> f32 {
+    let scaled = font.as_scaled(PxScale::from(size));
+    let mut width = 0.0;
+    let mut previous = None;
+
+    for ch in text.chars() {
+        let glyph_id = scaled.glyph_id(ch);
+        if let Some(previous) = previous {
+            width += scaled.kern(previous, glyph_id);
+        }
+        width += scaled.h_advance(glyph_id);
+        previous = Some(glyph_id);
+    }
+
+    width
+}
+
+/// Ascent and descent (negative) of the font at `size` pixels
+pub fn line_metrics(font: &impl Font, size: f32) -> (f32, f32) {
+    let scaled = font.as_scaled(PxScale::from(size));
+    (scaled.ascent(), scaled.descent())
+}
+
+/// Straight (non-premultiplied) RGBA color
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Rgba {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Rgba {
+    pub const WHITE: Rgba = Rgba::new(255, 255, 255, 255);
+    pub const BLACK: Rgba = Rgba::new(0, 0, 0, 255);
+
+    pub const fn new(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// Same color with its alpha scaled by `factor` (0.0 to 1.0)
+    pub fn faded(&self, factor: f32) -> Self {
+        Self {
+            a: (self.a as f32 * factor.clamp(0.0, 1.0)).round() as u8,
+            ..*self
+        }
+    }
+}
+
+/// Mutable drawing surface over an RGB frame
+pub struct Canvas<'a> {
+    data: &'a mut [u8],
+    width: u32,
+    height: u32,
+}
+
+impl<'a> Canvas<'a> {
+    pub fn new(frame: &'a mut VideoFrame) -> Result<Self> {
+        if frame.color_space != ColorSpace::RGB {
+            bail!("Canvas requires an RGB frame, got {:?}", frame.color_space);
+        }
+        if frame.data.len() < (frame.width * frame.height * 3) as usize {
+            bail!("Frame data is smaller than {}x{} RGB", frame.width, frame.height);
+        }
+
+        Ok(Self {
+            data: &mut frame.data,
+            width: frame.width,
+            height: frame.height,
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Blend `color` into one pixel with extra `coverage` (0.0 to 1.0)
+    pub fn blend_pixel(&mut self, x: i32, y: i32, color: Rgba, coverage: f32) {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return;
+        }
+
+        let alpha = coverage.clamp(0.0, 1.0) * color.a as f32 / 255.0;
+        if alpha <= 0.0 {
+            return;
+        }
+
+        let index = (y as usize * self.width as usize + x as usize) * 3;
+        for (offset, channel) in [color.r, color.g, color.b].into_iter().enumerate() {
+            let existing = self.data[index + offset] as f32;
+            self.data[index + offset] = (existing + (channel as f32 - existing) * alpha).round() as u8;
+        }
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 3] {
+        let index = (y as usize * self.width as usize + x as usize) * 3;
+        [self.data[index], self.data[index + 1], self.data[index + 2]]
+    }
+
+    pub fn fill_circle(&mut self, cx: f32, cy: f32, radius: f32, color: Rgba) {
+        self.shade_bounds(cx - radius, cy - radius, cx + radius, cy + radius, color, |x, y| {
+            radius - ((x - cx).powi(2) + (y - cy).powi(2)).sqrt()
+        });
+    }
+
+    /// Circle outline of the given stroke `thickness`
+    pub fn ring(&mut self, cx: f32, cy: f32, radius: f32, thickness: f32, color: Rgba) {
+        let half = thickness / 2.0;
+        let outer = radius + half;
+        self.shade_bounds(cx - outer, cy - outer, cx + outer, cy + outer, color, |x, y| {
+            half - (((x - cx).powi(2) + (y - cy).powi(2)).sqrt() - radius).abs()
+        });
+    }
+
+    /// Line segment with round caps
+    pub fn line(&mut self, x0: f32, y0: f32, x1: f32, y1: f32, thickness: f32, color: Rgba) {
+        let half = thickness / 2.0;
+        let (dx, dy) = (x1 - x0, y1 - y0);
+        let length_sq = dx * dx + dy * dy;
+
+        self.shade_bounds(
+            x0.min(x1) - half,
+            y0.min(y1) - half,
+            x0.max(x1) + half,
+            y0.max(y1) + half,
+            color,
+            |x, y| {
+                let t = if length_sq > 0.0 {
+                    (((x - x0) * dx + (y - y0) * dy) / length_sq).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                let (px, py) = (x0 + t * dx, y0 + t * dy);
+                half - ((x - px).powi(2) + (y - py).powi(2)).sqrt()
+            },
+        );
+    }
+
+    pub fn fill_rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: Rgba) {
+        self.fill_rounded_rect(x, y, width, height, 0.0, color);
+    }
+
+    pub fn fill_rounded_rect(&mut self, x: f32, y: f32, width: f32, height: f32, radius: f32, color: Rgba) {
+        let radius = radius.min(width / 2.0).min(height / 2.0).max(0.0);
+        let (cx, cy) = (x + width / 2.0, y + height / 2.0);
+        let (half_w, half_h) = (width / 2.0 - radius, height / 2.0 - radius);
+
+        self.shade_bounds(x, y, x + width, y + height, color, |px, py| {
+            // Signed distance to a rounded box, negated so inside is positive
+            let qx = (px - cx).abs() - half_w;
+            let qy = (py - cy).abs() - half_h;
+            let outside = (qx.max(0.0).powi(2) + qy.max(0.0).powi(2)).sqrt();
+            let inside = qx.max(qy).min(0.0);
+            radius - (outside + inside)
+        });
+    }
+
+    /// Filled polygon using the even-odd rule
+    pub fn fill_polygon(&mut self, points: &[(f32, f32)], color: Rgba) {
+        if points.len() < 3 {
+            return;
+        }
+
+        let min_y = points.iter().map(|p| p.1).fold(f32::INFINITY, f32::min).floor().max(0.0) as i32;
+        let max_y = points
+            .iter()
+            .map(|p| p.1)
+            .fold(f32::NEG_INFINITY, f32::max)
+            .ceil()
+            .min(self.height as f32) as i32;
+
+        // Four sub-scanlines per row give cheap vertical anti-aliasing
+        const SUBSAMPLES: usize = 4;
+        let mut coverage = vec![0.0f32; self.width as usize];
+
+        for y in min_y..max_y {
+            coverage.iter_mut().for_each(|c| *c = 0.0);
+            let mut touched = (usize::MAX, 0usize);
+
+            for sub in 0..SUBSAMPLES {
+                let sample_y = y as f32 + (sub as f32 + 0.5) / SUBSAMPLES as f32;
+                let mut crossings: Vec<f32> = Vec::new();
+
+                for i in 0..points.len() {
+                    let (ax, ay) = points[i];
+                    let (bx, by) = points[(i + 1) % points.len()];
+                    if (ay <= sample_y && by > sample_y) || (by <= sample_y && ay > sample_y) {
+                        crossings.push(ax + (sample_y - ay) / (by - ay) * (bx - ax));
+                    }
+                }
+                crossings.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+                for span in crossings.chunks(2) {
+                    if span.len() < 2 {
+                        continue;
+                    }
+                    let start = span[0].max(0.0);
+                    let end = span[1].min(self.width as f32);
+                    let mut x = start.floor() as usize;
+                    while (x as f32) < end {
+                        let left = start.max(x as f32);
+                        let right = end.min(x as f32 + 1.0);
+                        coverage[x] += (right - left).max(0.0) / SUBSAMPLES as f32;
+                        touched = (touched.0.min(x), touched.1.max(x));
+                        x += 1;
+                    }
+                }
+            }
+
+            if touched.0 == usize::MAX {
+                continue;
+            }
+            for (x, &amount) in coverage.iter().enumerate().take(touched.1 + 1).skip(touched.0) {
+                if amount > 0.0 {
+                    self.blend_pixel(x as i32, y, color, amount);
+                }
+            }
+        }
+    }
+
+    /// Draw a single line of text starting at `x` on `baseline`, returning its width
+    pub fn draw_text(&mut self, font: &impl Font, text: &str, x: f32, baseline: f32, size: f32, color: Rgba) -> f32 {
+        let scaled = font.as_scaled(PxScale::from(size));
+        let mut caret = x;
+        let mut previous = None;
+
+        for ch in text.chars() {
+            let glyph_id = scaled.glyph_id(ch);
+            if let Some(previous) = previous {
+                caret += scaled.kern(previous, glyph_id);
+            }
+
+            let glyph = glyph_id.with_scale_and_position(size, point(caret, baseline));
+            if let Some(outlined) = font.outline_glyph(glyph) {
+                let bounds = outlined.px_bounds();
+                outlined.draw(|gx, gy, coverage| {
+                    self.blend_pixel(bounds.min.x as i32 + gx as i32, bounds.min.y as i32 + gy as i32, color, coverage);
+                });
+            }
+
+            caret += scaled.h_advance(glyph_id);
+            previous = Some(glyph_id);
+        }
+
+        caret - x
+    }
+
+    /// Copy an RGB frame onto the canvas with its top-left corner at `(x, y)`
+    pub fn blit(&mut self, frame: &VideoFrame, x: i32, y: i32) -> Result<()> {
+        let rgb = frame.to_rgb()?;
+        for row in 0..rgb.height as i32 {
+            let target_y = y + row;
+            if target_y < 0 || target_y >= self.height as i32 {
+                continue;
+            }
+            for column in 0..rgb.width as i32 {
+                let target_x = x + column;
+                if target_x < 0 || target_x >= self.width as i32 {
+                    continue;
+                }
+                let source = ((row as usize) * rgb.width as usize + column as usize) * 3;
+                let target = (target_y as usize * self.width as usize + target_x as usize) * 3;
+                self.data[target..target + 3].copy_from_slice(&rgb.data[source..source + 3]);
+            }
+        }
+        Ok(())
+    }
+
+    /// Shade every pixel in a box by a signed distance (positive = inside)
+    fn shade_bounds<F>(&mut self, left: f32, top: f32, right: f32, bottom: f32, color: Rgba, distance: F)
+    where
+        F: Fn(f32, f32) -> f32,
+    {
+        let x_start = (left - 1.0).floor().max(0.0) as i32;
+        let y_start = (top - 1.0).floor().max(0.0) as i32;
+        let x_end = (right + 1.0).ceil().min(self.width as f32) as i32;
+        let y_end = (bottom + 1.0).ceil().min(self.height as f32) as i32;
+
+        for y in y_start..y_end {
+            for x in x_start..x_end {
+                let coverage = (distance(x as f32 + 0.5, y as f32 + 0.5) + 0.5).clamp(0.0, 1.0);
+                if coverage > 0.0 {
+                    self.blend_pixel(x, y, color, coverage);
+                }
+            }
+        }
+    }
+}
+
+/// Break `text` into lines no wider than `max_width`, splitting on spaces
+/// and inside words that are too long on their own
+pub fn wrap_text(font: &impl Font, text: &str, size: f32, max_width: f32) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+
+    for word in text.split_whitespace() {
+        let candidate = if line.is_empty() { word.to_string() } else { format!("{} {}", line, word) };
+        if text_width(font, &candidate, size) <= max_width {
+            line = candidate;
+            continue;
+        }
+        if !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+        }
+        for ch in word.chars() {
+            line.push(ch);
+            if text_width(font, &line, size) > max_width && line.chars().count() > 1 {
+                line.pop();
+                lines.push(std::mem::replace(&mut line, ch.to_string()));
+            }
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// Resize a frame to exactly `width` x `height` as RGB
+pub fn scale_frame(frame: &VideoFrame, width: u32, height: u32) -> Result<VideoFrame> {
+    let rgb = frame.to_rgb()?;
+    let image = image::RgbImage::from_raw(rgb.width, rgb.height, rgb.data)
+        .ok_or_else(|| anyhow::anyhow!("Frame data does not match {}x{}", rgb.width, rgb.height))?;
+    let scaled = image::imageops::resize(&image, width, height, image::imageops::FilterType::Triangle);
+    Ok(VideoFrame::new(scaled.into_raw(), width, height, ColorSpace::RGB, frame.timestamp))
+}
+
+/// Blank RGB frame filled with one color
+pub fn solid_frame(width: u32, height: u32, color: Rgba, timestamp: std::time::Duration) -> VideoFrame {
+    let mut data = Vec::with_capacity((width * height * 3) as usize);
+    for _ in 0..width * height {
+        data.extend_from_slice(&[color.r, color.g, color.b]);
+    }
+    VideoFrame::new(data, width, height, ColorSpace::RGB, timestamp)
+}
+
+/// Write a frame to disk as PNG, converting to RGB first if needed
+pub fn save_png(frame: &VideoFrame, path: &std::path::Path) -> Result<()> {
+    let rgb = frame.to_rgb()?;
+    let buffer = image::RgbImage::from_raw(rgb.width, rgb.height, rgb.data)
+        .ok_or_else(|| anyhow::anyhow!("Frame data does not match {}x{}", rgb.width, rgb.height))?;
+    buffer.save(path)?;
+    Ok(())
+}
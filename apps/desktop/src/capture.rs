@@ -2,28 +2,257 @@
 
 use aegnt_27::prelude::*;
 use std::error::Error;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use parking_lot::Mutex;
+
+use crate::audio_capture::{AudioCaptureConfig, AudioRecorder, AudioRecording};
+use crate::av_sync::{log_drift, AvSync, SyncConfig, SyncedRecording};
+use crate::browser_bridge::{BrowserBridge, BrowserBridgeConfig};
+use crate::config::CaptureConfig;
+use crate::editor_bridge::{EditorBridge, EditorBridgeConfig};
+use crate::keyboard_capture::{KeyboardCaptureConfig, KeyboardRecorder};
+use crate::session::SessionRecord;
+use crate::system_tray::{SystemTrayManager, TrayAudioMonitor};
+use crate::terminal_recording::{TerminalCaptureConfig, TerminalRecorder};
 
 pub struct CaptureEngine {
     // aegnt: AegntEngine,
+    config: CaptureConfig,
+    /// Shows the live audio levels and microphone alerts while capturing
+    tray: Option<Arc<SystemTrayManager>>,
+    active: Mutex<Option<ActiveCapture>>,
+}
+
+/// What records alongside the screen while a capture runs
+struct ActiveCapture {
+    /// Start of the capture clock every recorder timestamps against
+    origin: Instant,
+    audio: Option<AudioRecorder>,
+    /// Task feeding the tray from the audio meter
+    audio_monitor: Option<(TrayAudioMonitor, tokio::task::JoinHandle<()>)>,
+    /// Capture-clock time of every video frame, for retiming it to the audio
+    frame_timestamps: Vec<Duration>,
 }
 
 impl CaptureEngine {
-    pub async fn new() -> Result<Self, Box<dyn Error>> {
+    pub async fn new(config: CaptureConfig) -> Result<Self, Box<dyn Error>> {
         // let config = AegntConfig::default();
         // let aegnt = AegntEngine::with_config(config).await?;
         
-        Ok(Self { })
+        Ok(Self {
+            config,
+            tray: None,
+            active: Mutex::new(None),
+        })
+    }
+    
+    /// Report audio levels and alerts through the system tray
+    pub fn with_tray(mut self, tray: Arc<SystemTrayManager>) -> Self {
+        self.tray = Some(tray);
+        self
     }
     
     pub async fn start_capture(&self) -> Result<(), Box<dyn Error>> {
+        let mut active = self.active.lock();
+        if active.is_some() {
+            return Err("Capture is already running".into());
+        }
+        
         log::info!("🎥 Starting screen capture...");
+        let origin = Instant::now();
         // Implementation for screen capture
+        let audio = if self.config.audio_enabled {
+            self.start_audio_capture(&self.config.audio, origin)?
+        } else {
+            None
+        };
+        // A muted or clipping microphone is worth interrupting the user for
+        let audio_monitor = match (&audio, &self.tray) {
+            (Some(recorder), Some(tray)) => {
+                let monitor = TrayAudioMonitor::new(Arc::clone(tray), recorder.meter());
+                let task = monitor.start_monitoring();
+                Some((monitor, task))
+            }
+            _ => None,
+        };
+        
+        *active = Some(ActiveCapture {
+            origin,
+            audio,
+            audio_monitor,
+            frame_timestamps: Vec::new(),
+        });
         Ok(())
     }
     
-    pub async fn stop_capture(&self) -> Result<(), Box<dyn Error>> {
-        log::info!("⏹️ Stopping screen capture...");
+    /// Note when a video frame was captured, on the capture clock
+    pub fn record_frame(&self, timestamp: Duration) {
+        if let Some(active) = self.active.lock().as_mut() {
+            active.frame_timestamps.push(timestamp);
+        }
+    }
+    
+    /// Stop capturing and return the audio with its drift corrected; the
+    /// drift of each track is saved into `record`
+    pub async fn stop_capture(&self, record: &mut SessionRecord) -> Result<Option<SyncedRecording>, Box<dyn Error>> {
+        let Some(active) = self.active.lock().take() else {
+            return Ok(None);
+        };
+        
+        log::info!("⏹️ Stopping screen capture after {:.1}s...", active.origin.elapsed().as_secs_f64());
         // Implementation for stop capture
-        Ok(())
+        let Some(audio) = active.audio else {
+            return Ok(None);
+        };
+        if let Some((_, task)) = &active.audio_monitor {
+            task.abort();
+        }
+        let recording = audio.stop()?;
+        if let Some((monitor, _)) = active.audio_monitor {
+            // Alerts raised while the last samples drained
+            monitor.update().await;
+        }
+        if let Some(tray) = &self.tray {
+            tray.update_audio_levels(Vec::new());
+        }
+        let sync = &self.config.audio.sync;
+        let synced = sync_audio(&recording, sync, &active.frame_timestamps, self.config.fps, record)?;
+        Ok(Some(synced))
+    }
+    
+    /// Start an asciicast terminal recording alongside the screen capture
+    pub fn start_terminal_recording(
+        &self,
+        config: &TerminalCaptureConfig,
+        capture_origin: Instant,
+    ) -> Result<Option<TerminalRecorder>, Box<dyn Error>> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        
+        log::info!("🖥️ Recording terminal session ({}x{})", config.cols, config.rows);
+        Ok(Some(TerminalRecorder::spawn(config, capture_origin)?))
     }
-}
\ No newline at end of file
+    
+    /// Listen for editor plugins so markers can capture the exact buffer
+    pub fn start_editor_bridge(
+        &self,
+        config: &EditorBridgeConfig,
+        capture_origin: Instant,
+    ) -> Result<Option<EditorBridge>, Box<dyn Error>> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        
+        Ok(Some(EditorBridge::bind(config, capture_origin)?))
+    }
+    
+    /// Listen for the browser extension's tab, console and network events
+    pub fn start_browser_bridge(
+        &self,
+        config: &BrowserBridgeConfig,
+        capture_origin: Instant,
+    ) -> Result<Option<BrowserBridge>, Box<dyn Error>> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        
+        // The pairing token is shown by the app from `BrowserBridge::token`, not logged
+        Ok(Some(BrowserBridge::bind(config, capture_origin)?))
+    }
+    
+    /// Record key presses for the keystroke overlay; keys typed into
+    /// redacted windows are dropped before they are stored
+    pub fn start_keyboard_capture(
+        &self,
+        config: &KeyboardCaptureConfig,
+        capture_origin: Instant,
+    ) -> Result<Option<KeyboardRecorder>, Box<dyn Error>> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        
+        Ok(Some(KeyboardRecorder::start(config, capture_origin)?))
+    }
+    
+    /// Record the microphone and system audio tracks on the capture clock
+    pub fn start_audio_capture(
+        &self,
+        config: &AudioCaptureConfig,
+        capture_origin: Instant,
+    ) -> Result<Option<AudioRecorder>, Box<dyn Error>> {
+        if !config.microphone && !config.system_audio {
+            return Ok(None);
+        }
+        
+        Ok(Some(AudioRecorder::start(config, capture_origin)?))
+    }
+}
+
+/// Correct the recording's drift against the video and keep the per-track
+/// drift in the session record
+fn sync_audio(
+    recording: &AudioRecording,
+    config: &SyncConfig,
+    frame_timestamps: &[Duration],
+    fps: u32,
+    record: &mut SessionRecord,
+) -> anyhow::Result<SyncedRecording> {
+    let synced = AvSync::new(config.clone()).correct_recording(recording, frame_timestamps, fps as f64)?;
+    log_drift(&record.title, &synced.drift);
+    record.av_sync = synced.drift.clone();
+    Ok(synced)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aegnt_27::audio::AudioData;
+    use chrono::Utc;
+    use shared_types::CaptureSession;
+    use uuid::Uuid;
+
+    use crate::audio_capture::{AudioChunk, AudioTrack, TrackStats};
+    use crate::av_sync::{ClockPoint, SyncCorrection};
+
+    #[test]
+    fn test_stopped_audio_saves_its_drift_into_the_session() {
+        // A microphone running 1% fast for a minute
+        let clock = (1..=60)
+            .map(|second| ClockPoint {
+                capture_time: Duration::from_secs(second),
+                frames: second * 1_010,
+            })
+            .collect();
+        let recording = AudioRecording {
+            chunks: vec![AudioChunk {
+                track: AudioTrack::Microphone,
+                timestamp: Duration::ZERO,
+                audio: AudioData::new(vec![0.1; 60_600], 1_000, 1),
+            }],
+            stats: vec![TrackStats {
+                track: AudioTrack::Microphone,
+                sample_rate: 1_000,
+                channels: 1,
+                started: Some(Duration::ZERO),
+                frames: 60_600,
+                dropped_frames: 0,
+                clock,
+            }],
+        };
+        let session = CaptureSession {
+            id: Uuid::new_v4(),
+            project_id: Uuid::new_v4(),
+            started_at: Utc::now(),
+            duration: None,
+        };
+        let mut record = SessionRecord::new(session, "Fix login", "video.mp4");
+
+        let synced = sync_audio(&recording, &SyncConfig::default(), &[], 30, &mut record).unwrap();
+        assert_eq!(record.av_sync.len(), 1);
+        assert_eq!(record.av_sync[0].correction, Some(SyncCorrection::ResampleAudio));
+        assert!((record.av_sync[0].ppm - 10_000.0).abs() < 1.0);
+        assert!(synced.tracks[0].audio.frame_count().abs_diff(60_000) <= 2);
+    }
+}
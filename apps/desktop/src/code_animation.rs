@@ -0,0 +1,533 @@
+//! Typed-code animation rendering for DailyDoco Pro
+//!
+//! Renders a source file being "typed out" with syntax highlighting, paced by
+//! aegnt-27's humanized keystroke timings. A diff can be animated instead:
+//! its context appears at once and only the added lines are typed. Frames
+//! are rendered on demand so long snippets never have to sit in memory as
+//! raw video.
+
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use anyhow::{bail, Result};
+use ab_glyph::FontRef;
+
+use aegnt_27::typing::{HumanizedKeystroke, TypingConfig, TypingHumanizer};
+use aegnt_27::visual::VideoFrame;
+
+use crate::canvas::{line_metrics, monospace_font, solid_frame, text_width, Canvas};
+use crate::diff_walkthrough::{DiffLineKind, FileDiff};
+use crate::syntax_highlight::{CodeTheme, Language, SyntaxHighlighter, TokenKind};
+use crate::timeline::{Timeline, TimelineClip};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeAnimationConfig {
+    pub width: u32,
+    pub height: u32,
+    pub frame_rate: f64,
+
+    pub theme: CodeTheme,
+    pub font_size: f32,
+    /// Line spacing as a multiple of the font size
+    pub line_height: f32,
+    pub padding: f32,
+    pub show_line_numbers: bool,
+    pub tab_width: usize,
+
+    pub typing: TypingConfig,
+    /// Speed relative to the humanized timings, applied before the caps
+    pub speed: f64,
+    /// Extra pause after finishing each line
+    pub line_pause: Duration,
+    /// Leading whitespace appears instantly, as with editor auto-indent
+    pub skip_indentation: bool,
+    /// Upper bound on typing speed, whatever the humanizer produces
+    pub max_chars_per_second: f64,
+    /// Longest gap allowed between two keystrokes
+    pub max_pause: Duration,
+
+    /// Empty editor shown before typing starts
+    pub lead_in: Duration,
+    /// Finished code held on screen after typing ends
+    pub hold_after: Duration,
+    pub scroll_duration: Duration,
+    pub caret_blink: Duration,
+}
+
+impl Default for CodeAnimationConfig {
+    fn default() -> Self {
+        Self {
+            width: 1920,
+            height: 1080,
+            frame_rate: 30.0,
+            theme: CodeTheme::dark(),
+            font_size: 28.0,
+            line_height: 1.5,
+            padding: 48.0,
+            show_line_numbers: true,
+            tab_width: 4,
+            typing: TypingConfig {
+                base_wpm: 90.0,
+                ..Default::default()
+            },
+            speed: 1.5,
+            line_pause: Duration::from_millis(300),
+            skip_indentation: true,
+            max_chars_per_second: 25.0,
+            max_pause: Duration::from_millis(1200),
+            lead_in: Duration::from_millis(500),
+            hold_after: Duration::from_secs(2),
+            scroll_duration: Duration::from_millis(180),
+            caret_blink: Duration::from_millis(530),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EditKind {
+    Insert(char),
+    Delete,
+    Newline,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TimedEdit {
+    time: Duration,
+    kind: EditKind,
+}
+
+/// A scheduled typing animation of one source file
+pub struct CodeAnimation {
+    config: CodeAnimationConfig,
+    source_lines: Vec<Vec<char>>,
+    char_kinds: Vec<Vec<TokenKind>>,
+    edits: Vec<TimedEdit>,
+    duration: Duration,
+    font: FontRef<'static>,
+}
+
+impl CodeAnimation {
+    /// Schedule the animation, pacing every line with humanized keystrokes
+    pub async fn new(source: &str, language: Language, config: CodeAnimationConfig) -> Result<Self> {
+        let source = source.replace("\r\n", "\n");
+        let lines = source.trim_end_matches('\n').split('\n').map(|line| (line, true));
+        Self::schedule(lines.collect(), language, config).await
+    }
+
+    /// Animate one file of a diff: context lines appear at once, added lines
+    /// are typed in place and removed lines are left out. Hunks are
+    /// separated by a blank line.
+    pub async fn from_diff(file: &FileDiff, config: CodeAnimationConfig) -> Result<Self> {
+        if file.binary || file.hunks.is_empty() {
+            bail!("{} has no text changes to animate", file.path());
+        }
+
+        let mut lines = Vec::new();
+        for (index, hunk) in file.hunks.iter().enumerate() {
+            if index > 0 {
+                lines.push(("", false));
+            }
+            for line in &hunk.lines {
+                match line.kind {
+                    DiffLineKind::Context => lines.push((line.text.as_str(), false)),
+                    DiffLineKind::Added => lines.push((line.text.as_str(), true)),
+                    DiffLineKind::Removed => {}
+                }
+            }
+        }
+        Self::schedule(lines, file.language(), config).await
+    }
+
+    /// Schedule `(line, typed)` pairs; lines not typed appear all at once
+    async fn schedule(lines: Vec<(&str, bool)>, language: Language, config: CodeAnimationConfig) -> Result<Self> {
+        if config.frame_rate <= 0.0 || config.width == 0 || config.height == 0 {
+            bail!("Animation needs a positive frame rate and frame size");
+        }
+        if config.max_chars_per_second <= 0.0 || config.speed <= 0.0 {
+            bail!("Typing speed and max_chars_per_second must be positive");
+        }
+
+        let tab = " ".repeat(config.tab_width);
+        let source = lines.iter().map(|(line, _)| line.replace('\t', &tab)).collect::<Vec<_>>().join("\n");
+        let source_lines: Vec<Vec<char>> = source.split('\n').map(|line| line.chars().collect()).collect();
+        let char_kinds = SyntaxHighlighter::new(language).char_kinds(&source);
+
+        let mut humanizer = TypingHumanizer::new(config.typing.clone()).await?;
+        let min_interval = Duration::from_secs_f64(1.0 / config.max_chars_per_second);
+
+        let mut edits = Vec::new();
+        let mut time = config.lead_in;
+
+        let mut typing_started = false;
+
+        for (index, (line, &(_, typed))) in source_lines.iter().zip(&lines).enumerate() {
+            if !typed {
+                // Lines ahead of the first typed one are there from the start
+                let at = if typing_started { time } else { Duration::ZERO };
+                if index > 0 {
+                    edits.push(TimedEdit { time: at, kind: EditKind::Newline });
+                }
+                edits.extend(line.iter().map(|&ch| TimedEdit { time: at, kind: EditKind::Insert(ch) }));
+                continue;
+            }
+            typing_started = true;
+
+            if index > 0 {
+                time += min_interval;
+                edits.push(TimedEdit { time, kind: EditKind::Newline });
+                time += config.line_pause;
+            }
+
+            let indent = if config.skip_indentation {
+                line.iter().take_while(|c| c.is_whitespace()).count()
+            } else {
+                0
+            };
+            for &ch in &line[..indent] {
+                edits.push(TimedEdit { time, kind: EditKind::Insert(ch) });
+            }
+
+            let content = &line[indent..];
+            if content.is_empty() {
+                continue;
+            }
+
+            let text: String = content.iter().collect();
+            let sequence = humanizer.humanize_text(&text).await?;
+
+            let mut previous = Duration::ZERO;
+            for (offset, kind) in keystroke_edits(&sequence.keystrokes, content) {
+                let gap = offset
+                    .saturating_sub(previous)
+                    .div_f64(config.speed)
+                    .min(config.max_pause)
+                    .max(min_interval);
+                time += gap;
+                previous = offset;
+                edits.push(TimedEdit { time, kind });
+            }
+        }
+
+        let duration = time + config.hold_after;
+        let font = monospace_font();
+
+        Ok(Self {
+            config,
+            source_lines,
+            char_kinds,
+            edits,
+            duration,
+            font,
+        })
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn frame_count(&self) -> usize {
+        (self.duration.as_secs_f64() * self.config.frame_rate).ceil() as usize
+    }
+
+    /// Clip covering the whole animation, for placing it on a timeline
+    pub fn timeline_clip(&self) -> TimelineClip {
+        TimelineClip::new(Duration::ZERO, self.duration)
+    }
+
+    pub fn timeline(&self) -> Timeline {
+        Timeline::new(vec![self.timeline_clip()])
+    }
+
+    /// Text on screen at `timestamp`
+    pub fn text_at(&self, timestamp: Duration) -> String {
+        self.lines_at(timestamp)
+            .iter()
+            .map(|line| line.iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn frame(&self, index: usize) -> Result<VideoFrame> {
+        self.render_at(Duration::from_secs_f64(index as f64 / self.config.frame_rate))
+    }
+
+    /// Lazily render every frame in order
+    pub fn frames(&self) -> impl Iterator<Item = Result<VideoFrame>> + '_ {
+        (0..self.frame_count()).map(move |index| self.frame(index))
+    }
+
+    pub fn render_at(&self, timestamp: Duration) -> Result<VideoFrame> {
+        let config = &self.config;
+        let theme = &config.theme;
+        let mut frame = solid_frame(config.width, config.height, theme.background, timestamp);
+        let mut canvas = Canvas::new(&mut frame)?;
+
+        let lines = self.lines_at(timestamp);
+        let line_height = config.font_size * config.line_height;
+        let (ascent, descent) = line_metrics(&self.font, config.font_size);
+        let char_width = text_width(&self.font, "M", config.font_size);
+        let visible_lines = (((config.height as f32 - config.padding * 2.0) / line_height).floor() as usize).max(1);
+
+        let gutter_width = if config.show_line_numbers {
+            let digits = self.source_lines.len().to_string().len().max(2);
+            char_width * (digits as f32 + 2.0)
+        } else {
+            0.0
+        };
+        if config.show_line_numbers {
+            canvas.fill_rect(0.0, 0.0, config.padding / 2.0 + gutter_width, config.height as f32, theme.gutter);
+        }
+
+        let scroll = self.scroll_at(timestamp, visible_lines);
+        let first_line = scroll.floor() as usize;
+        let text_left = config.padding / 2.0 + gutter_width + config.padding / 2.0;
+        // Center glyphs vertically within each line box
+        let baseline_offset = (line_height - (ascent - descent)) / 2.0 + ascent;
+
+        for (index, line) in lines.iter().enumerate().skip(first_line).take(visible_lines + 1) {
+            let top = config.padding + (index as f32 - scroll) * line_height;
+            let baseline = top + baseline_offset;
+
+            if config.show_line_numbers {
+                let number = (index + 1).to_string();
+                let x = config.padding / 2.0 + gutter_width - char_width * (number.len() as f32 + 1.0);
+                canvas.draw_text(&self.font, &number, x, baseline, config.font_size, theme.line_number);
+            }
+
+            for (column, &ch) in line.iter().enumerate() {
+                if ch == ' ' {
+                    continue;
+                }
+                // Typos are not in the source, so they stay in the plain color
+                let kind = match self.source_lines.get(index).and_then(|l| l.get(column)) {
+                    Some(&expected) if expected == ch => self.char_kinds[index][column],
+                    _ => TokenKind::Plain,
+                };
+                let x = text_left + column as f32 * char_width;
+                canvas.draw_text(&self.font, &ch.to_string(), x, baseline, config.font_size, theme.color(kind));
+            }
+        }
+
+        if self.caret_visible(timestamp) {
+            let caret_line = lines.len() - 1;
+            let top = config.padding + (caret_line as f32 - scroll) * line_height;
+            let x = text_left + lines[caret_line].len() as f32 * char_width;
+            canvas.fill_rect(x, top + (line_height - config.font_size) / 2.0, 2.0, config.font_size, theme.caret);
+        }
+
+        Ok(frame)
+    }
+
+    fn applied_edits(&self, timestamp: Duration) -> &[TimedEdit] {
+        let count = self.edits.partition_point(|edit| edit.time <= timestamp);
+        &self.edits[..count]
+    }
+
+    fn lines_at(&self, timestamp: Duration) -> Vec<Vec<char>> {
+        let mut lines: Vec<Vec<char>> = vec![Vec::new()];
+        for edit in self.applied_edits(timestamp) {
+            let current = lines.last_mut().expect("at least one line");
+            match edit.kind {
+                EditKind::Insert(ch) => current.push(ch),
+                EditKind::Delete => {
+                    current.pop();
+                }
+                EditKind::Newline => lines.push(Vec::new()),
+            }
+        }
+        lines
+    }
+
+    /// First visible line (fractional while scrolling) keeping the caret on screen
+    fn scroll_at(&self, timestamp: Duration, visible_lines: usize) -> f32 {
+        let applied = self.applied_edits(timestamp);
+        let newlines: Vec<Duration> = applied
+            .iter()
+            .filter(|edit| edit.kind == EditKind::Newline)
+            .map(|edit| edit.time)
+            .collect();
+
+        let target = |caret_line: usize| caret_line.saturating_sub(visible_lines - 1) as f32;
+        let Some(&last_newline) = newlines.last() else {
+            return 0.0;
+        };
+
+        let current = target(newlines.len());
+        let previous = target(newlines.len() - 1);
+        let progress = (timestamp.saturating_sub(last_newline).as_secs_f32()
+            / self.config.scroll_duration.as_secs_f32().max(f32::EPSILON))
+            .min(1.0);
+        let eased = 1.0 - (1.0 - progress).powi(3);
+
+        previous + (current - previous) * eased
+    }
+
+    /// Solid while typing, blinking once idle
+    fn caret_visible(&self, timestamp: Duration) -> bool {
+        let last_edit = self.applied_edits(timestamp).last().map(|e| e.time).unwrap_or(Duration::ZERO);
+        let idle = timestamp.saturating_sub(last_edit);
+        if idle < self.config.caret_blink {
+            return true;
+        }
+
+        let blink = self.config.caret_blink.as_secs_f64().max(f64::EPSILON);
+        ((idle.as_secs_f64() / blink) as u64).is_multiple_of(2)
+    }
+}
+
+/// Replay humanized keystrokes as edits relative to the start of the line
+///
+/// Typos the humanizer left uncorrected are fixed at the end of the line,
+/// so the finished animation always matches the source exactly.
+fn keystroke_edits(keystrokes: &[HumanizedKeystroke], target: &[char]) -> Vec<(Duration, EditKind)> {
+    let mut edits = Vec::new();
+    let mut buffer: Vec<char> = Vec::new();
+
+    for keystroke in keystrokes {
+        for ch in keystroke.character.chars() {
+            if ch == '\u{0008}' {
+                buffer.pop();
+                edits.push((keystroke.timestamp, EditKind::Delete));
+            } else {
+                buffer.push(ch);
+                edits.push((keystroke.timestamp, EditKind::Insert(ch)));
+            }
+        }
+    }
+
+    if buffer != target {
+        let common = buffer.iter().zip(target).take_while(|(a, b)| a == b).count();
+        let mut time = edits.last().map(|e| e.0).unwrap_or(Duration::ZERO) + Duration::from_millis(300);
+        let step = Duration::from_millis(60);
+
+        for _ in common..buffer.len() {
+            time += step;
+            edits.push((time, EditKind::Delete));
+        }
+        for &ch in &target[common..] {
+            time += step;
+            edits.push((time, EditKind::Insert(ch)));
+        }
+    }
+
+    edits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "fn main() {\n    let answer = 42;\n\n    println!(\"{}\", answer);\n}";
+
+    fn small_config() -> CodeAnimationConfig {
+        CodeAnimationConfig {
+            width: 480,
+            height: 160,
+            font_size: 16.0,
+            padding: 16.0,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_animation_ends_with_source() {
+        let animation = CodeAnimation::new(SOURCE, Language::Rust, small_config()).await.unwrap();
+
+        assert_eq!(animation.text_at(Duration::ZERO), "");
+        assert_eq!(animation.text_at(animation.duration()), SOURCE);
+    }
+
+    #[tokio::test]
+    async fn test_speed_cap_bounds_duration() {
+        let config = CodeAnimationConfig {
+            max_chars_per_second: 10.0,
+            ..small_config()
+        };
+        let animation = CodeAnimation::new(SOURCE, Language::Rust, config).await.unwrap();
+
+        let typed_chars = SOURCE.lines().map(|l| l.trim_start().chars().count()).sum::<usize>();
+        assert!(animation.duration() >= Duration::from_secs_f64(typed_chars as f64 / 10.0));
+    }
+
+    #[test]
+    fn test_uncorrected_typo_is_fixed() {
+        let keystroke = |ch: &str, ms: u64| HumanizedKeystroke {
+            character: ch.to_string(),
+            timestamp: Duration::from_millis(ms),
+            hold_duration: Duration::from_millis(50),
+            keystroke_type: aegnt_27::typing::KeystrokeType::Normal,
+            confidence: 0.9,
+        };
+        let keystrokes = vec![keystroke("l", 0), keystroke("w", 100), keystroke("t", 200)];
+        let target: Vec<char> = "let".chars().collect();
+
+        let mut buffer = Vec::new();
+        for (_, edit) in keystroke_edits(&keystrokes, &target) {
+            match edit {
+                EditKind::Insert(ch) => buffer.push(ch),
+                EditKind::Delete => {
+                    buffer.pop();
+                }
+                EditKind::Newline => unreachable!(),
+            }
+        }
+        assert_eq!(buffer, target);
+    }
+
+    #[tokio::test]
+    async fn test_diff_types_only_added_lines() {
+        let diff = "diff --git a/src/lib.rs b/src/lib.rs
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,4 @@
+ fn parse(input: &str) -> u32 {
+-    input.len() as u32
++    let trimmed = input.trim();
++    trimmed.len() as u32
+ }
+@@ -20,2 +21,2 @@
+-fn old() {}
++fn new() {}
+ // end
+";
+        let files = crate::diff_walkthrough::parse_unified_diff(diff);
+        let animation = CodeAnimation::from_diff(&files[0], small_config()).await.unwrap();
+
+        // Context before the first addition is there before typing starts
+        assert_eq!(animation.text_at(Duration::ZERO), "fn parse(input: &str) -> u32 {");
+        let expected = [
+            "fn parse(input: &str) -> u32 {",
+            "    let trimmed = input.trim();",
+            "    trimmed.len() as u32",
+            "}",
+            "",
+            "fn new() {}",
+            "// end",
+        ];
+        assert_eq!(animation.text_at(animation.duration()), expected.join("\n"));
+        // Only the three added lines take typing time
+        let typed_chars = "let trimmed = input.trim();trimmed.len() as u32fn new() {}".chars().count();
+        let bound = small_config().lead_in + small_config().hold_after;
+        assert!(animation.duration() >= bound + Duration::from_secs_f64(typed_chars as f64 / 25.0));
+
+        let binary = crate::diff_walkthrough::FileDiff {
+            binary: true,
+            ..Default::default()
+        };
+        assert!(CodeAnimation::from_diff(&binary, small_config()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_frames_render_progress() {
+        let animation = CodeAnimation::new(SOURCE, Language::Rust, small_config()).await.unwrap();
+        let background = small_config().theme.background;
+
+        let first = animation.frame(0).unwrap();
+        let last = animation.frame(animation.frame_count() - 1).unwrap();
+        let differs = |frame: &VideoFrame| {
+            frame.data.chunks(3).filter(|px| px != &[background.r, background.g, background.b]).count()
+        };
+
+        assert!(differs(&last) > differs(&first));
+        assert_eq!(last.width, 480);
+    }
+}
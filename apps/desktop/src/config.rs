@@ -3,6 +3,13 @@
 use aegnt_27::prelude::*;
 use serde::{Deserialize, Serialize};
 
+use crate::audio_capture::AudioCaptureConfig;
+use crate::audio_export::AudioExportConfig;
+use crate::browser_bridge::BrowserBridgeConfig;
+use crate::editor_bridge::EditorBridgeConfig;
+use crate::keyboard_capture::KeyboardCaptureConfig;
+use crate::terminal_recording::TerminalCaptureConfig;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DailyDocoConfig {
     // pub aegnt: AegntConfig,
@@ -15,6 +22,21 @@ pub struct CaptureConfig {
     pub quality: VideoQuality,
     pub fps: u32,
     pub audio_enabled: bool,
+    /// Microphone and system audio tracks recorded when `audio_enabled` is set
+    #[serde(default)]
+    pub audio: AudioCaptureConfig,
+    /// Optional asciicast recording of a terminal session
+    #[serde(default)]
+    pub terminal: TerminalCaptureConfig,
+    /// Loopback endpoint for editor plugins
+    #[serde(default)]
+    pub editor_bridge: EditorBridgeConfig,
+    /// Loopback WebSocket for the browser extension
+    #[serde(default)]
+    pub browser_bridge: BrowserBridgeConfig,
+    /// Key presses for the keystroke overlay
+    #[serde(default)]
+    pub keyboard: KeyboardCaptureConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,6 +50,9 @@ pub enum VideoQuality {
 pub struct ExportConfig {
     pub format: VideoFormat,
     pub compression: CompressionLevel,
+    /// Cut rendering, loudness normalisation and sample format of the audio
+    #[serde(default)]
+    pub audio: AudioExportConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -52,10 +77,16 @@ impl Default for DailyDocoConfig {
                 quality: VideoQuality::HD1080,
                 fps: 30,
                 audio_enabled: true,
+                audio: AudioCaptureConfig::default(),
+                terminal: TerminalCaptureConfig::default(),
+                editor_bridge: EditorBridgeConfig::default(),
+                browser_bridge: BrowserBridgeConfig::default(),
+                keyboard: KeyboardCaptureConfig::default(),
             },
             export: ExportConfig {
                 format: VideoFormat::MP4,
                 compression: CompressionLevel::Medium,
+                audio: AudioExportConfig::default(),
             },
         }
     }
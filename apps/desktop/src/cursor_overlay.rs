@@ -0,0 +1,326 @@
+//! Cursor highlight and click overlay rendering for DailyDoco Pro
+//!
+//! Draws a halo around the pointer, ripples on clicks, trails while dragging
+//! and a short indicator on scroll onto exported frames. Everything is driven
+//! by the recorded input log, which shares the capture clock with the frames.
+
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use anyhow::Result;
+
+use aegnt_27::visual::VideoFrame;
+
+use crate::canvas::{Canvas, Rgba};
+use crate::cursor_smoothing::SmoothedCursorPath;
+use crate::input_log::{InputEventKind, InputEventLog, MouseButton};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CursorOverlayConfig {
+    pub halo_enabled: bool,
+    pub halo_radius: f32,
+    pub halo_color: Rgba,
+
+    pub ripples_enabled: bool,
+    pub ripple_duration: Duration,
+    pub ripple_radius: f32,
+    pub ripple_thickness: f32,
+    pub left_click_color: Rgba,
+    pub right_click_color: Rgba,
+
+    pub drag_trail_enabled: bool,
+    /// How much recent movement the trail shows while a button is held
+    pub drag_trail_length: Duration,
+    pub drag_trail_width: f32,
+    pub drag_trail_color: Rgba,
+    /// Pointer travel, in pixels, before a held button counts as a drag
+    pub drag_threshold: f32,
+
+    pub scroll_indicator_enabled: bool,
+    pub scroll_indicator_duration: Duration,
+    pub scroll_indicator_color: Rgba,
+
+    /// Draw an enlarged arrow cursor on top of the recorded one
+    pub synthetic_cursor: bool,
+    pub cursor_scale: f32,
+}
+
+impl Default for CursorOverlayConfig {
+    fn default() -> Self {
+        Self {
+            halo_enabled: true,
+            halo_radius: 28.0,
+            halo_color: Rgba::new(255, 214, 0, 80),
+            ripples_enabled: true,
+            ripple_duration: Duration::from_millis(450),
+            ripple_radius: 42.0,
+            ripple_thickness: 4.0,
+            left_click_color: Rgba::new(255, 214, 0, 220),
+            right_click_color: Rgba::new(64, 156, 255, 220),
+            drag_trail_enabled: true,
+            drag_trail_length: Duration::from_millis(600),
+            drag_trail_width: 6.0,
+            drag_trail_color: Rgba::new(255, 214, 0, 160),
+            drag_threshold: 6.0,
+            scroll_indicator_enabled: true,
+            scroll_indicator_duration: Duration::from_millis(350),
+            scroll_indicator_color: Rgba::new(255, 255, 255, 200),
+            synthetic_cursor: false,
+            cursor_scale: 2.0,
+        }
+    }
+}
+
+/// Outline of the standard arrow pointer, tip at the origin, 1x scale
+const ARROW_OUTLINE: [(f32, f32); 7] = [
+    (0.0, 0.0),
+    (0.0, 17.0),
+    (4.0, 13.0),
+    (7.0, 20.0),
+    (9.5, 19.0),
+    (6.5, 12.0),
+    (12.0, 12.0),
+];
+
+/// Renders cursor and click highlights from an input log
+pub struct CursorOverlayRenderer {
+    config: CursorOverlayConfig,
+    log: InputEventLog,
+    cursor_path: Option<SmoothedCursorPath>,
+}
+
+impl CursorOverlayRenderer {
+    pub fn new(config: CursorOverlayConfig, log: InputEventLog) -> Self {
+        Self {
+            config,
+            log,
+            cursor_path: None,
+        }
+    }
+
+    /// Follow a smoothed cursor track instead of the raw pointer positions
+    pub fn with_cursor_path(mut self, path: SmoothedCursorPath) -> Self {
+        self.cursor_path = Some(path);
+        self
+    }
+
+    /// Draw the overlay at the frame's own timestamp
+    pub fn render_frame(&self, frame: &VideoFrame) -> Result<VideoFrame> {
+        self.render_frame_at(frame, frame.timestamp)
+    }
+
+    /// Draw the overlay for `capture_time`, for frames retimed by the editor
+    pub fn render_frame_at(&self, frame: &VideoFrame, capture_time: Duration) -> Result<VideoFrame> {
+        let mut output = frame.to_rgb()?;
+        {
+            let mut canvas = Canvas::new(&mut output)?;
+
+            if let Some((x, y)) = self.cursor_position(capture_time) {
+                if self.config.halo_enabled {
+                    canvas.fill_circle(x, y, self.config.halo_radius, self.config.halo_color);
+                }
+                if self.config.drag_trail_enabled {
+                    self.draw_drag_trail(&mut canvas, capture_time);
+                }
+                if self.config.ripples_enabled {
+                    self.draw_ripples(&mut canvas, capture_time);
+                }
+                if self.config.scroll_indicator_enabled {
+                    self.draw_scroll_indicator(&mut canvas, capture_time, x, y);
+                }
+                if self.config.synthetic_cursor {
+                    self.draw_synthetic_cursor(&mut canvas, x, y);
+                }
+            }
+        }
+
+        Ok(output)
+    }
+
+    pub fn render_frames(&self, frames: &[VideoFrame]) -> Result<Vec<VideoFrame>> {
+        frames.iter().map(|frame| self.render_frame(frame)).collect()
+    }
+
+    fn cursor_position(&self, timestamp: Duration) -> Option<(f32, f32)> {
+        match &self.cursor_path {
+            Some(path) => path.position_at(timestamp).map(|(x, y)| (x as f32, y as f32)),
+            None => self.log.pointer_at(timestamp).map(|(x, y)| (x as f32, y as f32)),
+        }
+    }
+
+    fn draw_ripples(&self, canvas: &mut Canvas<'_>, now: Duration) {
+        let window_start = now.saturating_sub(self.config.ripple_duration);
+
+        for event in self.log.range(window_start, now + Duration::from_nanos(1)) {
+            let InputEventKind::ButtonDown { button, x, y } = event.kind else {
+                continue;
+            };
+
+            let progress = now.saturating_sub(event.timestamp).as_secs_f32()
+                / self.config.ripple_duration.as_secs_f32().max(f32::EPSILON);
+            let color = match button {
+                MouseButton::Right => self.config.right_click_color,
+                _ => self.config.left_click_color,
+            };
+
+            // Expand quickly, then fade
+            let eased = 1.0 - (1.0 - progress).powi(3);
+            let radius = 6.0 + (self.config.ripple_radius - 6.0) * eased;
+            canvas.ring(
+                x as f32,
+                y as f32,
+                radius,
+                self.config.ripple_thickness,
+                color.faded(1.0 - progress),
+            );
+        }
+    }
+
+    fn draw_drag_trail(&self, canvas: &mut Canvas<'_>, now: Duration) {
+        // Find the press that is still held at `now`
+        let mut press: Option<(Duration, MouseButton)> = None;
+        for event in self.log.range(Duration::ZERO, now + Duration::from_nanos(1)) {
+            match event.kind {
+                InputEventKind::ButtonDown { button, .. } => press = Some((event.timestamp, button)),
+                InputEventKind::ButtonUp { button, .. } if press.map(|p| p.1) == Some(button) => press = None,
+                _ => {}
+            }
+        }
+
+        let Some((pressed_at, _)) = press else {
+            return;
+        };
+
+        let trail_start = pressed_at.max(now.saturating_sub(self.config.drag_trail_length));
+        let points: Vec<(Duration, f32, f32)> = self
+            .log
+            .range(trail_start, now + Duration::from_nanos(1))
+            .iter()
+            .filter_map(|e| e.position().map(|(x, y)| (e.timestamp, x as f32, y as f32)))
+            .collect();
+
+        let (Some(first), Some(last)) = (points.first(), points.last()) else {
+            return;
+        };
+        let travel = ((last.1 - first.1).powi(2) + (last.2 - first.2).powi(2)).sqrt();
+        if travel < self.config.drag_threshold {
+            return;
+        }
+
+        let span = now.saturating_sub(trail_start).as_secs_f32().max(f32::EPSILON);
+        for pair in points.windows(2) {
+            // Older parts of the trail fade out
+            let age = now.saturating_sub(pair[1].0).as_secs_f32() / span;
+            canvas.line(
+                pair[0].1,
+                pair[0].2,
+                pair[1].1,
+                pair[1].2,
+                self.config.drag_trail_width,
+                self.config.drag_trail_color.faded(1.0 - age),
+            );
+        }
+    }
+
+    fn draw_scroll_indicator(&self, canvas: &mut Canvas<'_>, now: Duration, x: f32, y: f32) {
+        let window_start = now.saturating_sub(self.config.scroll_indicator_duration);
+        let Some(delta_y) = self
+            .log
+            .range(window_start, now + Duration::from_nanos(1))
+            .iter()
+            .rev()
+            .find_map(|e| match e.kind {
+                InputEventKind::Scroll { delta_y, .. } if delta_y != 0.0 => Some(delta_y),
+                _ => None,
+            })
+        else {
+            return;
+        };
+
+        // Chevron beside the pointer pointing in the scroll direction
+        let direction = if delta_y > 0.0 { 1.0 } else { -1.0 };
+        let (cx, cy) = (x + self.config.halo_radius + 10.0, y);
+        let (size, color) = (8.0, self.config.scroll_indicator_color);
+        let (tail, tip) = (cy - direction * size / 2.0, cy + direction * size / 2.0);
+        canvas.line(cx - size, tail, cx, tip, 3.0, color);
+        canvas.line(cx, tip, cx + size, tail, 3.0, color);
+    }
+
+    fn draw_synthetic_cursor(&self, canvas: &mut Canvas<'_>, x: f32, y: f32) {
+        let scale = self.config.cursor_scale.max(0.1);
+        let outline: Vec<(f32, f32)> = ARROW_OUTLINE
+            .iter()
+            .map(|(px, py)| (x + px * scale, y + py * scale))
+            .collect();
+
+        canvas.fill_polygon(&outline, Rgba::WHITE);
+        for i in 0..outline.len() {
+            let (a, b) = (outline[i], outline[(i + 1) % outline.len()]);
+            canvas.line(a.0, a.1, b.0, b.1, scale, Rgba::BLACK);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::solid_frame;
+    use crate::input_log::InputEvent;
+
+    fn click_log() -> InputEventLog {
+        let mut log = InputEventLog::new();
+        log.record(InputEvent::new(Duration::from_millis(0), InputEventKind::PointerMove { x: 50, y: 50 }));
+        log.record(InputEvent::new(
+            Duration::from_millis(100),
+            InputEventKind::ButtonDown { button: MouseButton::Left, x: 50, y: 50 },
+        ));
+        log.record(InputEvent::new(
+            Duration::from_millis(150),
+            InputEventKind::ButtonUp { button: MouseButton::Left, x: 50, y: 50 },
+        ));
+        log
+    }
+
+    #[test]
+    fn test_ripple_drawn_after_click() {
+        let config = CursorOverlayConfig { halo_enabled: false, ..Default::default() };
+        let renderer = CursorOverlayRenderer::new(config, click_log());
+
+        let before = solid_frame(120, 120, Rgba::BLACK, Duration::from_millis(90));
+        let after = solid_frame(120, 120, Rgba::BLACK, Duration::from_millis(200));
+
+        let rendered_before = renderer.render_frame(&before).unwrap();
+        assert!(rendered_before.data.iter().all(|&v| v == 0));
+
+        let rendered_after = renderer.render_frame(&after).unwrap();
+        assert!(rendered_after.data.iter().any(|&v| v > 0));
+    }
+
+    #[test]
+    fn test_halo_follows_pointer() {
+        let renderer = CursorOverlayRenderer::new(CursorOverlayConfig::default(), click_log());
+        let mut frame = renderer
+            .render_frame(&solid_frame(120, 120, Rgba::BLACK, Duration::from_millis(20)))
+            .unwrap();
+
+        let canvas = Canvas::new(&mut frame).unwrap();
+        assert!(canvas.pixel(50, 50)[0] > 0);
+        assert_eq!(canvas.pixel(5, 5), [0, 0, 0]);
+    }
+
+    #[test]
+    fn test_synthetic_cursor() {
+        let config = CursorOverlayConfig {
+            halo_enabled: false,
+            synthetic_cursor: true,
+            ..Default::default()
+        };
+        let renderer = CursorOverlayRenderer::new(config, click_log());
+        let mut frame = renderer
+            .render_frame(&solid_frame(120, 120, Rgba::BLACK, Duration::from_millis(20)))
+            .unwrap();
+
+        let canvas = Canvas::new(&mut frame).unwrap();
+        // Inside the arrow body at 2x scale
+        assert_eq!(canvas.pixel(54, 70), [255, 255, 255]);
+    }
+}
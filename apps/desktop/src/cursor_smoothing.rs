@@ -0,0 +1,377 @@
+//! Offline cursor path cleaning for DailyDoco Pro
+//!
+//! Recorded pointer trajectories are jittery. This module splits the pointer
+//! log into strokes between clicks, smooths each one and returns a clean path
+//! for drawing a synthetic cursor. Click positions and times are never moved.
+
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use anyhow::Result;
+
+use aegnt_27::mouse::{MousePath, Point};
+use aegnt_27::utils::math::{generate_cubic_bezier_points, smooth_values, Point2D};
+
+use crate::input_log::{InputEventKind, InputEventLog, MouseButton};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CursorSmoothingConfig {
+    /// Smoothing strength (0.0 = raw path, 1.0 = strongest)
+    pub strength: f64,
+    /// Movements smaller than this many pixels are treated as hand tremor
+    pub jitter_threshold: f64,
+    /// Interval at which strokes are resampled before smoothing
+    pub sample_interval: Duration,
+    /// Largest moving-average window used at full strength
+    pub max_window: usize,
+    /// Strokes up to this long are also pulled toward a single Bezier curve
+    pub bezier_max_duration: Duration,
+}
+
+impl Default for CursorSmoothingConfig {
+    fn default() -> Self {
+        Self {
+            strength: 0.6,
+            jitter_threshold: 2.0,
+            sample_interval: Duration::from_millis(10),
+            max_window: 31,
+            bezier_max_duration: Duration::from_millis(1200),
+        }
+    }
+}
+
+/// Pointer movement between two anchors (clicks or the log edges)
+///
+/// Points in `path` are evenly spaced over `path.total_duration`, so the time
+/// of every point is known exactly from `start`.
+#[derive(Debug, Clone)]
+pub struct CursorStroke {
+    pub start: Duration,
+    pub path: MousePath,
+}
+
+impl CursorStroke {
+    /// Timestamp of point `index` in this stroke
+    pub fn point_time(&self, index: usize) -> Duration {
+        let steps = self.path.points.len().saturating_sub(1).max(1);
+        self.start + self.path.total_duration.mul_f64(index as f64 / steps as f64)
+    }
+}
+
+/// A button press or release that the smoothed path must pass through
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CursorClick {
+    pub timestamp: Duration,
+    pub x: i32,
+    pub y: i32,
+    pub button: MouseButton,
+    pub pressed: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CursorSample {
+    pub timestamp: Duration,
+    pub x: f64,
+    pub y: f64,
+}
+
+/// Cleaned cursor track ready for rendering
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SmoothedCursorPath {
+    pub samples: Vec<CursorSample>,
+    pub clicks: Vec<CursorClick>,
+}
+
+impl SmoothedCursorPath {
+    /// Cursor position at `timestamp`, linearly interpolated
+    pub fn position_at(&self, timestamp: Duration) -> Option<(f64, f64)> {
+        let index = self.samples.partition_point(|s| s.timestamp <= timestamp);
+        if index == 0 {
+            return self.samples.first().map(|s| (s.x, s.y));
+        }
+        if index >= self.samples.len() {
+            return self.samples.last().map(|s| (s.x, s.y));
+        }
+
+        let (a, b) = (self.samples[index - 1], self.samples[index]);
+        let span = b.timestamp.saturating_sub(a.timestamp).as_secs_f64();
+        if span <= 0.0 {
+            return Some((b.x, b.y));
+        }
+
+        let t = timestamp.saturating_sub(a.timestamp).as_secs_f64() / span;
+        Some((a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t))
+    }
+}
+
+/// Smooths recorded cursor strokes
+pub struct CursorSmoother {
+    config: CursorSmoothingConfig,
+}
+
+impl CursorSmoother {
+    pub fn new(config: CursorSmoothingConfig) -> Self {
+        Self { config }
+    }
+
+    /// Split the pointer log into strokes that start and end on clicks
+    pub fn strokes_from_log(&self, log: &InputEventLog) -> Result<(Vec<CursorStroke>, Vec<CursorClick>)> {
+        let mut clicks = Vec::new();
+        let mut anchors: Vec<usize> = Vec::new();
+        let positions: Vec<(Duration, i32, i32)> = log.pointer_positions();
+
+        let mut position_index = 0;
+        for event in &log.events {
+            let (button, x, y, pressed) = match event.kind {
+                InputEventKind::ButtonDown { button, x, y } => (button, x, y, true),
+                InputEventKind::ButtonUp { button, x, y } => (button, x, y, false),
+                _ => {
+                    if event.position().is_some() {
+                        position_index += 1;
+                    }
+                    continue;
+                }
+            };
+
+            clicks.push(CursorClick { timestamp: event.timestamp, x, y, button, pressed });
+            anchors.push(position_index);
+            position_index += 1;
+        }
+
+        let mut boundaries = vec![0];
+        boundaries.extend(anchors);
+        boundaries.push(positions.len().saturating_sub(1));
+        boundaries.dedup();
+
+        let mut strokes = Vec::new();
+        for pair in boundaries.windows(2) {
+            let raw = &positions[pair[0]..=pair[1]];
+            if let Some(stroke) = self.resample_stroke(raw)? {
+                strokes.push(stroke);
+            }
+        }
+
+        Ok((strokes, clicks))
+    }
+
+    /// Smooth every stroke of a recorded log
+    pub fn smooth_log(&self, log: &InputEventLog) -> Result<SmoothedCursorPath> {
+        let (strokes, clicks) = self.strokes_from_log(log)?;
+        let mut path = self.smooth_strokes(&strokes);
+        path.clicks = clicks;
+        Ok(path)
+    }
+
+    /// Smooth a sequence of strokes into one continuous track
+    pub fn smooth_strokes(&self, strokes: &[CursorStroke]) -> SmoothedCursorPath {
+        let mut samples: Vec<CursorSample> = Vec::new();
+
+        for stroke in strokes {
+            let smoothed = self.smooth_stroke(stroke);
+            for (i, point) in smoothed.into_iter().enumerate() {
+                let timestamp = stroke.point_time(i);
+                // Consecutive strokes share their anchor point
+                if samples.last().map(|s| s.timestamp == timestamp).unwrap_or(false) {
+                    continue;
+                }
+                samples.push(CursorSample { timestamp, x: point.x, y: point.y });
+            }
+        }
+
+        SmoothedCursorPath { samples, clicks: Vec::new() }
+    }
+
+    /// Smooth one stroke, keeping its first and last point exactly in place
+    pub fn smooth_stroke(&self, stroke: &CursorStroke) -> Vec<Point2D> {
+        let raw: Vec<Point2D> = stroke.path.points.iter().map(|p| p.to_point2d()).collect();
+        if raw.len() < 3 || self.config.strength <= 0.0 {
+            return raw;
+        }
+
+        let steady = self.remove_jitter(&raw);
+
+        let strength = self.config.strength.clamp(0.0, 1.0);
+        let window = 1 + (strength * self.config.max_window.saturating_sub(1) as f64).round() as usize;
+        let xs: Vec<f64> = steady.iter().map(|p| p.x).collect();
+        let ys: Vec<f64> = steady.iter().map(|p| p.y).collect();
+        let xs = smooth_values(&xs, window);
+        let ys = smooth_values(&ys, window);
+        let mut smoothed: Vec<Point2D> = xs.into_iter().zip(ys).map(|(x, y)| Point2D::new(x, y)).collect();
+
+        pin_endpoints(&mut smoothed, raw[0], raw[raw.len() - 1]);
+
+        // Short flicks read best as one clean curve
+        if stroke.path.total_duration <= self.config.bezier_max_duration {
+            let curve = fit_bezier(&smoothed);
+            let blend = strength * strength;
+            for (point, target) in smoothed.iter_mut().zip(curve) {
+                *point = point.lerp(&target, blend);
+            }
+        }
+
+        smoothed
+    }
+
+    /// Hold the cursor still while it moves less than the jitter threshold
+    fn remove_jitter(&self, points: &[Point2D]) -> Vec<Point2D> {
+        let mut steady = Vec::with_capacity(points.len());
+        let mut held = points[0];
+
+        for point in points {
+            if held.distance_to(point) >= self.config.jitter_threshold {
+                held = *point;
+            }
+            steady.push(held);
+        }
+
+        steady
+    }
+
+    /// Resample raw positions at a fixed interval, ending exactly on the anchor
+    fn resample_stroke(&self, raw: &[(Duration, i32, i32)]) -> Result<Option<CursorStroke>> {
+        let (Some(first), Some(last)) = (raw.first(), raw.last()) else {
+            return Ok(None);
+        };
+
+        let duration = last.0.saturating_sub(first.0);
+        if duration.is_zero() {
+            return Ok(None);
+        }
+
+        let interval = self.config.sample_interval.max(Duration::from_millis(1));
+        let steps = (duration.as_secs_f64() / interval.as_secs_f64()).ceil().max(1.0) as usize;
+
+        let mut points = Vec::with_capacity(steps + 1);
+        let mut cursor = 0;
+        for i in 0..=steps {
+            let timestamp = first.0 + duration.mul_f64(i as f64 / steps as f64);
+            while cursor + 1 < raw.len() && raw[cursor + 1].0 <= timestamp {
+                cursor += 1;
+            }
+
+            let (t0, x0, y0) = raw[cursor];
+            let point = match raw.get(cursor + 1) {
+                Some(&(t1, x1, y1)) if t1 > t0 => {
+                    let t = timestamp.saturating_sub(t0).as_secs_f64() / (t1 - t0).as_secs_f64();
+                    Point2D::new(x0 as f64, y0 as f64).lerp(&Point2D::new(x1 as f64, y1 as f64), t)
+                }
+                _ => Point2D::new(x0 as f64, y0 as f64),
+            };
+            points.push(Point::from(point));
+        }
+
+        // Anchors are click positions and must be exact
+        points[0] = Point::new(first.1, first.2);
+        points[steps] = Point::new(last.1, last.2);
+
+        Ok(Some(CursorStroke {
+            start: first.0,
+            path: MousePath::from_points(points, duration)?,
+        }))
+    }
+}
+
+impl Default for CursorSmoother {
+    fn default() -> Self {
+        Self::new(CursorSmoothingConfig::default())
+    }
+}
+
+/// Spread the endpoint error linearly so the ends land exactly on the anchors
+fn pin_endpoints(points: &mut [Point2D], start: Point2D, end: Point2D) {
+    let last = points.len() - 1;
+    let start_error = Point2D::new(start.x - points[0].x, start.y - points[0].y);
+    let end_error = Point2D::new(end.x - points[last].x, end.y - points[last].y);
+
+    for (i, point) in points.iter_mut().enumerate() {
+        let t = i as f64 / last as f64;
+        point.x += start_error.x * (1.0 - t) + end_error.x * t;
+        point.y += start_error.y * (1.0 - t) + end_error.y * t;
+    }
+}
+
+/// Cubic Bezier through both endpoints and the points at 1/3 and 2/3
+fn fit_bezier(points: &[Point2D]) -> Vec<Point2D> {
+    let n = points.len();
+    let p0 = points[0];
+    let p3 = points[n - 1];
+    let q1 = points[(n - 1) / 3];
+    let q2 = points[2 * (n - 1) / 3];
+
+    let p1 = Point2D::new(
+        (-5.0 * p0.x + 18.0 * q1.x - 9.0 * q2.x + 2.0 * p3.x) / 6.0,
+        (-5.0 * p0.y + 18.0 * q1.y - 9.0 * q2.y + 2.0 * p3.y) / 6.0,
+    );
+    let p2 = Point2D::new(
+        (2.0 * p0.x - 9.0 * q1.x + 18.0 * q2.x - 5.0 * p3.x) / 6.0,
+        (2.0 * p0.y - 9.0 * q1.y + 18.0 * q2.y - 5.0 * p3.y) / 6.0,
+    );
+
+    generate_cubic_bezier_points(p0, p1, p2, p3, n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input_log::InputEvent;
+
+    fn jittery_log() -> InputEventLog {
+        let mut log = InputEventLog::new();
+        log.record(InputEvent::new(
+            Duration::from_millis(0),
+            InputEventKind::ButtonDown { button: MouseButton::Left, x: 100, y: 100 },
+        ));
+        for i in 1..100 {
+            let jitter = if i % 2 == 0 { 3 } else { -3 };
+            log.record(InputEvent::new(
+                Duration::from_millis(i * 10),
+                InputEventKind::PointerMove { x: 100 + i as i32 * 5, y: 100 + jitter },
+            ));
+        }
+        log.record(InputEvent::new(
+            Duration::from_millis(1000),
+            InputEventKind::ButtonUp { button: MouseButton::Left, x: 600, y: 100 },
+        ));
+        log
+    }
+
+    #[test]
+    fn test_clicks_are_preserved() {
+        let path = CursorSmoother::default().smooth_log(&jittery_log()).unwrap();
+
+        assert_eq!(path.clicks.len(), 2);
+        for click in &path.clicks {
+            let (x, y) = path.position_at(click.timestamp).unwrap();
+            assert!((x - click.x as f64).abs() < 1e-9);
+            assert!((y - click.y as f64).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_jitter_is_reduced() {
+        let path = CursorSmoother::default().smooth_log(&jittery_log()).unwrap();
+        let middle: Vec<&CursorSample> = path
+            .samples
+            .iter()
+            .filter(|s| s.timestamp > Duration::from_millis(200) && s.timestamp < Duration::from_millis(800))
+            .collect();
+
+        let max_deviation = middle.iter().map(|s| (s.y - 100.0).abs()).fold(0.0, f64::max);
+        assert!(max_deviation < 1.5, "deviation {}", max_deviation);
+    }
+
+    #[test]
+    fn test_zero_strength_keeps_raw_points() {
+        let smoother = CursorSmoother::new(CursorSmoothingConfig { strength: 0.0, ..Default::default() });
+        let stroke = CursorStroke {
+            start: Duration::ZERO,
+            path: MousePath::from_points(
+                vec![Point::new(0, 0), Point::new(10, 3), Point::new(20, 0)],
+                Duration::from_millis(20),
+            )
+            .unwrap(),
+        };
+
+        let smoothed = smoother.smooth_stroke(&stroke);
+        assert_eq!(smoothed[1], Point2D::new(10.0, 3.0));
+    }
+}
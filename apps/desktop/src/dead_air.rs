@@ -0,0 +1,227 @@
+//! Dead-air trimming for DailyDoco Pro
+//!
+//! Long silences while the presenter thinks lose viewers. Voice activity
+//! detection splits the narration into speech and silence; every pause
+//! longer than `max_pause` becomes a suggestion to shorten it to a natural
+//! pause. The suggestions feed the pacing engine, which plays short pauses
+//! faster and cuts long ones, and can be applied to a timeline directly by
+//! removing the middle of each pause.
+
+use std::ops::Range;
+use std::time::Duration;
+
+use aegnt_27::audio::AudioData;
+use aegnt_27::audio_vad::{ActivitySegment, VadConfig};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::timeline::{CutTransition, Timeline, TimelineClip};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadAirConfig {
+    /// Pauses longer than this are shortened
+    pub max_pause: Duration,
+    /// Length a shortened pause is brought down to
+    pub natural_pause: Duration,
+    #[serde(default)]
+    pub vad: VadConfig,
+}
+
+impl Default for DeadAirConfig {
+    fn default() -> Self {
+        Self {
+            max_pause: Duration::from_millis(1500),
+            natural_pause: Duration::from_millis(600),
+            vad: VadConfig::default(),
+        }
+    }
+}
+
+/// A pause in the narration worth shortening
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TrimSuggestion {
+    pub pause_start: Duration,
+    pub pause_end: Duration,
+    /// Length the pause is shortened to
+    pub keep: Duration,
+}
+
+impl TrimSuggestion {
+    pub fn pause(&self) -> Duration {
+        self.pause_end.saturating_sub(self.pause_start)
+    }
+
+    pub fn removed(&self) -> Duration {
+        self.pause().saturating_sub(self.keep)
+    }
+
+    /// Source range to remove: the middle of the pause, so half the kept
+    /// pause stays on each side of the speech around it
+    pub fn cut(&self) -> Range<Duration> {
+        let start = self.pause_start + self.keep / 2;
+        start..start + self.removed()
+    }
+
+    /// Playback speed that fits the whole pause into the kept length
+    pub fn speed_factor(&self) -> f64 {
+        self.pause().as_secs_f64() / self.keep.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+/// Finds overlong pauses in narration
+pub struct DeadAirTrimmer {
+    config: DeadAirConfig,
+}
+
+impl DeadAirTrimmer {
+    pub fn new(config: DeadAirConfig) -> Self {
+        Self { config }
+    }
+
+    /// Speech and silence segments of the narration
+    pub fn detect(&self, narration: &AudioData) -> Result<Vec<ActivitySegment>> {
+        narration
+            .detect_voice_activity(&self.config.vad)
+            .map_err(|e| anyhow!("Voice activity detection failed: {}", e))
+    }
+
+    /// Trim suggestions for every overlong pause in the narration
+    pub fn suggest(&self, narration: &AudioData) -> Result<Vec<TrimSuggestion>> {
+        Ok(self.suggest_from_segments(&self.detect(narration)?))
+    }
+
+    pub fn suggest_from_segments(&self, segments: &[ActivitySegment]) -> Vec<TrimSuggestion> {
+        let keep = self.config.natural_pause.min(self.config.max_pause);
+        segments
+            .iter()
+            .filter(|segment| !segment.is_speech() && segment.duration() > self.config.max_pause)
+            .map(|segment| TrimSuggestion {
+                pause_start: segment.start,
+                pause_end: segment.end,
+                keep,
+            })
+            .collect()
+    }
+}
+
+impl Default for DeadAirTrimmer {
+    fn default() -> Self {
+        Self::new(DeadAirConfig::default())
+    }
+}
+
+/// Remove the cut range of every suggestion from the timeline's clips;
+/// clips split by a cut are joined with a default transition
+pub fn trim_timeline(timeline: &Timeline, suggestions: &[TrimSuggestion]) -> Timeline {
+    let mut cuts: Vec<Range<Duration>> = suggestions.iter().map(TrimSuggestion::cut).collect();
+    cuts.sort_by_key(|cut| cut.start);
+
+    let mut clips = Vec::with_capacity(timeline.clips.len());
+    for clip in &timeline.clips {
+        let mut start = clip.source_start;
+        let mut transition = Some(clip.transition.clone());
+        let overlapping = cuts.iter().filter(|cut| cut.start < clip.source_end && cut.end > clip.source_start);
+        for cut in overlapping.chain(std::iter::once(&(clip.source_end..clip.source_end))) {
+            let end = cut.start.max(start);
+            if end > start {
+                let mut piece = TimelineClip::new(start, end);
+                piece.transition = transition.take().unwrap_or_else(CutTransition::default);
+                clips.push(piece);
+            }
+            start = cut.end.max(start);
+        }
+    }
+    Timeline::new(clips)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aegnt_27::audio_vad::VoiceActivity;
+
+    use crate::dynamic_pacing_engine::{DynamicPacingEngine, PacingAction, PacingConfig};
+
+    fn secs(value: f64) -> Duration {
+        Duration::from_secs_f64(value)
+    }
+
+    fn segment(start: f64, end: f64, activity: VoiceActivity) -> ActivitySegment {
+        ActivitySegment {
+            start: secs(start),
+            end: secs(end),
+            activity,
+        }
+    }
+
+    /// Narration with pauses of 1 s, 2 s and 6 s
+    fn segments() -> Vec<ActivitySegment> {
+        vec![
+            segment(0.0, 4.0, VoiceActivity::Speech),
+            segment(4.0, 5.0, VoiceActivity::Silence),
+            segment(5.0, 8.0, VoiceActivity::Speech),
+            segment(8.0, 10.0, VoiceActivity::Silence),
+            segment(10.0, 14.0, VoiceActivity::Speech),
+            segment(14.0, 20.0, VoiceActivity::Silence),
+            segment(20.0, 24.0, VoiceActivity::Speech),
+        ]
+    }
+
+    #[test]
+    fn test_only_long_pauses_are_shortened_to_a_natural_pause() {
+        let suggestions = DeadAirTrimmer::default().suggest_from_segments(&segments());
+        assert_eq!(suggestions.len(), 2);
+
+        assert_eq!(suggestions[0].pause(), secs(2.0));
+        assert_eq!(suggestions[0].keep, Duration::from_millis(600));
+        assert_eq!(suggestions[0].cut(), secs(8.3)..secs(9.7));
+        assert!((suggestions[0].speed_factor() - 2.0 / 0.6).abs() < 1e-9);
+        assert_eq!(suggestions[1].removed(), secs(5.4));
+    }
+
+    #[test]
+    fn test_trimmed_timeline_drops_the_dead_air() {
+        let suggestions = DeadAirTrimmer::default().suggest_from_segments(&segments());
+        let timeline = Timeline::new(vec![
+            TimelineClip::new(Duration::ZERO, secs(12.0)),
+            TimelineClip::new(secs(16.0), secs(24.0)),
+        ]);
+
+        let trimmed = trim_timeline(&timeline, &suggestions);
+        let ranges: Vec<_> = trimmed.clips.iter().map(|c| (c.source_start, c.source_end)).collect();
+        assert_eq!(
+            ranges,
+            vec![
+                (Duration::ZERO, secs(8.3)),
+                (secs(9.7), secs(12.0)),
+                (secs(19.7), secs(24.0)),
+            ]
+        );
+        assert_eq!(trimmed.duration(), timeline.duration() - secs(1.4) - secs(3.7));
+    }
+
+    #[test]
+    fn test_pacing_engine_speeds_up_short_pauses_and_cuts_long_ones() {
+        let config = DeadAirConfig {
+            natural_pause: Duration::from_millis(1200),
+            ..DeadAirConfig::default()
+        };
+        let suggestions = DeadAirTrimmer::new(config).suggest_from_segments(&segments());
+        let mut engine = DynamicPacingEngine::new(PacingConfig::default());
+
+        let decisions = engine.plan_dead_air(&suggestions);
+        assert_eq!(decisions.len(), 3);
+
+        // 2 s into 1.2 s is within the 2x speed limit
+        assert_eq!(decisions[0].timestamp, secs(8.0));
+        assert!(matches!(decisions[0].action, PacingAction::SpeedUp { factor } if (factor - 2.0 / 1.2).abs() < 1e-9));
+        assert_eq!(decisions[1].timestamp, secs(10.0));
+        assert!(matches!(decisions[1].action, PacingAction::SpeedUp { factor } if factor == 1.0));
+
+        // 6 s into 1.2 s would need 5x, so it is cut
+        assert_eq!(decisions[2].timestamp, secs(14.6));
+        let PacingAction::Cut { duration } = decisions[2].action else {
+            panic!("expected a cut, got {:?}", decisions[2].action);
+        };
+        assert!((decisions[2].timestamp + duration).abs_diff(secs(19.4)) < Duration::from_micros(1));
+    }
+}
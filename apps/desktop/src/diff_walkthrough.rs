@@ -0,0 +1,1061 @@
+//! Code walkthrough videos from git diffs for DailyDoco Pro
+//!
+//! Renders a commit range without recording anything: every hunk is shown
+//! syntax-highlighted with added and removed lines tinted, the view scrolls
+//! from change to change, and each file becomes a chapter. A narration
+//! outline and `ZoomTarget::Code` pacing decisions are derived from the
+//! commit messages and hunk headers.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use anyhow::{bail, Context, Result};
+use ab_glyph::FontRef;
+
+use aegnt_27::visual::VideoFrame;
+
+use crate::canvas::{line_metrics, monospace_font, solid_frame, text_width, Canvas, Rgba};
+use crate::dynamic_pacing_engine::{PacingAction, PacingDecision, ZoomTarget};
+use crate::syntax_highlight::{CodeTheme, Language, SyntaxHighlighter, TokenKind};
+use crate::timeline::{Timeline, TimelineClip};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DiffLineKind {
+    Context,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    pub old_line: Option<usize>,
+    pub new_line: Option<usize>,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffHunk {
+    pub old_start: usize,
+    pub old_count: usize,
+    pub new_start: usize,
+    pub new_count: usize,
+    /// Enclosing function or section git printed after the `@@` header
+    pub section: String,
+    pub lines: Vec<DiffLine>,
+}
+
+impl DiffHunk {
+    pub fn additions(&self) -> usize {
+        self.lines.iter().filter(|line| line.kind == DiffLineKind::Added).count()
+    }
+
+    pub fn deletions(&self) -> usize {
+        self.lines.iter().filter(|line| line.kind == DiffLineKind::Removed).count()
+    }
+
+    /// Lines of the new file this hunk covers, 1-based and inclusive
+    pub fn new_line_range(&self) -> (usize, usize) {
+        let start = self.new_start.max(1);
+        (start, start + self.new_count.saturating_sub(1))
+    }
+
+    pub fn header(&self) -> String {
+        let header = format!(
+            "@@ -{},{} +{},{} @@",
+            self.old_start, self.old_count, self.new_start, self.new_count
+        );
+        if self.section.is_empty() {
+            header
+        } else {
+            format!("{} {}", header, self.section)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FileDiff {
+    /// `None` for added files
+    pub old_path: Option<String>,
+    /// `None` for deleted files
+    pub new_path: Option<String>,
+    pub binary: bool,
+    pub hunks: Vec<DiffHunk>,
+}
+
+impl FileDiff {
+    pub fn path(&self) -> &str {
+        self.new_path.as_deref().or(self.old_path.as_deref()).unwrap_or_default()
+    }
+
+    pub fn language(&self) -> Language {
+        Language::from_path(Path::new(self.path()))
+    }
+
+    pub fn additions(&self) -> usize {
+        self.hunks.iter().map(DiffHunk::additions).sum()
+    }
+
+    pub fn deletions(&self) -> usize {
+        self.hunks.iter().map(DiffHunk::deletions).sum()
+    }
+}
+
+/// Parse `git diff` output in unified format
+pub fn parse_unified_diff(text: &str) -> Vec<FileDiff> {
+    let mut files: Vec<FileDiff> = Vec::new();
+    // Lines still expected on the old and new side of the open hunk
+    let mut remaining = (0usize, 0usize);
+    let mut next_line = (0usize, 0usize);
+
+    for line in text.lines() {
+        if remaining != (0, 0) {
+            if let Some(file) = files.last_mut() {
+                let hunk = file.hunks.last_mut().expect("hunk is open");
+                let (kind, body) = match line.chars().next() {
+                    Some('+') => (DiffLineKind::Added, &line[1..]),
+                    Some('-') => (DiffLineKind::Removed, &line[1..]),
+                    Some(' ') => (DiffLineKind::Context, &line[1..]),
+                    Some('\\') => continue,
+                    // git drops the space on empty context lines in some setups
+                    None => (DiffLineKind::Context, ""),
+                    _ => {
+                        remaining = (0, 0);
+                        continue;
+                    }
+                };
+
+                let old_line = (kind != DiffLineKind::Added).then(|| {
+                    next_line.0 += 1;
+                    remaining.0 = remaining.0.saturating_sub(1);
+                    next_line.0 - 1
+                });
+                let new_line = (kind != DiffLineKind::Removed).then(|| {
+                    next_line.1 += 1;
+                    remaining.1 = remaining.1.saturating_sub(1);
+                    next_line.1 - 1
+                });
+                hunk.lines.push(DiffLine {
+                    kind,
+                    old_line,
+                    new_line,
+                    text: body.to_string(),
+                });
+                continue;
+            }
+        }
+
+        if let Some(paths) = line.strip_prefix("diff --git ") {
+            let (old, new) = match paths.rfind(" b/") {
+                Some(split) => (&paths[..split], &paths[split + 1..]),
+                None => (paths, paths),
+            };
+            files.push(FileDiff {
+                old_path: Some(strip_side_prefix(old).to_string()),
+                new_path: Some(strip_side_prefix(new).to_string()),
+                ..Default::default()
+            });
+            continue;
+        }
+
+        let Some(file) = files.last_mut() else {
+            continue;
+        };
+        if let Some(path) = line.strip_prefix("--- ") {
+            file.old_path = side_path(path);
+        } else if let Some(path) = line.strip_prefix("+++ ") {
+            file.new_path = side_path(path);
+        } else if line.starts_with("new file mode") {
+            file.old_path = None;
+        } else if line.starts_with("deleted file mode") {
+            file.new_path = None;
+        } else if let Some(path) = line.strip_prefix("rename from ") {
+            file.old_path = Some(path.to_string());
+        } else if let Some(path) = line.strip_prefix("rename to ") {
+            file.new_path = Some(path.to_string());
+        } else if line.starts_with("Binary files ") || line == "GIT binary patch" {
+            file.binary = true;
+        } else if let Some(hunk) = parse_hunk_header(line) {
+            remaining = (hunk.old_count, hunk.new_count);
+            next_line = (hunk.old_start, hunk.new_start);
+            file.hunks.push(hunk);
+        }
+    }
+
+    files
+}
+
+fn strip_side_prefix(path: &str) -> &str {
+    path.strip_prefix("a/").or_else(|| path.strip_prefix("b/")).unwrap_or(path)
+}
+
+fn side_path(path: &str) -> Option<String> {
+    let path = path.trim_end();
+    (path != "/dev/null").then(|| strip_side_prefix(path).to_string())
+}
+
+/// `@@ -a,b +c,d @@ section`, counts default to 1 when omitted
+fn parse_hunk_header(line: &str) -> Option<DiffHunk> {
+    let rest = line.strip_prefix("@@ -")?;
+    let (ranges, section) = rest.split_once(" @@")?;
+    let (old, new) = ranges.split_once(" +")?;
+
+    let range = |text: &str| -> Option<(usize, usize)> {
+        match text.split_once(',') {
+            Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+            None => Some((text.parse().ok()?, 1)),
+        }
+    };
+    let (old_start, old_count) = range(old)?;
+    let (new_start, new_count) = range(new)?;
+
+    Some(DiffHunk {
+        old_start,
+        old_count,
+        new_start,
+        new_count,
+        section: section.trim().to_string(),
+        lines: Vec::new(),
+    })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffCommit {
+    pub hash: String,
+    pub summary: String,
+    pub body: String,
+}
+
+/// A commit range in a local repository, read through the `git` CLI
+#[derive(Debug, Clone)]
+pub struct GitRange {
+    pub repo: PathBuf,
+    /// Anything `git log` accepts, e.g. `main..feature` or `v1.2.0..v1.3.0`
+    pub range: String,
+}
+
+impl GitRange {
+    pub fn new(repo: impl Into<PathBuf>, range: impl Into<String>) -> Self {
+        Self {
+            repo: repo.into(),
+            range: range.into(),
+        }
+    }
+
+    /// Commits in the range, oldest first
+    pub fn commits(&self) -> Result<Vec<DiffCommit>> {
+        let output = self.git(&[
+            "log",
+            "--reverse",
+            "--no-color",
+            "--format=%H%x1f%s%x1f%b%x1e",
+            "--end-of-options",
+            &self.range,
+        ])?;
+        Ok(output
+            .split('\u{1e}')
+            .filter_map(|record| {
+                let mut fields = record.trim_start_matches('\n').splitn(3, '\u{1f}');
+                let hash = fields.next()?.trim();
+                if hash.is_empty() {
+                    return None;
+                }
+                Some(DiffCommit {
+                    hash: hash.to_string(),
+                    summary: fields.next().unwrap_or_default().trim().to_string(),
+                    body: fields.next().unwrap_or_default().trim().to_string(),
+                })
+            })
+            .collect())
+    }
+
+    /// Combined diff of the range
+    pub fn diff(&self) -> Result<Vec<FileDiff>> {
+        let output = self.git(&["diff", "-M", "--no-color", "--no-ext-diff", "--end-of-options", &self.range])?;
+        Ok(parse_unified_diff(&output))
+    }
+
+    fn git(&self, args: &[&str]) -> Result<String> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&self.repo)
+            .args(args)
+            .output()
+            .context("Running git")?;
+        if !output.status.success() {
+            bail!("git {} failed: {}", args[0], String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffWalkthroughConfig {
+    pub width: u32,
+    pub height: u32,
+    pub frame_rate: f64,
+
+    pub theme: CodeTheme,
+    pub font_size: f32,
+    /// Line spacing as a multiple of the font size
+    pub line_height: f32,
+    pub padding: f32,
+    pub tab_width: usize,
+    pub added_background: Rgba,
+    pub removed_background: Rgba,
+    pub added_marker: Rgba,
+    pub removed_marker: Rgba,
+    /// Opacity of hunks other than the one being explained
+    pub inactive_opacity: f32,
+
+    /// Commit summary card before the first file, skipped without commits
+    pub intro_duration: Duration,
+    /// File name card at the start of every chapter
+    pub title_duration: Duration,
+    pub scroll_duration: Duration,
+    /// Time for the change highlight to fade in once a hunk is in view
+    pub highlight_fade: Duration,
+    pub hold_base: Duration,
+    pub hold_per_changed_line: Duration,
+    pub max_hold: Duration,
+}
+
+impl Default for DiffWalkthroughConfig {
+    fn default() -> Self {
+        Self {
+            width: 1920,
+            height: 1080,
+            frame_rate: 30.0,
+            theme: CodeTheme::dark(),
+            font_size: 26.0,
+            line_height: 1.5,
+            padding: 40.0,
+            tab_width: 4,
+            added_background: Rgba::new(46, 160, 67, 70),
+            removed_background: Rgba::new(248, 81, 73, 70),
+            added_marker: Rgba::new(87, 201, 108, 255),
+            removed_marker: Rgba::new(248, 113, 106, 255),
+            inactive_opacity: 0.35,
+            intro_duration: Duration::from_secs(4),
+            title_duration: Duration::from_millis(1500),
+            scroll_duration: Duration::from_millis(700),
+            highlight_fade: Duration::from_millis(300),
+            hold_base: Duration::from_secs(3),
+            hold_per_changed_line: Duration::from_millis(400),
+            max_hold: Duration::from_secs(12),
+        }
+    }
+}
+
+/// One file of the walkthrough, or the commit overview
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalkthroughChapter {
+    pub title: String,
+    pub start: Duration,
+    pub end: Duration,
+}
+
+/// A line of the narration script outline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NarrationCue {
+    pub timestamp: Duration,
+    pub text: String,
+}
+
+enum Row {
+    Separator(String),
+    Line {
+        hunk: usize,
+        kind: DiffLineKind,
+        old_line: Option<usize>,
+        new_line: Option<usize>,
+        chars: Vec<char>,
+        kinds: Vec<TokenKind>,
+    },
+}
+
+/// Hunks of one file laid out as a scrollable list of rows
+struct FileView {
+    file: usize,
+    rows: Vec<Row>,
+    /// First and last changed row of each hunk
+    changed_rows: Vec<(usize, usize)>,
+    number_digits: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum SceneKind {
+    Intro,
+    Title { view: usize },
+    /// Scrolls from the previous hunk until `focus_at`, then holds
+    Hunk { view: usize, hunk: usize, focus_at: Duration, previous: Option<usize> },
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Scene {
+    start: Duration,
+    end: Duration,
+    kind: SceneKind,
+}
+
+/// A rendered walkthrough of a diff, frames produced on demand
+pub struct DiffWalkthrough {
+    config: DiffWalkthroughConfig,
+    commits: Vec<DiffCommit>,
+    files: Vec<FileDiff>,
+    views: Vec<FileView>,
+    scenes: Vec<Scene>,
+    chapters: Vec<WalkthroughChapter>,
+    duration: Duration,
+    font: FontRef<'static>,
+}
+
+impl DiffWalkthrough {
+    /// Lay out a walkthrough of `files`; binary files and files without
+    /// hunks (pure renames, mode changes) are skipped
+    pub fn new(commits: Vec<DiffCommit>, files: Vec<FileDiff>, config: DiffWalkthroughConfig) -> Result<Self> {
+        if config.frame_rate <= 0.0 || config.width == 0 || config.height == 0 {
+            bail!("Walkthrough needs a positive frame rate and frame size");
+        }
+
+        let views: Vec<FileView> = files
+            .iter()
+            .enumerate()
+            .filter(|(_, file)| !file.binary && !file.hunks.is_empty())
+            .map(|(index, file)| FileView::new(index, file, config.tab_width))
+            .collect();
+        if views.is_empty() {
+            bail!("Diff has no text changes to walk through");
+        }
+
+        let mut scenes = Vec::new();
+        let mut chapters = Vec::new();
+        let mut time = Duration::ZERO;
+
+        if !commits.is_empty() {
+            scenes.push(Scene { start: time, end: time + config.intro_duration, kind: SceneKind::Intro });
+            time += config.intro_duration;
+            chapters.push(WalkthroughChapter {
+                title: "Overview".to_string(),
+                start: Duration::ZERO,
+                end: time,
+            });
+        }
+
+        for (view_index, view) in views.iter().enumerate() {
+            let chapter_start = time;
+            scenes.push(Scene {
+                start: time,
+                end: time + config.title_duration,
+                kind: SceneKind::Title { view: view_index },
+            });
+            time += config.title_duration;
+
+            for (hunk_index, hunk) in files[view.file].hunks.iter().enumerate() {
+                let start = time;
+                let previous = hunk_index.checked_sub(1);
+                if previous.is_some() {
+                    time += config.scroll_duration;
+                }
+                let focus_at = time;
+                let changed = (hunk.additions() + hunk.deletions()) as u32;
+                time += (config.hold_base + config.hold_per_changed_line * changed).min(config.max_hold);
+
+                scenes.push(Scene {
+                    start,
+                    end: time,
+                    kind: SceneKind::Hunk { view: view_index, hunk: hunk_index, focus_at, previous },
+                });
+            }
+
+            chapters.push(WalkthroughChapter {
+                title: files[view.file].path().to_string(),
+                start: chapter_start,
+                end: time,
+            });
+        }
+
+        Ok(Self {
+            config,
+            commits,
+            files,
+            views,
+            scenes,
+            chapters,
+            duration: time,
+            font: monospace_font(),
+        })
+    }
+
+    /// Read the commits and diff of a range and lay out the walkthrough
+    pub fn from_git(range: &GitRange, config: DiffWalkthroughConfig) -> Result<Self> {
+        Self::new(range.commits()?, range.diff()?, config)
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn frame_count(&self) -> usize {
+        (self.duration.as_secs_f64() * self.config.frame_rate).ceil() as usize
+    }
+
+    pub fn chapters(&self) -> &[WalkthroughChapter] {
+        &self.chapters
+    }
+
+    /// One clip per chapter, so chapter boundaries become cut points
+    pub fn timeline(&self) -> Timeline {
+        Timeline::new(
+            self.chapters
+                .iter()
+                .map(|chapter| TimelineClip::new(chapter.start, chapter.end))
+                .collect(),
+        )
+    }
+
+    /// Narration script outline built from commit messages and hunk headers
+    pub fn narration_outline(&self) -> Vec<NarrationCue> {
+        let mut cues = Vec::new();
+
+        for scene in &self.scenes {
+            match scene.kind {
+                SceneKind::Intro => {
+                    let summaries: Vec<&str> = self.commits.iter().map(|c| c.summary.as_str()).collect();
+                    let noun = if summaries.len() == 1 { "commit" } else { "commits" };
+                    cues.push(NarrationCue {
+                        timestamp: scene.start,
+                        text: format!(
+                            "This walkthrough covers {} {}: {}.",
+                            summaries.len(),
+                            noun,
+                            summaries.join("; ")
+                        ),
+                    });
+                }
+                SceneKind::Title { view } => {
+                    let file = &self.files[self.views[view].file];
+                    let change = match (&file.old_path, &file.new_path) {
+                        (None, _) => "New file".to_string(),
+                        (_, None) => "Deleted file".to_string(),
+                        (Some(old), Some(new)) if old != new => format!("Renamed from {}", old),
+                        _ => "Changes to".to_string(),
+                    };
+                    cues.push(NarrationCue {
+                        timestamp: scene.start,
+                        text: format!(
+                            "{} {}: {} added, {} removed.",
+                            change,
+                            file.path(),
+                            plural(file.additions(), "line"),
+                            plural(file.deletions(), "line")
+                        ),
+                    });
+                }
+                SceneKind::Hunk { view, hunk, focus_at, .. } => {
+                    let hunk = &self.files[self.views[view].file].hunks[hunk];
+                    let (start, end) = hunk.new_line_range();
+                    let location = if hunk.section.is_empty() {
+                        format!("Lines {}-{}", start, end)
+                    } else {
+                        format!("In `{}` (lines {}-{})", hunk.section, start, end)
+                    };
+                    cues.push(NarrationCue {
+                        timestamp: focus_at,
+                        text: format!(
+                            "{}: {} added, {} removed.",
+                            location,
+                            plural(hunk.additions(), "line"),
+                            plural(hunk.deletions(), "line")
+                        ),
+                    });
+                }
+            }
+        }
+
+        cues
+    }
+
+    /// The narration outline as a Markdown list grouped by chapter
+    pub fn narration_script(&self) -> String {
+        let cues = self.narration_outline();
+        let mut script = String::new();
+
+        for chapter in &self.chapters {
+            script.push_str(&format!("## {} ({})\n\n", chapter.title, format_clock(chapter.start)));
+            for cue in cues.iter().filter(|c| c.timestamp >= chapter.start && c.timestamp < chapter.end) {
+                script.push_str(&format!("- [{}] {}\n", format_clock(cue.timestamp), cue.text));
+            }
+            script.push('\n');
+        }
+
+        script
+    }
+
+    /// A `ZoomTarget::Code` decision for each hunk while it is explained
+    pub fn pacing_decisions(&self) -> Vec<PacingDecision> {
+        self.scenes
+            .iter()
+            .filter_map(|scene| match scene.kind {
+                SceneKind::Hunk { view, hunk, focus_at, .. } => {
+                    let file = &self.files[self.views[view].file];
+                    let hunk = &file.hunks[hunk];
+                    let changed = hunk.additions() + hunk.deletions();
+                    let (start, end) = hunk.new_line_range();
+                    Some(PacingDecision {
+                        timestamp: focus_at,
+                        action: PacingAction::Zoom {
+                            target: ZoomTarget::Code { line_range: (start, end) },
+                            duration: scene.end.saturating_sub(focus_at),
+                        },
+                        intensity: (changed as f64 / 20.0).clamp(0.2, 1.0),
+                        reasoning: format!("Walk through {} changed lines in {}", changed, file.path()),
+                        confidence: 0.9,
+                    })
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn frame(&self, index: usize) -> Result<VideoFrame> {
+        self.render_at(Duration::from_secs_f64(index as f64 / self.config.frame_rate))
+    }
+
+    /// Lazily render every frame in order
+    pub fn frames(&self) -> impl Iterator<Item = Result<VideoFrame>> + '_ {
+        (0..self.frame_count()).map(move |index| self.frame(index))
+    }
+
+    pub fn render_at(&self, timestamp: Duration) -> Result<VideoFrame> {
+        let config = &self.config;
+        let mut frame = solid_frame(config.width, config.height, config.theme.background, timestamp);
+        let mut canvas = Canvas::new(&mut frame)?;
+
+        let index = self.scenes.partition_point(|scene| scene.start <= timestamp).max(1) - 1;
+        let scene = self.scenes[index];
+
+        match scene.kind {
+            SceneKind::Intro => self.draw_intro(&mut canvas),
+            SceneKind::Title { view } => self.draw_title(&mut canvas, &self.views[view]),
+            SceneKind::Hunk { view, hunk, focus_at, previous } => {
+                let view = &self.views[view];
+                let scroll = if timestamp < focus_at {
+                    let from = match previous {
+                        Some(previous) => self.hunk_scroll(view, previous, 1.0),
+                        None => self.hunk_scroll(view, hunk, 0.0),
+                    };
+                    let to = self.hunk_scroll(view, hunk, 0.0);
+                    let progress = timestamp.saturating_sub(scene.start).as_secs_f32()
+                        / focus_at.saturating_sub(scene.start).as_secs_f32().max(f32::EPSILON);
+                    from + (to - from) * ease_in_out(progress)
+                } else {
+                    // Hunks taller than the screen are read top to bottom
+                    let reading_start = focus_at + config.highlight_fade;
+                    let progress = timestamp.saturating_sub(reading_start).as_secs_f32()
+                        / scene.end.saturating_sub(reading_start).as_secs_f32().max(f32::EPSILON);
+                    self.hunk_scroll(view, hunk, ease_in_out(progress))
+                };
+                let highlight = (timestamp.saturating_sub(focus_at).as_secs_f32()
+                    / config.highlight_fade.as_secs_f32().max(f32::EPSILON))
+                .min(1.0);
+
+                self.draw_file(&mut canvas, view, hunk, scroll, highlight);
+            }
+        }
+
+        Ok(frame)
+    }
+
+    fn line_height(&self) -> f32 {
+        self.config.font_size * self.config.line_height
+    }
+
+    fn header_height(&self) -> f32 {
+        self.line_height() * 1.6
+    }
+
+    fn code_top(&self) -> f32 {
+        self.header_height() + self.config.padding / 2.0
+    }
+
+    /// Rows that fit below the header, fractional
+    fn visible_rows(&self) -> f32 {
+        ((self.config.height as f32 - self.code_top() - self.config.padding / 2.0) / self.line_height()).max(1.0)
+    }
+
+    /// First visible row while `hunk` is explained. Changes that fit are
+    /// centred; taller ones are scrolled through as `progress` goes 0 to 1.
+    fn hunk_scroll(&self, view: &FileView, hunk: usize, progress: f32) -> f32 {
+        let visible = self.visible_rows();
+        let max_scroll = (view.rows.len() as f32 - visible).max(0.0);
+        let (first, last) = view.changed_rows[hunk];
+        let (first, last) = (first as f32, last as f32);
+
+        let scroll = if last - first + 1.0 <= visible {
+            (first + last + 1.0 - visible) / 2.0
+        } else {
+            // Keep a row of context above the first and below the last change
+            let top = first - 1.0;
+            let bottom = last + 2.0 - visible;
+            top + (bottom - top) * progress.clamp(0.0, 1.0)
+        };
+        scroll.clamp(0.0, max_scroll)
+    }
+
+    fn draw_intro(&self, canvas: &mut Canvas) {
+        let config = &self.config;
+        let theme = &config.theme;
+        let title_size = config.font_size * 1.6;
+        let mut baseline = config.padding * 2.0 + title_size;
+
+        canvas.draw_text(&self.font, "Code walkthrough", config.padding * 2.0, baseline, title_size, theme.foreground);
+        baseline += title_size * 1.2;
+
+        let max_lines = ((config.height as f32 - baseline - config.padding) / self.line_height()).max(0.0) as usize;
+        for commit in self.commits.iter().take(max_lines) {
+            baseline += self.line_height();
+            let x = config.padding * 2.0;
+            let hash = &commit.hash[..commit.hash.len().min(7)];
+            let hash_width = canvas.draw_text(&self.font, hash, x, baseline, config.font_size, theme.number);
+            let x = x + hash_width + config.font_size;
+            canvas.draw_text(&self.font, &commit.summary, x, baseline, config.font_size, theme.foreground);
+        }
+    }
+
+    fn draw_title(&self, canvas: &mut Canvas, view: &FileView) {
+        let config = &self.config;
+        let file = &self.files[view.file];
+        let size = config.font_size * 1.5;
+        let x = config.padding * 2.0;
+        let baseline = config.height as f32 / 2.0;
+
+        canvas.draw_text(&self.font, file.path(), x, baseline, size, config.theme.foreground);
+        let added = format!("+{}", file.additions());
+        let counts_baseline = baseline + size * 1.4;
+        let width = canvas.draw_text(&self.font, &added, x, counts_baseline, config.font_size, config.added_marker);
+        let removed = format!("-{}", file.deletions());
+        let x = x + width + config.font_size;
+        canvas.draw_text(&self.font, &removed, x, counts_baseline, config.font_size, config.removed_marker);
+    }
+
+    fn draw_file(&self, canvas: &mut Canvas, view: &FileView, active_hunk: usize, scroll: f32, highlight: f32) {
+        let config = &self.config;
+        let theme = &config.theme;
+        let line_height = self.line_height();
+        let (ascent, descent) = line_metrics(&self.font, config.font_size);
+        let baseline_offset = (line_height - (ascent - descent)) / 2.0 + ascent;
+        let char_width = text_width(&self.font, "M", config.font_size);
+
+        // Header with the file path and the section being explained
+        let header_height = self.header_height();
+        canvas.fill_rect(0.0, 0.0, config.width as f32, header_height, theme.gutter);
+        let header_baseline = (header_height - (ascent - descent)) / 2.0 + ascent;
+        let file = &self.files[view.file];
+        let path = file.path();
+        let width = canvas.draw_text(&self.font, path, config.padding, header_baseline, config.font_size, theme.foreground);
+        let section = &file.hunks[active_hunk].section;
+        if !section.is_empty() {
+            let x = config.padding + width + char_width * 2.0;
+            canvas.draw_text(&self.font, section, x, header_baseline, config.font_size, theme.comment);
+        }
+
+        let code_top = self.code_top();
+        let visible_rows = self.visible_rows();
+
+        let number_width = char_width * (view.number_digits as f32 + 1.0);
+        let gutter_width = config.padding / 2.0 + number_width * 2.0 + char_width;
+        canvas.fill_rect(0.0, header_height, gutter_width, config.height as f32 - header_height, theme.gutter);
+        let marker_x = gutter_width + char_width * 0.5;
+        let text_left = marker_x + char_width * 2.0;
+
+        let first = scroll.floor() as usize;
+        for (index, row) in view.rows.iter().enumerate().skip(first).take(visible_rows.ceil() as usize + 1) {
+            let top = code_top + (index as f32 - scroll) * line_height;
+            let baseline = top + baseline_offset;
+
+            match row {
+                Row::Separator(header) => {
+                    canvas.fill_rect(gutter_width, top, config.width as f32 - gutter_width, line_height, theme.gutter);
+                    canvas.draw_text(&self.font, header, text_left, baseline, config.font_size, theme.line_number);
+                }
+                Row::Line { hunk, kind, old_line, new_line, chars, kinds } => {
+                    let active = *hunk == active_hunk;
+                    let opacity = if active { 1.0 } else { config.inactive_opacity };
+                    let tint = if active { highlight } else { config.inactive_opacity };
+
+                    let (background, marker, sign) = match kind {
+                        DiffLineKind::Added => (Some(config.added_background), config.added_marker, "+"),
+                        DiffLineKind::Removed => (Some(config.removed_background), config.removed_marker, "-"),
+                        DiffLineKind::Context => (None, theme.line_number, " "),
+                    };
+                    if let Some(background) = background {
+                        let width = config.width as f32 - gutter_width;
+                        canvas.fill_rect(gutter_width, top, width, line_height, background.faded(tint));
+                    }
+
+                    for (column, number) in [old_line, new_line].into_iter().enumerate() {
+                        if let Some(number) = number {
+                            let text = number.to_string();
+                            let right = config.padding / 2.0 + number_width * (column as f32 + 1.0);
+                            let x = right - char_width * (text.len() as f32 + 0.5);
+                            let color = theme.line_number.faded(opacity);
+                            canvas.draw_text(&self.font, &text, x, baseline, config.font_size, color);
+                        }
+                    }
+                    canvas.draw_text(&self.font, sign, marker_x, baseline, config.font_size, marker.faded(opacity));
+
+                    // Draw runs of equally colored characters in one call
+                    let mut column = 0;
+                    while column < chars.len() {
+                        let kind = kinds[column];
+                        let end = (column..chars.len()).find(|&c| kinds[c] != kind).unwrap_or(chars.len());
+                        let run: String = chars[column..end].iter().collect();
+                        if !run.trim().is_empty() {
+                            let x = text_left + column as f32 * char_width;
+                            let color = theme.color(kind).faded(opacity);
+                            canvas.draw_text(&self.font, &run, x, baseline, config.font_size, color);
+                        }
+                        column = end;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl FileView {
+    fn new(file_index: usize, file: &FileDiff, tab_width: usize) -> Self {
+        let highlighter = SyntaxHighlighter::new(file.language());
+        let tab = " ".repeat(tab_width);
+        let mut rows = Vec::new();
+        let mut changed_rows = Vec::with_capacity(file.hunks.len());
+        let mut max_number = 0;
+
+        for (hunk_index, hunk) in file.hunks.iter().enumerate() {
+            rows.push(Row::Separator(hunk.header()));
+
+            // Highlight the hunk as one text so multi-line tokens keep their color
+            let texts: Vec<String> = hunk.lines.iter().map(|line| line.text.replace('\t', &tab)).collect();
+            let kinds = highlighter.char_kinds(&texts.join("\n"));
+
+            let first_row = rows.len();
+            let mut changed = (usize::MAX, 0);
+            for (offset, (line, text)) in hunk.lines.iter().zip(texts).enumerate() {
+                if line.kind != DiffLineKind::Context {
+                    changed = (changed.0.min(first_row + offset), first_row + offset);
+                }
+                max_number = max_number.max(line.old_line.unwrap_or(0)).max(line.new_line.unwrap_or(0));
+                rows.push(Row::Line {
+                    hunk: hunk_index,
+                    kind: line.kind,
+                    old_line: line.old_line,
+                    new_line: line.new_line,
+                    chars: text.chars().collect(),
+                    kinds: kinds.get(offset).cloned().unwrap_or_default(),
+                });
+            }
+
+            changed_rows.push(if changed.0 == usize::MAX { (first_row, rows.len() - 1) } else { changed });
+        }
+
+        Self {
+            file: file_index,
+            rows,
+            changed_rows,
+            number_digits: max_number.to_string().len().max(2),
+        }
+    }
+}
+
+fn ease_in_out(t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    if t < 0.5 {
+        4.0 * t * t * t
+    } else {
+        1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+    }
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("1 {}", noun)
+    } else {
+        format!("{} {}s", count, noun)
+    }
+}
+
+fn format_clock(timestamp: Duration) -> String {
+    let seconds = timestamp.as_secs();
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,4 +1,5 @@ mod parser
+ fn parse(input: &str) -> u32 {
+-    input.len() as u32
++    let trimmed = input.trim();
++    trimmed.len() as u32
+ }
+
+@@ -20,3 +21,3 @@ fn helper()
+ fn helper() {
+-    println!(\"old\");
++    println!(\"new\");
+ }
+diff --git a/notes.md b/notes.md
+new file mode 100644
+index 0000000..3333333
+--- /dev/null
++++ b/notes.md
+@@ -0,0 +1,2 @@
++# Notes
++Parser now trims input.
+diff --git a/logo.png b/logo.png
+index 4444444..5555555 100644
+Binary files a/logo.png and b/logo.png differ
+";
+
+    fn small_config() -> DiffWalkthroughConfig {
+        DiffWalkthroughConfig {
+            width: 480,
+            height: 270,
+            font_size: 14.0,
+            padding: 16.0,
+            ..Default::default()
+        }
+    }
+
+    fn commits() -> Vec<DiffCommit> {
+        vec![DiffCommit {
+            hash: "abcdef0123456789".to_string(),
+            summary: "Trim parser input".to_string(),
+            body: String::new(),
+        }]
+    }
+
+    #[test]
+    fn test_parse_unified_diff() {
+        let files = parse_unified_diff(DIFF);
+        assert_eq!(files.len(), 3);
+
+        let lib = &files[0];
+        assert_eq!(lib.path(), "src/lib.rs");
+        assert_eq!(lib.language(), Language::Rust);
+        assert_eq!((lib.additions(), lib.deletions()), (3, 2));
+        assert_eq!(lib.hunks[0].section, "mod parser");
+        assert_eq!(lib.hunks[1].new_line_range(), (21, 23));
+
+        let added = &lib.hunks[0].lines[2];
+        assert_eq!((added.kind, added.old_line, added.new_line), (DiffLineKind::Added, None, Some(2)));
+        let closing = &lib.hunks[0].lines[4];
+        assert_eq!((closing.old_line, closing.new_line), (Some(3), Some(4)));
+
+        assert_eq!(files[1].old_path, None);
+        assert_eq!(files[1].path(), "notes.md");
+        assert!(files[2].binary && files[2].hunks.is_empty());
+    }
+
+    #[test]
+    fn test_chapters_narration_and_zoom() {
+        let walkthrough = DiffWalkthrough::new(commits(), parse_unified_diff(DIFF), small_config()).unwrap();
+
+        let titles: Vec<&str> = walkthrough.chapters().iter().map(|c| c.title.as_str()).collect();
+        assert_eq!(titles, ["Overview", "src/lib.rs", "notes.md"]);
+        assert_eq!(walkthrough.chapters().last().unwrap().end, walkthrough.duration());
+        assert_eq!(walkthrough.timeline().cut_points().len(), 2);
+
+        let outline = walkthrough.narration_outline();
+        assert!(outline[0].text.contains("1 commit: Trim parser input"));
+        assert!(outline.iter().any(|cue| cue.text.starts_with("In `fn helper()` (lines 21-23)")));
+        assert!(outline.iter().any(|cue| cue.text.starts_with("New file notes.md")));
+        assert!(walkthrough.narration_script().contains("## src/lib.rs"));
+
+        let zooms: Vec<(usize, usize)> = walkthrough
+            .pacing_decisions()
+            .iter()
+            .filter_map(|decision| match &decision.action {
+                PacingAction::Zoom { target: ZoomTarget::Code { line_range }, .. } => Some(*line_range),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(zooms, [(1, 5), (21, 23), (1, 2)]);
+    }
+
+    #[test]
+    fn test_changed_lines_are_tinted() {
+        let config = small_config();
+        let walkthrough = DiffWalkthrough::new(Vec::new(), parse_unified_diff(DIFF), config.clone()).unwrap();
+
+        // First hunk, fully highlighted
+        let scene = walkthrough.scenes.iter().find(|s| matches!(s.kind, SceneKind::Hunk { .. })).unwrap();
+        let mut frame = walkthrough.render_at(scene.end - Duration::from_millis(10)).unwrap();
+        let canvas = Canvas::new(&mut frame).unwrap();
+
+        let right = config.width - 2;
+        let mut added_rows = 0;
+        let mut removed_rows = 0;
+        for y in 0..config.height {
+            let [r, g, _] = canvas.pixel(right, y);
+            if g as i32 - r as i32 > 20 {
+                added_rows += 1;
+            } else if r as i32 - g as i32 > 20 {
+                removed_rows += 1;
+            }
+        }
+        let line_height = (config.font_size * config.line_height) as u32;
+        assert!(added_rows >= line_height * 2 - 2, "added rows {}", added_rows);
+        assert!(removed_rows >= line_height - 1, "removed rows {}", removed_rows);
+    }
+
+    #[test]
+    fn test_reads_range_from_git() {
+        let repo = std::env::temp_dir().join(format!("dailydoco-diff-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&repo);
+        std::fs::create_dir_all(&repo).unwrap();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(&repo)
+                .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+                .args(args)
+                .output()
+                .unwrap();
+            assert!(status.status.success(), "{}", String::from_utf8_lossy(&status.stderr));
+        };
+
+        git(&["init", "-q"]);
+        std::fs::write(repo.join("main.py"), "def main():\n    return 1\n").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "-q", "-m", "Initial"]);
+        git(&["tag", "start"]);
+        std::fs::write(repo.join("main.py"), "def main():\n    return 2\n").unwrap();
+        git(&["commit", "-q", "-am", "Return two\n\nThe answer changed."]);
+
+        let range = GitRange::new(&repo, "start..HEAD");
+        let commits = range.commits().unwrap();
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0].summary, "Return two");
+        assert_eq!(commits[0].body, "The answer changed.");
+
+        let walkthrough = DiffWalkthrough::from_git(&range, small_config()).unwrap();
+        assert_eq!(walkthrough.chapters()[1].title, "main.py");
+        assert_eq!(walkthrough.files[0].language(), Language::Python);
+
+        // A range that looks like an option is treated as a revision, not passed through to git
+        let output = repo.join("leak");
+        let hostile = GitRange::new(&repo, format!("--output={}", output.display()));
+        assert!(hostile.diff().is_err());
+        assert!(!output.exists());
+
+        std::fs::remove_dir_all(&repo).unwrap();
+    }
+}
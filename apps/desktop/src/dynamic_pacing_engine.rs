@@ -1,11 +1,13 @@
 // SPRINT 5: Dynamic Pacing Engine
 // TASK-028: Ultra-tier pacing intelligence with psychological flow optimization
 
-use std::collections::{HashMap, VecDeque};
+use std::collections::VecDeque;
 use std::time::Duration;
 use serde::{Deserialize, Serialize};
 use anyhow::Result;
 
+use crate::dead_air::TrimSuggestion;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PacingDecision {
     pub timestamp: Duration,
@@ -22,6 +24,8 @@ pub enum PacingAction {
     Pause { duration: Duration },
     Emphasize { duration: Duration },
     Transition { style: TransitionStyle },
+    /// Remove `duration` of footage starting at the decision's timestamp
+    Cut { duration: Duration },
     Zoom { target: ZoomTarget, duration: Duration },
     Highlight { elements: Vec<String> },
 }
@@ -42,6 +46,7 @@ pub enum ZoomTarget {
     Browser,
     Editor,
     Cursor,
+    Region { center_x: f64, center_y: f64, zoom: f64 },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -166,6 +171,60 @@ impl DynamicPacingEngine {
         Ok(pacing_decision)
     }
 
+    /// Turn dead-air trim suggestions into pacing decisions: a pause that
+    /// fits its natural length within `max_speed_factor` is sped up and
+    /// played back at normal speed after it, a longer one is cut
+    pub fn plan_dead_air(&mut self, suggestions: &[TrimSuggestion]) -> Vec<PacingDecision> {
+        let mut decisions = Vec::with_capacity(suggestions.len() * 2);
+
+        for suggestion in suggestions.iter().filter(|s| !s.removed().is_zero()) {
+            let factor = suggestion.speed_factor();
+            let intensity = suggestion.removed().as_secs_f64() / suggestion.pause().as_secs_f64();
+
+            if factor <= self.config.max_speed_factor {
+                decisions.push(PacingDecision {
+                    timestamp: suggestion.pause_start,
+                    action: PacingAction::SpeedUp { factor },
+                    intensity,
+                    reasoning: format!(
+                        "Playing a {:.1}s pause at {:.1}x to leave a {:.1}s natural pause",
+                        suggestion.pause().as_secs_f64(),
+                        factor,
+                        suggestion.keep.as_secs_f64()
+                    ),
+                    confidence: 0.9,
+                });
+                decisions.push(PacingDecision {
+                    timestamp: suggestion.pause_end,
+                    action: PacingAction::SpeedUp { factor: 1.0 },
+                    intensity: 0.0,
+                    reasoning: "Back to normal speed as narration resumes".to_string(),
+                    confidence: 0.9,
+                });
+            } else {
+                decisions.push(PacingDecision {
+                    timestamp: suggestion.cut().start,
+                    action: PacingAction::Cut { duration: suggestion.removed() },
+                    intensity,
+                    reasoning: format!(
+                        "Cutting {:.1}s of dead air, keeping a {:.1}s natural pause",
+                        suggestion.removed().as_secs_f64(),
+                        suggestion.keep.as_secs_f64()
+                    ),
+                    confidence: 0.9,
+                });
+            }
+        }
+
+        for decision in &decisions {
+            self.pacing_history.push_back(decision.clone());
+            if self.pacing_history.len() > 1000 {
+                self.pacing_history.pop_front();
+            }
+        }
+        decisions
+    }
+
     /// Analyze current viewer psychological state
     async fn analyze_viewer_state(&self, context: &PacingContext) -> Result<ViewerState> {
         let engagement_trend = self.calculate_engagement_trend(&context.viewer_engagement_history);
@@ -265,7 +324,9 @@ impl DynamicPacingEngine {
         engagement_prediction: EngagementPrediction,
         timestamp: Duration,
     ) -> Result<PacingDecision> {
-        let mut decision_score = HashMap::new();
+        // Candidate actions with their scores; a list rather than a map, as
+        // actions carry float parameters and can't be hashed
+        let mut decision_score = Vec::new();
         
         // Analyze different pacing actions
         self.score_speed_adjustments(&mut decision_score, &viewer_state, &content_complexity);
@@ -276,9 +337,8 @@ impl DynamicPacingEngine {
 
         // Select best action based on scoring
         let best_action = decision_score
-            .iter()
+            .into_iter()
             .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
-            .map(|(action, &score)| (action.clone(), score))
             .unwrap_or((PacingAction::SpeedUp { factor: 1.0 }, 0.5));
 
         let reasoning = self.generate_reasoning(&best_action.0, &viewer_state, &content_complexity);
@@ -295,99 +355,99 @@ impl DynamicPacingEngine {
     /// Score speed adjustment options
     fn score_speed_adjustments(
         &self,
-        scores: &mut HashMap<PacingAction, f64>,
+        scores: &mut Vec<(PacingAction, f64)>,
         viewer_state: &ViewerState,
         content_complexity: &ComplexityMoment,
     ) {
         // Slow down if cognitive load is high or content is complex
         if viewer_state.cognitive_load > self.config.cognitive_load_threshold || content_complexity.current_difficulty > 0.7 {
             let slow_factor = 0.7;
-            scores.insert(
+            scores.push((
                 PacingAction::SlowDown { factor: slow_factor },
                 0.8 + (content_complexity.current_difficulty - 0.5) * 0.4,
-            );
+            ));
         }
 
         // Speed up if content is simple and engagement is dropping
         if content_complexity.current_difficulty < 0.4 && viewer_state.current_engagement < 0.6 {
             let speed_factor = 1.3;
-            scores.insert(
+            scores.push((
                 PacingAction::SpeedUp { factor: speed_factor },
                 0.7 + (0.6 - viewer_state.current_engagement) * 0.5,
-            );
+            ));
         }
     }
 
     /// Score pause insertion options
     fn score_pauses(
         &self,
-        scores: &mut HashMap<PacingAction, f64>,
+        scores: &mut Vec<(PacingAction, f64)>,
         viewer_state: &ViewerState,
         engagement_prediction: &EngagementPrediction,
     ) {
         // Insert pauses if fatigue is high or complex concept just introduced
         if viewer_state.fatigue_level > 0.6 || engagement_prediction.drop_off_risk > 0.7 {
-            scores.insert(
+            scores.push((
                 PacingAction::Pause { duration: Duration::from_secs(2) },
                 0.6 + viewer_state.fatigue_level * 0.3,
-            );
+            ));
         }
     }
 
     /// Score emphasis opportunities
     fn score_emphasis(
         &self,
-        scores: &mut HashMap<PacingAction, f64>,
+        scores: &mut Vec<(PacingAction, f64)>,
         content_complexity: &ComplexityMoment,
     ) {
         // Emphasize important or difficult concepts
         if content_complexity.importance_score > 0.8 {
-            scores.insert(
+            scores.push((
                 PacingAction::Emphasize { duration: Duration::from_secs(3) },
                 content_complexity.importance_score,
-            );
+            ));
         }
     }
 
     /// Score transition improvements
     fn score_transitions(
         &self,
-        scores: &mut HashMap<PacingAction, f64>,
+        scores: &mut Vec<(PacingAction, f64)>,
         viewer_state: &ViewerState,
     ) {
         // Smooth transitions if attention is dropping
         if viewer_state.attention_level < 0.5 {
-            scores.insert(
+            scores.push((
                 PacingAction::Transition { style: TransitionStyle::Smooth },
                 0.5 + (0.5 - viewer_state.attention_level),
-            );
+            ));
         }
     }
 
     /// Score visual enhancement options
     fn score_visual_enhancements(
         &self,
-        scores: &mut HashMap<PacingAction, f64>,
+        scores: &mut Vec<(PacingAction, f64)>,
         viewer_state: &ViewerState,
         content_complexity: &ComplexityMoment,
     ) {
         // Zoom to important code sections
         if content_complexity.has_code_focus && viewer_state.attention_level < 0.6 {
-            scores.insert(
+            scores.push((
                 PacingAction::Zoom { 
                     target: ZoomTarget::Code { line_range: (1, 10) },
                     duration: Duration::from_secs(2)
                 },
                 0.6 + content_complexity.code_importance * 0.3,
-            );
+            ));
         }
 
         // Highlight important elements
         if content_complexity.importance_score > 0.7 && viewer_state.current_engagement < 0.7 {
-            scores.insert(
+            scores.push((
                 PacingAction::Highlight { elements: vec!["cursor".to_string(), "code".to_string()] },
                 0.7 + (content_complexity.importance_score - 0.7) * 0.5,
-            );
+            ));
         }
     }
 
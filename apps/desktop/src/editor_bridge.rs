@@ -0,0 +1,398 @@
+//! Editor bridge for DailyDoco Pro
+//!
+//! Screenshots of code can't be copied, so editor plugins (VS Code, Neovim)
+//! report the exact buffer instead. They connect to a loopback TCP port and
+//! exchange newline-delimited JSON messages:
+//!
+//! - editor → app `{"type":"hello","editor":"nvim","version":"0.10"}`, answered
+//!   with `{"type":"welcome","protocol":1}`
+//! - editor → app `{"type":"snapshot","reason":"focus","path":"src/main.rs",
+//!   "visible_range":[10,42],"cursor_line":12,"text":"…"}`, sent when focus
+//!   changes or in answer to a request; lines are 1-based and inclusive
+//! - app → editor `{"type":"snapshot_request","reason":"marker","label":"…"}`,
+//!   sent to every editor when a marker fires
+//!
+//! Snapshots are timestamped on the capture clock. They become code snippets
+//! for the written docs and player package, and their visible range drives
+//! `ZoomTarget::Code` decisions.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Context, Result};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::dynamic_pacing_engine::{PacingAction, PacingDecision, ZoomTarget};
+use crate::session::CodeSnippet;
+use crate::syntax_highlight::Language;
+
+pub const PROTOCOL_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EditorBridgeConfig {
+    pub enabled: bool,
+    /// Loopback port to listen on, 0 picks a free one
+    pub port: u16,
+    /// Longer messages are rejected and the connection closed
+    pub max_message_bytes: usize,
+}
+
+impl Default for EditorBridgeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            port: 47_321,
+            max_message_bytes: 4 * 1024 * 1024,
+        }
+    }
+}
+
+/// Why the editor sent a snapshot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotReason {
+    /// The editor gained focus or switched buffers
+    Focus,
+    /// Answer to a `snapshot_request` sent when a marker fired
+    Marker,
+}
+
+/// Messages editors send to the app
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EditorMessage {
+    Hello {
+        editor: String,
+        #[serde(default)]
+        version: Option<String>,
+    },
+    Snapshot {
+        reason: SnapshotReason,
+        path: String,
+        /// First and last visible line, 1-based and inclusive
+        visible_range: (usize, usize),
+        #[serde(default)]
+        cursor_line: Option<usize>,
+        text: String,
+    },
+}
+
+/// Messages the app sends to editors
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BridgeMessage {
+    Welcome { protocol: u32 },
+    SnapshotRequest { reason: SnapshotReason, label: String },
+    Error { message: String },
+}
+
+/// The active buffer of an editor at one moment of the session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EditorSnapshot {
+    /// Capture-clock time the snapshot arrived
+    pub timestamp: Duration,
+    /// Editor name from its hello, empty if it never said hello
+    pub editor: String,
+    pub reason: SnapshotReason,
+    pub path: String,
+    pub visible_range: (usize, usize),
+    pub cursor_line: Option<usize>,
+    pub text: String,
+}
+
+impl EditorSnapshot {
+    /// The visible lines, exactly as they were in the buffer
+    pub fn visible_text(&self) -> String {
+        let (start, end) = self.visible_range;
+        let lines: Vec<&str> = self
+            .text
+            .lines()
+            .skip(start.saturating_sub(1))
+            .take(end.saturating_sub(start.max(1)) + 1)
+            .collect();
+        lines.join("\n")
+    }
+
+    /// The visible lines as a snippet for docs and player packages
+    pub fn to_snippet(&self) -> CodeSnippet {
+        CodeSnippet {
+            timestamp: self.timestamp,
+            path: Some(self.path.clone()),
+            language: Language::from_path(Path::new(&self.path)),
+            code: self.visible_text(),
+        }
+    }
+}
+
+/// Zoom to each snapshot's visible lines until the next snapshot, holding
+/// for at most `max_hold`
+pub fn pacing_decisions(snapshots: &[EditorSnapshot], max_hold: Duration) -> Vec<PacingDecision> {
+    let mut sorted: Vec<&EditorSnapshot> = snapshots.iter().collect();
+    sorted.sort_by_key(|snapshot| snapshot.timestamp);
+
+    sorted
+        .iter()
+        .enumerate()
+        .map(|(index, snapshot)| {
+            let hold = sorted
+                .get(index + 1)
+                .map(|next| next.timestamp.saturating_sub(snapshot.timestamp).min(max_hold))
+                .unwrap_or(max_hold);
+            let (start, end) = snapshot.visible_range;
+            PacingDecision {
+                timestamp: snapshot.timestamp,
+                action: PacingAction::Zoom {
+                    target: ZoomTarget::Code { line_range: (start, end) },
+                    duration: hold,
+                },
+                intensity: if snapshot.reason == SnapshotReason::Marker { 0.8 } else { 0.5 },
+                reasoning: format!("Editor showed {} lines {}-{}", snapshot.path, start, end),
+                confidence: 0.95,
+            }
+        })
+        .collect()
+}
+
+type Clients = Arc<Mutex<Vec<TcpStream>>>;
+
+/// Listens for editor plugins on a loopback port
+pub struct EditorBridge {
+    address: SocketAddr,
+    accept: Option<JoinHandle<()>>,
+    clients: Clients,
+    snapshots: Arc<Mutex<Vec<EditorSnapshot>>>,
+    running: Arc<AtomicBool>,
+}
+
+impl EditorBridge {
+    /// Start listening; `capture_origin` is the instant the capture clock reads zero
+    pub fn bind(config: &EditorBridgeConfig, capture_origin: Instant) -> Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, config.port))
+            .with_context(|| format!("Binding editor bridge to port {}", config.port))?;
+        let address = listener.local_addr()?;
+
+        let clients: Clients = Arc::new(Mutex::new(Vec::new()));
+        let snapshots = Arc::new(Mutex::new(Vec::new()));
+        let running = Arc::new(AtomicBool::new(true));
+        let accept = std::thread::spawn({
+            let (clients, snapshots, running) = (Arc::clone(&clients), Arc::clone(&snapshots), Arc::clone(&running));
+            let max_message_bytes = config.max_message_bytes;
+            move || {
+                for stream in listener.incoming() {
+                    if !running.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let Ok(stream) = stream else { continue };
+                    let Ok(writer) = stream.try_clone() else { continue };
+                    clients.lock().push(writer);
+
+                    let snapshots = Arc::clone(&snapshots);
+                    std::thread::spawn(move || {
+                        if let Err(e) = serve_editor(stream, capture_origin, max_message_bytes, snapshots) {
+                            log::warn!("Editor bridge connection closed: {}", e);
+                        }
+                    });
+                }
+            }
+        });
+
+        log::info!("📝 Editor bridge listening on {}", address);
+        Ok(Self {
+            address,
+            accept: Some(accept),
+            clients,
+            snapshots,
+            running,
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Ask every connected editor for a snapshot, e.g. when a marker fires.
+    /// Returns how many editors were asked.
+    pub fn request_snapshots(&self, label: &str) -> usize {
+        let request = BridgeMessage::SnapshotRequest {
+            reason: SnapshotReason::Marker,
+            label: label.to_string(),
+        };
+        let mut clients = self.clients.lock();
+        // Editors that went away are dropped on the first failed write
+        clients.retain_mut(|client| send(client, &request).is_ok());
+        clients.len()
+    }
+
+    /// Snapshots received so far, in arrival order
+    pub fn snapshots(&self) -> Vec<EditorSnapshot> {
+        self.snapshots.lock().clone()
+    }
+
+    /// Stop listening and return every snapshot received
+    pub fn stop(mut self) -> Result<Vec<EditorSnapshot>> {
+        self.running.store(false, Ordering::SeqCst);
+        for client in self.clients.lock().drain(..) {
+            let _ = client.shutdown(std::net::Shutdown::Both);
+        }
+        // Wake the accept loop so it sees the flag
+        let _ = TcpStream::connect(self.address);
+        if let Some(accept) = self.accept.take() {
+            accept.join().map_err(|_| anyhow!("Editor bridge thread panicked"))?;
+        }
+        Ok(std::mem::take(&mut *self.snapshots.lock()))
+    }
+}
+
+/// Connection thread: read messages until the editor disconnects
+fn serve_editor(
+    stream: TcpStream,
+    capture_origin: Instant,
+    max_message_bytes: usize,
+    snapshots: Arc<Mutex<Vec<EditorSnapshot>>>,
+) -> Result<()> {
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    let mut editor = String::new();
+    let mut line = Vec::new();
+
+    loop {
+        line.clear();
+        let read = (&mut reader).take(max_message_bytes as u64 + 1).read_until(b'\n', &mut line)?;
+        if read == 0 {
+            return Ok(());
+        }
+        if line.len() > max_message_bytes {
+            let message = format!("Message longer than {} bytes", max_message_bytes);
+            send(&mut writer, &BridgeMessage::Error { message: message.clone() })?;
+            bail!(message);
+        }
+        if line.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+
+        match serde_json::from_slice::<EditorMessage>(&line) {
+            Ok(EditorMessage::Hello { editor: name, version }) => {
+                log::info!("📝 {} {} connected", name, version.as_deref().unwrap_or_default());
+                editor = name;
+                send(&mut writer, &BridgeMessage::Welcome { protocol: PROTOCOL_VERSION })?;
+            }
+            Ok(EditorMessage::Snapshot { reason, path, visible_range, cursor_line, text }) => {
+                snapshots.lock().push(EditorSnapshot {
+                    timestamp: capture_origin.elapsed(),
+                    editor: editor.clone(),
+                    reason,
+                    path,
+                    visible_range: (visible_range.0.min(visible_range.1), visible_range.0.max(visible_range.1)),
+                    cursor_line,
+                    text,
+                });
+            }
+            Err(e) => send(&mut writer, &BridgeMessage::Error { message: format!("Invalid message: {}", e) })?,
+        }
+    }
+}
+
+fn send(stream: &mut TcpStream, message: &BridgeMessage) -> Result<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    stream.write_all(&line)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(seconds: u64, range: (usize, usize)) -> EditorSnapshot {
+        EditorSnapshot {
+            timestamp: Duration::from_secs(seconds),
+            editor: "nvim".to_string(),
+            reason: SnapshotReason::Focus,
+            path: "src/lib.rs".to_string(),
+            visible_range: range,
+            cursor_line: None,
+            text: (1..=10).map(|i| format!("line {}", i)).collect::<Vec<_>>().join("\n"),
+        }
+    }
+
+    fn read_message(reader: &mut BufReader<TcpStream>) -> BridgeMessage {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        serde_json::from_str(&line).unwrap()
+    }
+
+    #[test]
+    fn test_snapshot_becomes_snippet() {
+        let snippet = snapshot(3, (4, 6)).to_snippet();
+
+        assert_eq!(snippet.code, "line 4\nline 5\nline 6");
+        assert_eq!(snippet.language, Language::Rust);
+        assert_eq!(snippet.timestamp, Duration::from_secs(3));
+        // A range past the end of the buffer keeps what is there
+        assert_eq!(snapshot(0, (9, 40)).visible_text(), "line 9\nline 10");
+    }
+
+    #[test]
+    fn test_visible_range_drives_code_zoom() {
+        let decisions = pacing_decisions(&[snapshot(20, (30, 60)), snapshot(5, (1, 25))], Duration::from_secs(8));
+
+        assert_eq!(decisions.len(), 2);
+        assert_eq!(decisions[0].timestamp, Duration::from_secs(5));
+        match &decisions[0].action {
+            PacingAction::Zoom { target: ZoomTarget::Code { line_range }, duration } => {
+                assert_eq!(*line_range, (1, 25));
+                assert_eq!(*duration, Duration::from_secs(8));
+            }
+            other => panic!("unexpected action {:?}", other),
+        }
+        assert!(decisions[1].reasoning.contains("lines 30-60"));
+    }
+
+    #[test]
+    fn test_bridge_protocol_round_trip() {
+        let config = EditorBridgeConfig {
+            port: 0,
+            ..Default::default()
+        };
+        let bridge = EditorBridge::bind(&config, Instant::now()).unwrap();
+        let stream = TcpStream::connect(bridge.local_addr()).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let mut writer = stream.try_clone().unwrap();
+        let mut reader = BufReader::new(stream);
+
+        writer.write_all(b"{\"type\":\"hello\",\"editor\":\"vscode\"}\n").unwrap();
+        assert_eq!(read_message(&mut reader), BridgeMessage::Welcome { protocol: PROTOCOL_VERSION });
+        writer.write_all(b"not json\n").unwrap();
+        assert!(matches!(read_message(&mut reader), BridgeMessage::Error { .. }));
+
+        assert_eq!(bridge.request_snapshots("Fix the parser"), 1);
+        match read_message(&mut reader) {
+            BridgeMessage::SnapshotRequest { reason, label } => {
+                assert_eq!(reason, SnapshotReason::Marker);
+                assert_eq!(label, "Fix the parser");
+            }
+            other => panic!("unexpected message {:?}", other),
+        }
+        let snapshot = r#"{"type":"snapshot","reason":"marker","path":"src/parse.rs","visible_range":[12,3],"#;
+        writer.write_all(format!("{}\"text\":\"fn parse() {{}}\"}}\n", snapshot).as_bytes()).unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while bridge.snapshots().is_empty() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let snapshots = bridge.stop().unwrap();
+        // Stopping closes the connection
+        assert_eq!(reader.read(&mut [0u8; 1]).unwrap(), 0);
+
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].editor, "vscode");
+        assert_eq!(snapshots[0].path, "src/parse.rs");
+        assert_eq!(snapshots[0].visible_range, (3, 12));
+    }
+}
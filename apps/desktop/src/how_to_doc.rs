@@ -0,0 +1,572 @@
+//! Step-by-step how-to documents for DailyDoco Pro
+//!
+//! Turns a session record into a written guide: one step per selected
+//! segment, each with a heading, a keyframe screenshot, the commands and
+//! code involved, and a link back to that moment in the exported video.
+
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use anyhow::{Context, Result};
+
+use crate::canvas::save_png;
+use crate::intelligent_clip_selector::{EventType, VideoSegment};
+use crate::session::{
+    encode_url, escape_html, format_timestamp, CodeSnippet, FrameSource, SessionCommand, SessionCommit, SessionRecord,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HowToConfig {
+    /// Link target for the video, defaults to the video's file name
+    pub video_url: Option<String>,
+    /// Screenshot directory, relative to the output directory
+    pub image_dir: String,
+    pub include_narration: bool,
+    pub include_command_output: bool,
+    /// Longer command output is truncated to its last lines
+    pub max_output_lines: usize,
+}
+
+impl Default for HowToConfig {
+    fn default() -> Self {
+        Self {
+            video_url: None,
+            image_dir: "images".to_string(),
+            include_narration: true,
+            include_command_output: true,
+            max_output_lines: 12,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocStep {
+    pub number: usize,
+    pub heading: String,
+    pub source_start: Duration,
+    pub source_end: Duration,
+    /// Where the step starts in the exported video
+    pub video_start: Duration,
+    pub video_end: Duration,
+    /// Recording time the screenshot is taken at
+    pub keyframe_time: Duration,
+    /// Screenshot path relative to the document
+    pub screenshot: Option<String>,
+    pub narration: String,
+    pub commands: Vec<SessionCommand>,
+    pub snippets: Vec<CodeSnippet>,
+    pub commits: Vec<SessionCommit>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HowToDocument {
+    pub title: String,
+    pub video_url: String,
+    pub steps: Vec<DocStep>,
+}
+
+pub struct HowToGenerator {
+    config: HowToConfig,
+}
+
+impl HowToGenerator {
+    pub fn new(config: HowToConfig) -> Self {
+        Self { config }
+    }
+
+    /// Lay out the steps without taking screenshots
+    pub fn build(&self, record: &SessionRecord) -> HowToDocument {
+        let video_url = self.config.video_url.clone().unwrap_or_else(|| {
+            record
+                .video_path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        });
+
+        let mut video_start = Duration::ZERO;
+        let mut steps = Vec::with_capacity(record.segments.len());
+
+        for (index, segment) in record.segments.iter().enumerate() {
+            let (start, end) = (segment.start_time, segment.end_time);
+            let length = end.saturating_sub(start);
+
+            let narration = if self.config.include_narration {
+                record.transcript_between(start, end)
+            } else {
+                String::new()
+            };
+
+            let mut commands: Vec<SessionCommand> = record.commands_between(start, end).into_iter().cloned().collect();
+            for command in &mut commands {
+                command.output = if self.config.include_command_output {
+                    command.output.as_deref().map(|output| tail_lines(output, self.config.max_output_lines))
+                } else {
+                    None
+                };
+            }
+
+            steps.push(DocStep {
+                number: index + 1,
+                heading: step_heading(record, segment),
+                source_start: start,
+                source_end: end,
+                video_start,
+                video_end: video_start + length,
+                keyframe_time: keyframe_time(segment),
+                screenshot: None,
+                narration,
+                commands,
+                snippets: record.snippets_between(start, end).into_iter().cloned().collect(),
+                commits: record.commits_between(start, end).into_iter().cloned().collect(),
+            });
+            video_start += length;
+        }
+
+        HowToDocument {
+            title: record.title.clone(),
+            video_url,
+            steps,
+        }
+    }
+
+    /// Build the document, grab a screenshot per step and write
+    /// `index.md` and `index.html` into `out_dir`
+    pub fn generate(
+        &self,
+        record: &SessionRecord,
+        frames: Option<&mut dyn FrameSource>,
+        out_dir: &Path,
+    ) -> Result<HowToDocument> {
+        let mut document = self.build(record);
+        std::fs::create_dir_all(out_dir).with_context(|| format!("Creating {}", out_dir.display()))?;
+
+        if let Some(frames) = frames {
+            self.capture_screenshots(&mut document, frames, out_dir)?;
+        }
+
+        document.write(out_dir)?;
+        Ok(document)
+    }
+
+    /// Save a keyframe per step under the image directory of `out_dir`
+    /// and point each step's screenshot at it
+    pub fn capture_screenshots(
+        &self,
+        document: &mut HowToDocument,
+        frames: &mut dyn FrameSource,
+        out_dir: &Path,
+    ) -> Result<()> {
+        let image_dir = out_dir.join(&self.config.image_dir);
+        std::fs::create_dir_all(&image_dir).with_context(|| format!("Creating {}", image_dir.display()))?;
+
+        for step in &mut document.steps {
+            let name = format!("step-{:02}.png", step.number);
+            let frame = frames.frame_at(step.keyframe_time)?;
+            save_png(&frame, &image_dir.join(&name))?;
+            step.screenshot = Some(format!("{}/{}", self.config.image_dir, name));
+        }
+        Ok(())
+    }
+}
+
+impl Default for HowToGenerator {
+    fn default() -> Self {
+        Self::new(HowToConfig::default())
+    }
+}
+
+impl HowToDocument {
+    /// Link to a moment in the video using a media fragment
+    pub fn video_link(&self, timestamp: Duration) -> String {
+        format!("{}#t={:.1}", encode_url(&self.video_url), timestamp.as_secs_f64())
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::new();
+        let _ = writeln!(md, "# {}\n", self.title);
+        if !self.video_url.is_empty() {
+            let _ = writeln!(md, "Watch the full video: [{}]({})\n", self.video_url, encode_url(&self.video_url));
+        }
+
+        for step in &self.steps {
+            let _ = writeln!(md, "## {}. {}\n", step.number, step.heading);
+            let _ = writeln!(
+                md,
+                "[▶ {}]({}) – {}\n",
+                format_timestamp(step.video_start),
+                self.video_link(step.video_start),
+                format_timestamp(step.video_end)
+            );
+
+            if let Some(screenshot) = &step.screenshot {
+                let _ = writeln!(md, "![Step {}: {}]({})\n", step.number, step.heading, screenshot);
+            }
+            if !step.narration.is_empty() {
+                let _ = writeln!(md, "{}\n", step.narration);
+            }
+
+            if !step.commands.is_empty() {
+                let mut block = String::new();
+                for command in &step.commands {
+                    let _ = writeln!(block, "$ {}", command.command);
+                    if let Some(output) = &command.output {
+                        let _ = writeln!(block, "{}", output);
+                    }
+                }
+                md.push_str(&fenced(&block, "shell"));
+            }
+
+            for snippet in &step.snippets {
+                if let Some(path) = &snippet.path {
+                    let _ = writeln!(md, "`{}`\n", path);
+                }
+                md.push_str(&fenced(&snippet.code, snippet.language.name()));
+            }
+
+            if !step.commits.is_empty() {
+                md.push_str("Commits:\n\n");
+                for commit in &step.commits {
+                    let _ = writeln!(md, "- `{}` {}", short_hash(&commit.hash), commit.summary);
+                }
+                md.push('\n');
+            }
+        }
+
+        md
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        let title = escape_html(&self.title);
+        let _ = writeln!(html, "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">");
+        let _ = writeln!(html, "<title>{}</title>\n<style>{}</style>\n</head>\n<body>", title, HTML_STYLE);
+        let _ = writeln!(html, "<h1>{}</h1>", title);
+        if !self.video_url.is_empty() {
+            let _ = writeln!(
+                html,
+                "<p>Watch the full video: <a href=\"{}\">{}</a></p>",
+                escape_html(&encode_url(&self.video_url)),
+                escape_html(&self.video_url)
+            );
+        }
+
+        html.push_str(&self.steps_html());
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+
+    /// Step sections without the surrounding page, for embedding. Time
+    /// links carry `data-time` so an embedding page can seek its player.
+    pub fn steps_html(&self) -> String {
+        let mut html = String::new();
+
+        for step in &self.steps {
+            let heading = escape_html(&step.heading);
+            let _ = writeln!(html, "<section class=\"step\" id=\"step-{}\">", step.number);
+            let _ = writeln!(html, "<h2>{}. {}</h2>", step.number, heading);
+            let _ = writeln!(
+                html,
+                "<p class=\"time\"><a href=\"{}\" data-time=\"{:.1}\">▶ {}</a> – {}</p>",
+                escape_html(&self.video_link(step.video_start)),
+                step.video_start.as_secs_f64(),
+                format_timestamp(step.video_start),
+                format_timestamp(step.video_end)
+            );
+
+            if let Some(screenshot) = &step.screenshot {
+                let _ = writeln!(
+                    html,
+                    "<img src=\"{}\" alt=\"Step {}: {}\">",
+                    escape_html(screenshot),
+                    step.number,
+                    heading
+                );
+            }
+            if !step.narration.is_empty() {
+                let _ = writeln!(html, "<p>{}</p>", escape_html(&step.narration));
+            }
+
+            if !step.commands.is_empty() {
+                html.push_str("<pre><code class=\"language-shell\">");
+                for command in &step.commands {
+                    let _ = writeln!(html, "$ {}", escape_html(&command.command));
+                    if let Some(output) = &command.output {
+                        let _ = writeln!(html, "{}", escape_html(output));
+                    }
+                }
+                html.push_str("</code></pre>\n");
+            }
+
+            for snippet in &step.snippets {
+                if let Some(path) = &snippet.path {
+                    let _ = writeln!(html, "<p class=\"path\">{}</p>", escape_html(path));
+                }
+                let _ = writeln!(
+                    html,
+                    "<pre><code class=\"language-{}\">{}</code></pre>",
+                    snippet.language.name(),
+                    escape_html(snippet.code.trim_end())
+                );
+            }
+
+            if !step.commits.is_empty() {
+                html.push_str("<ul class=\"commits\">\n");
+                for commit in &step.commits {
+                    let _ = writeln!(
+                        html,
+                        "<li><code>{}</code> {}</li>",
+                        escape_html(short_hash(&commit.hash)),
+                        escape_html(&commit.summary)
+                    );
+                }
+                html.push_str("</ul>\n");
+            }
+            html.push_str("</section>\n");
+        }
+
+        html
+    }
+
+    /// Write `index.md` and `index.html`, returning their paths
+    pub fn write(&self, out_dir: &Path) -> Result<(PathBuf, PathBuf)> {
+        let markdown = out_dir.join("index.md");
+        let html = out_dir.join("index.html");
+        std::fs::write(&markdown, self.to_markdown()).with_context(|| format!("Writing {}", markdown.display()))?;
+        std::fs::write(&html, self.to_html()).with_context(|| format!("Writing {}", html.display()))?;
+        Ok((markdown, html))
+    }
+}
+
+const HTML_STYLE: &str = "body{font-family:system-ui,sans-serif;max-width:860px;margin:2rem auto;padding:0 1rem;\
+line-height:1.5;color:#1f2328}img{max-width:100%;border:1px solid #d0d7de;border-radius:6px}\
+pre{background:#f6f8fa;padding:12px;border-radius:6px;overflow-x:auto}.time{color:#57606a}\
+.path{font-family:monospace;margin-bottom:0}";
+
+/// Marker label, else commit summary, else a heading for the main event
+fn step_heading(record: &SessionRecord, segment: &VideoSegment) -> String {
+    let (start, end) = (segment.start_time, segment.end_time);
+    if let Some(marker) = record.markers_between(start, end).first() {
+        return marker.label.clone();
+    }
+    if let Some(commit) = record.commits_between(start, end).first() {
+        return commit.summary.clone();
+    }
+
+    let main_event = segment
+        .events
+        .iter()
+        .max_by(|a, b| a.score.total_cmp(&b.score))
+        .map(|event| &event.event_type);
+    match main_event {
+        Some(event_type) => event_heading(event_type).to_string(),
+        None => "Continue".to_string(),
+    }
+}
+
+fn event_heading(event_type: &EventType) -> &'static str {
+    match event_type {
+        EventType::CodeGeneration => "Write the code",
+        EventType::Debugging => "Debug the problem",
+        EventType::Testing => "Run the tests",
+        EventType::Refactoring => "Refactor",
+        EventType::Documentation => "Document the change",
+        EventType::ErrorResolution => "Fix the error",
+        EventType::BreakthroughMoment => "Get it working",
+        EventType::LearningMoment => "Understand how it works",
+        EventType::CollaborativeMoment => "Review together",
+        EventType::DeploymentMoment => "Deploy",
+    }
+}
+
+/// The most important event in the segment, or its midpoint
+fn keyframe_time(segment: &VideoSegment) -> Duration {
+    let midpoint = segment.start_time + segment.end_time.saturating_sub(segment.start_time) / 2;
+    segment
+        .events
+        .iter()
+        .filter(|event| event.timestamp >= segment.start_time && event.timestamp < segment.end_time)
+        .max_by(|a, b| a.score.total_cmp(&b.score))
+        .map(|event| event.timestamp)
+        .unwrap_or(midpoint)
+}
+
+/// Fence code for Markdown, lengthening the fence if the code contains one
+fn fenced(code: &str, language: &str) -> String {
+    let mut fence = "```".to_string();
+    while code.contains(&fence) {
+        fence.push('`');
+    }
+    format!("{}{}\n{}\n{}\n\n", fence, language, code.trim_end(), fence)
+}
+
+fn tail_lines(text: &str, max_lines: usize) -> String {
+    let lines: Vec<&str> = text.trim_end().lines().collect();
+    if lines.len() <= max_lines {
+        return lines.join("\n");
+    }
+    let mut tail = vec!["…"];
+    tail.extend_from_slice(&lines[lines.len() - max_lines..]);
+    tail.join("\n")
+}
+
+fn short_hash(hash: &str) -> &str {
+    &hash[..hash.len().min(7)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use aegnt_27::visual::VideoFrame;
+    use chrono::Utc;
+    use shared_types::CaptureSession;
+    use uuid::Uuid;
+
+    use crate::canvas::{solid_frame, Rgba};
+    use crate::intelligent_clip_selector::ImportanceScore;
+    use crate::session::SessionMarker;
+    use crate::syntax_highlight::Language;
+
+    fn segment(start: u64, end: u64, events: Vec<(u64, f64, EventType)>) -> VideoSegment {
+        VideoSegment {
+            start_time: Duration::from_secs(start),
+            end_time: Duration::from_secs(end),
+            importance_score: 0.8,
+            events: events
+                .into_iter()
+                .map(|(timestamp, score, event_type)| ImportanceScore {
+                    timestamp: Duration::from_secs(timestamp),
+                    score,
+                    confidence: 0.9,
+                    event_type,
+                    context: HashMap::new(),
+                })
+                .collect(),
+            narrative_weight: 0.5,
+            viewer_engagement_prediction: 0.5,
+        }
+    }
+
+    fn record() -> SessionRecord {
+        let session = CaptureSession {
+            id: Uuid::new_v4(),
+            project_id: Uuid::new_v4(),
+            started_at: Utc::now(),
+            duration: None,
+        };
+        let mut record = SessionRecord::new(session, "Add a <config> loader", "/tmp/exports/session.mp4");
+        record.segments = vec![
+            segment(10, 40, vec![(15, 0.4, EventType::CodeGeneration), (30, 0.9, EventType::CodeGeneration)]),
+            segment(100, 120, vec![(110, 0.7, EventType::Testing)]),
+            segment(200, 230, vec![]),
+        ];
+        record.markers.push(SessionMarker {
+            timestamp: Duration::from_secs(12),
+            label: "Parse the config file".to_string(),
+        });
+        record.commands.push(SessionCommand {
+            timestamp: Duration::from_secs(105),
+            command: "cargo test config".to_string(),
+            output: Some((1..=20).map(|i| format!("line {}", i)).collect::<Vec<_>>().join("\n")),
+        });
+        record.commits.push(SessionCommit {
+            timestamp: Duration::from_secs(210),
+            hash: "0123456789abcdef".to_string(),
+            summary: "Load config from disk".to_string(),
+            files: vec!["src/config.rs".to_string()],
+        });
+        record.snippets.push(CodeSnippet {
+            timestamp: Duration::from_secs(20),
+            path: Some("src/config.rs".to_string()),
+            language: Language::Rust,
+            code: "fn load() -> Config {\n    todo!()\n}\n".to_string(),
+        });
+        record
+    }
+
+    struct Solid;
+
+    impl FrameSource for Solid {
+        fn frame_at(&mut self, timestamp: Duration) -> Result<VideoFrame> {
+            Ok(solid_frame(32, 18, Rgba::new(40, 80, 120, 255), timestamp))
+        }
+    }
+
+    #[test]
+    fn test_steps_follow_segments() {
+        let document = HowToGenerator::default().build(&record());
+        assert_eq!(document.video_url, "session.mp4");
+        assert_eq!(document.steps.len(), 3);
+
+        let headings: Vec<&str> = document.steps.iter().map(|s| s.heading.as_str()).collect();
+        assert_eq!(headings, ["Parse the config file", "Run the tests", "Load config from disk"]);
+
+        // Video times follow the cut, not the recording
+        assert_eq!(document.steps[1].video_start, Duration::from_secs(30));
+        assert_eq!(document.steps[2].video_start, Duration::from_secs(50));
+        assert_eq!(document.steps[0].keyframe_time, Duration::from_secs(30));
+        assert_eq!(document.steps[2].keyframe_time, Duration::from_secs(215));
+
+        let output = document.steps[1].commands[0].output.as_deref().unwrap();
+        assert!(output.starts_with('…') && output.ends_with("line 20"));
+        assert!(!output.contains("line 8\n"));
+    }
+
+    #[test]
+    fn test_markdown_and_html_output() {
+        let document = HowToGenerator::default().build(&record());
+
+        let markdown = document.to_markdown();
+        assert!(markdown.contains("## 2. Run the tests"));
+        assert!(markdown.contains("[▶ 0:30](session.mp4#t=30.0)"));
+        assert!(markdown.contains("```rust\nfn load() -> Config {"));
+        assert!(markdown.contains("$ cargo test config"));
+        assert!(markdown.contains("- `0123456` Load config from disk"));
+
+        let html = document.to_html();
+        assert!(html.contains("<h1>Add a &lt;config&gt; loader</h1>"));
+        assert!(html.contains("fn load() -&gt; Config {"));
+        assert!(html.contains("href=\"session.mp4#t=50.0\""));
+    }
+
+    #[test]
+    fn test_video_links_are_percent_encoded() {
+        let config = HowToConfig {
+            video_url: Some("takes/Demo (take 2) 100%.mp4?sig=a%2Fb".to_string()),
+            ..Default::default()
+        };
+        let document = HowToGenerator::new(config).build(&record());
+
+        let url = "takes/Demo%20%28take%202%29%20100%25.mp4?sig=a%2Fb";
+        let markdown = document.to_markdown();
+        assert!(markdown.contains(&format!("[takes/Demo (take 2) 100%.mp4?sig=a%2Fb]({})", url)));
+        assert!(markdown.contains(&format!("[▶ 0:30]({}#t=30.0)", url)));
+        assert!(document.to_html().contains(&format!("href=\"{}#t=50.0\"", url)));
+    }
+
+    #[test]
+    fn test_generate_writes_screenshots() {
+        let out_dir = std::env::temp_dir().join(format!("dailydoco-howto-{}", Uuid::new_v4()));
+        let mut frames = Solid;
+        let document = HowToGenerator::default()
+            .generate(&record(), Some(&mut frames), &out_dir)
+            .unwrap();
+
+        assert_eq!(document.steps[0].screenshot.as_deref(), Some("images/step-01.png"));
+        assert!(out_dir.join("images/step-03.png").exists());
+        let markdown = std::fs::read_to_string(out_dir.join("index.md")).unwrap();
+        assert!(markdown.contains("![Step 1: Parse the config file](images/step-01.png)"));
+        assert!(out_dir.join("index.html").exists());
+
+        std::fs::remove_dir_all(&out_dir).unwrap();
+    }
+
+    #[test]
+    fn test_fence_survives_backticks() {
+        let fenced = fenced("let s = \"```\";", "rust");
+        assert!(fenced.starts_with("````rust\n"));
+        assert!(fenced.trim_end().ends_with("````"));
+    }
+}
@@ -0,0 +1,185 @@
+//! Recorded input events for DailyDoco Pro
+//!
+//! Pointer and window-focus events captured alongside the video, timestamped
+//! against the capture clock so post-processing can line them up with frames.
+//! Key events are never recorded while a redacted window, such as a password
+//! manager, has focus.
+
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+
+/// Mouse buttons that can appear in the log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+}
+
+/// On-screen rectangle of a window in capture pixels
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl WindowGeometry {
+    pub fn new(x: i32, y: i32, width: u32, height: u32) -> Self {
+        Self { x, y, width, height }
+    }
+
+    pub fn center(&self) -> (f64, f64) {
+        (
+            self.x as f64 + self.width as f64 / 2.0,
+            self.y as f64 + self.height as f64 / 2.0,
+        )
+    }
+
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        x >= self.x as f64
+            && y >= self.y as f64
+            && x < self.x as f64 + self.width as f64
+            && y < self.y as f64 + self.height as f64
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum InputEventKind {
+    PointerMove { x: i32, y: i32 },
+    ButtonDown { button: MouseButton, x: i32, y: i32 },
+    ButtonUp { button: MouseButton, x: i32, y: i32 },
+    Scroll { x: i32, y: i32, delta_x: f64, delta_y: f64 },
+    /// Physical key press; `keycode` is the evdev / PC scan code
+    KeyDown { keycode: u16 },
+    KeyUp { keycode: u16 },
+    WindowFocus { title: String, geometry: WindowGeometry },
+}
+
+/// A single recorded input event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InputEvent {
+    /// Time since capture start, on the same clock as video frames
+    pub timestamp: Duration,
+    pub kind: InputEventKind,
+}
+
+impl InputEvent {
+    pub fn new(timestamp: Duration, kind: InputEventKind) -> Self {
+        Self { timestamp, kind }
+    }
+
+    /// Pointer position carried by this event, if any
+    pub fn position(&self) -> Option<(i32, i32)> {
+        match self.kind {
+            InputEventKind::PointerMove { x, y }
+            | InputEventKind::ButtonDown { x, y, .. }
+            | InputEventKind::ButtonUp { x, y, .. }
+            | InputEventKind::Scroll { x, y, .. } => Some((x, y)),
+            InputEventKind::KeyDown { .. }
+            | InputEventKind::KeyUp { .. }
+            | InputEventKind::WindowFocus { .. } => None,
+        }
+    }
+}
+
+/// Time-ordered log of input events for one capture session
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InputEventLog {
+    pub events: Vec<InputEvent>,
+    /// Case-insensitive window title fragments whose key events are dropped
+    #[serde(skip)]
+    pub redacted_windows: Vec<String>,
+}
+
+impl InputEventLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Log that drops key events while a window matching one of
+    /// `redacted_windows` has focus
+    pub fn with_redacted_windows(redacted_windows: Vec<String>) -> Self {
+        Self {
+            events: Vec::new(),
+            redacted_windows,
+        }
+    }
+
+    /// Append an event, keeping the log sorted by timestamp; key events in a
+    /// redacted window are discarded
+    pub fn record(&mut self, event: InputEvent) {
+        let is_key = matches!(event.kind, InputEventKind::KeyDown { .. } | InputEventKind::KeyUp { .. });
+        if is_key && self.is_redacted_at(event.timestamp) {
+            return;
+        }
+        let index = self
+            .events
+            .partition_point(|existing| existing.timestamp <= event.timestamp);
+        self.events.insert(index, event);
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.events.last().map(|e| e.timestamp).unwrap_or(Duration::ZERO)
+    }
+
+    /// All pointer positions in time order
+    pub fn pointer_positions(&self) -> Vec<(Duration, i32, i32)> {
+        self.events
+            .iter()
+            .filter_map(|e| e.position().map(|(x, y)| (e.timestamp, x, y)))
+            .collect()
+    }
+
+    /// Pointer position at `timestamp`, holding the last known position
+    pub fn pointer_at(&self, timestamp: Duration) -> Option<(i32, i32)> {
+        self.events
+            .iter()
+            .take_while(|e| e.timestamp <= timestamp)
+            .filter_map(|e| e.position())
+            .last()
+    }
+
+    /// Focused window geometry at `timestamp`
+    pub fn window_at(&self, timestamp: Duration) -> Option<WindowGeometry> {
+        self.events
+            .iter()
+            .take_while(|e| e.timestamp <= timestamp)
+            .filter_map(|e| match &e.kind {
+                InputEventKind::WindowFocus { geometry, .. } => Some(*geometry),
+                _ => None,
+            })
+            .last()
+    }
+
+    /// Focused window title at `timestamp`
+    pub fn window_title_at(&self, timestamp: Duration) -> Option<&str> {
+        self.events
+            .iter()
+            .take_while(|e| e.timestamp <= timestamp)
+            .filter_map(|e| match &e.kind {
+                InputEventKind::WindowFocus { title, .. } => Some(title.as_str()),
+                _ => None,
+            })
+            .last()
+    }
+
+    /// Whether the window focused at `timestamp` is redacted
+    pub fn is_redacted_at(&self, timestamp: Duration) -> bool {
+        self.window_title_at(timestamp).is_some_and(|title| is_redacted_title(&self.redacted_windows, title))
+    }
+
+    /// Events inside `[start, end)`
+    pub fn range(&self, start: Duration, end: Duration) -> &[InputEvent] {
+        let from = self.events.partition_point(|e| e.timestamp < start);
+        let to = self.events.partition_point(|e| e.timestamp < end);
+        &self.events[from..to.max(from)]
+    }
+}
+
+/// Whether `title` contains any of the case-insensitive `fragments`
+pub fn is_redacted_title(fragments: &[String], title: &str) -> bool {
+    let title = title.to_lowercase();
+    fragments.iter().any(|fragment| title.contains(&fragment.to_lowercase()))
+}
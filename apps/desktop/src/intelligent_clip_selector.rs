@@ -15,7 +15,7 @@ pub struct ImportanceScore {
     pub context: HashMap<String, String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum EventType {
     CodeGeneration,
     Debugging,
@@ -258,7 +258,7 @@ impl IntelligentClipSelector {
                 
                 // Update event type tracking
                 for event in &candidate.events {
-                    *selected_event_types.entry(&event.event_type).or_insert(0) += 1;
+                    *selected_event_types.entry(event.event_type.clone()).or_insert(0) += 1;
                 }
             }
             
@@ -285,7 +285,7 @@ impl IntelligentClipSelector {
     fn meets_diversity_requirements(
         &self,
         segment: &VideoSegment,
-        selected_types: &HashMap<&EventType, usize>,
+        selected_types: &HashMap<EventType, usize>,
     ) -> Result<bool> {
         // Implement diversity logic based on event types
         let total_selected = selected_types.values().sum::<usize>();
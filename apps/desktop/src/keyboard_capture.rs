@@ -0,0 +1,324 @@
+//! Keyboard capture for DailyDoco Pro
+//!
+//! Key presses are read from the Linux evdev keyboards while a session is
+//! recorded, timestamped on the capture clock and written to an
+//! `InputEventLog` for the keystroke overlay. The focused window is looked
+//! up before each batch of keys is stored, so the log sees the focus change
+//! first and drops keys typed into a redacted window instead of storing them.
+//! When the focused window can't be told, the keys are dropped too.
+
+use std::io::{ErrorKind, Read};
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Result};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::input_log::{InputEvent, InputEventKind, InputEventLog, WindowGeometry};
+use crate::keystroke_overlay::KeystrokeOverlayConfig;
+
+/// Size of a `struct input_event` on 64-bit Linux
+const EVENT_SIZE: usize = 24;
+const EV_KEY: u16 = 0x01;
+/// Key codes from here on are mouse and joystick buttons
+const BTN_MISC: u16 = 0x100;
+/// How long a reader waits when its device has nothing to read
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyboardCaptureConfig {
+    pub enabled: bool,
+    /// Keyboard devices to read; empty reads every keyboard under `/dev/input/by-path`
+    pub devices: Vec<PathBuf>,
+    /// Case-insensitive window title fragments whose key events are never stored
+    pub redacted_windows: Vec<String>,
+}
+
+impl Default for KeyboardCaptureConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            devices: Vec::new(),
+            redacted_windows: KeystrokeOverlayConfig::default().redacted_windows,
+        }
+    }
+}
+
+/// Title and geometry of the focused window, `None` when it can't be told
+pub type FocusProbe = Arc<dyn Fn() -> Option<(String, WindowGeometry)> + Send + Sync>;
+
+/// Log being written and the window focus it last recorded
+struct KeyboardState {
+    log: InputEventLog,
+    focus: Option<(String, WindowGeometry)>,
+}
+
+/// Records key presses from every keyboard until stopped
+pub struct KeyboardRecorder {
+    running: Arc<AtomicBool>,
+    readers: Vec<JoinHandle<()>>,
+    state: Arc<Mutex<KeyboardState>>,
+}
+
+impl KeyboardRecorder {
+    /// Start reading the configured keyboards, with focus from `xdotool`
+    pub fn start(config: &KeyboardCaptureConfig, capture_origin: Instant) -> Result<Self> {
+        let focus: FocusProbe = Arc::new(active_window);
+        if focus().is_none() {
+            bail!("Can't tell which window has focus (is xdotool installed?), so keys couldn't be redacted");
+        }
+        let devices = open_keyboards(&config.devices)?;
+        log::info!("⌨️ Recording {} keyboard(s)", devices.len());
+        Ok(Self::start_with(devices, focus, config, capture_origin))
+    }
+
+    /// Start reading evdev events from `devices`, looking the focused window up with `focus`
+    pub fn start_with(
+        devices: Vec<Box<dyn Read + Send>>,
+        focus: FocusProbe,
+        config: &KeyboardCaptureConfig,
+        capture_origin: Instant,
+    ) -> Self {
+        let running = Arc::new(AtomicBool::new(true));
+        let state = Arc::new(Mutex::new(KeyboardState {
+            log: InputEventLog::with_redacted_windows(config.redacted_windows.clone()),
+            focus: None,
+        }));
+        let readers = devices
+            .into_iter()
+            .map(|device| {
+                let (running, state, focus) = (Arc::clone(&running), Arc::clone(&state), Arc::clone(&focus));
+                std::thread::spawn(move || read_keys(device, &state, &focus, capture_origin, &running))
+            })
+            .collect();
+
+        Self { running, readers, state }
+    }
+
+    /// Stop reading and return the log
+    pub fn stop(mut self) -> Result<InputEventLog> {
+        self.running.store(false, Ordering::SeqCst);
+        for reader in self.readers.drain(..) {
+            reader.join().map_err(|_| anyhow!("Keyboard reader thread panicked"))?;
+        }
+        Ok(std::mem::take(&mut self.state.lock().log))
+    }
+}
+
+/// Reader thread: decode evdev events until the device closes or the
+/// recorder stops
+fn read_keys(
+    mut device: Box<dyn Read + Send>,
+    state: &Mutex<KeyboardState>,
+    focus: &FocusProbe,
+    capture_origin: Instant,
+    running: &AtomicBool,
+) {
+    let mut buffer = [0u8; EVENT_SIZE * 64];
+    let mut pending: Vec<u8> = Vec::new();
+
+    while running.load(Ordering::SeqCst) {
+        let read = match device.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                std::thread::sleep(POLL_INTERVAL);
+                continue;
+            }
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => {
+                log::warn!("⌨️ Keyboard device closed: {}", e);
+                break;
+            }
+        };
+        pending.extend_from_slice(&buffer[..read]);
+
+        let whole = pending.len() - pending.len() % EVENT_SIZE;
+        let keys: Vec<InputEventKind> = pending[..whole].chunks_exact(EVENT_SIZE).filter_map(key_event).collect();
+        pending.drain(..whole);
+        if keys.is_empty() {
+            continue;
+        }
+
+        // Unknown focus can't be checked against the redaction rules
+        let Some(window) = focus() else { continue };
+        let timestamp = capture_origin.elapsed();
+        let mut state = state.lock();
+        if state.focus.as_ref() != Some(&window) {
+            let (title, geometry) = window.clone();
+            state.log.record(InputEvent::new(timestamp, InputEventKind::WindowFocus { title, geometry }));
+            state.focus = Some(window);
+        }
+        for kind in keys {
+            state.log.record(InputEvent::new(timestamp, kind));
+        }
+    }
+}
+
+/// Key press or release in a raw `struct input_event`; auto-repeats and
+/// other events are skipped
+fn key_event(raw: &[u8]) -> Option<InputEventKind> {
+    let kind = u16::from_ne_bytes([raw[16], raw[17]]);
+    let keycode = u16::from_ne_bytes([raw[18], raw[19]]);
+    let value = i32::from_ne_bytes([raw[20], raw[21], raw[22], raw[23]]);
+    match (kind, value) {
+        (EV_KEY, 1) if keycode < BTN_MISC => Some(InputEventKind::KeyDown { keycode }),
+        (EV_KEY, 0) if keycode < BTN_MISC => Some(InputEventKind::KeyUp { keycode }),
+        _ => None,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn open_keyboards(devices: &[PathBuf]) -> Result<Vec<Box<dyn Read + Send>>> {
+    use anyhow::Context;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let paths = if devices.is_empty() {
+        let dir = std::path::Path::new("/dev/input/by-path");
+        std::fs::read_dir(dir)
+            .with_context(|| format!("Listing {}", dir.display()))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.to_string_lossy().ends_with("-event-kbd"))
+            .collect()
+    } else {
+        devices.to_vec()
+    };
+    if paths.is_empty() {
+        bail!("No keyboard found under /dev/input/by-path");
+    }
+
+    paths
+        .iter()
+        .map(|path| {
+            let device = std::fs::OpenOptions::new()
+                .read(true)
+                .custom_flags(libc::O_NONBLOCK)
+                .open(path)
+                .with_context(|| format!("Opening {} (is the user in the input group?)", path.display()))?;
+            Ok(Box::new(device) as Box<dyn Read + Send>)
+        })
+        .collect()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn open_keyboards(_devices: &[PathBuf]) -> Result<Vec<Box<dyn Read + Send>>> {
+    bail!("Keyboard capture reads evdev devices and is only available on Linux")
+}
+
+/// Focused window from `xdotool`
+fn active_window() -> Option<(String, WindowGeometry)> {
+    let output = Command::new("xdotool")
+        .args(["getactivewindow", "getwindowname", "getwindowgeometry", "--shell"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_active_window(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Window name on the first line, then `KEY=value` geometry lines
+fn parse_active_window(text: &str) -> Option<(String, WindowGeometry)> {
+    let mut lines = text.lines();
+    let title = lines.next()?.to_string();
+    let mut geometry = WindowGeometry::new(0, 0, 0, 0);
+    for line in lines {
+        match line.split_once('=') {
+            Some(("X", value)) => geometry.x = value.parse().ok()?,
+            Some(("Y", value)) => geometry.y = value.parse().ok()?,
+            Some(("WIDTH", value)) => geometry.width = value.parse().ok()?,
+            Some(("HEIGHT", value)) => geometry.height = value.parse().ok()?,
+            _ => {}
+        }
+    }
+    Some((title, geometry))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    /// Hands out one event per read, as a keyboard does
+    struct Keyboard(std::io::Cursor<Vec<u8>>);
+
+    impl Read for Keyboard {
+        fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+            let len = buffer.len().min(EVENT_SIZE);
+            self.0.read(&mut buffer[..len])
+        }
+    }
+
+    fn raw_event(kind: u16, keycode: u16, value: i32) -> Vec<u8> {
+        let mut raw = vec![0u8; 16];
+        raw.extend_from_slice(&kind.to_ne_bytes());
+        raw.extend_from_slice(&keycode.to_ne_bytes());
+        raw.extend_from_slice(&value.to_ne_bytes());
+        raw
+    }
+
+    #[test]
+    fn test_parses_xdotool_output() {
+        let output = "main.rs - Visual Studio Code\nWINDOW=6291463\nX=10\nY=-20\nWIDTH=1280\nHEIGHT=720\nSCREEN=0\n";
+        let (title, geometry) = parse_active_window(output).unwrap();
+        assert_eq!(title, "main.rs - Visual Studio Code");
+        assert_eq!(geometry, WindowGeometry::new(10, -20, 1280, 720));
+        assert!(parse_active_window("").is_none());
+    }
+
+    #[test]
+    fn test_records_keys_and_drops_them_in_redacted_windows() {
+        // Ctrl+P in the editor, with a repeat and a sync event in between,
+        // then a password typed into the vault
+        let mut bytes = Vec::new();
+        for (kind, keycode, value) in [
+            (EV_KEY, 29, 1),
+            (EV_KEY, 25, 1),
+            (EV_KEY, 25, 2),
+            (0, 0, 0),
+            (EV_KEY, 25, 0),
+            (EV_KEY, 29, 0),
+            (EV_KEY, 31, 1),
+            (EV_KEY, 31, 0),
+        ] {
+            bytes.extend(raw_event(kind, keycode, value));
+        }
+
+        // Focus moves to the vault after the editor's four key events
+        let calls = Arc::new(AtomicUsize::new(0));
+        let focus: FocusProbe = Arc::new({
+            let calls = Arc::clone(&calls);
+            move || {
+                let title = if calls.fetch_add(1, Ordering::SeqCst) < 4 { "main.rs - Code" } else { "KeePassXC" };
+                Some((title.to_string(), WindowGeometry::new(0, 0, 800, 600)))
+            }
+        });
+        let config = KeyboardCaptureConfig::default();
+        let devices: Vec<Box<dyn Read + Send>> = vec![Box::new(Keyboard(std::io::Cursor::new(bytes)))];
+        let recorder = KeyboardRecorder::start_with(devices, focus, &config, Instant::now());
+        while !recorder.readers.iter().all(JoinHandle::is_finished) {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        let log = recorder.stop().unwrap();
+
+        let kinds: Vec<&InputEventKind> = log.events.iter().map(|event| &event.kind).collect();
+        assert!(matches!(kinds[0], InputEventKind::WindowFocus { title, .. } if title == "main.rs - Code"));
+        assert_eq!(
+            kinds[1..5],
+            [
+                &InputEventKind::KeyDown { keycode: 29 },
+                &InputEventKind::KeyDown { keycode: 25 },
+                &InputEventKind::KeyUp { keycode: 25 },
+                &InputEventKind::KeyUp { keycode: 29 },
+            ]
+        );
+        assert!(matches!(kinds[5], InputEventKind::WindowFocus { title, .. } if title == "KeePassXC"));
+        assert_eq!(kinds.len(), 6);
+        assert_eq!(calls.load(Ordering::SeqCst), 6);
+    }
+}
@@ -0,0 +1,542 @@
+//! Keyboard shortcut overlay for DailyDoco Pro
+//!
+//! Turns recorded key events into shortcut badges such as `Ctrl+Shift+P`
+//! and draws them onto exported frames. Plain typing is never shown. Key
+//! events in a redacted window are dropped as they are recorded when the
+//! log is created with `InputEventLog::with_redacted_windows`, and skipped
+//! here for logs recorded without it.
+
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use anyhow::{anyhow, Result};
+use ab_glyph::FontRef;
+
+use aegnt_27::typing::KeyboardLayout;
+use aegnt_27::visual::VideoFrame;
+
+use crate::canvas::{line_metrics, monospace_font, text_width, Canvas, Rgba};
+use crate::input_log::{is_redacted_title, InputEventKind, InputEventLog};
+
+// Modifier keycodes (evdev / PC scan codes), independent of layout
+const KEY_LEFT_CTRL: u16 = 29;
+const KEY_RIGHT_CTRL: u16 = 97;
+const KEY_LEFT_SHIFT: u16 = 42;
+const KEY_RIGHT_SHIFT: u16 = 54;
+const KEY_LEFT_ALT: u16 = 56;
+const KEY_ALT_GR: u16 = 100;
+const KEY_LEFT_SUPER: u16 = 125;
+const KEY_RIGHT_SUPER: u16 = 126;
+const KEY_ESC: u16 = 1;
+const KEY_CAPS_LOCK: u16 = 58;
+
+/// Keys that only move or edit the caret; shown only as part of a shortcut
+const EDITING_KEYS: &[u16] = &[14, 15, 28, 102, 103, 104, 105, 106, 107, 108, 109, 110, 111];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BadgePosition {
+    BottomCenter,
+    BottomLeft,
+    BottomRight,
+    TopRight,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeystrokeOverlayConfig {
+    /// Layout used to label keycodes: qwerty, dvorak, colemak, azerty or qwertz
+    pub keyboard_layout: String,
+    /// Show F1-F12 and Esc even without a modifier
+    pub show_function_keys: bool,
+    /// Case-insensitive window title fragments whose key events are dropped;
+    /// also pass these to `InputEventLog::with_redacted_windows` when recording
+    pub redacted_windows: Vec<String>,
+    /// Label Super and Alt as Cmd and Option, for recordings made on a Mac
+    #[serde(default)]
+    pub mac_modifier_labels: bool,
+
+    pub badge_duration: Duration,
+    pub fade_duration: Duration,
+    /// Repeats of the same shortcut within this window become one `×N` badge
+    pub repeat_window: Duration,
+    pub max_visible: usize,
+
+    pub position: BadgePosition,
+    pub margin: f32,
+    pub font_size: f32,
+    pub key_padding: f32,
+    pub background_color: Rgba,
+    pub keycap_color: Rgba,
+    pub text_color: Rgba,
+}
+
+impl Default for KeystrokeOverlayConfig {
+    fn default() -> Self {
+        Self {
+            keyboard_layout: "qwerty".to_string(),
+            show_function_keys: true,
+            mac_modifier_labels: false,
+            redacted_windows: vec![
+                "password".to_string(),
+                "1password".to_string(),
+                "bitwarden".to_string(),
+                "keepass".to_string(),
+                "lastpass".to_string(),
+                "keychain".to_string(),
+            ],
+            badge_duration: Duration::from_millis(1500),
+            fade_duration: Duration::from_millis(250),
+            repeat_window: Duration::from_millis(800),
+            max_visible: 3,
+            position: BadgePosition::BottomCenter,
+            margin: 48.0,
+            font_size: 28.0,
+            key_padding: 12.0,
+            background_color: Rgba::new(20, 20, 24, 200),
+            keycap_color: Rgba::new(70, 70, 80, 230),
+            text_color: Rgba::WHITE,
+        }
+    }
+}
+
+/// One shortcut as it will appear on screen
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Shortcut {
+    /// First press, on the capture clock
+    pub timestamp: Duration,
+    /// Most recent press when the shortcut was repeated
+    pub last_pressed: Duration,
+    /// Key labels in display order, modifiers first
+    pub keys: Vec<String>,
+    pub repeat_count: u32,
+}
+
+impl Shortcut {
+    /// Text form, e.g. `Ctrl+Shift+P`
+    pub fn label(&self) -> String {
+        self.keys.join("+")
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct HeldModifiers {
+    ctrl: bool,
+    alt: bool,
+    shift: bool,
+    super_key: bool,
+}
+
+impl HeldModifiers {
+    fn update(&mut self, keycode: u16, pressed: bool) -> bool {
+        match keycode {
+            KEY_LEFT_CTRL | KEY_RIGHT_CTRL => self.ctrl = pressed,
+            KEY_LEFT_SHIFT | KEY_RIGHT_SHIFT => self.shift = pressed,
+            KEY_LEFT_ALT => self.alt = pressed,
+            KEY_LEFT_SUPER | KEY_RIGHT_SUPER => self.super_key = pressed,
+            // AltGr composes characters, so it is treated as typing
+            KEY_ALT_GR => {}
+            _ => return false,
+        }
+        true
+    }
+
+    /// Held modifiers that turn a key press into a command
+    fn is_command(&self) -> bool {
+        self.ctrl || self.alt || self.super_key
+    }
+
+    fn labels(&self, layout: &KeyboardLayout) -> Vec<String> {
+        [
+            (self.ctrl, KEY_LEFT_CTRL),
+            (self.alt, KEY_LEFT_ALT),
+            (self.shift, KEY_LEFT_SHIFT),
+            (self.super_key, KEY_LEFT_SUPER),
+        ]
+        .iter()
+        .filter(|(held, _)| *held)
+        .filter_map(|&(_, code)| layout.key_label(code).map(str::to_string))
+        .collect()
+    }
+}
+
+/// Extracts displayable shortcuts from key events
+pub struct ShortcutExtractor {
+    config: KeystrokeOverlayConfig,
+    layout: KeyboardLayout,
+}
+
+impl ShortcutExtractor {
+    pub fn new(config: KeystrokeOverlayConfig) -> Result<Self> {
+        let mut layout = KeyboardLayout::new(&config.keyboard_layout)
+            .map_err(|e| anyhow!("Unsupported keyboard layout {}: {:?}", config.keyboard_layout, e))?;
+        if config.mac_modifier_labels {
+            layout = layout.with_mac_modifiers();
+        }
+        Ok(Self { config, layout })
+    }
+
+    pub fn extract(&self, log: &InputEventLog) -> Vec<Shortcut> {
+        let mut shortcuts: Vec<Shortcut> = Vec::new();
+        let mut modifiers = HeldModifiers::default();
+        let mut held_keys: Vec<u16> = Vec::new();
+        let mut redacted = false;
+
+        for event in &log.events {
+            let (keycode, pressed) = match &event.kind {
+                InputEventKind::KeyDown { keycode } => (*keycode, true),
+                InputEventKind::KeyUp { keycode } => (*keycode, false),
+                InputEventKind::WindowFocus { title, .. } => {
+                    // Releases in a redacted window weren't recorded, so
+                    // nothing is known to be held on leaving it
+                    if redacted {
+                        modifiers = HeldModifiers::default();
+                        held_keys.clear();
+                    }
+                    redacted = is_redacted_title(&self.config.redacted_windows, title);
+                    continue;
+                }
+                _ => continue,
+            };
+            if redacted {
+                continue;
+            }
+
+            if modifiers.update(keycode, pressed) {
+                continue;
+            }
+
+            if !pressed {
+                held_keys.retain(|&k| k != keycode);
+                continue;
+            }
+
+            // Auto-repeat sends KeyDown again without a KeyUp
+            if held_keys.contains(&keycode) {
+                continue;
+            }
+            held_keys.push(keycode);
+
+            if !self.is_shown(keycode, &modifiers) {
+                continue;
+            }
+
+            let Some(key_label) = self.layout.key_label(keycode) else {
+                continue;
+            };
+            let mut keys = modifiers.labels(&self.layout);
+            keys.push(key_label.to_string());
+
+            match shortcuts.last_mut() {
+                Some(previous)
+                    if previous.keys == keys
+                        && event.timestamp.saturating_sub(previous.last_pressed) <= self.config.repeat_window =>
+                {
+                    previous.repeat_count += 1;
+                    previous.last_pressed = event.timestamp;
+                }
+                _ => shortcuts.push(Shortcut {
+                    timestamp: event.timestamp,
+                    last_pressed: event.timestamp,
+                    keys,
+                    repeat_count: 1,
+                }),
+            }
+        }
+
+        shortcuts
+    }
+
+    fn is_shown(&self, keycode: u16, modifiers: &HeldModifiers) -> bool {
+        if keycode == KEY_CAPS_LOCK {
+            return false;
+        }
+        if modifiers.is_command() {
+            return true;
+        }
+        if self.layout.is_character_key(keycode) || EDITING_KEYS.contains(&keycode) {
+            // Plain or shifted typing, caret movement and selection
+            return false;
+        }
+
+        self.config.show_function_keys && (keycode == KEY_ESC || self.is_function_key(keycode))
+    }
+
+    fn is_function_key(&self, keycode: u16) -> bool {
+        (59..=68).contains(&keycode) || keycode == 87 || keycode == 88
+    }
+}
+
+/// Draws shortcut badges onto exported frames
+pub struct KeystrokeOverlayRenderer {
+    config: KeystrokeOverlayConfig,
+    shortcuts: Vec<Shortcut>,
+    font: FontRef<'static>,
+}
+
+impl KeystrokeOverlayRenderer {
+    pub fn new(config: KeystrokeOverlayConfig, log: &InputEventLog) -> Result<Self> {
+        let shortcuts = ShortcutExtractor::new(config.clone())?.extract(log);
+        Ok(Self::from_shortcuts(config, shortcuts))
+    }
+
+    pub fn from_shortcuts(config: KeystrokeOverlayConfig, shortcuts: Vec<Shortcut>) -> Self {
+        Self {
+            config,
+            shortcuts,
+            font: monospace_font(),
+        }
+    }
+
+    pub fn shortcuts(&self) -> &[Shortcut] {
+        &self.shortcuts
+    }
+
+    /// Shortcuts on screen at `timestamp`, oldest first, with their opacity
+    pub fn visible_at(&self, timestamp: Duration) -> Vec<(&Shortcut, f32)> {
+        let mut visible: Vec<(&Shortcut, f32)> = self
+            .shortcuts
+            .iter()
+            .filter(|s| s.timestamp <= timestamp)
+            .filter_map(|s| {
+                let hide_at = s.last_pressed + self.config.badge_duration;
+                if timestamp >= hide_at {
+                    return None;
+                }
+                let remaining = hide_at - timestamp;
+                let opacity = if remaining < self.config.fade_duration {
+                    remaining.as_secs_f32() / self.config.fade_duration.as_secs_f32()
+                } else {
+                    1.0
+                };
+                Some((s, opacity))
+            })
+            .collect();
+
+        let overflow = visible.len().saturating_sub(self.config.max_visible);
+        visible.drain(..overflow);
+        visible
+    }
+
+    /// Draw the badges at the frame's own timestamp
+    pub fn render_frame(&self, frame: &VideoFrame) -> Result<VideoFrame> {
+        self.render_frame_at(frame, frame.timestamp)
+    }
+
+    /// Draw the badges for `capture_time`, for frames retimed by the editor
+    pub fn render_frame_at(&self, frame: &VideoFrame, capture_time: Duration) -> Result<VideoFrame> {
+        let mut output = frame.to_rgb()?;
+        let visible = self.visible_at(capture_time);
+        if visible.is_empty() {
+            return Ok(output);
+        }
+
+        let mut canvas = Canvas::new(&mut output)?;
+        let (ascent, descent) = line_metrics(&self.font, self.config.font_size);
+        let keycap_height = ascent - descent + self.config.key_padding;
+        let badge_height = keycap_height + self.config.key_padding;
+        let spacing = self.config.key_padding / 2.0;
+
+        // Newest badge sits closest to the anchor edge
+        for (stack_index, (shortcut, opacity)) in visible.iter().rev().enumerate() {
+            let width = self.badge_width(shortcut);
+            let x = match self.config.position {
+                BadgePosition::BottomCenter => (canvas.width() as f32 - width) / 2.0,
+                BadgePosition::BottomLeft => self.config.margin,
+                BadgePosition::BottomRight | BadgePosition::TopRight => canvas.width() as f32 - self.config.margin - width,
+            };
+            let offset = stack_index as f32 * (badge_height + spacing);
+            let y = match self.config.position {
+                BadgePosition::TopRight => self.config.margin + offset,
+                _ => canvas.height() as f32 - self.config.margin - badge_height - offset,
+            };
+
+            self.draw_badge(&mut canvas, shortcut, x, y, badge_height, ascent, *opacity);
+        }
+
+        Ok(output)
+    }
+
+    fn badge_width(&self, shortcut: &Shortcut) -> f32 {
+        let padding = self.config.key_padding;
+        let plus = text_width(&self.font, "+", self.config.font_size);
+        let keys: f32 = shortcut
+            .keys
+            .iter()
+            .map(|k| text_width(&self.font, k, self.config.font_size) + padding * 2.0)
+            .sum();
+        let separators = (shortcut.keys.len().saturating_sub(1)) as f32 * (plus + padding);
+        let repeat = if shortcut.repeat_count > 1 {
+            text_width(&self.font, &format!("×{}", shortcut.repeat_count), self.config.font_size) + padding
+        } else {
+            0.0
+        };
+
+        keys + separators + repeat + padding
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn draw_badge(
+        &self,
+        canvas: &mut Canvas<'_>,
+        shortcut: &Shortcut,
+        x: f32,
+        y: f32,
+        height: f32,
+        ascent: f32,
+        opacity: f32,
+    ) {
+        let padding = self.config.key_padding;
+        let size = self.config.font_size;
+        let text_color = self.config.text_color.faded(opacity);
+        let keycap_height = height - padding;
+        let baseline = y + padding + ascent;
+
+        canvas.fill_rounded_rect(
+            x,
+            y,
+            self.badge_width(shortcut),
+            height,
+            height / 4.0,
+            self.config.background_color.faded(opacity),
+        );
+
+        let mut caret = x + padding / 2.0;
+        for (index, key) in shortcut.keys.iter().enumerate() {
+            if index > 0 {
+                caret += padding / 2.0;
+                caret += canvas.draw_text(&self.font, "+", caret, baseline, size, text_color);
+                caret += padding / 2.0;
+            }
+
+            let keycap_width = text_width(&self.font, key, size) + padding * 2.0;
+            canvas.fill_rounded_rect(
+                caret,
+                y + padding / 2.0,
+                keycap_width,
+                keycap_height,
+                6.0,
+                self.config.keycap_color.faded(opacity),
+            );
+            canvas.draw_text(&self.font, key, caret + padding, baseline, size, text_color);
+            caret += keycap_width;
+        }
+
+        if shortcut.repeat_count > 1 {
+            caret += padding;
+            canvas.draw_text(&self.font, &format!("×{}", shortcut.repeat_count), caret, baseline, size, text_color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::solid_frame;
+    use crate::input_log::{InputEvent, WindowGeometry};
+
+    const KEY_P: u16 = 25;
+    const KEY_A: u16 = 30;
+    const KEY_F5: u16 = 63;
+
+    fn press(log: &mut InputEventLog, ms: u64, keys: &[u16]) {
+        for (i, &key) in keys.iter().enumerate() {
+            log.record(InputEvent::new(Duration::from_millis(ms + i as u64), InputEventKind::KeyDown { keycode: key }));
+        }
+        for (i, &key) in keys.iter().rev().enumerate() {
+            log.record(InputEvent::new(Duration::from_millis(ms + 50 + i as u64), InputEventKind::KeyUp { keycode: key }));
+        }
+    }
+
+    fn focus(log: &mut InputEventLog, ms: u64, title: &str) {
+        log.record(InputEvent::new(
+            Duration::from_millis(ms),
+            InputEventKind::WindowFocus {
+                title: title.to_string(),
+                geometry: WindowGeometry::new(0, 0, 1920, 1080),
+            },
+        ));
+    }
+
+    #[test]
+    fn test_shortcuts_exclude_typing() {
+        let mut log = InputEventLog::new();
+        focus(&mut log, 0, "main.rs - Visual Studio Code");
+        press(&mut log, 100, &[KEY_A]);
+        press(&mut log, 300, &[KEY_LEFT_SHIFT, KEY_A]);
+        press(&mut log, 500, &[KEY_LEFT_CTRL, KEY_LEFT_SHIFT, KEY_P]);
+        press(&mut log, 900, &[KEY_F5]);
+
+        let shortcuts = ShortcutExtractor::new(KeystrokeOverlayConfig::default()).unwrap().extract(&log);
+        let labels: Vec<String> = shortcuts.iter().map(|s| s.label()).collect();
+
+        assert_eq!(labels, vec!["Ctrl+Shift+P", "F5"]);
+    }
+
+    #[test]
+    fn test_redacted_window_hides_shortcuts() {
+        let config = KeystrokeOverlayConfig::default();
+        let record = |log: &mut InputEventLog| {
+            focus(log, 0, "Bitwarden - Vault");
+            press(log, 100, &[KEY_LEFT_CTRL, KEY_A]);
+            focus(log, 500, "Terminal");
+            press(log, 600, &[KEY_LEFT_CTRL, KEY_A]);
+            // Ctrl goes down in the terminal and comes up in the vault
+            log.record(InputEvent::new(Duration::from_millis(700), InputEventKind::KeyDown { keycode: KEY_LEFT_CTRL }));
+            focus(log, 710, "Bitwarden - Vault");
+            log.record(InputEvent::new(Duration::from_millis(720), InputEventKind::KeyUp { keycode: KEY_LEFT_CTRL }));
+            focus(log, 800, "Terminal");
+            press(log, 900, &[KEY_A]);
+        };
+
+        let mut unredacted = InputEventLog::new();
+        record(&mut unredacted);
+        let mut redacted = InputEventLog::with_redacted_windows(config.redacted_windows.clone());
+        record(&mut redacted);
+
+        // Nothing typed in the vault reaches the log
+        let in_vault = |t: Duration| t < Duration::from_millis(500) || (710..800).contains(&t.as_millis());
+        let is_key = |e: &InputEvent| matches!(e.kind, InputEventKind::KeyDown { .. } | InputEventKind::KeyUp { .. });
+        assert!(!redacted.events.iter().any(|e| is_key(e) && in_vault(e.timestamp)));
+        assert!(unredacted.events.iter().any(|e| is_key(e) && in_vault(e.timestamp)));
+
+        let extractor = ShortcutExtractor::new(config).unwrap();
+        for log in [&unredacted, &redacted] {
+            let shortcuts = extractor.extract(log);
+            assert_eq!(shortcuts.len(), 1);
+            assert_eq!(shortcuts[0].timestamp, Duration::from_millis(601));
+        }
+    }
+
+    #[test]
+    fn test_layout_labels_and_repeats() {
+        let mut log = InputEventLog::new();
+        // Keycode 16 is labelled A on AZERTY
+        press(&mut log, 0, &[KEY_LEFT_CTRL, 16]);
+        press(&mut log, 200, &[KEY_LEFT_CTRL, 16]);
+
+        let config = KeystrokeOverlayConfig {
+            keyboard_layout: "azerty".to_string(),
+            ..Default::default()
+        };
+        let shortcuts = ShortcutExtractor::new(config).unwrap().extract(&log);
+
+        assert_eq!(shortcuts.len(), 1);
+        assert_eq!(shortcuts[0].label(), "Ctrl+A");
+        assert_eq!(shortcuts[0].repeat_count, 2);
+    }
+
+    #[test]
+    fn test_badge_rendered_while_visible() {
+        let mut log = InputEventLog::new();
+        press(&mut log, 100, &[KEY_LEFT_CTRL, KEY_P]);
+        let renderer = KeystrokeOverlayRenderer::new(KeystrokeOverlayConfig::default(), &log).unwrap();
+
+        let shown = renderer
+            .render_frame(&solid_frame(640, 360, Rgba::BLACK, Duration::from_millis(400)))
+            .unwrap();
+        assert!(shown.data.iter().any(|&v| v > 0));
+
+        let hidden = renderer
+            .render_frame(&solid_frame(640, 360, Rgba::BLACK, Duration::from_millis(3000)))
+            .unwrap();
+        assert!(hidden.data.iter().all(|&v| v == 0));
+    }
+}
@@ -5,15 +5,59 @@
  */
 
 use aegnt_27::prelude::*;
+use std::collections::HashMap;
 use std::error::Error;
+use std::path::PathBuf;
 
 mod capture;
 mod ui;
 mod config;
+mod intelligent_clip_selector;
+mod dynamic_pacing_engine;
+mod timeline;
+mod input_log;
+mod keyboard_capture;
+mod camera_path;
+mod cursor_smoothing;
+mod canvas;
+mod cursor_overlay;
+mod keystroke_overlay;
+mod syntax_highlight;
+mod code_animation;
+mod terminal_recording;
+mod terminal_renderer;
+mod session;
+mod how_to_doc;
+mod brand_kit;
+mod site_export;
+mod diff_walkthrough;
+mod storyboard;
+mod release_notes;
+mod player_package;
+mod editor_bridge;
+mod browser_bridge;
+mod audio_capture;
+mod av_sync;
+mod audio_export;
+mod dead_air;
+mod music_bed;
+mod audio_preview;
+mod audio_meter;
+mod recording_frames;
+mod system_tray;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     env_logger::init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("export-site") => return export_site(&args[1..]),
+        Some("release-notes") => return release_notes(&args[1..]),
+        Some("storyboard") => return storyboard(&args[1..]),
+        Some("player") => return player(&args[1..]),
+        _ => {}
+    }
     
     // Initialize aegnt-27 system
     // let aegnt_config = AegntConfig::default();
@@ -27,4 +71,164 @@ async fn main() -> Result<(), Box<dyn Error>> {
     log::info!("📱 Desktop application ready");
     
     Ok(())
-}
\ No newline at end of file
+}
+
+/// `export-site <project-dir> [--out <dir>] [--brand-kit <file>]`
+fn export_site(args: &[String]) -> Result<(), Box<dyn Error>> {
+    const USAGE: &str = "usage: dailydoco-desktop export-site <project-dir> [--out <dir>] [--brand-kit <file>]";
+
+    let mut project_dir = None;
+    let mut out_dir = None;
+    let mut brand_kit = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" => out_dir = Some(PathBuf::from(args.next().ok_or(USAGE)?)),
+            "--brand-kit" => brand_kit = Some(PathBuf::from(args.next().ok_or(USAGE)?)),
+            _ if project_dir.is_none() => project_dir = Some(PathBuf::from(arg)),
+            _ => return Err(USAGE.into()),
+        }
+    }
+    let project_dir = project_dir.ok_or(USAGE)?;
+
+    let project = session::ProjectSessions::load(&project_dir)?;
+    let mut config = site_export::SiteExportConfig::default();
+    // Fall back to the project's own brand kit when none is given
+    let brand_kit = brand_kit.or_else(|| Some(project_dir.join("brand-kit.json")).filter(|path| path.is_file()));
+    if let Some(path) = brand_kit {
+        config.brand_kit = brand_kit::BrandKit::load(&path)?;
+    }
+
+    // Screenshots come from each session's video; sessions without one export without them
+    let mut sources: HashMap<_, Box<dyn session::FrameSource>> = project
+        .sessions
+        .iter()
+        .filter(|record| record.video_path.is_file())
+        .map(|record| (record.session.id, Box::new(recording_frames::RecordingFrames::new(record)) as _))
+        .collect();
+
+    let out_dir = out_dir.unwrap_or_else(|| project_dir.join("site"));
+    let index = site_export::SiteExporter::new(config).export(&project, &mut sources, &out_dir)?;
+    println!("Exported {} sessions to {}", project.sessions.len(), index.display());
+    Ok(())
+}
+
+/// `release-notes <repo> <from-tag> <to-tag> [--project <dir>] [--out <dir>]`
+fn release_notes(args: &[String]) -> Result<(), Box<dyn Error>> {
+    const USAGE: &str =
+        "usage: dailydoco-desktop release-notes <repo> <from-tag> <to-tag> [--project <dir>] [--out <dir>]";
+
+    let mut positional = Vec::new();
+    let mut project_dir = None;
+    let mut out_dir = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--project" => project_dir = Some(PathBuf::from(args.next().ok_or(USAGE)?)),
+            "--out" => out_dir = Some(PathBuf::from(args.next().ok_or(USAGE)?)),
+            _ => positional.push(arg.as_str()),
+        }
+    }
+    let [repo, from, to] = positional[..] else {
+        return Err(USAGE.into());
+    };
+
+    // Linked sessions come from the project, if one is given
+    let sessions = match &project_dir {
+        Some(dir) => session::ProjectSessions::load(dir)?.sessions,
+        None => Vec::new(),
+    };
+    let notes = release_notes::ReleaseNotes::gather(std::path::Path::new(repo), from, to, &sessions)?;
+    let storyboard = notes.storyboard(&mut Default::default(), &Default::default())?;
+
+    let out_dir = out_dir.unwrap_or_else(|| PathBuf::from(format!("release-{}", to)));
+    notes.write(&storyboard, &Default::default(), &out_dir)?;
+    println!("Wrote {} changes to {}", notes.changes.len(), out_dir.display());
+    Ok(())
+}
+
+/// `storyboard <session.json> [--pacing <decisions.json>] [--out <dir>]`
+fn storyboard(args: &[String]) -> Result<(), Box<dyn Error>> {
+    const USAGE: &str = "usage: dailydoco-desktop storyboard <session.json> [--pacing <decisions.json>] [--out <dir>]";
+
+    let mut session_file = None;
+    let mut pacing_file = None;
+    let mut out_dir = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--pacing" => pacing_file = Some(PathBuf::from(args.next().ok_or(USAGE)?)),
+            "--out" => out_dir = Some(PathBuf::from(args.next().ok_or(USAGE)?)),
+            _ if session_file.is_none() => session_file = Some(PathBuf::from(arg)),
+            _ => return Err(USAGE.into()),
+        }
+    }
+    let session_file = session_file.ok_or(USAGE)?;
+
+    let mut record = session::SessionRecord::load(&session_file)?;
+    if record.video_path.is_relative() {
+        if let Some(dir) = session_file.parent() {
+            record.video_path = dir.join(&record.video_path);
+        }
+    }
+    let pacing: Vec<dynamic_pacing_engine::PacingDecision> = match &pacing_file {
+        Some(path) => serde_json::from_str(&std::fs::read_to_string(path)?)?,
+        None => Vec::new(),
+    };
+    // Keyframes come from the session video; without one the panels keep their placeholders
+    let mut frames = record.video_path.is_file().then(|| recording_frames::RecordingFrames::new(&record));
+    let frames = frames.as_mut().map(|frames| frames as &mut dyn session::FrameSource);
+    let storyboard = storyboard::Storyboard::from_session(&record, &pacing, frames, &Default::default())?;
+
+    let out_dir = out_dir.unwrap_or_else(|| session_file.with_file_name("storyboard"));
+    std::fs::create_dir_all(&out_dir)?;
+    let index = storyboard.write_html(&out_dir)?;
+    std::fs::write(out_dir.join("storyboard.json"), serde_json::to_string_pretty(&storyboard)?)?;
+    // Three rows of three panels fit a landscape page with room for notes
+    let sheet = storyboard::ContactSheetConfig {
+        columns: 3,
+        rows_per_page: 3,
+        note_lines: 5,
+        ..Default::default()
+    };
+    storyboard.save_pages(&sheet, &out_dir, "contact-sheet")?;
+    storyboard.save_pdf(&sheet, &out_dir.join("storyboard.pdf"))?;
+    println!("Wrote {} panels to {}", storyboard.panels.len(), index.display());
+    Ok(())
+}
+
+/// `player <session.json> [--out <dir>] [--brand-kit <file>]`
+fn player(args: &[String]) -> Result<(), Box<dyn Error>> {
+    const USAGE: &str = "usage: dailydoco-desktop player <session.json> [--out <dir>] [--brand-kit <file>]";
+
+    let mut session_file = None;
+    let mut out_dir = None;
+    let mut brand_kit = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" => out_dir = Some(PathBuf::from(args.next().ok_or(USAGE)?)),
+            "--brand-kit" => brand_kit = Some(PathBuf::from(args.next().ok_or(USAGE)?)),
+            _ if session_file.is_none() => session_file = Some(PathBuf::from(arg)),
+            _ => return Err(USAGE.into()),
+        }
+    }
+    let session_file = session_file.ok_or(USAGE)?;
+
+    let mut record = session::SessionRecord::load(&session_file)?;
+    if record.video_path.is_relative() {
+        if let Some(dir) = session_file.parent() {
+            record.video_path = dir.join(&record.video_path);
+        }
+    }
+    let mut config = player_package::PlayerPackageConfig::default();
+    if let Some(path) = brand_kit {
+        config.brand_kit = brand_kit::BrandKit::load(&path)?;
+    }
+
+    let out_dir = out_dir.unwrap_or_else(|| session_file.with_file_name("player"));
+    let mut frames = recording_frames::RecordingFrames::new(&record);
+    let index = player_package::PlayerPackager::new(config).export(&record, Some(&mut frames), &out_dir)?;
+    println!("Wrote player package to {}", index.display());
+    Ok(())
+}
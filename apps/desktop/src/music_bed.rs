@@ -0,0 +1,285 @@
+//! Background music bed with voice-driven ducking for DailyDoco Pro
+//!
+//! The music is looped, or crossfaded into itself, until it covers the
+//! whole timeline, then faded in at the start and out at the end. Voice
+//! activity detection on the narration drives a sidechain: the music falls
+//! by the duck depth while someone talks and comes back up in the pauses.
+//! Gain moves in straight lines in dB, and the attack ramp finishes as
+//! speech starts, so the first word is never masked.
+
+use std::f32::consts::FRAC_PI_2;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use aegnt_27::audio::AudioData;
+use aegnt_27::audio_vad::{ActivitySegment, VadConfig};
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+
+/// How music shorter than the timeline is stretched over it
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum MusicFit {
+    /// Repeat the track back to back
+    Loop,
+    /// Repeat the track, blending each repeat into the last over this long
+    Crossfade(Duration),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MusicBedConfig {
+    /// Music file, `.wav` or `.flac`
+    pub path: PathBuf,
+    /// Level of the music while nobody talks, dB
+    pub gain_db: f32,
+    /// How far the music drops under narration, dB
+    pub duck_depth_db: f32,
+    /// Time to fall to the ducked level, finishing as speech starts
+    pub attack: Duration,
+    /// Time to come back up after speech ends
+    pub release: Duration,
+    pub fade_in: Duration,
+    pub fade_out: Duration,
+    pub fit: MusicFit,
+    /// Detector that finds the narration the music ducks under
+    #[serde(default)]
+    pub vad: VadConfig,
+}
+
+impl MusicBedConfig {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            gain_db: -18.0,
+            duck_depth_db: 12.0,
+            attack: Duration::from_millis(250),
+            release: Duration::from_millis(800),
+            fade_in: Duration::from_secs(2),
+            fade_out: Duration::from_secs(3),
+            fit: MusicFit::Crossfade(Duration::from_secs(2)),
+            vad: VadConfig::default(),
+        }
+    }
+}
+
+/// Lays a music track under narration
+pub struct MusicBed {
+    config: MusicBedConfig,
+    music: AudioData,
+}
+
+impl MusicBed {
+    pub fn new(config: MusicBedConfig, music: AudioData) -> Self {
+        Self { config, music }
+    }
+
+    /// Load the configured music file
+    pub fn load(config: MusicBedConfig) -> Result<Self> {
+        let music = AudioData::load(&config.path)
+            .map_err(|e| anyhow!("Failed to load music {}: {}", config.path.display(), e))?;
+        Ok(Self::new(config, music))
+    }
+
+    /// Mix the music under the narration, ducking wherever speech is detected
+    pub fn apply(&self, narration: &AudioData) -> Result<AudioData> {
+        let segments = narration
+            .detect_voice_activity(&self.config.vad)
+            .map_err(|e| anyhow!("Voice activity detection failed: {}", e))?;
+        self.apply_with_segments(narration, &segments)
+    }
+
+    pub fn apply_with_segments(&self, narration: &AudioData, segments: &[ActivitySegment]) -> Result<AudioData> {
+        let bed = self.bed(narration, segments)?;
+        narration.mix(&bed, 1.0).map_err(|e| anyhow!("Failed to mix music: {}", e))
+    }
+
+    /// The music alone, fitted, faded and ducked to go under the narration
+    pub fn bed(&self, narration: &AudioData, segments: &[ActivitySegment]) -> Result<AudioData> {
+        let music = self
+            .music
+            .conform(narration.sample_rate, narration.channels)
+            .map_err(|e| anyhow!("Failed to convert music: {}", e))?;
+        if music.frame_count() == 0 {
+            bail!("Music track is empty");
+        }
+
+        let frames = narration.frame_count();
+        let mut bed = self.fit(&music, frames);
+        let gains = self.gains(frames, narration.sample_rate, segments);
+        let channels = bed.channels.max(1) as usize;
+        for (frame, gain) in bed.samples.chunks_exact_mut(channels).zip(&gains) {
+            frame.iter_mut().for_each(|s| *s *= gain);
+        }
+        Ok(bed)
+    }
+
+    /// Repeat the music until it is `frames` long
+    fn fit(&self, music: &AudioData, frames: usize) -> AudioData {
+        let channels = music.channels.max(1) as usize;
+        let length = music.frame_count();
+        let overlap = match self.config.fit {
+            MusicFit::Loop => 0,
+            MusicFit::Crossfade(duration) => {
+                ((duration.as_secs_f64() * music.sample_rate as f64) as usize).min(length / 2)
+            }
+        };
+
+        let mut samples = vec![0.0f32; frames * channels];
+        let mut position = 0;
+        while position < frames {
+            let continues = position + length < frames;
+            for index in 0..length.min(frames - position) {
+                // Equal-power blend where one repeat overlaps the next
+                let mut gain = 1.0;
+                if position > 0 && index < overlap {
+                    gain *= (FRAC_PI_2 * index as f32 / overlap as f32).sin();
+                }
+                if continues && index >= length - overlap {
+                    gain *= (FRAC_PI_2 * (index - (length - overlap)) as f32 / overlap as f32).cos();
+                }
+                let (from, to) = (index * channels, (position + index) * channels);
+                for channel in 0..channels {
+                    samples[to + channel] += music.samples[from + channel] * gain;
+                }
+            }
+            position += length - overlap;
+        }
+        AudioData::new(samples, music.sample_rate, music.channels)
+    }
+
+    /// Per-frame linear gain: level, ducking and fades
+    fn gains(&self, frames: usize, sample_rate: u32, segments: &[ActivitySegment]) -> Vec<f32> {
+        let to_frames = |d: Duration| (d.as_secs_f64() * sample_rate as f64) as usize;
+        let depth = self.config.duck_depth_db.max(0.0);
+
+        // Attenuation in dB: full depth under speech, ramping down ahead of
+        // it over the attack and back up after it over the release
+        let mut attenuation = vec![0.0f32; frames];
+        for segment in segments.iter().filter(|s| s.is_speech()) {
+            let end = to_frames(segment.end).min(frames);
+            attenuation[to_frames(segment.start).min(end)..end].iter_mut().for_each(|a| *a = depth);
+        }
+        let attack_step = depth / to_frames(self.config.attack).max(1) as f32;
+        for index in (0..frames.saturating_sub(1)).rev() {
+            attenuation[index] = attenuation[index].max(attenuation[index + 1] - attack_step);
+        }
+        let release_step = depth / to_frames(self.config.release).max(1) as f32;
+        for index in 1..frames {
+            attenuation[index] = attenuation[index].max(attenuation[index - 1] - release_step);
+        }
+
+        let fade_in = to_frames(self.config.fade_in).min(frames);
+        let fade_out = to_frames(self.config.fade_out).min(frames);
+        attenuation
+            .iter()
+            .enumerate()
+            .map(|(index, attenuation)| {
+                let mut gain = 10f32.powf((self.config.gain_db - attenuation) / 20.0);
+                if index < fade_in {
+                    gain *= (FRAC_PI_2 * index as f32 / fade_in as f32).sin();
+                }
+                let remaining = frames - index;
+                if remaining <= fade_out {
+                    gain *= (FRAC_PI_2 * (remaining - 1) as f32 / fade_out as f32).sin();
+                }
+                gain
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aegnt_27::audio_vad::VoiceActivity;
+
+    const RATE: u32 = 8000;
+
+    fn constant(seconds: f32, value: f32) -> AudioData {
+        AudioData::new(vec![value; (seconds * RATE as f32) as usize], RATE, 1)
+    }
+
+    fn config(fit: MusicFit) -> MusicBedConfig {
+        MusicBedConfig {
+            gain_db: 0.0,
+            fade_in: Duration::ZERO,
+            fade_out: Duration::ZERO,
+            fit,
+            ..MusicBedConfig::new("music.flac")
+        }
+    }
+
+    fn at(seconds: f32) -> usize {
+        (seconds * RATE as f32) as usize
+    }
+
+    #[test]
+    fn test_music_loops_or_crossfades_to_timeline_length() {
+        let narration = constant(10.0, 0.0);
+        // Ramp music makes each repeat's start visible
+        let ramp = AudioData::new((0..at(4.0)).map(|n| n as f32 / at(4.0) as f32).collect(), RATE, 1);
+
+        let looped = MusicBed::new(config(MusicFit::Loop), ramp.clone()).bed(&narration, &[]).unwrap();
+        assert_eq!(looped.frame_count(), narration.frame_count());
+        assert_eq!(looped.samples[at(4.0)], 0.0);
+        assert_eq!(looped.samples[at(8.0) + 1], ramp.samples[1]);
+
+        let faded = MusicBed::new(config(MusicFit::Crossfade(Duration::from_secs(1))), ramp.clone())
+            .bed(&narration, &[])
+            .unwrap();
+        assert_eq!(faded.frame_count(), narration.frame_count());
+        // Second repeat starts at 3 s, blending into the first's last second
+        let blend = faded.samples[at(3.5)];
+        let expected =
+            ramp.samples[at(3.5)] * (FRAC_PI_2 * 0.5).cos() + ramp.samples[at(0.5)] * (FRAC_PI_2 * 0.5).sin();
+        assert!((blend - expected).abs() < 1e-3);
+        assert!((faded.samples[at(5.0)] - ramp.samples[at(2.0)]).abs() < 1e-6);
+        // The last repeat runs to the end without fading out
+        assert!(faded.samples[at(10.0) - 1] > 0.5);
+    }
+
+    #[test]
+    fn test_music_ducks_under_speech_with_attack_and_release() {
+        let narration = constant(10.0, 0.0);
+        let bed = MusicBed::new(config(MusicFit::Loop), constant(10.0, 1.0));
+        let speech = ActivitySegment {
+            start: Duration::from_secs(4),
+            end: Duration::from_secs(6),
+            activity: VoiceActivity::Speech,
+        };
+        let music = bed.bed(&narration, &[speech]).unwrap();
+        let db = |seconds: f32| 20.0 * music.samples[at(seconds)].log10();
+
+        assert!(db(2.0).abs() < 1e-3);
+        // Fully ducked by the time speech starts, halfway down mid-attack
+        assert!((db(4.0) + 12.0).abs() < 0.1);
+        assert!((db(4.0 - 0.125) + 6.0).abs() < 0.1);
+        assert!((db(5.0) + 12.0).abs() < 1e-3);
+        // Released over 800 ms after speech ends
+        assert!((db(6.4) + 6.0).abs() < 0.1);
+        assert!(db(7.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_fades_and_mix_under_narration() {
+        let narration = constant(8.0, 0.25);
+        let config = MusicBedConfig {
+            gain_db: -6.0,
+            duck_depth_db: 0.0,
+            fade_in: Duration::from_secs(2),
+            fade_out: Duration::from_secs(2),
+            fit: MusicFit::Loop,
+            ..MusicBedConfig::new("music.flac")
+        };
+        let bed = MusicBed::new(config, AudioData::new(vec![0.5; at(3.0) * 2], RATE, 2));
+
+        let mixed = bed.apply_with_segments(&narration, &[]).unwrap();
+        assert_eq!((mixed.channels, mixed.frame_count()), (1, narration.frame_count()));
+        let level = 0.5 * 10f32.powf(-6.0 / 20.0);
+        assert!((mixed.samples[0] - 0.25).abs() < 1e-6);
+        assert!((mixed.samples[at(1.0)] - 0.25 - level * (FRAC_PI_2 * 0.5).sin()).abs() < 1e-3);
+        assert!((mixed.samples[at(4.0)] - 0.25 - level).abs() < 1e-3);
+        assert!((mixed.samples[at(8.0) - 1] - 0.25).abs() < 1e-6);
+
+        assert!(MusicBed::new(MusicBedConfig::new("x.wav"), constant(0.0, 0.0)).bed(&narration, &[]).is_err());
+    }
+}
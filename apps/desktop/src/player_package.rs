@@ -0,0 +1,460 @@
+//! Interactive HTML5 player packages for DailyDoco Pro
+//!
+//! Exports one session as a self-contained folder to attach to wiki pages:
+//! the video with WebVTT captions and chapters, a sidebar of clickable
+//! chapters and markers with the code captured at each marker ready to
+//! copy, and the written steps, which follow playback as the video runs.
+
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use anyhow::{bail, Context, Result};
+
+use crate::brand_kit::BrandKit;
+use crate::how_to_doc::{HowToConfig, HowToDocument, HowToGenerator};
+use crate::session::{encode_url, escape_html, format_timestamp, CodeSnippet, FrameSource, SessionRecord};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerPackageConfig {
+    pub brand_kit: BrandKit,
+    pub how_to: HowToConfig,
+    /// Snippets captured this close to a marker are listed under it
+    pub snippet_window: Duration,
+    /// BCP 47 language of the captions track
+    pub caption_language: String,
+}
+
+impl Default for PlayerPackageConfig {
+    fn default() -> Self {
+        Self {
+            brand_kit: BrandKit::default(),
+            how_to: HowToConfig::default(),
+            snippet_window: Duration::from_secs(5),
+            caption_language: "en".to_string(),
+        }
+    }
+}
+
+/// A marker placed on the exported video, with the code captured around it
+#[derive(Debug, Clone)]
+pub struct PlayerMarker<'a> {
+    pub video_time: Duration,
+    pub label: &'a str,
+    pub snippets: Vec<&'a CodeSnippet>,
+}
+
+pub struct PlayerPackager {
+    config: PlayerPackageConfig,
+}
+
+impl PlayerPackager {
+    pub fn new(config: PlayerPackageConfig) -> Self {
+        Self { config }
+    }
+
+    /// Write the package into `out_dir`, returning the path of its page.
+    /// Screenshots for the steps are taken from `frames` when given.
+    pub fn export(
+        &self,
+        record: &SessionRecord,
+        frames: Option<&mut dyn FrameSource>,
+        out_dir: &Path,
+    ) -> Result<PathBuf> {
+        let Some(video_name) = record.video_path.file_name() else {
+            bail!("Session {} has no video", record.title);
+        };
+        if !record.video_path.is_file() {
+            bail!("Video {} not found", record.video_path.display());
+        }
+        std::fs::create_dir_all(out_dir).with_context(|| format!("Creating {}", out_dir.display()))?;
+
+        let video_name = video_name.to_string_lossy().into_owned();
+        std::fs::copy(&record.video_path, out_dir.join(&video_name))
+            .with_context(|| format!("Copying video {}", record.video_path.display()))?;
+
+        let mut how_to = self.config.how_to.clone();
+        how_to.video_url = Some(video_name);
+        let generator = HowToGenerator::new(how_to);
+        let mut document = generator.build(record);
+        if let Some(frames) = frames {
+            generator.capture_screenshots(&mut document, frames, out_dir)?;
+        }
+
+        std::fs::write(out_dir.join("captions.vtt"), captions_vtt(record))?;
+        std::fs::write(out_dir.join("chapters.vtt"), chapters_vtt(&document))?;
+        std::fs::write(
+            out_dir.join("player.css"),
+            format!("{}\n{}", self.config.brand_kit.css_variables(), PLAYER_CSS),
+        )?;
+        std::fs::write(out_dir.join("player.js"), PLAYER_JS)?;
+
+        let index = out_dir.join("index.html");
+        let markers = self.markers(record);
+        std::fs::write(&index, self.page(record, &document, &markers))
+            .with_context(|| format!("Writing {}", index.display()))?;
+        Ok(index)
+    }
+
+    /// Markers that made it into the video, in playback order. Markers in
+    /// parts that were cut are left out.
+    pub fn markers<'a>(&self, record: &'a SessionRecord) -> Vec<PlayerMarker<'a>> {
+        let window = self.config.snippet_window;
+        let mut markers: Vec<PlayerMarker> = record
+            .markers
+            .iter()
+            .filter_map(|marker| {
+                let video_time = record.video_time(marker.timestamp)?;
+                let snippets = record
+                    .snippets
+                    .iter()
+                    .filter(|snippet| snippet.timestamp.abs_diff(marker.timestamp) <= window)
+                    .collect();
+                Some(PlayerMarker {
+                    video_time,
+                    label: &marker.label,
+                    snippets,
+                })
+            })
+            .collect();
+        markers.sort_by_key(|marker| marker.video_time);
+        markers
+    }
+
+    fn page(&self, record: &SessionRecord, document: &HowToDocument, markers: &[PlayerMarker]) -> String {
+        let title = escape_html(&record.title);
+        let language = escape_html(&self.config.caption_language);
+        let mut html = String::new();
+        let _ = writeln!(html, "<!DOCTYPE html>\n<html lang=\"{}\">\n<head>\n<meta charset=\"utf-8\">", language);
+        let _ = writeln!(html, "<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">");
+        let _ = writeln!(html, "<title>{}</title>\n<link rel=\"stylesheet\" href=\"player.css\">", title);
+        let _ = writeln!(html, "</head>\n<body>\n<h1>{}</h1>\n<div class=\"player\">\n<div class=\"main\">", title);
+
+        let _ = writeln!(
+            html,
+            "<video id=\"video\" controls preload=\"metadata\" src=\"{}\">\n\
+             <track kind=\"captions\" src=\"captions.vtt\" srclang=\"{}\" label=\"Captions\" default>\n\
+             <track kind=\"chapters\" src=\"chapters.vtt\" srclang=\"{}\" label=\"Chapters\">\n</video>",
+            escape_html(&encode_url(&document.video_url)),
+            language,
+            language
+        );
+        let _ = writeln!(html, "<div id=\"steps\">\n{}</div>\n</div>", document.steps_html());
+
+        html.push_str("<aside class=\"sidebar\">\n<h2>Chapters</h2>\n<ol class=\"chapters\">\n");
+        for step in &document.steps {
+            let _ = writeln!(
+                html,
+                "<li><a href=\"#step-{}\" data-time=\"{:.1}\" data-step=\"{}\">\
+                 <span class=\"time\">{}</span> {}</a></li>",
+                step.number,
+                step.video_start.as_secs_f64(),
+                step.number,
+                format_timestamp(step.video_start),
+                escape_html(&step.heading)
+            );
+        }
+        html.push_str("</ol>\n");
+
+        if !markers.is_empty() {
+            html.push_str("<h2>Markers</h2>\n<ul class=\"markers\">\n");
+            for marker in markers {
+                let _ = writeln!(
+                    html,
+                    "<li><a href=\"#\" data-time=\"{:.1}\"><span class=\"time\">{}</span> {}</a>",
+                    marker.video_time.as_secs_f64(),
+                    format_timestamp(marker.video_time),
+                    escape_html(marker.label)
+                );
+                for snippet in &marker.snippets {
+                    html.push_str("<div class=\"snippet\">");
+                    if let Some(path) = &snippet.path {
+                        let _ = write!(html, "<p class=\"path\">{}</p>", escape_html(path));
+                    }
+                    let _ = writeln!(
+                        html,
+                        "<button class=\"copy\" type=\"button\">Copy</button>\
+                         <pre><code class=\"language-{}\">{}</code></pre></div>",
+                        snippet.language.name(),
+                        escape_html(snippet.code.trim_end())
+                    );
+                }
+                html.push_str("</li>\n");
+            }
+            html.push_str("</ul>\n");
+        }
+        html.push_str("</aside>\n</div>\n");
+
+        // Step ranges on the video clock, used to follow playback
+        let ranges: Vec<String> = document
+            .steps
+            .iter()
+            .map(|step| {
+                format!(
+                    "[{},{:.3},{:.3}]",
+                    step.number,
+                    step.video_start.as_secs_f64(),
+                    step.video_end.as_secs_f64()
+                )
+            })
+            .collect();
+        let _ = writeln!(html, "<script>window.DAILYDOCO_STEPS=[{}];</script>", ranges.join(","));
+        html.push_str("<script src=\"player.js\"></script>\n</body>\n</html>\n");
+        html
+    }
+}
+
+impl Default for PlayerPackager {
+    fn default() -> Self {
+        Self::new(PlayerPackageConfig::default())
+    }
+}
+
+/// Transcript cues on the exported video's clock. A transcript segment that
+/// spans a cut becomes one cue per kept part, and cut parts are dropped.
+pub fn captions_vtt(record: &SessionRecord) -> String {
+    let mut vtt = String::from("WEBVTT\n\n");
+    let mut position = Duration::ZERO;
+
+    for segment in &record.segments {
+        for transcript in &record.transcript {
+            let start = transcript.start.max(segment.start_time);
+            let end = transcript.end.min(segment.end_time);
+            let text = transcript.text.trim();
+            if start >= end || text.is_empty() {
+                continue;
+            }
+            let offset = position + (start - segment.start_time);
+            let _ = writeln!(
+                vtt,
+                "{} --> {}\n{}\n",
+                vtt_timestamp(offset),
+                vtt_timestamp(offset + (end - start)),
+                vtt_text(text)
+            );
+        }
+        position += segment.end_time.saturating_sub(segment.start_time);
+    }
+
+    vtt
+}
+
+/// One chapter cue per how-to step
+pub fn chapters_vtt(document: &HowToDocument) -> String {
+    let mut vtt = String::from("WEBVTT\n\n");
+    for step in &document.steps {
+        let _ = writeln!(
+            vtt,
+            "step-{}\n{} --> {}\n{}\n",
+            step.number,
+            vtt_timestamp(step.video_start),
+            vtt_timestamp(step.video_end),
+            vtt_text(&step.heading)
+        );
+    }
+    vtt
+}
+
+/// `hh:mm:ss.mmm`
+fn vtt_timestamp(timestamp: Duration) -> String {
+    let millis = timestamp.as_millis();
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        millis / 3_600_000,
+        (millis / 60_000) % 60,
+        (millis / 1000) % 60,
+        millis % 1000
+    )
+}
+
+/// Cue text on one line, with the characters WebVTT reserves escaped
+fn vtt_text(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+const PLAYER_CSS: &str = "*{box-sizing:border-box}\
+body{margin:0;padding:24px;background:var(--brand-bg);color:var(--brand-text);font-family:var(--brand-font);\
+line-height:1.6}\
+a{color:var(--brand-accent);text-decoration:none}\
+h1,h2{line-height:1.25}\
+.player{display:grid;grid-template-columns:minmax(0,1fr) 320px;gap:24px;align-items:start}\
+video{width:100%;border-radius:8px;background:#000}\
+#steps{max-height:60vh;overflow-y:auto;padding-right:8px}\
+.step{border-left:3px solid transparent;padding-left:12px;margin-top:16px;opacity:.6;transition:opacity .2s}\
+.step.active{border-left-color:var(--brand-primary);opacity:1}\
+.step img{max-width:100%;border-radius:6px;border:1px solid var(--brand-border)}\
+.sidebar{position:sticky;top:24px;max-height:calc(100vh - 48px);overflow-y:auto;background:var(--brand-surface);\
+border:1px solid var(--brand-border);border-radius:8px;padding:8px 16px}\
+.sidebar h2{font-size:1em;margin:12px 0 4px}\
+.chapters,.markers{padding-left:0;list-style:none;margin:0}\
+.chapters li,.markers li{padding:4px 0}\
+.chapters a.active{font-weight:600}\
+.time{color:var(--brand-muted);font-size:.9em;font-variant-numeric:tabular-nums}\
+.snippet{position:relative;margin-top:6px}\
+.copy{position:absolute;top:4px;right:4px;font-size:.8em;cursor:pointer;border-radius:4px;\
+border:1px solid var(--brand-border);background:var(--brand-bg);color:var(--brand-text)}\
+pre{background:var(--brand-bg);border:1px solid var(--brand-border);border-radius:6px;padding:12px;\
+overflow-x:auto;margin:0}\
+code,.path{font-family:var(--brand-code-font)}\
+.path{margin:0;color:var(--brand-muted);font-size:.85em}\
+@media (max-width:800px){.player{grid-template-columns:1fr}.sidebar{position:static}}";
+
+const PLAYER_JS: &str = r#"(function () {
+  var video = document.getElementById("video");
+  var steps = window.DAILYDOCO_STEPS || [];
+  var panel = document.getElementById("steps");
+  var current = null;
+
+  document.addEventListener("click", function (e) {
+    var copy = e.target.closest(".copy");
+    if (copy) {
+      var code = copy.parentNode.querySelector("code").textContent;
+      navigator.clipboard.writeText(code).then(function () {
+        copy.textContent = "Copied";
+        setTimeout(function () { copy.textContent = "Copy"; }, 1500);
+      });
+      return;
+    }
+    var link = e.target.closest("[data-time]");
+    if (!link) return;
+    e.preventDefault();
+    video.currentTime = parseFloat(link.dataset.time);
+    video.play();
+  });
+
+  function follow() {
+    var t = video.currentTime, active = null;
+    for (var i = 0; i < steps.length; i++) {
+      if (t >= steps[i][1] && t < steps[i][2]) active = steps[i][0];
+    }
+    if (active === current) return;
+    current = active;
+    document.querySelectorAll(".step.active, .chapters a.active").forEach(function (el) {
+      el.classList.remove("active");
+    });
+    if (active === null) return;
+    var section = document.getElementById("step-" + active);
+    var chapter = document.querySelector('.chapters a[data-step="' + active + '"]');
+    if (chapter) chapter.classList.add("active");
+    if (section) {
+      section.classList.add("active");
+      panel.scrollTo({ top: section.offsetTop - panel.offsetTop, behavior: "smooth" });
+    }
+  }
+
+  video.addEventListener("timeupdate", follow);
+  video.addEventListener("seeked", follow);
+})();
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use chrono::Utc;
+    use shared_types::CaptureSession;
+    use uuid::Uuid;
+
+    use crate::intelligent_clip_selector::{EventType, ImportanceScore, VideoSegment};
+    use crate::session::{SessionMarker, TranscriptSegment};
+    use crate::syntax_highlight::Language;
+
+    fn segment(start: u64, end: u64) -> VideoSegment {
+        VideoSegment {
+            start_time: Duration::from_secs(start),
+            end_time: Duration::from_secs(end),
+            importance_score: 0.8,
+            events: vec![ImportanceScore {
+                timestamp: Duration::from_secs(start + 1),
+                score: 0.8,
+                confidence: 0.9,
+                event_type: EventType::Testing,
+                context: HashMap::new(),
+            }],
+            narrative_weight: 0.5,
+            viewer_engagement_prediction: 0.5,
+        }
+    }
+
+    fn record(video_path: &Path) -> SessionRecord {
+        let session = CaptureSession {
+            id: Uuid::new_v4(),
+            project_id: Uuid::new_v4(),
+            started_at: Utc::now(),
+            duration: None,
+        };
+        let mut record = SessionRecord::new(session, "Retry failed uploads", video_path);
+        record.segments = vec![segment(10, 20), segment(50, 60)];
+        let transcript = [(8, 12, "First we <reproduce> it"), (18, 52, "then retry & pass"), (30, 40, "cut")];
+        for (start, end, text) in transcript {
+            record.transcript.push(TranscriptSegment {
+                start: Duration::from_secs(start),
+                end: Duration::from_secs(end),
+                text: text.to_string(),
+            });
+        }
+        for (seconds, label) in [(55, "Retry works"), (14, "Add backoff"), (30, "Lunch")] {
+            record.markers.push(SessionMarker {
+                timestamp: Duration::from_secs(seconds),
+                label: label.to_string(),
+            });
+        }
+        record.snippets.push(CodeSnippet {
+            timestamp: Duration::from_secs(16),
+            path: Some("src/upload.rs".to_string()),
+            language: Language::Rust,
+            code: "let delay = base * 2u32.pow(attempt);\n".to_string(),
+        });
+        record
+    }
+
+    #[test]
+    fn test_captions_follow_the_cut() {
+        let vtt = captions_vtt(&record(Path::new("video.mp4")));
+
+        assert!(vtt.starts_with("WEBVTT\n\n"));
+        assert!(vtt.contains("00:00:00.000 --> 00:00:02.000\nFirst we &lt;reproduce&gt; it\n"));
+        // Spans the cut, so it is split into one cue per kept part
+        assert!(vtt.contains("00:00:08.000 --> 00:00:10.000\nthen retry &amp; pass\n"));
+        assert!(vtt.contains("00:00:10.000 --> 00:00:12.000\nthen retry &amp; pass\n"));
+        assert!(!vtt.contains("cut\n"));
+    }
+
+    #[test]
+    fn test_markers_with_snippets_in_video_order() {
+        let record = record(Path::new("video.mp4"));
+        let markers = PlayerPackager::default().markers(&record);
+
+        let labels: Vec<&str> = markers.iter().map(|marker| marker.label).collect();
+        assert_eq!(labels, ["Add backoff", "Retry works"]);
+        assert_eq!(markers[0].video_time, Duration::from_secs(4));
+        assert_eq!(markers[1].video_time, Duration::from_secs(15));
+        assert_eq!(markers[0].snippets.len(), 1);
+        assert!(markers[1].snippets.is_empty());
+    }
+
+    #[test]
+    fn test_export_writes_package() {
+        let dir = std::env::temp_dir().join(format!("dailydoco-player-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let video = dir.join("recording.mp4");
+        std::fs::write(&video, b"not really a video").unwrap();
+
+        let out = dir.join("package");
+        let index = PlayerPackager::default().export(&record(&video), None, &out).unwrap();
+        let html = std::fs::read_to_string(&index).unwrap();
+
+        for file in ["recording.mp4", "captions.vtt", "chapters.vtt", "player.css", "player.js"] {
+            assert!(out.join(file).is_file(), "{} missing", file);
+        }
+        assert!(html.contains("<track kind=\"captions\" src=\"captions.vtt\" srclang=\"en\""));
+        assert!(html.contains("<section class=\"step\" id=\"step-2\">"));
+        assert!(html.contains("<button class=\"copy\" type=\"button\">Copy</button>"));
+        assert!(html.contains("window.DAILYDOCO_STEPS=[[1,0.000,10.000],[2,10.000,20.000]];"));
+        let chapters = std::fs::read_to_string(out.join("chapters.vtt")).unwrap();
+        assert!(chapters.contains("step-2\n00:00:10.000 --> 00:00:20.000\nRetry works\n"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,146 @@
+//! Keyframes decoded from a session's exported video
+//!
+//! `RecordingFrames` is the `FrameSource` for a saved session. A capture-clock
+//! timestamp is first mapped onto the cut video through the session's
+//! segments, then ffmpeg seeks to it and writes that one frame out as PNG.
+
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use aegnt_27::visual::{ColorSpace, VideoFrame};
+use anyhow::{anyhow, bail, Context, Result};
+
+use crate::session::{format_timestamp, FrameSource, SessionRecord};
+
+/// Frames of `SessionRecord::video_path`, extracted with ffmpeg
+pub struct RecordingFrames {
+    video_path: PathBuf,
+    /// Capture-clock spans kept in the video, in playback order
+    segments: Vec<(Duration, Duration)>,
+}
+
+impl RecordingFrames {
+    pub fn new(record: &SessionRecord) -> Self {
+        Self {
+            video_path: record.video_path.clone(),
+            segments: record.segments.iter().map(|s| (s.start_time, s.end_time)).collect(),
+        }
+    }
+
+    /// Position of a capture-clock `timestamp` in the video, as
+    /// `SessionRecord::video_time` computes it
+    fn video_time(&self, timestamp: Duration) -> Option<Duration> {
+        let mut position = Duration::ZERO;
+        for &(start, end) in &self.segments {
+            if timestamp >= start && timestamp < end {
+                return Some(position + (timestamp - start));
+            }
+            position += end.saturating_sub(start);
+        }
+        None
+    }
+
+    /// ffmpeg writing the single frame at `position` to stdout as PNG.
+    /// Seeking before `-i` is fast and still frame-accurate when transcoding.
+    fn command(&self, position: Duration) -> Command {
+        let mut command = Command::new("ffmpeg");
+        command.args(["-v", "error", "-ss", &format!("{:.3}", position.as_secs_f64()), "-i"]);
+        command.arg(&self.video_path);
+        command.args(["-frames:v", "1", "-f", "image2pipe", "-vcodec", "png", "-"]);
+        command.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped());
+        command
+    }
+}
+
+impl FrameSource for RecordingFrames {
+    fn frame_at(&mut self, timestamp: Duration) -> Result<VideoFrame> {
+        let position = self.video_time(timestamp).ok_or_else(|| {
+            anyhow!("{} was cut from {}", format_timestamp(timestamp), self.video_path.display())
+        })?;
+
+        let output = self.command(position).output().context("Running ffmpeg (is it installed?)")?;
+        if !output.status.success() {
+            bail!(
+                "ffmpeg couldn't extract the frame at {} from {}: {}",
+                format_timestamp(position),
+                self.video_path.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        // Past the end ffmpeg succeeds without writing a frame
+        if output.stdout.is_empty() {
+            bail!("{} has no frame at {}", self.video_path.display(), format_timestamp(position));
+        }
+        decode_png(&output.stdout, timestamp)
+    }
+}
+
+fn decode_png(png: &[u8], timestamp: Duration) -> Result<VideoFrame> {
+    let image = image::load_from_memory_with_format(png, image::ImageFormat::Png)
+        .context("Decoding the frame from ffmpeg")?
+        .into_rgb8();
+    let (width, height) = image.dimensions();
+    Ok(VideoFrame::new(image.into_raw(), width, height, ColorSpace::RGB, timestamp))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use shared_types::CaptureSession;
+    use uuid::Uuid;
+
+    use crate::intelligent_clip_selector::VideoSegment;
+
+    fn frames() -> RecordingFrames {
+        let session = CaptureSession {
+            id: Uuid::new_v4(),
+            project_id: Uuid::new_v4(),
+            started_at: Utc::now(),
+            duration: None,
+        };
+        let mut record = SessionRecord::new(session, "Fix login", "video.mp4");
+        for (start, end) in [(10, 20), (40, 45)] {
+            record.segments.push(VideoSegment {
+                start_time: Duration::from_secs(start),
+                end_time: Duration::from_secs(end),
+                importance_score: 0.8,
+                events: Vec::new(),
+                narrative_weight: 0.5,
+                viewer_engagement_prediction: 0.5,
+            });
+        }
+        RecordingFrames::new(&record)
+    }
+
+    #[test]
+    fn test_seeks_to_the_position_in_the_cut_video() {
+        let frames = frames();
+        assert_eq!(frames.video_time(Duration::from_secs(12)), Some(Duration::from_secs(2)));
+        assert_eq!(frames.video_time(Duration::from_secs(42)), Some(Duration::from_secs(12)));
+
+        let command = frames.command(Duration::from_millis(12_500));
+        let args: Vec<_> = command.get_args().map(|arg| arg.to_string_lossy().into_owned()).collect();
+        assert_eq!(args[..5], ["-v", "error", "-ss", "12.500", "-i"]);
+        assert_eq!(args[5], "video.mp4");
+    }
+
+    #[test]
+    fn test_cut_timestamps_are_an_error() {
+        let error = frames().frame_at(Duration::from_secs(30)).unwrap_err();
+        assert!(error.to_string().contains("was cut from video.mp4"));
+    }
+
+    #[test]
+    fn test_decodes_png_output() {
+        let mut image = image::RgbImage::new(4, 2);
+        image.put_pixel(3, 1, image::Rgb([200, 10, 30]));
+        let mut png = std::io::Cursor::new(Vec::new());
+        image.write_to(&mut png, image::ImageFormat::Png).unwrap();
+
+        let frame = decode_png(png.get_ref(), Duration::from_secs(3)).unwrap();
+        assert_eq!((frame.width, frame.height, frame.timestamp), (4, 2, Duration::from_secs(3)));
+        assert_eq!(frame.data[(4 + 3) * 3..][..3], [200, 10, 30]);
+    }
+}
@@ -0,0 +1,525 @@
+//! Release notes and release-video storyboards for DailyDoco Pro
+//!
+//! Gathers the commits between two tags, links each to the capture sessions
+//! that recorded it (and the markers set while working on it), groups them
+//! into categories modelled on `EventType`, and writes release notes plus a
+//! storyboard for the release video.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::Path;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use anyhow::{Context, Result};
+use uuid::Uuid;
+
+use crate::diff_walkthrough::{DiffCommit, GitRange};
+use crate::intelligent_clip_selector::EventType;
+use crate::session::{format_timestamp, FrameSource, SessionMarker, SessionRecord};
+use crate::storyboard::{ContactSheetConfig, Storyboard, StoryboardPanel};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ChangeCategory {
+    Feature,
+    Fix,
+    Performance,
+    Refactor,
+    Documentation,
+    Testing,
+    Other,
+}
+
+impl ChangeCategory {
+    /// Order categories appear in the notes
+    pub const ALL: [ChangeCategory; 7] = [
+        ChangeCategory::Feature,
+        ChangeCategory::Fix,
+        ChangeCategory::Performance,
+        ChangeCategory::Refactor,
+        ChangeCategory::Documentation,
+        ChangeCategory::Testing,
+        ChangeCategory::Other,
+    ];
+
+    pub fn heading(&self) -> &'static str {
+        match self {
+            ChangeCategory::Feature => "Features",
+            ChangeCategory::Fix => "Fixes",
+            ChangeCategory::Performance => "Performance",
+            ChangeCategory::Refactor => "Refactors",
+            ChangeCategory::Documentation => "Documentation",
+            ChangeCategory::Testing => "Tests",
+            ChangeCategory::Other => "Other changes",
+        }
+    }
+
+    pub fn from_event_type(event_type: &EventType) -> Self {
+        match event_type {
+            EventType::CodeGeneration | EventType::BreakthroughMoment => ChangeCategory::Feature,
+            EventType::Debugging | EventType::ErrorResolution => ChangeCategory::Fix,
+            EventType::Refactoring => ChangeCategory::Refactor,
+            EventType::Documentation => ChangeCategory::Documentation,
+            EventType::Testing => ChangeCategory::Testing,
+            EventType::LearningMoment | EventType::CollaborativeMoment | EventType::DeploymentMoment => {
+                ChangeCategory::Other
+            }
+        }
+    }
+
+    /// Category from a conventional-commit prefix (`feat:`, `fix(parser):`)
+    fn from_prefix(prefix: &str) -> Option<Self> {
+        Some(match prefix {
+            "feat" | "feature" => ChangeCategory::Feature,
+            "fix" | "bugfix" | "hotfix" => ChangeCategory::Fix,
+            "perf" => ChangeCategory::Performance,
+            "refactor" => ChangeCategory::Refactor,
+            "docs" | "doc" => ChangeCategory::Documentation,
+            "test" | "tests" => ChangeCategory::Testing,
+            "chore" | "build" | "ci" | "style" | "revert" => ChangeCategory::Other,
+            _ => return None,
+        })
+    }
+
+    /// Category from the leading verb of a plain commit subject, skipping
+    /// ticket references such as `[ABC-123]`
+    fn from_verb(summary: &str) -> Option<Self> {
+        let verb = summary
+            .split_whitespace()
+            .find(|word| !(word.starts_with('[') && word.ends_with(']')))?
+            .to_lowercase();
+        Some(match verb.as_str() {
+            "add" | "adds" | "added" | "implement" | "introduce" | "support" | "create" | "allow" | "enable" => {
+                ChangeCategory::Feature
+            }
+            "fix" | "fixes" | "fixed" | "resolve" | "correct" | "prevent" | "handle" => ChangeCategory::Fix,
+            "optimize" | "optimise" | "speed" | "cache" => ChangeCategory::Performance,
+            "refactor" | "rename" | "move" | "extract" | "simplify" | "clean" | "split" | "restructure" => {
+                ChangeCategory::Refactor
+            }
+            "document" | "docs" | "readme" => ChangeCategory::Documentation,
+            "test" | "tests" => ChangeCategory::Testing,
+            _ => return None,
+        })
+    }
+}
+
+/// A capture session in which a commit was made
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkedSession {
+    pub session_id: Uuid,
+    pub title: String,
+    /// When the commit was made, on the session's capture clock
+    pub timestamp: Duration,
+    /// Markers set between the session's previous commit and this one
+    pub markers: Vec<SessionMarker>,
+    /// Most important event while working towards the commit
+    pub main_event: Option<EventType>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReleaseChange {
+    pub hash: String,
+    /// Commit subject without its conventional-commit prefix
+    pub summary: String,
+    pub scope: Option<String>,
+    pub category: ChangeCategory,
+    pub breaking: bool,
+    pub sessions: Vec<LinkedSession>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReleaseNotes {
+    pub from: String,
+    pub to: String,
+    pub changes: Vec<ReleaseChange>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReleaseStoryboardConfig {
+    /// Planned length of the title panel
+    pub intro_duration: Duration,
+    /// Planned screen time of each change
+    pub change_duration: Duration,
+    pub max_panels: usize,
+    /// Also give chores and other uncategorized changes a panel
+    pub include_other: bool,
+}
+
+impl Default for ReleaseStoryboardConfig {
+    fn default() -> Self {
+        Self {
+            intro_duration: Duration::from_secs(4),
+            change_duration: Duration::from_secs(6),
+            max_panels: 24,
+            include_other: false,
+        }
+    }
+}
+
+impl ReleaseNotes {
+    /// Read commits between two tags from `repo` and link them to `sessions`
+    pub fn gather(repo: &Path, from: &str, to: &str, sessions: &[SessionRecord]) -> Result<Self> {
+        let commits = GitRange::new(repo, format!("{}..{}", from, to))
+            .commits()
+            .with_context(|| format!("Reading commits {}..{}", from, to))?;
+        Ok(Self::from_commits(from, to, &commits, sessions))
+    }
+
+    /// Categorize commits, oldest first, skipping merge commits
+    pub fn from_commits(from: &str, to: &str, commits: &[DiffCommit], sessions: &[SessionRecord]) -> Self {
+        let changes = commits
+            .iter()
+            .filter(|commit| !commit.summary.starts_with("Merge "))
+            .map(|commit| {
+                let linked = linked_sessions(&commit.hash, sessions);
+                let (prefix_category, scope, breaking, summary) = parse_subject(&commit.summary);
+                let category = prefix_category
+                    .or_else(|| ChangeCategory::from_verb(&summary))
+                    .or_else(|| {
+                        linked
+                            .iter()
+                            .find_map(|session| session.main_event.as_ref().map(ChangeCategory::from_event_type))
+                    })
+                    .unwrap_or(ChangeCategory::Other);
+
+                ReleaseChange {
+                    hash: commit.hash.clone(),
+                    summary,
+                    scope,
+                    category,
+                    breaking: breaking || commit.body.contains("BREAKING CHANGE"),
+                    sessions: linked,
+                }
+            })
+            .collect();
+
+        Self {
+            from: from.to_string(),
+            to: to.to_string(),
+            changes,
+        }
+    }
+
+    /// Non-empty categories in display order
+    pub fn grouped(&self) -> Vec<(ChangeCategory, Vec<&ReleaseChange>)> {
+        ChangeCategory::ALL
+            .iter()
+            .map(|&category| (category, self.changes.iter().filter(|c| c.category == category).collect::<Vec<_>>()))
+            .filter(|(_, changes)| !changes.is_empty())
+            .collect()
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::new();
+        let _ = writeln!(md, "# Release {}\n", self.to);
+        let _ = writeln!(md, "Changes since {} · {}\n", self.from, plural(self.changes.len(), "change"));
+
+        let breaking: Vec<&ReleaseChange> = self.changes.iter().filter(|c| c.breaking).collect();
+        if !breaking.is_empty() {
+            md.push_str("## ⚠ Breaking changes\n\n");
+            for change in breaking {
+                let _ = writeln!(md, "- {}", change_line(change));
+            }
+            md.push('\n');
+        }
+
+        for (category, changes) in self.grouped() {
+            let _ = writeln!(md, "## {}\n", category.heading());
+            for change in changes {
+                let _ = writeln!(md, "- {}", change_line(change));
+                for session in &change.sessions {
+                    let at = format_timestamp(session.timestamp);
+                    let _ = write!(md, "  - Recorded in *{}* at {}", session.title, at);
+                    if !session.markers.is_empty() {
+                        let labels: Vec<&str> = session.markers.iter().map(|m| m.label.as_str()).collect();
+                        let _ = write!(md, " — {}", labels.join(", "));
+                    }
+                    md.push('\n');
+                }
+            }
+            md.push('\n');
+        }
+
+        md
+    }
+
+    /// Panels for the release video: a title panel, then one per change
+    /// with a keyframe from the session that recorded it when available
+    pub fn storyboard(
+        &self,
+        sources: &mut HashMap<Uuid, Box<dyn FrameSource>>,
+        config: &ReleaseStoryboardConfig,
+    ) -> Result<Storyboard> {
+        let mut storyboard = Storyboard::new(format!("Release {}", self.to));
+        let mut intro = StoryboardPanel::new(
+            Duration::ZERO,
+            format!("Release {}", self.to),
+            format!("{} since {}", plural(self.changes.len(), "change"), self.from),
+        );
+        intro.placeholder = self.to.clone();
+        storyboard.panels.push(intro);
+
+        let mut timecode = config.intro_duration;
+        let changes = self
+            .grouped()
+            .into_iter()
+            .filter(|(category, _)| config.include_other || *category != ChangeCategory::Other)
+            .flat_map(|(_, changes)| changes)
+            .take(config.max_panels.saturating_sub(1));
+
+        for change in changes {
+            let mut caption = change.summary.clone();
+            let markers: Vec<&str> = change
+                .sessions
+                .iter()
+                .flat_map(|session| session.markers.iter().map(|m| m.label.as_str()))
+                .collect();
+            if !markers.is_empty() {
+                caption = format!("{} — {}", caption, markers.join(", "));
+            }
+
+            let mut panel = StoryboardPanel::new(timecode, change.category.heading(), caption);
+            panel.placeholder = change.category.heading().to_string();
+            for session in &change.sessions {
+                if let Some(source) = sources.get_mut(&session.session_id) {
+                    panel.keyframe = Some(source.frame_at(session.timestamp)?);
+                    break;
+                }
+            }
+
+            storyboard.panels.push(panel);
+            timecode += config.change_duration;
+        }
+
+        Ok(storyboard)
+    }
+
+    /// Write `release-notes.md`, `storyboard.json` and `storyboard.png`
+    pub fn write(&self, storyboard: &Storyboard, sheet: &ContactSheetConfig, out_dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(out_dir).with_context(|| format!("Creating {}", out_dir.display()))?;
+        std::fs::write(out_dir.join("release-notes.md"), self.to_markdown())?;
+        std::fs::write(out_dir.join("storyboard.json"), serde_json::to_string_pretty(storyboard)?)?;
+        storyboard.save_contact_sheet(sheet, &out_dir.join("storyboard.png"))
+    }
+}
+
+/// Sessions that recorded `hash`, matching abbreviated hashes either way
+fn linked_sessions(hash: &str, sessions: &[SessionRecord]) -> Vec<LinkedSession> {
+    let matches = |other: &str| other.len() >= 7 && (hash.starts_with(other) || other.starts_with(hash));
+
+    sessions
+        .iter()
+        .filter_map(|record| {
+            let commit = record.commits.iter().find(|c| matches(&c.hash))?;
+            let since = record
+                .commits
+                .iter()
+                .map(|c| c.timestamp)
+                .filter(|&t| t < commit.timestamp)
+                .max()
+                .unwrap_or(Duration::ZERO);
+
+            let markers = record
+                .markers
+                .iter()
+                .filter(|m| m.timestamp >= since && m.timestamp <= commit.timestamp)
+                .cloned()
+                .collect();
+            let main_event = record
+                .segments
+                .iter()
+                .flat_map(|segment| &segment.events)
+                .filter(|event| event.timestamp >= since && event.timestamp <= commit.timestamp)
+                .max_by(|a, b| a.score.total_cmp(&b.score))
+                .map(|event| event.event_type.clone());
+
+            Some(LinkedSession {
+                session_id: record.session.id,
+                title: record.title.clone(),
+                timestamp: commit.timestamp,
+                markers,
+                main_event,
+            })
+        })
+        .collect()
+}
+
+/// Split `type(scope)!: summary` into its parts; subjects without a known
+/// prefix are returned whole
+fn parse_subject(subject: &str) -> (Option<ChangeCategory>, Option<String>, bool, String) {
+    if let Some((prefix, rest)) = subject.split_once(':') {
+        let breaking = prefix.ends_with('!');
+        let prefix = prefix.trim_end_matches('!');
+        let (kind, scope) = match prefix.split_once('(') {
+            Some((kind, scope)) => (kind, Some(scope.trim_end_matches(')').to_string())),
+            None => (prefix, None),
+        };
+        if let Some(category) = ChangeCategory::from_prefix(&kind.to_lowercase()) {
+            return (Some(category), scope, breaking, capitalize(rest.trim()));
+        }
+    }
+    (None, None, false, subject.trim().to_string())
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn change_line(change: &ReleaseChange) -> String {
+    let hash = &change.hash[..change.hash.len().min(7)];
+    match &change.scope {
+        Some(scope) => format!("**{}:** {} (`{}`)", scope, change.summary, hash),
+        None => format!("{} (`{}`)", change.summary, hash),
+    }
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("1 {}", noun)
+    } else {
+        format!("{} {}s", count, noun)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aegnt_27::visual::VideoFrame;
+    use chrono::Utc;
+    use shared_types::CaptureSession;
+
+    use crate::canvas::{solid_frame, Rgba};
+    use crate::intelligent_clip_selector::{ImportanceScore, VideoSegment};
+    use crate::session::SessionCommit;
+
+    fn commit(hash: &str, summary: &str) -> DiffCommit {
+        DiffCommit {
+            hash: hash.to_string(),
+            summary: summary.to_string(),
+            body: String::new(),
+        }
+    }
+
+    fn commits() -> Vec<DiffCommit> {
+        vec![
+            commit("aaaaaaa1111", "feat(parser): support unicode identifiers"),
+            commit("bbbbbbb2222", "[LEX-42] Fix crash on empty input"),
+            commit("ccccccc3333", "Merge branch 'feature'"),
+            commit("ddddddd4444", "Tidy up the lexer"),
+            commit("eeeeeee5555", "docs: describe the grammar"),
+            commit("fffffff6666", "refactor!: drop the legacy AST"),
+        ]
+    }
+
+    fn session() -> SessionRecord {
+        let capture = CaptureSession {
+            id: Uuid::new_v4(),
+            project_id: Uuid::new_v4(),
+            started_at: Utc::now(),
+            duration: None,
+        };
+        let mut record = SessionRecord::new(capture, "Lexer cleanup", "video.mp4");
+        record.segments.push(VideoSegment {
+            start_time: Duration::from_secs(60),
+            end_time: Duration::from_secs(200),
+            importance_score: 0.7,
+            events: vec![ImportanceScore {
+                timestamp: Duration::from_secs(150),
+                score: 0.9,
+                confidence: 0.8,
+                event_type: EventType::Refactoring,
+                context: HashMap::new(),
+            }],
+            narrative_weight: 0.5,
+            viewer_engagement_prediction: 0.5,
+        });
+        for (seconds, hash) in [(100, "bbbbbbb"), (240, "ddddddd4444ffff")] {
+            record.commits.push(SessionCommit {
+                timestamp: Duration::from_secs(seconds),
+                hash: hash.to_string(),
+                summary: String::new(),
+                files: Vec::new(),
+            });
+        }
+        for (seconds, label) in [(90, "Reproduce crash"), (130, "Split token kinds")] {
+            record.markers.push(SessionMarker {
+                timestamp: Duration::from_secs(seconds),
+                label: label.to_string(),
+            });
+        }
+        record
+    }
+
+    struct Solid;
+
+    impl FrameSource for Solid {
+        fn frame_at(&mut self, timestamp: Duration) -> Result<VideoFrame> {
+            Ok(solid_frame(32, 18, Rgba::new(10, 200, 10, 255), timestamp))
+        }
+    }
+
+    #[test]
+    fn test_commits_are_categorized() {
+        let notes = ReleaseNotes::from_commits("v1.0.0", "v1.1.0", &commits(), &[session()]);
+        assert_eq!(notes.changes.len(), 5);
+
+        let categories: Vec<ChangeCategory> = notes.changes.iter().map(|c| c.category).collect();
+        assert_eq!(
+            categories,
+            [
+                ChangeCategory::Feature,
+                ChangeCategory::Fix,
+                // No prefix or known verb: falls back to the session's main event
+                ChangeCategory::Refactor,
+                ChangeCategory::Documentation,
+                ChangeCategory::Refactor,
+            ]
+        );
+        assert_eq!(notes.changes[0].scope.as_deref(), Some("parser"));
+        assert_eq!(notes.changes[0].summary, "Support unicode identifiers");
+        assert!(notes.changes[4].breaking);
+    }
+
+    #[test]
+    fn test_sessions_and_markers_are_linked() {
+        let notes = ReleaseNotes::from_commits("v1.0.0", "v1.1.0", &commits(), &[session()]);
+
+        let fix = &notes.changes[1];
+        assert_eq!(fix.sessions.len(), 1);
+        let labels: Vec<&str> = fix.sessions[0].markers.iter().map(|m| m.label.as_str()).collect();
+        assert_eq!(labels, ["Reproduce crash"]);
+
+        let tidy = &notes.changes[2];
+        assert_eq!(tidy.sessions[0].timestamp, Duration::from_secs(240));
+        assert_eq!(tidy.sessions[0].markers[0].label, "Split token kinds");
+        assert!(notes.changes[0].sessions.is_empty());
+
+        let markdown = notes.to_markdown();
+        assert!(markdown.contains("## ⚠ Breaking changes"));
+        assert!(markdown.contains("- **parser:** Support unicode identifiers (`aaaaaaa`)"));
+        assert!(markdown.contains("  - Recorded in *Lexer cleanup* at 1:40 — Reproduce crash"));
+        assert!(markdown.find("## Features").unwrap() < markdown.find("## Fixes").unwrap());
+    }
+
+    #[test]
+    fn test_storyboard_uses_session_keyframes() {
+        let record = session();
+        let notes = ReleaseNotes::from_commits("v1.0.0", "v1.1.0", &commits(), std::slice::from_ref(&record));
+
+        let mut sources: HashMap<Uuid, Box<dyn FrameSource>> = HashMap::new();
+        sources.insert(record.session.id, Box::new(Solid));
+        let storyboard = notes.storyboard(&mut sources, &ReleaseStoryboardConfig::default()).unwrap();
+
+        assert_eq!(storyboard.panels.len(), 6);
+        assert_eq!(storyboard.panels[0].title, "Release v1.1.0");
+        let fix = storyboard.panels.iter().find(|p| p.title == "Fixes").unwrap();
+        assert_eq!(fix.keyframe.as_ref().unwrap().timestamp, Duration::from_secs(100));
+        assert!(fix.caption.ends_with("— Reproduce crash"));
+        assert!(storyboard.panels[1].keyframe.is_none());
+        assert_eq!(storyboard.panels[2].timecode, Duration::from_secs(10));
+    }
+}
@@ -0,0 +1,265 @@
+//! Session records for DailyDoco Pro exports
+//!
+//! Everything captured about one session that written and web exports draw
+//! on: the selected segments, markers, shell commands, commits, transcript
+//! and code snippets, all on the recording's capture clock.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use anyhow::{Context, Result};
+
+use aegnt_27::visual::VideoFrame;
+use shared_types::{CaptureSession, Project};
+
+use crate::audio_preview::AudioPreview;
+use crate::av_sync::DriftStats;
+use crate::code_animation::CodeAnimation;
+use crate::intelligent_clip_selector::VideoSegment;
+use crate::syntax_highlight::Language;
+use crate::terminal_renderer::TerminalRenderer;
+
+/// A point the user flagged while recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionMarker {
+    pub timestamp: Duration,
+    pub label: String,
+}
+
+/// A shell command run during the session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionCommand {
+    pub timestamp: Duration,
+    pub command: String,
+    #[serde(default)]
+    pub output: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionCommit {
+    pub timestamp: Duration,
+    pub hash: String,
+    pub summary: String,
+    #[serde(default)]
+    pub files: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptSegment {
+    pub start: Duration,
+    pub end: Duration,
+    pub text: String,
+}
+
+/// Code shown or written at a point in the session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeSnippet {
+    pub timestamp: Duration,
+    #[serde(default)]
+    pub path: Option<String>,
+    pub language: Language,
+    pub code: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionRecord {
+    pub session: CaptureSession,
+    pub title: String,
+    /// The exported video, cut from the recording by `segments`
+    pub video_path: PathBuf,
+    pub segments: Vec<VideoSegment>,
+    #[serde(default)]
+    pub markers: Vec<SessionMarker>,
+    #[serde(default)]
+    pub commands: Vec<SessionCommand>,
+    #[serde(default)]
+    pub commits: Vec<SessionCommit>,
+    #[serde(default)]
+    pub transcript: Vec<TranscriptSegment>,
+    #[serde(default)]
+    pub snippets: Vec<CodeSnippet>,
+    /// Audio clock drift measured per track, and how it was corrected
+    #[serde(default)]
+    pub av_sync: Vec<DriftStats>,
+    /// Waveform peaks and spectrograms of each audio track, for the editor
+    #[serde(default)]
+    pub audio_previews: Vec<AudioPreview>,
+}
+
+impl SessionRecord {
+    pub fn new(session: CaptureSession, title: impl Into<String>, video_path: impl Into<PathBuf>) -> Self {
+        Self {
+            session,
+            title: title.into(),
+            video_path: video_path.into(),
+            segments: Vec::new(),
+            markers: Vec::new(),
+            commands: Vec::new(),
+            commits: Vec::new(),
+            transcript: Vec::new(),
+            snippets: Vec::new(),
+            av_sync: Vec::new(),
+            audio_previews: Vec::new(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).with_context(|| format!("Reading {}", path.display()))?;
+        serde_json::from_str(&text).with_context(|| format!("Parsing session {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Writing {}", path.display()))
+    }
+
+    pub fn markers_between(&self, start: Duration, end: Duration) -> Vec<&SessionMarker> {
+        self.markers.iter().filter(|m| m.timestamp >= start && m.timestamp < end).collect()
+    }
+
+    pub fn commands_between(&self, start: Duration, end: Duration) -> Vec<&SessionCommand> {
+        self.commands.iter().filter(|c| c.timestamp >= start && c.timestamp < end).collect()
+    }
+
+    pub fn commits_between(&self, start: Duration, end: Duration) -> Vec<&SessionCommit> {
+        self.commits.iter().filter(|c| c.timestamp >= start && c.timestamp < end).collect()
+    }
+
+    pub fn snippets_between(&self, start: Duration, end: Duration) -> Vec<&CodeSnippet> {
+        self.snippets.iter().filter(|s| s.timestamp >= start && s.timestamp < end).collect()
+    }
+
+    /// Position of a capture-clock `timestamp` in the exported video, `None`
+    /// when it falls in a part that was cut
+    pub fn video_time(&self, timestamp: Duration) -> Option<Duration> {
+        let mut position = Duration::ZERO;
+        for segment in &self.segments {
+            if timestamp >= segment.start_time && timestamp < segment.end_time {
+                return Some(position + (timestamp - segment.start_time));
+            }
+            position += segment.end_time.saturating_sub(segment.start_time);
+        }
+        None
+    }
+
+    /// Transcript text overlapping `[start, end)`, joined into one paragraph
+    pub fn transcript_between(&self, start: Duration, end: Duration) -> String {
+        self.transcript
+            .iter()
+            .filter(|t| t.start < end && t.end > start)
+            .map(|t| t.text.trim())
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// A project directory: `project.json`, plus one `sessions/<name>/session.json`
+/// per recorded session
+#[derive(Debug, Clone)]
+pub struct ProjectSessions {
+    pub root: PathBuf,
+    pub project: Project,
+    /// Sessions sorted by start time
+    pub sessions: Vec<SessionRecord>,
+}
+
+impl ProjectSessions {
+    /// Load a project directory. Relative video paths are resolved against
+    /// the directory of the session that references them.
+    pub fn load(root: &Path) -> Result<Self> {
+        let project_file = root.join("project.json");
+        let text = std::fs::read_to_string(&project_file)
+            .with_context(|| format!("Reading {}", project_file.display()))?;
+        let project: Project = serde_json::from_str(&text)
+            .with_context(|| format!("Parsing project {}", project_file.display()))?;
+
+        let mut sessions = Vec::new();
+        let sessions_dir = root.join("sessions");
+        if sessions_dir.is_dir() {
+            for entry in std::fs::read_dir(&sessions_dir)? {
+                let dir = entry?.path();
+                let session_file = dir.join("session.json");
+                if !session_file.is_file() {
+                    continue;
+                }
+                let mut record = SessionRecord::load(&session_file)?;
+                if record.video_path.is_relative() {
+                    record.video_path = dir.join(&record.video_path);
+                }
+                sessions.push(record);
+            }
+        }
+        sessions.sort_by_key(|record| record.session.started_at);
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            project,
+            sessions,
+        })
+    }
+}
+
+/// Anything that can produce a still frame of the recording
+pub trait FrameSource {
+    /// Frame at `timestamp` on the recording's capture clock
+    fn frame_at(&mut self, timestamp: Duration) -> Result<VideoFrame>;
+}
+
+impl FrameSource for TerminalRenderer {
+    fn frame_at(&mut self, timestamp: Duration) -> Result<VideoFrame> {
+        self.render_at(timestamp)
+    }
+}
+
+impl FrameSource for CodeAnimation {
+    fn frame_at(&mut self, timestamp: Duration) -> Result<VideoFrame> {
+        self.render_at(timestamp)
+    }
+}
+
+/// `m:ss`, or `h:mm:ss` past an hour, for display next to video links
+pub fn format_timestamp(timestamp: Duration) -> String {
+    let total = timestamp.as_secs();
+    let (hours, minutes, seconds) = (total / 3600, (total / 60) % 60, total % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+/// Escape text for use in HTML element content and attribute values
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// Percent-encode a URL or relative path for use as a link target. Path and
+/// query separators are kept, as are escapes already in it.
+pub fn encode_url(url: &str) -> String {
+    let bytes = url.as_bytes();
+    let mut encoded = String::with_capacity(url.len());
+    for (i, &byte) in bytes.iter().enumerate() {
+        let escape = byte == b'%' && bytes.len() > i + 2 && bytes[i + 1..i + 3].iter().all(u8::is_ascii_hexdigit);
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => encoded.push(byte as char),
+            b'/' | b':' | b'@' | b'!' | b'$' | b'&' | b'*' | b'+' | b',' | b';' | b'=' | b'?' => {
+                encoded.push(byte as char)
+            }
+            b'%' if escape => encoded.push('%'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
@@ -0,0 +1,563 @@
+//! Static documentation site export for DailyDoco Pro
+//!
+//! Renders every session of a project into a self-contained site that needs
+//! no backend: an index of sessions, a page per session with the video, its
+//! chapters and the how-to steps, and a prebuilt client-side search index.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use anyhow::{Context, Result};
+use uuid::Uuid;
+
+use crate::brand_kit::BrandKit;
+use crate::how_to_doc::{HowToConfig, HowToDocument, HowToGenerator};
+use crate::session::{encode_url, escape_html, format_timestamp, FrameSource, ProjectSessions, SessionRecord};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SiteExportConfig {
+    pub brand_kit: BrandKit,
+    /// Copy session videos into the site so it can be hosted as-is
+    pub copy_videos: bool,
+    pub how_to: HowToConfig,
+    /// Length of result excerpts in the search index, in characters
+    pub excerpt_length: usize,
+}
+
+impl Default for SiteExportConfig {
+    fn default() -> Self {
+        Self {
+            brand_kit: BrandKit::default(),
+            copy_videos: true,
+            how_to: HowToConfig::default(),
+            excerpt_length: 160,
+        }
+    }
+}
+
+/// One searchable page or step
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchDocument {
+    pub url: String,
+    pub title: String,
+    pub session: String,
+    pub excerpt: String,
+}
+
+/// Inverted index from lowercase term to the documents containing it
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchIndex {
+    pub docs: Vec<SearchDocument>,
+    pub terms: BTreeMap<String, Vec<usize>>,
+}
+
+impl SearchIndex {
+    pub fn add(&mut self, document: SearchDocument, text: &str) {
+        let id = self.docs.len();
+        let words: BTreeSet<String> = tokenize(&format!("{} {}", document.title, text)).collect();
+        for word in words {
+            self.terms.entry(word).or_default().push(id);
+        }
+        self.docs.push(document);
+    }
+
+    /// Documents containing a term starting with every query word, the
+    /// same matching the site's search box does
+    pub fn search(&self, query: &str) -> Vec<&SearchDocument> {
+        let mut result: Option<BTreeSet<usize>> = None;
+        for word in tokenize(query) {
+            let hits: BTreeSet<usize> = self
+                .terms
+                .range(word.clone()..)
+                .take_while(|(term, _)| term.starts_with(&word))
+                .flat_map(|(_, ids)| ids.iter().copied())
+                .collect();
+            result = Some(match result {
+                Some(previous) => previous.intersection(&hits).copied().collect(),
+                None => hits,
+            });
+        }
+        result.unwrap_or_default().into_iter().map(|id| &self.docs[id]).collect()
+    }
+}
+
+pub struct SiteExporter {
+    config: SiteExportConfig,
+}
+
+impl SiteExporter {
+    pub fn new(config: SiteExportConfig) -> Self {
+        Self { config }
+    }
+
+    /// Write the site into `out_dir`, returning the path of its index page.
+    /// Steps get screenshots from the frame source of their session, if any.
+    pub fn export(
+        &self,
+        project: &ProjectSessions,
+        sources: &mut HashMap<Uuid, Box<dyn FrameSource>>,
+        out_dir: &Path,
+    ) -> Result<PathBuf> {
+        std::fs::create_dir_all(out_dir).with_context(|| format!("Creating {}", out_dir.display()))?;
+
+        let logo = self.copy_logo(out_dir)?;
+        std::fs::write(out_dir.join("style.css"), self.stylesheet())?;
+        std::fs::write(out_dir.join("search.js"), SEARCH_JS)?;
+
+        let mut index = SearchIndex::default();
+        let mut listing = Vec::with_capacity(project.sessions.len());
+
+        for record in &project.sessions {
+            let slug = record.session.id.to_string();
+            let session_dir = out_dir.join("sessions").join(&slug);
+            std::fs::create_dir_all(&session_dir)?;
+
+            let video_url = self.copy_video(record, &session_dir)?;
+            let mut how_to = self.config.how_to.clone();
+            how_to.video_url = video_url.clone();
+            let generator = HowToGenerator::new(how_to);
+            let mut document = generator.build(record);
+            if let Some(frames) = sources.get_mut(&record.session.id) {
+                generator.capture_screenshots(&mut document, frames.as_mut(), &session_dir)?;
+            }
+
+            let page = self.session_page(record, &document, video_url.is_some(), logo.as_deref());
+            std::fs::write(session_dir.join("index.html"), page)?;
+
+            self.index_session(&mut index, record, &document, &format!("sessions/{}/index.html", slug));
+            listing.push((slug, record, document));
+        }
+
+        let index_json = serde_json::to_string(&index)?;
+        std::fs::write(out_dir.join("search-index.json"), &index_json)?;
+        // Script form so search also works when the site is opened from disk
+        std::fs::write(out_dir.join("search-index.js"), format!("window.DAILYDOCO_SEARCH={};\n", index_json))?;
+
+        let index_page = out_dir.join("index.html");
+        std::fs::write(&index_page, self.index_page(project, &listing, logo.as_deref()))?;
+        Ok(index_page)
+    }
+
+    fn copy_logo(&self, out_dir: &Path) -> Result<Option<String>> {
+        let Some(logo) = &self.config.brand_kit.logo else {
+            return Ok(None);
+        };
+        let extension = logo.extension().and_then(|ext| ext.to_str()).unwrap_or("png");
+        let name = format!("logo.{}", extension);
+        std::fs::copy(logo, out_dir.join(&name)).with_context(|| format!("Copying logo {}", logo.display()))?;
+        Ok(Some(name))
+    }
+
+    /// Copy the session video next to its page, `None` if there is none
+    fn copy_video(&self, record: &SessionRecord, session_dir: &Path) -> Result<Option<String>> {
+        let Some(name) = record.video_path.file_name() else {
+            return Ok(None);
+        };
+        if !record.video_path.is_file() {
+            log::warn!("Video {} not found, exporting session without it", record.video_path.display());
+            return Ok(None);
+        }
+        if self.config.copy_videos {
+            std::fs::copy(&record.video_path, session_dir.join(name))
+                .with_context(|| format!("Copying video {}", record.video_path.display()))?;
+            Ok(Some(name.to_string_lossy().into_owned()))
+        } else {
+            Ok(Some(record.video_path.to_string_lossy().into_owned()))
+        }
+    }
+
+    fn index_session(&self, index: &mut SearchIndex, record: &SessionRecord, document: &HowToDocument, url: &str) {
+        let transcript: Vec<&str> = record.transcript.iter().map(|t| t.text.as_str()).collect();
+        let transcript = transcript.join(" ");
+        index.add(
+            SearchDocument {
+                url: url.to_string(),
+                title: record.title.clone(),
+                session: record.title.clone(),
+                excerpt: excerpt(&transcript, self.config.excerpt_length),
+            },
+            &transcript,
+        );
+
+        for step in &document.steps {
+            let mut text = step.narration.clone();
+            for command in &step.commands {
+                text.push(' ');
+                text.push_str(&command.command);
+            }
+            for snippet in &step.snippets {
+                text.push(' ');
+                text.push_str(snippet.path.as_deref().unwrap_or_default());
+                text.push(' ');
+                text.push_str(&snippet.code);
+            }
+            for commit in &step.commits {
+                text.push(' ');
+                text.push_str(&commit.summary);
+            }
+
+            let summary = if step.narration.is_empty() { &text } else { &step.narration };
+            index.add(
+                SearchDocument {
+                    url: format!("{}#step-{}", url, step.number),
+                    title: step.heading.clone(),
+                    session: record.title.clone(),
+                    excerpt: excerpt(summary, self.config.excerpt_length),
+                },
+                &text,
+            );
+        }
+    }
+
+    fn stylesheet(&self) -> String {
+        format!("{}\n{}", self.config.brand_kit.css_variables(), SITE_CSS)
+    }
+
+    fn header(&self, root: &str, logo: Option<&str>) -> String {
+        let brand = escape_html(&self.config.brand_kit.name);
+        let logo = logo
+            .map(|logo| format!("<img class=\"logo\" src=\"{}{}\" alt=\"\">", root, escape_html(logo)))
+            .unwrap_or_default();
+        format!("<header><a class=\"brand\" href=\"{}index.html\">{}{}</a></header>\n", root, logo, brand)
+    }
+
+    fn page(&self, title: &str, root: &str, logo: Option<&str>, body: &str) -> String {
+        format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+             <title>{}</title>\n<link rel=\"stylesheet\" href=\"{}style.css\">\n</head>\n<body>\n{}<main>\n{}</main>\n\
+             </body>\n</html>\n",
+            escape_html(title),
+            root,
+            self.header(root, logo),
+            body
+        )
+    }
+
+    fn index_page(
+        &self,
+        project: &ProjectSessions,
+        sessions: &[(String, &SessionRecord, HowToDocument)],
+        logo: Option<&str>,
+    ) -> String {
+        let mut body = String::new();
+        let _ = writeln!(body, "<h1>{}</h1>", escape_html(&project.project.name));
+        body.push_str(
+            "<input id=\"search\" type=\"search\" placeholder=\"Search sessions, commands and code\" autocomplete=\"off\">\n\
+             <ul id=\"results\"></ul>\n<ul class=\"sessions\">\n",
+        );
+
+        for (slug, record, document) in sessions {
+            let length = document.steps.last().map(|step| step.video_end).unwrap_or_default();
+            let _ = writeln!(
+                body,
+                "<li><a href=\"sessions/{}/index.html\">{}</a><span class=\"meta\">{} · {} · {} chapters</span></li>",
+                slug,
+                escape_html(&record.title),
+                record.session.started_at.format("%Y-%m-%d %H:%M"),
+                format_timestamp(length),
+                document.steps.len()
+            );
+        }
+        body.push_str("</ul>\n<script src=\"search-index.js\"></script>\n<script src=\"search.js\"></script>\n");
+
+        self.page(&project.project.name, "", logo, &body)
+    }
+
+    fn session_page(
+        &self,
+        record: &SessionRecord,
+        document: &HowToDocument,
+        has_video: bool,
+        logo: Option<&str>,
+    ) -> String {
+        let mut body = String::new();
+        let _ = writeln!(body, "<h1>{}</h1>", escape_html(&record.title));
+        let _ = writeln!(
+            body,
+            "<p class=\"meta\">{}</p>",
+            record.session.started_at.format("%Y-%m-%d %H:%M UTC")
+        );
+
+        if has_video {
+            let _ = writeln!(
+                body,
+                "<video id=\"video\" controls preload=\"metadata\" src=\"{}\"></video>",
+                escape_html(&encode_url(&document.video_url))
+            );
+        }
+
+        if !document.steps.is_empty() {
+            body.push_str("<nav class=\"chapters\"><h2>Chapters</h2><ol>\n");
+            for step in &document.steps {
+                let _ = writeln!(
+                    body,
+                    "<li><a href=\"#step-{}\" data-time=\"{:.1}\"><span class=\"time\">{}</span> {}</a></li>",
+                    step.number,
+                    step.video_start.as_secs_f64(),
+                    format_timestamp(step.video_start),
+                    escape_html(&step.heading)
+                );
+            }
+            body.push_str("</ol></nav>\n");
+        }
+
+        body.push_str(&document.steps_html());
+        if has_video {
+            let _ = writeln!(body, "<script>{}</script>", SEEK_JS);
+        }
+
+        self.page(&record.title, "../../", logo, &body)
+    }
+}
+
+impl Default for SiteExporter {
+    fn default() -> Self {
+        Self::new(SiteExportConfig::default())
+    }
+}
+
+/// Lowercase words of two or more letters, digits or underscores
+fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| word.chars().count() > 1)
+        .map(|word| word.to_lowercase())
+}
+
+fn excerpt(text: &str, length: usize) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= length {
+        return text;
+    }
+    let mut cut: String = text.chars().take(length).collect();
+    if let Some(space) = cut.rfind(' ') {
+        cut.truncate(space);
+    }
+    cut.push('…');
+    cut
+}
+
+const SITE_CSS: &str = "*{box-sizing:border-box}\
+body{margin:0;background:var(--brand-bg);color:var(--brand-text);font-family:var(--brand-font);line-height:1.6}\
+a{color:var(--brand-accent)}\
+header{padding:12px 24px;border-bottom:1px solid var(--brand-border);background:var(--brand-surface)}\
+.brand{display:flex;align-items:center;gap:10px;color:var(--brand-text);font-weight:600;text-decoration:none}\
+.logo{height:28px}\
+main{max-width:960px;margin:0 auto;padding:24px}\
+h1,h2{line-height:1.25}\
+.meta,.time{color:var(--brand-muted);font-size:.9em}\
+.sessions,#results{list-style:none;padding:0}\
+.sessions li,#results li{padding:12px 0;border-bottom:1px solid var(--brand-border)}\
+.sessions .meta{display:block}\
+#search{width:100%;padding:10px 14px;border-radius:8px;border:1px solid var(--brand-border);\
+background:var(--brand-surface);color:var(--brand-text);font-size:1em}\
+#results p{margin:4px 0 0;color:var(--brand-muted)}\
+video{width:100%;border-radius:8px;background:#000}\
+.chapters{background:var(--brand-surface);border:1px solid var(--brand-border);border-radius:8px;padding:8px 20px;margin:16px 0}\
+.chapters a{text-decoration:none}\
+.step{border-top:1px solid var(--brand-border);margin-top:24px}\
+.step img{max-width:100%;border-radius:6px;border:1px solid var(--brand-border)}\
+pre{background:var(--brand-surface);border:1px solid var(--brand-border);border-radius:6px;padding:12px;overflow-x:auto}\
+code,.path{font-family:var(--brand-code-font)}\
+.path{margin-bottom:0;color:var(--brand-muted)}\
+.commits code{color:var(--brand-primary)}";
+
+const SEEK_JS: &str = "document.addEventListener('click',function(e){\
+var link=e.target.closest('[data-time]');var video=document.getElementById('video');\
+if(!link||!video)return;e.preventDefault();video.currentTime=parseFloat(link.dataset.time);video.play();\
+video.scrollIntoView({behavior:'smooth'});});";
+
+const SEARCH_JS: &str = r#"(function () {
+  var index = window.DAILYDOCO_SEARCH;
+  var input = document.getElementById('search');
+  var results = document.getElementById('results');
+  if (!index || !input) return;
+  var terms = Object.keys(index.terms);
+
+  function lookup(word) {
+    var hits = {};
+    terms.forEach(function (term) {
+      if (term.indexOf(word) === 0) {
+        index.terms[term].forEach(function (id) { hits[id] = true; });
+      }
+    });
+    return hits;
+  }
+
+  input.addEventListener('input', function () {
+    var words = input.value.toLowerCase().split(/[^\p{L}\p{N}_]+/u).filter(function (w) { return w.length > 1; });
+    results.innerHTML = '';
+    if (!words.length) return;
+
+    var found = null;
+    words.forEach(function (word) {
+      var hits = lookup(word);
+      if (found === null) { found = hits; return; }
+      Object.keys(found).forEach(function (id) { if (!hits[id]) delete found[id]; });
+    });
+
+    Object.keys(found).slice(0, 30).forEach(function (id) {
+      var doc = index.docs[id];
+      var item = document.createElement('li');
+      var link = document.createElement('a');
+      link.href = doc.url;
+      link.textContent = doc.title;
+      var meta = document.createElement('span');
+      meta.className = 'meta';
+      meta.textContent = ' · ' + doc.session;
+      var text = document.createElement('p');
+      text.textContent = doc.excerpt;
+      item.appendChild(link);
+      item.appendChild(meta);
+      item.appendChild(text);
+      results.appendChild(item);
+    });
+  });
+})();
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use aegnt_27::visual::VideoFrame;
+    use chrono::{TimeZone, Utc};
+    use shared_types::{CaptureSession, Project};
+
+    use crate::canvas::{solid_frame, Rgba};
+    use crate::intelligent_clip_selector::{EventType, ImportanceScore, VideoSegment};
+    use crate::session::{SessionCommand, SessionMarker, TranscriptSegment};
+
+    struct Solid;
+
+    impl FrameSource for Solid {
+        fn frame_at(&mut self, timestamp: Duration) -> Result<VideoFrame> {
+            Ok(solid_frame(32, 18, Rgba::new(40, 80, 120, 255), timestamp))
+        }
+    }
+
+    fn segment(start: u64, end: u64) -> VideoSegment {
+        VideoSegment {
+            start_time: Duration::from_secs(start),
+            end_time: Duration::from_secs(end),
+            importance_score: 0.8,
+            events: vec![ImportanceScore {
+                timestamp: Duration::from_secs(start + 1),
+                score: 0.8,
+                confidence: 0.9,
+                event_type: EventType::Debugging,
+                context: HashMap::new(),
+            }],
+            narrative_weight: 0.5,
+            viewer_engagement_prediction: 0.5,
+        }
+    }
+
+    fn project(root: &Path) -> ProjectSessions {
+        let project = Project {
+            id: Uuid::new_v4(),
+            name: "Parser rewrite".to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        std::fs::create_dir_all(root).unwrap();
+        std::fs::write(root.join("project.json"), serde_json::to_string(&project).unwrap()).unwrap();
+
+        for (day, title) in [(2, "Fix the tokenizer"), (1, "Set up the grammar")] {
+            let session = CaptureSession {
+                id: Uuid::new_v4(),
+                project_id: project.id,
+                started_at: Utc.with_ymd_and_hms(2026, 3, day, 9, 0, 0).unwrap(),
+                duration: None,
+            };
+            let mut record = SessionRecord::new(session, title, "video.mp4");
+            record.segments = vec![segment(0, 20), segment(60, 90)];
+            record.markers.push(SessionMarker {
+                timestamp: Duration::from_secs(5),
+                label: format!("{} start", title),
+            });
+            record.commands.push(SessionCommand {
+                timestamp: Duration::from_secs(70),
+                command: format!("cargo test --test day{}", day),
+                output: None,
+            });
+            record.transcript.push(TranscriptSegment {
+                start: Duration::from_secs(61),
+                end: Duration::from_secs(65),
+                text: "The lexer drops unicode identifiers here".to_string(),
+            });
+
+            let dir = root.join("sessions").join(format!("day{}", day));
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("video.mp4"), b"not really a video").unwrap();
+            record.save(&dir.join("session.json")).unwrap();
+        }
+
+        ProjectSessions::load(root).unwrap()
+    }
+
+    #[test]
+    fn test_project_sessions_load_sorted() {
+        let root = std::env::temp_dir().join(format!("dailydoco-project-{}", Uuid::new_v4()));
+        let project = project(&root);
+
+        let titles: Vec<&str> = project.sessions.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(titles, ["Set up the grammar", "Fix the tokenizer"]);
+        assert!(project.sessions[0].video_path.is_file());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_export_writes_self_contained_site() {
+        let root = std::env::temp_dir().join(format!("dailydoco-project-{}", Uuid::new_v4()));
+        let project = project(&root);
+        let out = root.join("site");
+
+        let mut config = SiteExportConfig::default();
+        config.brand_kit.primary = "#ff5500".to_string();
+        let mut sources: HashMap<Uuid, Box<dyn FrameSource>> = HashMap::new();
+        sources.insert(project.sessions[1].session.id, Box::new(Solid));
+        let index_page = SiteExporter::new(config).export(&project, &mut sources, &out).unwrap();
+
+        let index = std::fs::read_to_string(&index_page).unwrap();
+        assert!(index.contains("<h1>Parser rewrite</h1>"));
+        assert!(index.find("Set up the grammar").unwrap() < index.find("Fix the tokenizer").unwrap());
+        assert!(std::fs::read_to_string(out.join("style.css")).unwrap().contains("--brand-primary:#ff5500"));
+
+        let slug = project.sessions[1].session.id.to_string();
+        let session_dir = out.join("sessions").join(&slug);
+        assert!(session_dir.join("video.mp4").is_file());
+        let page = std::fs::read_to_string(session_dir.join("index.html")).unwrap();
+        assert!(page.contains("<video id=\"video\""));
+        assert!(page.contains("data-time=\"20.0\""));
+        assert!(page.contains("href=\"../../style.css\""));
+        assert!(page.contains("<img src=\"images/step-01.png\""));
+        assert!(session_dir.join("images/step-01.png").is_file());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_search_index_prefix_and_intersection() {
+        let root = std::env::temp_dir().join(format!("dailydoco-project-{}", Uuid::new_v4()));
+        let project = project(&root);
+        let out = root.join("site");
+        SiteExporter::default().export(&project, &mut HashMap::new(), &out).unwrap();
+
+        let json = std::fs::read_to_string(out.join("search-index.json")).unwrap();
+        let index: SearchIndex = serde_json::from_str(&json).unwrap();
+
+        let hits = index.search("unicode lex");
+        assert_eq!(hits.len(), 4);
+        assert!(hits.iter().all(|doc| doc.url.ends_with("#step-2") || !doc.url.contains('#')));
+
+        let hits = index.search("day2");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].session, "Fix the tokenizer");
+        assert!(index.search("nonexistent").is_empty());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,695 @@
+//! Storyboards for DailyDoco Pro videos
+//!
+//! A storyboard is an ordered list of panels, each a keyframe with its
+//! timecode, title, caption and notes. It renders to a contact sheet: a grid
+//! of thumbnails with captions underneath, saved as one PNG, as printable
+//! pages in PNG or PDF, or as an HTML page for review meetings.
+
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use anyhow::{bail, Context, Result};
+
+use aegnt_27::visual::VideoFrame;
+
+use crate::canvas::{
+    line_metrics, monospace_font, save_png, scale_frame, solid_frame, text_width, wrap_text, Canvas, Rgba,
+};
+use crate::dynamic_pacing_engine::PacingDecision;
+use crate::how_to_doc::{HowToConfig, HowToGenerator};
+use crate::intelligent_clip_selector::EventType;
+use crate::session::{escape_html, format_timestamp, FrameSource, SessionRecord};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoryboardPanel {
+    /// Where the panel sits in the planned video
+    pub timecode: Duration,
+    pub title: String,
+    pub caption: String,
+    /// Shown in place of a missing keyframe, e.g. the change category
+    pub placeholder: String,
+    /// Short supporting lines, e.g. top events and pacing reasoning
+    #[serde(default)]
+    pub notes: Vec<String>,
+    #[serde(skip)]
+    pub keyframe: Option<VideoFrame>,
+}
+
+impl StoryboardPanel {
+    pub fn new(timecode: Duration, title: impl Into<String>, caption: impl Into<String>) -> Self {
+        Self {
+            timecode,
+            title: title.into(),
+            caption: caption.into(),
+            placeholder: String::new(),
+            notes: Vec::new(),
+            keyframe: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Storyboard {
+    pub title: String,
+    pub panels: Vec<StoryboardPanel>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContactSheetConfig {
+    pub columns: usize,
+    pub thumbnail_width: u32,
+    pub thumbnail_height: u32,
+    pub gutter: u32,
+    pub padding: u32,
+    pub font_size: f32,
+    /// Caption lines under each thumbnail, longer captions are cut short
+    pub caption_lines: usize,
+    /// Note lines under the caption, one note per line
+    pub note_lines: usize,
+    /// Rows of panels per page in `render_pages`, 0 keeps every panel on one page
+    pub rows_per_page: usize,
+    pub background: Rgba,
+    pub panel_background: Rgba,
+    pub text: Rgba,
+    pub muted_text: Rgba,
+    pub accent: Rgba,
+}
+
+impl Default for ContactSheetConfig {
+    fn default() -> Self {
+        Self {
+            columns: 4,
+            thumbnail_width: 480,
+            thumbnail_height: 270,
+            gutter: 24,
+            padding: 40,
+            font_size: 16.0,
+            caption_lines: 3,
+            note_lines: 0,
+            rows_per_page: 0,
+            background: Rgba::new(2, 6, 23, 255),
+            panel_background: Rgba::new(15, 23, 42, 255),
+            text: Rgba::new(241, 245, 249, 255),
+            muted_text: Rgba::new(148, 163, 184, 255),
+            accent: Rgba::new(12, 154, 229, 255),
+        }
+    }
+}
+
+/// What a review storyboard shows for each segment of a session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SegmentStoryboardConfig {
+    /// Highest-scoring events listed per panel
+    pub top_events: usize,
+    /// Pacing decisions listed per panel, most confident first
+    pub pacing_notes: usize,
+}
+
+impl Default for SegmentStoryboardConfig {
+    fn default() -> Self {
+        Self {
+            top_events: 3,
+            pacing_notes: 2,
+        }
+    }
+}
+
+impl Storyboard {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            panels: Vec::new(),
+        }
+    }
+
+    /// One panel per segment of the session's timeline. The timecode is the
+    /// segment's start in the exported video and the title is its chapter
+    /// heading; notes list the top events and the reasoning of the pacing
+    /// decisions that fall inside it. Pacing timestamps are on the capture
+    /// clock, like the events.
+    pub fn from_session(
+        record: &SessionRecord,
+        pacing: &[PacingDecision],
+        mut frames: Option<&mut dyn FrameSource>,
+        config: &SegmentStoryboardConfig,
+    ) -> Result<Self> {
+        let document = HowToGenerator::new(HowToConfig::default()).build(record);
+        let mut storyboard = Storyboard::new(record.title.clone());
+
+        for (step, segment) in document.steps.iter().zip(&record.segments) {
+            let to_video = |timestamp: Duration| step.video_start + timestamp.saturating_sub(step.source_start);
+            let mut panel = StoryboardPanel::new(step.video_start, step.heading.clone(), step.narration.clone());
+            panel.placeholder = format!("Segment {}", step.number);
+
+            let mut events: Vec<_> = segment
+                .events
+                .iter()
+                .filter(|event| event.timestamp >= step.source_start && event.timestamp < step.source_end)
+                .collect();
+            events.sort_by(|a, b| b.score.total_cmp(&a.score));
+            for event in events.into_iter().take(config.top_events) {
+                panel.notes.push(format!(
+                    "{} {:.2} at {}",
+                    event_label(&event.event_type),
+                    event.score,
+                    format_timestamp(to_video(event.timestamp))
+                ));
+            }
+
+            let mut decisions: Vec<_> = pacing
+                .iter()
+                .filter(|decision| decision.timestamp >= step.source_start && decision.timestamp < step.source_end)
+                .collect();
+            decisions.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+            for decision in decisions.into_iter().take(config.pacing_notes) {
+                let at = format_timestamp(to_video(decision.timestamp));
+                panel.notes.push(format!("Pacing at {}: {}", at, decision.reasoning));
+            }
+
+            if let Some(frames) = frames.as_deref_mut() {
+                panel.keyframe = Some(frames.frame_at(step.keyframe_time)?);
+            }
+            storyboard.panels.push(panel);
+        }
+
+        Ok(storyboard)
+    }
+
+    /// Render every panel into one grid image
+    pub fn render_contact_sheet(&self, config: &ContactSheetConfig) -> Result<VideoFrame> {
+        let columns = config.columns.min(self.panels.len().max(1));
+        let rows = self.panels.len().div_ceil(config.columns.max(1)).max(1);
+        render_sheet(&self.title, &self.panels, columns, rows, config)
+    }
+
+    /// Render the contact sheet as pages of `config.rows_per_page` rows.
+    /// Every page has the same size so the pages print evenly.
+    pub fn render_pages(&self, config: &ContactSheetConfig) -> Result<Vec<VideoFrame>> {
+        let per_page = config.columns * config.rows_per_page;
+        if per_page == 0 || self.panels.len() <= per_page {
+            let rows = config.rows_per_page.max(self.panels.len().div_ceil(config.columns.max(1))).max(1);
+            return Ok(vec![render_sheet(&self.title, &self.panels, config.columns, rows, config)?]);
+        }
+
+        let page_count = self.panels.len().div_ceil(per_page);
+        self.panels
+            .chunks(per_page)
+            .enumerate()
+            .map(|(index, panels)| {
+                let title = format!("{} — page {}/{}", self.title, index + 1, page_count);
+                render_sheet(&title, panels, config.columns, config.rows_per_page, config)
+            })
+            .collect()
+    }
+
+    pub fn save_contact_sheet(&self, config: &ContactSheetConfig, path: &Path) -> Result<()> {
+        save_png(&self.render_contact_sheet(config)?, path)
+    }
+
+    /// Save each page as `<stem>-NN.png` in `dir`, returning the paths
+    pub fn save_pages(&self, config: &ContactSheetConfig, dir: &Path, stem: &str) -> Result<Vec<PathBuf>> {
+        let mut paths = Vec::new();
+        for (index, page) in self.render_pages(config)?.iter().enumerate() {
+            let path = dir.join(format!("{}-{:02}.png", stem, index + 1));
+            save_png(page, &path)?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+
+    /// Save every page into one PDF, a page per contact sheet page
+    pub fn save_pdf(&self, config: &ContactSheetConfig, path: &Path) -> Result<()> {
+        let pages = self.render_pages(config)?;
+        std::fs::write(path, image_pdf(&pages)?).with_context(|| format!("Writing {}", path.display()))
+    }
+
+    /// Review page with one row per panel. Keyframes are expected at
+    /// `<image_dir>/panel-NN.png`, as written by `write_html`.
+    pub fn to_html(&self, image_dir: &str) -> String {
+        let mut html = String::new();
+        let title = escape_html(&self.title);
+        let _ = writeln!(html, "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">");
+        let _ = writeln!(html, "<title>{}</title>\n<style>{}</style>\n</head>\n<body>", title, HTML_STYLE);
+        let _ = writeln!(html, "<h1>{}</h1>", title);
+
+        for (index, panel) in self.panels.iter().enumerate() {
+            let heading = escape_html(&panel.title);
+            html.push_str("<section class=\"panel\">\n");
+            match &panel.keyframe {
+                Some(_) => {
+                    let _ = writeln!(
+                        html,
+                        "<img src=\"{}/panel-{:02}.png\" alt=\"{}\">",
+                        escape_html(image_dir),
+                        index + 1,
+                        heading
+                    );
+                }
+                None => {
+                    let label = if panel.placeholder.is_empty() { &panel.title } else { &panel.placeholder };
+                    let _ = writeln!(html, "<div class=\"placeholder\">{}</div>", escape_html(label));
+                }
+            }
+
+            html.push_str("<div>\n");
+            let _ = writeln!(
+                html,
+                "<h2><span class=\"timecode\">{}</span> {}</h2>",
+                format_timecode(panel.timecode),
+                heading
+            );
+            if !panel.caption.is_empty() {
+                let _ = writeln!(html, "<p>{}</p>", escape_html(&panel.caption));
+            }
+            if !panel.notes.is_empty() {
+                html.push_str("<ul>\n");
+                for note in &panel.notes {
+                    let _ = writeln!(html, "<li>{}</li>", escape_html(note));
+                }
+                html.push_str("</ul>\n");
+            }
+            html.push_str("</div>\n</section>\n");
+        }
+
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+
+    /// Write `index.html` with its keyframes under `keyframes/`
+    pub fn write_html(&self, out_dir: &Path) -> Result<PathBuf> {
+        let image_dir = out_dir.join("keyframes");
+        std::fs::create_dir_all(&image_dir).with_context(|| format!("Creating {}", image_dir.display()))?;
+        for (index, panel) in self.panels.iter().enumerate() {
+            if let Some(keyframe) = &panel.keyframe {
+                save_png(keyframe, &image_dir.join(format!("panel-{:02}.png", index + 1)))?;
+            }
+        }
+
+        let path = out_dir.join("index.html");
+        std::fs::write(&path, self.to_html("keyframes")).with_context(|| format!("Writing {}", path.display()))?;
+        Ok(path)
+    }
+}
+
+const HTML_STYLE: &str = "body{font-family:system-ui,sans-serif;max-width:1100px;margin:2rem auto;padding:0 1rem;\
+color:#1f2328}.panel{display:grid;grid-template-columns:360px 1fr;gap:1.5rem;padding:1rem 0;\
+border-top:1px solid #d0d7de;break-inside:avoid}.panel img,.placeholder{width:360px;aspect-ratio:16/9;\
+border-radius:6px;border:1px solid #d0d7de}.placeholder{display:flex;align-items:center;justify-content:center;\
+background:#0f172a;color:#0c9ae5;font-size:1.4rem}h2{margin:0 0 .5rem;font-size:1.2rem}\
+.timecode{font-family:monospace;color:#0c9ae5;margin-right:.5rem}ul{color:#57606a;padding-left:1.2rem}";
+
+/// Lay `panels` out in a `columns` x `rows` grid under `title`
+fn render_sheet(
+    title: &str,
+    panels: &[StoryboardPanel],
+    columns: usize,
+    rows: usize,
+    config: &ContactSheetConfig,
+) -> Result<VideoFrame> {
+    if columns == 0 || config.thumbnail_width == 0 || config.thumbnail_height == 0 {
+        bail!("Contact sheet needs at least one column and a thumbnail size");
+    }
+
+    let font = monospace_font();
+    let line_height = (config.font_size * 1.4).ceil();
+    let (ascent, _) = line_metrics(&font, config.font_size);
+    let title_size = config.font_size * 1.75;
+    let header_height = (title_size * 2.0).ceil() as u32;
+    // Timecode and title line, then the caption and notes
+    let text_lines = 1 + config.caption_lines + config.note_lines;
+    let caption_height = (line_height * text_lines as f32).ceil() as u32 + config.gutter / 2;
+    let cell_width = config.thumbnail_width;
+    let cell_height = config.thumbnail_height + caption_height;
+
+    let (columns, rows) = (columns as u32, rows as u32);
+    let width = config.padding * 2 + columns * cell_width + (columns - 1) * config.gutter;
+    let height = config.padding * 2 + header_height + rows * cell_height + (rows - 1) * config.gutter;
+
+    let mut sheet = solid_frame(width, height, config.background, Duration::ZERO);
+    let mut canvas = Canvas::new(&mut sheet)?;
+    let title_baseline = config.padding as f32 + title_size;
+    canvas.draw_text(&font, title, config.padding as f32, title_baseline, title_size, config.text);
+
+    for (index, panel) in panels.iter().enumerate() {
+        let column = index as u32 % columns;
+        let row = index as u32 / columns;
+        let x = config.padding + column * (cell_width + config.gutter);
+        let y = config.padding + header_height + row * (cell_height + config.gutter);
+
+        match &panel.keyframe {
+            Some(keyframe) => {
+                let thumbnail = scale_frame(keyframe, config.thumbnail_width, config.thumbnail_height)?;
+                canvas.blit(&thumbnail, x as i32, y as i32)?;
+            }
+            None => {
+                let (w, h) = (config.thumbnail_width as f32, config.thumbnail_height as f32);
+                canvas.fill_rect(x as f32, y as f32, w, h, config.panel_background);
+                let label = if panel.placeholder.is_empty() { &panel.title } else { &panel.placeholder };
+                let size = config.font_size * 1.5;
+                let label_width = text_width(&font, label, size).min(w);
+                let baseline = y as f32 + h / 2.0 + size / 3.0;
+                canvas.draw_text(&font, label, x as f32 + (w - label_width) / 2.0, baseline, size, config.accent);
+            }
+        }
+
+        let mut baseline = y as f32 + config.thumbnail_height as f32 + config.gutter as f32 / 2.0 + ascent;
+        let timecode = format_timecode(panel.timecode);
+        let timecode_width = canvas.draw_text(&font, &timecode, x as f32, baseline, config.font_size, config.accent);
+        let title_x = x as f32 + timecode_width + config.font_size * 0.75;
+        let title_width = cell_width as f32 - (title_x - x as f32);
+        let title = truncate_to_width(&font, &panel.title, config.font_size, title_width);
+        canvas.draw_text(&font, &title, title_x, baseline, config.font_size, config.text);
+
+        let mut lines = wrap_text(&font, &panel.caption, config.font_size, cell_width as f32);
+        if lines.len() > config.caption_lines {
+            lines.truncate(config.caption_lines);
+            if let Some(last) = lines.last_mut() {
+                *last = truncate_to_width(&font, &format!("{}…", last), config.font_size, cell_width as f32);
+            }
+        }
+        for line in lines {
+            baseline += line_height;
+            canvas.draw_text(&font, &line, x as f32, baseline, config.font_size, config.muted_text);
+        }
+        for note in panel.notes.iter().take(config.note_lines) {
+            baseline += line_height;
+            let note = truncate_to_width(&font, &format!("• {}", note), config.font_size, cell_width as f32);
+            canvas.draw_text(&font, &note, x as f32, baseline, config.font_size, config.text);
+        }
+    }
+
+    Ok(sheet)
+}
+
+/// A minimal PDF with one JPEG image filling each page, at 150 dpi
+fn image_pdf(pages: &[VideoFrame]) -> Result<Vec<u8>> {
+    const POINTS_PER_PIXEL: f32 = 72.0 / 150.0;
+
+    let mut pdf = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+    let mut offsets = Vec::new();
+    // Catalog and page tree are objects 1 and 2, then three objects per page
+    let page_ids: Vec<usize> = (0..pages.len()).map(|index| 3 + index * 3).collect();
+
+    offsets.push(pdf.len());
+    pdf.extend_from_slice(b"1 0 obj\n<< /Type /Catalog /Pages 2 0 R >>\nendobj\n");
+    offsets.push(pdf.len());
+    let kids: Vec<String> = page_ids.iter().map(|id| format!("{} 0 R", id)).collect();
+    write!(pdf, "2 0 obj\n<< /Type /Pages /Kids [{}] /Count {} >>\nendobj\n", kids.join(" "), pages.len())?;
+
+    for (page, id) in pages.iter().zip(page_ids) {
+        let rgb = page.to_rgb()?;
+        let mut jpeg = Vec::new();
+        image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, 90).encode(
+            &rgb.data,
+            rgb.width,
+            rgb.height,
+            image::ColorType::Rgb8,
+        )?;
+        let (width, height) = (rgb.width as f32 * POINTS_PER_PIXEL, rgb.height as f32 * POINTS_PER_PIXEL);
+        let content = format!("q {:.2} 0 0 {:.2} 0 0 cm /Im0 Do Q", width, height);
+
+        offsets.push(pdf.len());
+        write!(
+            pdf,
+            "{} 0 obj\n<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] \
+             /Resources << /XObject << /Im0 {} 0 R >> >> /Contents {} 0 R >>\nendobj\n",
+            id,
+            width,
+            height,
+            id + 2,
+            id + 1
+        )?;
+        offsets.push(pdf.len());
+        write!(pdf, "{} 0 obj\n<< /Length {} >>\nstream\n{}\nendstream\nendobj\n", id + 1, content.len(), content)?;
+        offsets.push(pdf.len());
+        write!(
+            pdf,
+            "{} 0 obj\n<< /Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceRGB \
+             /BitsPerComponent 8 /Filter /DCTDecode /Length {} >>\nstream\n",
+            id + 2,
+            rgb.width,
+            rgb.height,
+            jpeg.len()
+        )?;
+        pdf.extend_from_slice(&jpeg);
+        pdf.extend_from_slice(b"\nendstream\nendobj\n");
+    }
+
+    let xref = pdf.len();
+    write!(pdf, "xref\n0 {}\n0000000000 65535 f \n", offsets.len() + 1)?;
+    for offset in &offsets {
+        writeln!(pdf, "{:010} 00000 n ", offset)?;
+    }
+    write!(pdf, "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", offsets.len() + 1, xref)?;
+    Ok(pdf)
+}
+
+fn event_label(event_type: &EventType) -> &'static str {
+    match event_type {
+        EventType::CodeGeneration => "Code generation",
+        EventType::Debugging => "Debugging",
+        EventType::Testing => "Testing",
+        EventType::Refactoring => "Refactoring",
+        EventType::Documentation => "Documentation",
+        EventType::ErrorResolution => "Error resolution",
+        EventType::BreakthroughMoment => "Breakthrough",
+        EventType::LearningMoment => "Learning moment",
+        EventType::CollaborativeMoment => "Collaboration",
+        EventType::DeploymentMoment => "Deployment",
+    }
+}
+
+/// `mm:ss.f`, precise enough to cue a panel in an editor
+pub fn format_timecode(timestamp: Duration) -> String {
+    let tenths = timestamp.as_millis() / 100;
+    format!("{:02}:{:02}.{}", tenths / 600, (tenths / 10) % 60, tenths % 10)
+}
+
+/// Shorten `text` with an ellipsis until it fits `max_width`
+fn truncate_to_width(font: &impl ab_glyph::Font, text: &str, size: f32, max_width: f32) -> String {
+    if text_width(font, text, size) <= max_width {
+        return text.to_string();
+    }
+    let mut chars: Vec<char> = text.trim_end_matches('…').chars().collect();
+    while !chars.is_empty() {
+        chars.pop();
+        let candidate: String = chars.iter().collect::<String>().trim_end().to_string() + "…";
+        if text_width(font, &candidate, size) <= max_width {
+            return candidate;
+        }
+    }
+    String::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use chrono::Utc;
+    use shared_types::CaptureSession;
+    use uuid::Uuid;
+
+    use crate::dynamic_pacing_engine::PacingAction;
+    use crate::intelligent_clip_selector::{ImportanceScore, VideoSegment};
+    use crate::session::SessionMarker;
+
+    fn storyboard(panels: usize) -> Storyboard {
+        let mut storyboard = Storyboard::new("Release v1.2.0");
+        for index in 0..panels {
+            let mut panel = StoryboardPanel::new(
+                Duration::from_secs(index as u64 * 6),
+                format!("Panel {}", index + 1),
+                "A caption long enough that it has to wrap across several lines in a small thumbnail cell",
+            );
+            if index % 2 == 0 {
+                panel.keyframe = Some(solid_frame(64, 36, Rgba::new(200, 30, 30, 255), Duration::ZERO));
+            } else {
+                panel.placeholder = "Fixes".to_string();
+            }
+            storyboard.panels.push(panel);
+        }
+        storyboard
+    }
+
+    fn small_config() -> ContactSheetConfig {
+        ContactSheetConfig {
+            columns: 3,
+            thumbnail_width: 160,
+            thumbnail_height: 90,
+            gutter: 10,
+            padding: 12,
+            font_size: 11.0,
+            caption_lines: 2,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_contact_sheet_grid_size() {
+        let config = small_config();
+        let sheet = storyboard(5).render_contact_sheet(&config).unwrap();
+        let single = storyboard(1).render_contact_sheet(&config).unwrap();
+
+        assert_eq!(sheet.width, 12 * 2 + 3 * 160 + 2 * 10);
+        assert_eq!(single.width, 12 * 2 + 160);
+        // Two rows of cells, one row more than the single-panel sheet
+        let cell_with_gutter = sheet.height - single.height;
+        assert!(cell_with_gutter > 90 + 10);
+    }
+
+    #[test]
+    fn test_keyframes_are_scaled_into_cells() {
+        let config = small_config();
+        let mut sheet = storyboard(2).render_contact_sheet(&config).unwrap();
+        let canvas = Canvas::new(&mut sheet).unwrap();
+
+        let header = (config.font_size * 1.75 * 2.0).ceil() as u32;
+        let top = config.padding + header;
+        // First panel has a red keyframe filling the thumbnail
+        assert_eq!(canvas.pixel(config.padding + 80, top + 45), [200, 30, 30]);
+        assert_eq!(canvas.pixel(config.padding + 159, top + 89), [200, 30, 30]);
+        // Second panel falls back to the placeholder background
+        let second = config.padding + 160 + config.gutter;
+        let background = config.panel_background;
+        assert_eq!(canvas.pixel(second + 4, top + 4), [background.r, background.g, background.b]);
+    }
+
+    #[test]
+    fn test_timecode_and_truncation() {
+        assert_eq!(format_timecode(Duration::from_millis(83_450)), "01:23.4");
+
+        let font = monospace_font();
+        let short = truncate_to_width(&font, "A very long panel title", 12.0, 80.0);
+        assert!(short.ends_with('…'));
+        assert!(text_width(&font, &short, 12.0) <= 80.0);
+        assert_eq!(truncate_to_width(&font, "Short", 12.0, 80.0), "Short");
+    }
+
+    fn event(seconds: u64, score: f64, event_type: EventType) -> ImportanceScore {
+        ImportanceScore {
+            timestamp: Duration::from_secs(seconds),
+            score,
+            confidence: 0.9,
+            event_type,
+            context: HashMap::new(),
+        }
+    }
+
+    fn session_record() -> SessionRecord {
+        let session = CaptureSession {
+            id: Uuid::new_v4(),
+            project_id: Uuid::new_v4(),
+            started_at: Utc::now(),
+            duration: None,
+        };
+        let mut record = SessionRecord::new(session, "Fix the flaky upload test", "session.mp4");
+        record.segments = vec![
+            VideoSegment {
+                start_time: Duration::from_secs(10),
+                end_time: Duration::from_secs(40),
+                importance_score: 0.8,
+                events: vec![
+                    event(12, 0.3, EventType::Testing),
+                    event(20, 0.9, EventType::Debugging),
+                    event(30, 0.6, EventType::ErrorResolution),
+                ],
+                narrative_weight: 0.5,
+                viewer_engagement_prediction: 0.5,
+            },
+            VideoSegment {
+                start_time: Duration::from_secs(100),
+                end_time: Duration::from_secs(120),
+                importance_score: 0.6,
+                events: vec![event(110, 0.7, EventType::Testing)],
+                narrative_weight: 0.5,
+                viewer_engagement_prediction: 0.5,
+            },
+        ];
+        record.markers.push(SessionMarker {
+            timestamp: Duration::from_secs(11),
+            label: "Reproduce the failure".to_string(),
+        });
+        record
+    }
+
+    struct Solid;
+
+    impl FrameSource for Solid {
+        fn frame_at(&mut self, timestamp: Duration) -> Result<VideoFrame> {
+            Ok(solid_frame(32, 18, Rgba::new(10, 200, 10, 255), timestamp))
+        }
+    }
+
+    #[test]
+    fn test_panels_from_session_segments() {
+        let pacing = vec![
+            PacingDecision {
+                timestamp: Duration::from_secs(104),
+                action: PacingAction::SpeedUp { factor: 1.5 },
+                intensity: 0.5,
+                reasoning: "Speeding to 1.5x due to simple content".to_string(),
+                confidence: 0.8,
+            },
+            PacingDecision {
+                timestamp: Duration::from_secs(300),
+                action: PacingAction::SlowDown { factor: 0.8 },
+                intensity: 0.5,
+                reasoning: "Outside every segment".to_string(),
+                confidence: 0.9,
+            },
+        ];
+        let config = SegmentStoryboardConfig {
+            top_events: 2,
+            ..Default::default()
+        };
+        let storyboard = Storyboard::from_session(&session_record(), &pacing, Some(&mut Solid), &config).unwrap();
+
+        assert_eq!(storyboard.panels.len(), 2);
+        let first = &storyboard.panels[0];
+        assert_eq!(first.title, "Reproduce the failure");
+        assert_eq!(first.timecode, Duration::ZERO);
+        assert_eq!(first.notes, ["Debugging 0.90 at 0:10", "Error resolution 0.60 at 0:20"]);
+        assert_eq!(first.keyframe.as_ref().unwrap().timestamp, Duration::from_secs(20));
+
+        // The second segment starts 30s into the exported video
+        let second = &storyboard.panels[1];
+        assert_eq!(second.timecode, Duration::from_secs(30));
+        assert_eq!(second.notes[1], "Pacing at 0:34: Speeding to 1.5x due to simple content");
+    }
+
+    #[test]
+    fn test_pages_and_pdf() {
+        let config = ContactSheetConfig {
+            rows_per_page: 1,
+            note_lines: 2,
+            ..small_config()
+        };
+        let pages = storyboard(7).render_pages(&config).unwrap();
+        assert_eq!(pages.len(), 3);
+        assert!(pages.iter().all(|page| page.width == pages[0].width && page.height == pages[0].height));
+
+        let pdf = image_pdf(&pages).unwrap();
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.starts_with("%PDF-1.4"));
+        assert!(text.contains("/Count 3"));
+        assert_eq!(text.matches("/Subtype /Image").count(), 3);
+        // The xref offsets point at the objects they name
+        let xref = text.rfind("startxref\n").unwrap();
+        let start: usize = text[xref + 10..].lines().next().unwrap().parse().unwrap();
+        assert!(pdf[start..].starts_with(b"xref"));
+    }
+
+    #[test]
+    fn test_html_review_page() {
+        let mut board = storyboard(2);
+        board.panels[0].notes.push("Pacing at 0:04: slow <down>".to_string());
+        let html = board.to_html("keyframes");
+
+        assert!(html.contains("<img src=\"keyframes/panel-01.png\""));
+        assert!(html.contains("<div class=\"placeholder\">Fixes</div>"));
+        assert!(html.contains("<span class=\"timecode\">00:06.0</span> Panel 2"));
+        assert!(html.contains("<li>Pacing at 0:04: slow &lt;down&gt;</li>"));
+    }
+}
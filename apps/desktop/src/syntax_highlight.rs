@@ -0,0 +1,386 @@
+//! Lightweight syntax highlighting for rendered code in DailyDoco Pro
+//!
+//! A small per-language lexer rather than a full grammar engine: rendered
+//! code only needs keywords, strings, comments, numbers, types and calls to
+//! read well on screen, and the lexer must never fail on partial input.
+
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+
+use crate::canvas::Rgba;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Language {
+    Rust,
+    TypeScript,
+    JavaScript,
+    Python,
+    Go,
+    Shell,
+    Json,
+    Plain,
+}
+
+impl Language {
+    pub fn from_extension(extension: &str) -> Self {
+        match extension.to_lowercase().as_str() {
+            "rs" => Language::Rust,
+            "ts" | "tsx" => Language::TypeScript,
+            "js" | "jsx" | "mjs" | "cjs" => Language::JavaScript,
+            "py" => Language::Python,
+            "go" => Language::Go,
+            "sh" | "bash" | "zsh" => Language::Shell,
+            "json" => Language::Json,
+            _ => Language::Plain,
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Self {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .map(Self::from_extension)
+            .unwrap_or(Language::Plain)
+    }
+
+    /// Lowercase name, as used for Markdown code fences
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::Rust => "rust",
+            Language::TypeScript => "typescript",
+            Language::JavaScript => "javascript",
+            Language::Python => "python",
+            Language::Go => "go",
+            Language::Shell => "shell",
+            Language::Json => "json",
+            Language::Plain => "text",
+        }
+    }
+
+    fn line_comment(&self) -> Option<&'static str> {
+        match self {
+            Language::Rust | Language::TypeScript | Language::JavaScript | Language::Go => Some("//"),
+            Language::Python | Language::Shell => Some("#"),
+            Language::Json | Language::Plain => None,
+        }
+    }
+
+    fn has_block_comments(&self) -> bool {
+        matches!(self, Language::Rust | Language::TypeScript | Language::JavaScript | Language::Go)
+    }
+
+    fn keywords(&self) -> &'static [&'static str] {
+        match self {
+            Language::Rust => &[
+                "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+                "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+                "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type", "unsafe",
+                "use", "where", "while",
+            ],
+            Language::TypeScript | Language::JavaScript => &[
+                "async", "await", "break", "case", "catch", "class", "const", "continue", "default", "delete",
+                "do", "else", "enum", "export", "extends", "false", "finally", "for", "from", "function", "if",
+                "implements", "import", "in", "instanceof", "interface", "let", "new", "null", "of", "private",
+                "protected", "public", "readonly", "return", "static", "super", "switch", "this", "throw",
+                "true", "try", "type", "typeof", "undefined", "var", "void", "while", "yield",
+            ],
+            Language::Python => &[
+                "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del", "elif",
+                "else", "except", "False", "finally", "for", "from", "global", "if", "import", "in", "is",
+                "lambda", "None", "nonlocal", "not", "or", "pass", "raise", "return", "self", "True", "try",
+                "while", "with", "yield",
+            ],
+            Language::Go => &[
+                "break", "case", "chan", "const", "continue", "default", "defer", "else", "fallthrough",
+                "false", "for", "func", "go", "goto", "if", "import", "interface", "map", "nil", "package",
+                "range", "return", "select", "struct", "switch", "true", "type", "var",
+            ],
+            Language::Shell => &[
+                "case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function", "if", "in",
+                "local", "return", "then", "while",
+            ],
+            Language::Json => &["false", "null", "true"],
+            Language::Plain => &[],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TokenKind {
+    Plain,
+    Keyword,
+    Type,
+    Function,
+    String,
+    Number,
+    Comment,
+    Punctuation,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub text: String,
+}
+
+/// Colors for rendered code
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeTheme {
+    pub background: Rgba,
+    pub foreground: Rgba,
+    pub gutter: Rgba,
+    pub line_number: Rgba,
+    pub caret: Rgba,
+    pub keyword: Rgba,
+    pub type_name: Rgba,
+    pub function: Rgba,
+    pub string: Rgba,
+    pub number: Rgba,
+    pub comment: Rgba,
+    pub punctuation: Rgba,
+}
+
+impl CodeTheme {
+    pub fn dark() -> Self {
+        Self {
+            background: Rgba::new(40, 44, 52, 255),
+            foreground: Rgba::new(171, 178, 191, 255),
+            gutter: Rgba::new(33, 37, 43, 255),
+            line_number: Rgba::new(92, 99, 112, 255),
+            caret: Rgba::new(82, 139, 255, 255),
+            keyword: Rgba::new(198, 120, 221, 255),
+            type_name: Rgba::new(229, 192, 123, 255),
+            function: Rgba::new(97, 175, 239, 255),
+            string: Rgba::new(152, 195, 121, 255),
+            number: Rgba::new(209, 154, 102, 255),
+            comment: Rgba::new(127, 132, 142, 255),
+            punctuation: Rgba::new(171, 178, 191, 255),
+        }
+    }
+
+    pub fn light() -> Self {
+        Self {
+            background: Rgba::new(250, 250, 250, 255),
+            foreground: Rgba::new(56, 58, 66, 255),
+            gutter: Rgba::new(240, 240, 241, 255),
+            line_number: Rgba::new(157, 157, 159, 255),
+            caret: Rgba::new(82, 111, 255, 255),
+            keyword: Rgba::new(166, 38, 164, 255),
+            type_name: Rgba::new(193, 132, 1, 255),
+            function: Rgba::new(64, 120, 242, 255),
+            string: Rgba::new(80, 161, 79, 255),
+            number: Rgba::new(152, 104, 1, 255),
+            comment: Rgba::new(160, 161, 167, 255),
+            punctuation: Rgba::new(56, 58, 66, 255),
+        }
+    }
+
+    pub fn color(&self, kind: TokenKind) -> Rgba {
+        match kind {
+            TokenKind::Plain => self.foreground,
+            TokenKind::Keyword => self.keyword,
+            TokenKind::Type => self.type_name,
+            TokenKind::Function => self.function,
+            TokenKind::String => self.string,
+            TokenKind::Number => self.number,
+            TokenKind::Comment => self.comment,
+            TokenKind::Punctuation => self.punctuation,
+        }
+    }
+}
+
+impl Default for CodeTheme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+pub struct SyntaxHighlighter {
+    language: Language,
+}
+
+impl SyntaxHighlighter {
+    pub fn new(language: Language) -> Self {
+        Self { language }
+    }
+
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
+    /// Tokens for each line of `source`; block comments may span lines
+    pub fn highlight(&self, source: &str) -> Vec<Vec<Token>> {
+        let mut in_block_comment = false;
+        source
+            .split('\n')
+            .map(|line| self.highlight_line(line.trim_end_matches('\r'), &mut in_block_comment))
+            .collect()
+    }
+
+    /// Token kind of every character, line by line
+    pub fn char_kinds(&self, source: &str) -> Vec<Vec<TokenKind>> {
+        self.highlight(source)
+            .into_iter()
+            .map(|tokens| {
+                tokens
+                    .iter()
+                    .flat_map(|token| std::iter::repeat_n(token.kind, token.text.chars().count()))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn highlight_line(&self, line: &str, in_block_comment: &mut bool) -> Vec<Token> {
+        let chars: Vec<char> = line.chars().collect();
+        let mut tokens: Vec<Token> = Vec::new();
+        let mut i = 0;
+
+        let starts_with = |at: usize, pattern: &str| {
+            pattern.chars().enumerate().all(|(k, p)| chars.get(at + k) == Some(&p))
+        };
+
+        while i < chars.len() {
+            let start = i;
+
+            let kind = if *in_block_comment {
+                continue_block_comment(&chars, &mut i, in_block_comment);
+                TokenKind::Comment
+            } else if self.language.has_block_comments() && starts_with(i, "/*") {
+                *in_block_comment = true;
+                i += 2;
+                continue_block_comment(&chars, &mut i, in_block_comment);
+                TokenKind::Comment
+            } else if self.language.line_comment().is_some_and(|prefix| starts_with(i, prefix)) {
+                i = chars.len();
+                TokenKind::Comment
+            } else if let Some(end) = self.string_end(&chars, i) {
+                i = end;
+                TokenKind::String
+            } else if chars[i].is_ascii_digit() {
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_' || chars[i] == '.') {
+                    i += 1;
+                }
+                TokenKind::Number
+            } else if chars[i].is_alphabetic() || chars[i] == '_' || (chars[i] == '$' && self.language == Language::Shell) {
+                i += 1;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                self.classify_word(&word, chars.get(i).copied())
+            } else if chars[i].is_whitespace() {
+                while i < chars.len() && chars[i].is_whitespace() {
+                    i += 1;
+                }
+                TokenKind::Plain
+            } else {
+                i += 1;
+                TokenKind::Punctuation
+            };
+
+            let text: String = chars[start..i].iter().collect();
+            match tokens.last_mut() {
+                Some(last) if last.kind == kind => last.text.push_str(&text),
+                _ => tokens.push(Token { kind, text }),
+            }
+        }
+
+        tokens
+    }
+
+    /// End index of a string literal starting at `start`, if one starts there
+    fn string_end(&self, chars: &[char], start: usize) -> Option<usize> {
+        let quote = chars[start];
+        let is_quote = match quote {
+            '"' => true,
+            '`' => matches!(self.language, Language::TypeScript | Language::JavaScript | Language::Go | Language::Shell),
+            // Rust uses ' for lifetimes, so only treat short char literals as strings
+            '\'' if self.language == Language::Rust => {
+                chars.get(start + 2) == Some(&'\'') || (chars.get(start + 1) == Some(&'\\') && chars.get(start + 3) == Some(&'\''))
+            }
+            '\'' => self.language != Language::Json,
+            _ => false,
+        };
+        if !is_quote {
+            return None;
+        }
+
+        let mut i = start + 1;
+        while i < chars.len() {
+            match chars[i] {
+                '\\' => i += 2,
+                c if c == quote => return Some(i + 1),
+                _ => i += 1,
+            }
+        }
+
+        // Unterminated strings run to the end of the line
+        Some(chars.len())
+    }
+
+    fn classify_word(&self, word: &str, next: Option<char>) -> TokenKind {
+        if self.language.keywords().contains(&word) {
+            TokenKind::Keyword
+        } else if self.language == Language::Shell && word.starts_with('$') {
+            TokenKind::Type
+        } else if next == Some('(') || (self.language == Language::Rust && next == Some('!')) {
+            TokenKind::Function
+        } else if word.chars().next().is_some_and(|c| c.is_uppercase()) && self.language != Language::Json {
+            TokenKind::Type
+        } else {
+            TokenKind::Plain
+        }
+    }
+}
+
+fn continue_block_comment(chars: &[char], i: &mut usize, in_block_comment: &mut bool) {
+    while *i < chars.len() {
+        if chars[*i] == '*' && chars.get(*i + 1) == Some(&'/') {
+            *i += 2;
+            *in_block_comment = false;
+            return;
+        }
+        *i += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(tokens: &[Token]) -> Vec<(TokenKind, &str)> {
+        tokens.iter().map(|t| (t.kind, t.text.as_str())).collect()
+    }
+
+    #[test]
+    fn test_rust_line() {
+        let highlighter = SyntaxHighlighter::new(Language::Rust);
+        let lines = highlighter.highlight("let name: String = format!(\"hi\"); // greet");
+
+        let tokens = kinds(&lines[0]);
+        assert_eq!(tokens[0], (TokenKind::Keyword, "let"));
+        assert!(tokens.contains(&(TokenKind::Type, "String")));
+        assert!(tokens.contains(&(TokenKind::Function, "format")));
+        assert!(tokens.contains(&(TokenKind::String, "\"hi\"")));
+        assert_eq!(*tokens.last().unwrap(), (TokenKind::Comment, "// greet"));
+    }
+
+    #[test]
+    fn test_block_comment_spans_lines() {
+        let highlighter = SyntaxHighlighter::new(Language::TypeScript);
+        let lines = highlighter.highlight("/* start\nstill comment */ const x = 1;");
+
+        assert_eq!(kinds(&lines[0]), vec![(TokenKind::Comment, "/* start")]);
+        assert_eq!(lines[1][0], Token { kind: TokenKind::Comment, text: "still comment */".to_string() });
+        assert!(kinds(&lines[1]).contains(&(TokenKind::Number, "1")));
+    }
+
+    #[test]
+    fn test_char_kinds_cover_every_character() {
+        let source = "fn main() {\n    println!(\"{}\", 42);\n}";
+        let kinds = SyntaxHighlighter::new(Language::Rust).char_kinds(source);
+
+        for (line, line_kinds) in source.split('\n').zip(&kinds) {
+            assert_eq!(line.chars().count(), line_kinds.len());
+        }
+    }
+}
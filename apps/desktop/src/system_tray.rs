@@ -6,7 +6,6 @@
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio::sync::mpsc;
-use serde::{Deserialize, Serialize};
 
 #[cfg(target_os = "windows")]
 use windows::Win32::UI::Shell::Shell_NotifyIconW;
@@ -17,8 +16,8 @@ use cocoa::appkit::{NSStatusBar, NSStatusItem};
 #[cfg(target_os = "linux")]
 use x11::xlib;
 
-use crate::capture::CaptureStatus;
-use crate::config::Config;
+use crate::audio_meter::{AudioMeter, TrackLevel};
+use crate::config::DailyDocoConfig;
 
 /// System tray application state
 #[derive(Debug, Clone)]
@@ -29,6 +28,18 @@ pub struct SystemTrayState {
     pub last_activity: String,
     pub cpu_usage: f32,
     pub memory_usage: f32,
+    /// Live level of each track being recorded
+    pub audio_levels: Vec<TrackLevel>,
+    /// Notifications shown this run, oldest first
+    pub notifications: Vec<TrayNotification>,
+}
+
+/// A notification shown from the tray
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrayNotification {
+    pub title: String,
+    pub message: String,
+    pub urgent: bool,
 }
 
 /// Visual status indicators for the tray icon
@@ -83,18 +94,29 @@ pub enum TrayAction {
 pub struct SystemTrayManager {
     state: Arc<Mutex<SystemTrayState>>,
     action_sender: mpsc::UnboundedSender<TrayAction>,
-    config: Config,
+    config: DailyDocoConfig,
     #[cfg(target_os = "windows")]
     hwnd: windows::Win32::Foundation::HWND,
     #[cfg(target_os = "macos")]
     status_item: *mut cocoa::base::id,
     #[cfg(target_os = "linux")]
-    display: *mut x11::xlib::Display,
+    display: X11Display,
 }
 
+/// X connection kept open for the tray icon
+#[cfg(target_os = "linux")]
+struct X11Display(*mut xlib::Display);
+
+// The connection is only opened, never drawn on yet, so no two threads can
+// use it at once; the monitors need the manager on tokio's worker threads
+#[cfg(target_os = "linux")]
+unsafe impl Send for X11Display {}
+#[cfg(target_os = "linux")]
+unsafe impl Sync for X11Display {}
+
 impl SystemTrayManager {
     /// Create a new system tray manager
-    pub async fn new(config: Config) -> Result<Self, SystemTrayError> {
+    pub async fn new(config: DailyDocoConfig) -> Result<Self, SystemTrayError> {
         let (action_sender, action_receiver) = mpsc::unbounded_channel();
         
         let initial_state = SystemTrayState {
@@ -104,6 +126,8 @@ impl SystemTrayManager {
             last_activity: "DailyDoco Pro started".to_string(),
             cpu_usage: 0.0,
             memory_usage: 0.0,
+            audio_levels: Vec::new(),
+            notifications: Vec::new(),
         };
 
         let state = Arc::new(Mutex::new(initial_state));
@@ -175,6 +199,17 @@ impl SystemTrayManager {
         Ok(())
     }
 
+    /// Update the live audio levels shown while capturing
+    pub fn update_audio_levels(&self, levels: Vec<TrackLevel>) {
+        let mut state = self.state.lock().unwrap();
+        state.audio_levels = levels;
+    }
+
+    /// Current tray state, e.g. for the UI's level meters
+    pub fn state(&self) -> SystemTrayState {
+        self.state.lock().unwrap().clone()
+    }
+
     /// Show a notification
     pub async fn show_notification(
         &self,
@@ -182,6 +217,12 @@ impl SystemTrayManager {
         message: &str,
         urgent: bool,
     ) -> Result<(), SystemTrayError> {
+        self.state.lock().unwrap().notifications.push(TrayNotification {
+            title: title.to_string(),
+            message: message.to_string(),
+            urgent,
+        });
+
         #[cfg(target_os = "windows")]
         self.show_windows_notification(title, message, urgent).await?;
         
@@ -250,7 +291,7 @@ impl SystemTrayManager {
     }
 
     #[cfg(target_os = "windows")]
-    fn create_windows_tray(config: &Config) -> Result<windows::Win32::Foundation::HWND, SystemTrayError> {
+    fn create_windows_tray(config: &DailyDocoConfig) -> Result<windows::Win32::Foundation::HWND, SystemTrayError> {
         use windows::Win32::UI::WindowsAndMessaging::*;
         use windows::Win32::Foundation::*;
         
@@ -293,7 +334,7 @@ impl SystemTrayManager {
         use windows::Win32::UI::WindowsAndMessaging::*;
         
         match msg {
-            WM_USER + 1 => {
+            msg if msg == WM_USER + 1 => {
                 // Tray icon message
                 match lparam.0 as u32 {
                     WM_RBUTTONUP => {
@@ -313,7 +354,7 @@ impl SystemTrayManager {
     }
 
     #[cfg(target_os = "macos")]
-    fn create_macos_tray(config: &Config) -> Result<*mut cocoa::base::id, SystemTrayError> {
+    fn create_macos_tray(config: &DailyDocoConfig) -> Result<*mut cocoa::base::id, SystemTrayError> {
         use cocoa::appkit::*;
         use cocoa::base::*;
         use cocoa::foundation::*;
@@ -331,7 +372,7 @@ impl SystemTrayManager {
     }
 
     #[cfg(target_os = "linux")]
-    fn create_linux_tray(config: &Config) -> Result<*mut x11::xlib::Display, SystemTrayError> {
+    fn create_linux_tray(_config: &DailyDocoConfig) -> Result<X11Display, SystemTrayError> {
         use x11::xlib::*;
         
         unsafe {
@@ -341,7 +382,7 @@ impl SystemTrayManager {
             }
             
             // TODO: Implement proper Linux tray icon using freedesktop standards
-            Ok(display)
+            Ok(X11Display(display))
         }
     }
 
@@ -358,7 +399,7 @@ impl SystemTrayManager {
     }
 
     #[cfg(target_os = "linux")]
-    async fn update_linux_icon(&self, status: TrayStatus) -> Result<(), SystemTrayError> {
+    async fn update_linux_icon(&self, _status: TrayStatus) -> Result<(), SystemTrayError> {
         // TODO: Update Linux tray icon
         Ok(())
     }
@@ -370,14 +411,33 @@ impl SystemTrayManager {
     }
 
     #[cfg(target_os = "macos")]
-    async fn show_macos_notification(&self, title: &str, message: &str, urgent: bool) -> Result<(), SystemTrayError> {
-        // TODO: Show macOS notification
+    async fn show_macos_notification(&self, title: &str, message: &str, _urgent: bool) -> Result<(), SystemTrayError> {
+        let quote = |text: &str| format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""));
+        let script = format!("display notification {} with title {}", quote(message), quote(title));
+        let status = tokio::process::Command::new("osascript")
+            .arg("-e")
+            .arg(script)
+            .status()
+            .await
+            .map_err(|e| SystemTrayError::NotificationError(e.to_string()))?;
+        if !status.success() {
+            return Err(SystemTrayError::NotificationError(format!("osascript exited with {}", status)));
+        }
         Ok(())
     }
 
     #[cfg(target_os = "linux")]
     async fn show_linux_notification(&self, title: &str, message: &str, urgent: bool) -> Result<(), SystemTrayError> {
-        // TODO: Show Linux notification using libnotify
+        let status = tokio::process::Command::new("notify-send")
+            .args(["--app-name", "DailyDoco Pro", "--urgency", if urgent { "critical" } else { "normal" }])
+            .arg(title)
+            .arg(message)
+            .status()
+            .await
+            .map_err(|e| SystemTrayError::NotificationError(format!("notify-send: {}", e)))?;
+        if !status.success() {
+            return Err(SystemTrayError::NotificationError(format!("notify-send exited with {}", status)));
+        }
         Ok(())
     }
 }
@@ -469,9 +529,51 @@ impl TrayMetricsMonitor {
     }
 }
 
+/// Live audio levels and microphone alerts for the tray
+#[derive(Clone)]
+pub struct TrayAudioMonitor {
+    tray_manager: Arc<SystemTrayManager>,
+    meter: AudioMeter,
+}
+
+impl TrayAudioMonitor {
+    pub fn new(tray_manager: Arc<SystemTrayManager>, meter: AudioMeter) -> Self {
+        Self { tray_manager, meter }
+    }
+
+    /// Poll the recorder's meter until the returned task is aborted
+    pub fn start_monitoring(&self) -> tokio::task::JoinHandle<()> {
+        let monitor = self.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_millis(100));
+
+            loop {
+                interval.tick().await;
+                monitor.update().await;
+            }
+        })
+    }
+
+    /// Show the current levels, and a notification for each new alert
+    pub async fn update(&self) {
+        self.tray_manager.update_audio_levels(self.meter.levels());
+
+        // A silent or clipping mic ruins the take, so tell the user now
+        for alert in self.meter.take_alerts() {
+            let (title, message) = (alert.title(), alert.message());
+            if let Err(e) = self.tray_manager.show_notification(&title, &message, alert.is_urgent()).await {
+                log::error!("Failed to show audio alert: {}", e);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::audio_capture::AudioTrack;
+    use crate::audio_meter::{level_channel, MeterConfig, TrackMeter};
 
     #[tokio::test]
     async fn test_tray_status() {
@@ -489,10 +591,53 @@ mod tests {
             last_activity: "Test activity".to_string(),
             cpu_usage: 15.5,
             memory_usage: 128.0,
+            audio_levels: Vec::new(),
+            notifications: Vec::new(),
         };
 
         assert_eq!(state.status, TrayStatus::Processing);
         assert!(state.capture_active);
         assert_eq!(state.processing_queue, 2);
     }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_silent_microphone_shows_a_notification() {
+        let (action_sender, _actions) = mpsc::unbounded_channel();
+        let tray = Arc::new(SystemTrayManager {
+            state: Arc::new(Mutex::new(SystemTrayState {
+                status: TrayStatus::Active,
+                capture_active: true,
+                processing_queue: 0,
+                last_activity: "Capturing".to_string(),
+                cpu_usage: 0.0,
+                memory_usage: 0.0,
+                audio_levels: Vec::new(),
+                notifications: Vec::new(),
+            })),
+            action_sender,
+            config: DailyDocoConfig::default(),
+            display: X11Display(std::ptr::null_mut()),
+        });
+
+        // Eleven seconds of a muted mic
+        let config = MeterConfig::default();
+        let meter = AudioMeter::default();
+        let (mut tap, mut readings) = level_channel(1_000, 1, &config);
+        let mut track_meter = TrackMeter::new(AudioTrack::Microphone, 1_000, config, meter.clone());
+        (0..11_000).for_each(|_| tap.add(0.0));
+        while let Ok(reading) = readings.pop() {
+            track_meter.update(reading);
+        }
+
+        let monitor = TrayAudioMonitor::new(Arc::clone(&tray), meter.clone());
+        monitor.update().await;
+        monitor.update().await;
+
+        let state = tray.state();
+        assert_eq!(state.audio_levels.len(), 1);
+        assert_eq!(state.notifications.len(), 1);
+        assert_eq!(state.notifications[0].title, "Microphone is silent");
+        assert!(state.notifications[0].urgent);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,517 @@
+//! Terminal session recording in asciicast v2 for DailyDoco Pro
+//!
+//! Terminal-heavy sessions compress poorly as video, so the shell can be run
+//! in a PTY and its output recorded as asciicast v2 instead: a JSON header
+//! line followed by one `[time, code, data]` line per event. Recordings can
+//! be rendered to frames later or exported unchanged for asciinema players.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, bail, Context, Result};
+use parking_lot::Mutex;
+use portable_pty::{native_pty_system, Child, CommandBuilder, MasterPty, PtySize};
+use serde::de::{self, SeqAccess, Visitor};
+use serde::ser::SerializeTuple;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerminalCaptureConfig {
+    pub enabled: bool,
+    /// Shell to launch, `None` uses the user's default shell
+    pub shell: Option<String>,
+    pub cols: u16,
+    pub rows: u16,
+    /// Also record keystrokes sent to the terminal as `i` events
+    pub record_input: bool,
+}
+
+impl Default for TerminalCaptureConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            shell: None,
+            cols: 120,
+            rows: 32,
+            record_input: false,
+        }
+    }
+}
+
+/// First line of an asciicast v2 file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AsciicastHeader {
+    pub version: u8,
+    pub width: u16,
+    pub height: u16,
+    /// Unix time the recording started
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<f64>,
+    /// Players compress pauses longer than this many seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_time_limit: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
+}
+
+impl AsciicastHeader {
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            version: 2,
+            width,
+            height,
+            timestamp: None,
+            duration: None,
+            idle_time_limit: None,
+            command: None,
+            title: None,
+            env: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AsciicastEventKind {
+    Output,
+    Input,
+    /// Terminal resize, data is `"COLSxROWS"`
+    Resize,
+    Marker,
+}
+
+impl AsciicastEventKind {
+    fn code(&self) -> &'static str {
+        match self {
+            AsciicastEventKind::Output => "o",
+            AsciicastEventKind::Input => "i",
+            AsciicastEventKind::Resize => "r",
+            AsciicastEventKind::Marker => "m",
+        }
+    }
+
+    fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "o" => Some(AsciicastEventKind::Output),
+            "i" => Some(AsciicastEventKind::Input),
+            "r" => Some(AsciicastEventKind::Resize),
+            "m" => Some(AsciicastEventKind::Marker),
+            _ => None,
+        }
+    }
+}
+
+/// One event line, serialized as `[time, code, data]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct AsciicastEvent {
+    /// Seconds since the start of the recording
+    pub time: f64,
+    pub kind: AsciicastEventKind,
+    pub data: String,
+}
+
+impl AsciicastEvent {
+    pub fn new(time: Duration, kind: AsciicastEventKind, data: impl Into<String>) -> Self {
+        Self {
+            time: time.as_secs_f64(),
+            kind,
+            data: data.into(),
+        }
+    }
+
+    pub fn timestamp(&self) -> Duration {
+        Duration::from_secs_f64(self.time.max(0.0))
+    }
+
+    /// New terminal size for resize events, as `(cols, rows)`
+    pub fn resize_size(&self) -> Option<(u16, u16)> {
+        if self.kind != AsciicastEventKind::Resize {
+            return None;
+        }
+        let (cols, rows) = self.data.split_once('x')?;
+        Some((cols.trim().parse().ok()?, rows.trim().parse().ok()?))
+    }
+}
+
+impl Serialize for AsciicastEvent {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        // Microsecond precision keeps files small and round-trips exactly
+        let time = (self.time * 1_000_000.0).round() / 1_000_000.0;
+        let mut tuple = serializer.serialize_tuple(3)?;
+        tuple.serialize_element(&time)?;
+        tuple.serialize_element(self.kind.code())?;
+        tuple.serialize_element(&self.data)?;
+        tuple.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for AsciicastEvent {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct EventVisitor;
+
+        impl<'de> Visitor<'de> for EventVisitor {
+            type Value = AsciicastEvent;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an asciicast event [time, code, data]")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<Self::Value, A::Error> {
+                let time: f64 = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let code: String = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                let data: String = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(2, &self))?;
+                let kind = AsciicastEventKind::from_code(&code)
+                    .ok_or_else(|| de::Error::custom(format!("unknown event code {:?}", code)))?;
+                Ok(AsciicastEvent { time, kind, data })
+            }
+        }
+
+        deserializer.deserialize_tuple(3, EventVisitor)
+    }
+}
+
+/// A complete asciicast v2 recording
+#[derive(Debug, Clone, PartialEq)]
+pub struct Asciicast {
+    pub header: AsciicastHeader,
+    pub events: Vec<AsciicastEvent>,
+}
+
+impl Asciicast {
+    pub fn new(header: AsciicastHeader) -> Self {
+        Self {
+            header,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.events.last().map(|e| e.timestamp()).unwrap_or(Duration::ZERO)
+    }
+
+    pub fn from_reader(reader: impl Read) -> Result<Self> {
+        let mut lines = BufReader::new(reader).lines();
+        let header_line = lines.next().context("Empty asciicast")??;
+        let header: AsciicastHeader = serde_json::from_str(&header_line).context("Invalid asciicast header")?;
+        if header.version != 2 {
+            bail!("Unsupported asciicast version {}", header.version);
+        }
+
+        let mut events = Vec::new();
+        for (index, line) in lines.enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let event: AsciicastEvent = serde_json::from_str(&line)
+                .with_context(|| format!("Invalid asciicast event on line {}", index + 2))?;
+            events.push(event);
+        }
+
+        Ok(Self { header, events })
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        Self::from_reader(text.as_bytes())
+    }
+
+    pub fn write_to(&self, mut writer: impl Write) -> Result<()> {
+        let mut header = self.header.clone();
+        header.duration.get_or_insert(self.duration().as_secs_f64());
+
+        serde_json::to_writer(&mut writer, &header)?;
+        writer.write_all(b"\n")?;
+        for event in &self.events {
+            serde_json::to_writer(&mut writer, event)?;
+            writer.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    pub fn to_cast_string(&self) -> Result<String> {
+        let mut buffer = Vec::new();
+        self.write_to(&mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let file = std::fs::File::open(path).with_context(|| format!("Opening {}", path.display()))?;
+        Self::from_reader(file)
+    }
+
+    /// Write the recording unchanged, ready for embedding with an asciinema player
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let file = std::fs::File::create(path).with_context(|| format!("Creating {}", path.display()))?;
+        let mut writer = std::io::BufWriter::new(file);
+        self.write_to(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// A finished recording placed on the capture clock
+#[derive(Debug, Clone)]
+pub struct TerminalRecording {
+    /// Capture-clock time of the recording's first instant
+    pub capture_offset: Duration,
+    pub cast: Asciicast,
+}
+
+impl TerminalRecording {
+    /// Recording time for a capture-clock timestamp
+    pub fn cast_time(&self, capture_time: Duration) -> Duration {
+        capture_time.saturating_sub(self.capture_offset)
+    }
+}
+
+/// Records a shell running in a PTY
+pub struct TerminalRecorder {
+    master: Box<dyn MasterPty + Send>,
+    writer: Box<dyn Write + Send>,
+    child: Box<dyn Child + Send + Sync>,
+    reader: Option<JoinHandle<()>>,
+    events: Arc<Mutex<Vec<AsciicastEvent>>>,
+    header: AsciicastHeader,
+    started: Instant,
+    capture_offset: Duration,
+    record_input: bool,
+}
+
+impl TerminalRecorder {
+    /// Launch the shell; `capture_origin` is the instant the capture clock reads zero
+    pub fn spawn(config: &TerminalCaptureConfig, capture_origin: Instant) -> Result<Self> {
+        let pty = native_pty_system()
+            .openpty(PtySize {
+                rows: config.rows,
+                cols: config.cols,
+                pixel_width: 0,
+                pixel_height: 0,
+            })
+            .map_err(|e| anyhow!("Failed to open PTY: {}", e))?;
+
+        let mut command = match &config.shell {
+            Some(shell) => CommandBuilder::new(shell),
+            None => CommandBuilder::new_default_prog(),
+        };
+        command.env("TERM", "xterm-256color");
+
+        let child = pty
+            .slave
+            .spawn_command(command)
+            .map_err(|e| anyhow!("Failed to start shell: {}", e))?;
+        let reader = pty.master.try_clone_reader().map_err(|e| anyhow!("PTY reader: {}", e))?;
+        let writer = pty.master.take_writer().map_err(|e| anyhow!("PTY writer: {}", e))?;
+
+        let started = Instant::now();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let reader = std::thread::spawn({
+            let events = Arc::clone(&events);
+            move || record_output(reader, started, events)
+        });
+
+        let mut header = AsciicastHeader::new(config.cols, config.rows);
+        header.timestamp = SystemTime::now().duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs());
+        header.command = config.shell.clone();
+        header.env.insert("TERM".to_string(), "xterm-256color".to_string());
+        if let Ok(shell) = std::env::var("SHELL") {
+            header.env.insert("SHELL".to_string(), shell);
+        }
+
+        Ok(Self {
+            master: pty.master,
+            writer,
+            child,
+            reader: Some(reader),
+            events,
+            header,
+            started,
+            capture_offset: started.saturating_duration_since(capture_origin),
+            record_input: config.record_input,
+        })
+    }
+
+    /// Send keystrokes to the shell
+    pub fn write_input(&mut self, data: &[u8]) -> Result<()> {
+        self.writer.write_all(data)?;
+        self.writer.flush()?;
+
+        if self.record_input {
+            self.push(AsciicastEventKind::Input, String::from_utf8_lossy(data).into_owned());
+        }
+        Ok(())
+    }
+
+    pub fn resize(&mut self, cols: u16, rows: u16) -> Result<()> {
+        self.master
+            .resize(PtySize {
+                rows,
+                cols,
+                pixel_width: 0,
+                pixel_height: 0,
+            })
+            .map_err(|e| anyhow!("Failed to resize PTY: {}", e))?;
+        self.push(AsciicastEventKind::Resize, format!("{}x{}", cols, rows));
+        Ok(())
+    }
+
+    /// Mark a point of interest, e.g. a chapter boundary
+    pub fn add_marker(&self, label: &str) {
+        self.push(AsciicastEventKind::Marker, label.to_string());
+    }
+
+    pub fn is_running(&mut self) -> bool {
+        matches!(self.child.try_wait(), Ok(None))
+    }
+
+    /// Stop the shell if still running and return the recording
+    pub fn stop(mut self) -> Result<TerminalRecording> {
+        if self.is_running() {
+            self.child.kill().context("Failed to stop shell")?;
+        }
+        self.child.wait().context("Failed to reap shell")?;
+
+        // Closing our end lets the reader thread see EOF
+        drop(self.writer);
+        drop(self.master);
+        if let Some(reader) = self.reader.take() {
+            reader.join().map_err(|_| anyhow!("Terminal reader thread panicked"))?;
+        }
+
+        let mut events = std::mem::take(&mut *self.events.lock());
+        events.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap_or(std::cmp::Ordering::Equal));
+
+        Ok(TerminalRecording {
+            capture_offset: self.capture_offset,
+            cast: Asciicast {
+                header: self.header,
+                events,
+            },
+        })
+    }
+
+    fn push(&self, kind: AsciicastEventKind, data: String) {
+        let event = AsciicastEvent::new(self.started.elapsed(), kind, data);
+        self.events.lock().push(event);
+    }
+}
+
+/// Reader thread: record PTY output until EOF
+fn record_output(mut reader: Box<dyn Read + Send>, started: Instant, events: Arc<Mutex<Vec<AsciicastEvent>>>) {
+    let mut buffer = [0u8; 8192];
+    let mut pending: Vec<u8> = Vec::new();
+
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) | Err(_) => break,
+            Ok(read) => read,
+        };
+        pending.extend_from_slice(&buffer[..read]);
+
+        let text = take_utf8_prefix(&mut pending);
+        if !text.is_empty() {
+            events
+                .lock()
+                .push(AsciicastEvent::new(started.elapsed(), AsciicastEventKind::Output, text));
+        }
+    }
+
+    if !pending.is_empty() {
+        let text = String::from_utf8_lossy(&pending).into_owned();
+        events
+            .lock()
+            .push(AsciicastEvent::new(started.elapsed(), AsciicastEventKind::Output, text));
+    }
+}
+
+/// Remove and return the longest valid UTF-8 prefix, keeping a split
+/// multi-byte character for the next read
+fn take_utf8_prefix(pending: &mut Vec<u8>) -> String {
+    match std::str::from_utf8(pending) {
+        Ok(text) => {
+            let text = text.to_string();
+            pending.clear();
+            text
+        }
+        Err(error) => {
+            let valid = error.valid_up_to();
+            let consumed = match error.error_len() {
+                // Invalid bytes mid-stream: replace them rather than stall
+                Some(len) => valid + len,
+                None => valid,
+            };
+            let text = String::from_utf8_lossy(&pending[..consumed]).into_owned();
+            pending.drain(..consumed);
+            text
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CAST: &str = r#"{"version": 2, "width": 80, "height": 24, "timestamp": 1504467315, "env": {"SHELL": "/bin/zsh", "TERM": "xterm-256color"}}
+[0.248848, "o", "\u001b[1;31mHello \u001b[32mWorld!\u001b[0m\n"]
+[1.001376, "o", "That was ok\rThis is better."]
+[1.5, "m", "chapter 2"]
+[2.143733, "r", "90x30"]
+"#;
+
+    #[test]
+    fn test_parse_and_round_trip() {
+        let cast = Asciicast::parse(CAST).unwrap();
+        assert_eq!(cast.header.width, 80);
+        assert_eq!(cast.events.len(), 4);
+        assert_eq!(cast.events[2].kind, AsciicastEventKind::Marker);
+        assert_eq!(cast.events[3].resize_size(), Some((90, 30)));
+
+        let written = cast.to_cast_string().unwrap();
+        let reparsed = Asciicast::parse(&written).unwrap();
+        assert_eq!(reparsed.events, cast.events);
+        assert_eq!(reparsed.header.duration, Some(2.143733));
+    }
+
+    #[test]
+    fn test_split_utf8_is_kept_for_next_read() {
+        let bytes = "héllo".as_bytes();
+        let mut pending = bytes[..2].to_vec();
+
+        assert_eq!(take_utf8_prefix(&mut pending), "h");
+        assert_eq!(pending.len(), 1);
+
+        pending.extend_from_slice(&bytes[2..]);
+        assert_eq!(take_utf8_prefix(&mut pending), "éllo");
+        assert!(pending.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_records_shell_output() {
+        let config = TerminalCaptureConfig {
+            enabled: true,
+            shell: Some("/bin/sh".to_string()),
+            ..Default::default()
+        };
+        let mut recorder = TerminalRecorder::spawn(&config, Instant::now()).unwrap();
+        recorder.write_input(b"echo dailydoco-$((40 + 2)); exit\n").unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while recorder.is_running() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(20));
+        }
+
+        let recording = recorder.stop().unwrap();
+        let output: String = recording.cast.events.iter().map(|e| e.data.as_str()).collect();
+        assert!(output.contains("dailydoco-42"));
+    }
+}
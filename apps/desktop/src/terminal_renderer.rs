@@ -0,0 +1,347 @@
+//! Asciicast to video frame rendering for DailyDoco Pro
+//!
+//! Replays recorded terminal output through a VT100 emulator and draws the
+//! screen with the bundled monospace font. The font is sized to fit the
+//! terminal grid into the requested frame, so text stays crisp at any
+//! resolution instead of being scaled from a low-resolution capture.
+
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use anyhow::{bail, Result};
+use ab_glyph::FontRef;
+
+use aegnt_27::visual::VideoFrame;
+
+use crate::canvas::{line_metrics, monospace_font, solid_frame, text_width, Canvas, Rgba};
+use crate::terminal_recording::{Asciicast, AsciicastEventKind};
+
+/// Terminal colors; `ansi` holds the 16 standard and bright colors
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerminalPalette {
+    pub background: Rgba,
+    pub foreground: Rgba,
+    pub cursor: Rgba,
+    pub ansi: [Rgba; 16],
+}
+
+impl Default for TerminalPalette {
+    fn default() -> Self {
+        Self {
+            background: Rgba::new(30, 30, 36, 255),
+            foreground: Rgba::new(220, 223, 228, 255),
+            cursor: Rgba::new(220, 223, 228, 200),
+            ansi: [
+                Rgba::new(40, 44, 52, 255),
+                Rgba::new(224, 108, 117, 255),
+                Rgba::new(152, 195, 121, 255),
+                Rgba::new(229, 192, 123, 255),
+                Rgba::new(97, 175, 239, 255),
+                Rgba::new(198, 120, 221, 255),
+                Rgba::new(86, 182, 194, 255),
+                Rgba::new(220, 223, 228, 255),
+                Rgba::new(92, 99, 112, 255),
+                Rgba::new(240, 128, 136, 255),
+                Rgba::new(172, 215, 141, 255),
+                Rgba::new(240, 210, 145, 255),
+                Rgba::new(120, 195, 255, 255),
+                Rgba::new(215, 145, 235, 255),
+                Rgba::new(110, 205, 215, 255),
+                Rgba::new(255, 255, 255, 255),
+            ],
+        }
+    }
+}
+
+impl TerminalPalette {
+    fn resolve(&self, color: vt100::Color, fallback: Rgba, bold: bool) -> Rgba {
+        match color {
+            vt100::Color::Default => fallback,
+            // Bold brightens the eight base colors, as most terminals do
+            vt100::Color::Idx(index) if index < 8 && bold => self.ansi[index as usize + 8],
+            vt100::Color::Idx(index) if index < 16 => self.ansi[index as usize],
+            vt100::Color::Idx(index) if index < 232 => {
+                let cube = index - 16;
+                let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+                Rgba::new(level(cube / 36), level((cube / 6) % 6), level(cube % 6), 255)
+            }
+            vt100::Color::Idx(index) => {
+                let gray = 8 + (index - 232) * 10;
+                Rgba::new(gray, gray, gray, 255)
+            }
+            vt100::Color::Rgb(r, g, b) => Rgba::new(r, g, b, 255),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerminalRenderConfig {
+    pub width: u32,
+    pub height: u32,
+    pub frame_rate: f64,
+    pub padding: f32,
+    /// Line spacing as a multiple of the font size
+    pub line_height: f32,
+    pub palette: TerminalPalette,
+    pub show_cursor: bool,
+    /// Pauses longer than this are shortened to it; falls back to the
+    /// recording's own `idle_time_limit`
+    pub idle_time_limit: Option<Duration>,
+}
+
+impl Default for TerminalRenderConfig {
+    fn default() -> Self {
+        Self {
+            width: 1920,
+            height: 1080,
+            frame_rate: 30.0,
+            padding: 32.0,
+            line_height: 1.25,
+            palette: TerminalPalette::default(),
+            show_cursor: true,
+            idle_time_limit: Some(Duration::from_secs(2)),
+        }
+    }
+}
+
+/// Renders an asciicast recording as video frames
+pub struct TerminalRenderer {
+    config: TerminalRenderConfig,
+    cast: Asciicast,
+    /// Event times after idle compression
+    event_times: Vec<Duration>,
+    parser: vt100::Parser,
+    next_event: usize,
+    font: FontRef<'static>,
+}
+
+impl TerminalRenderer {
+    pub fn new(cast: Asciicast, config: TerminalRenderConfig) -> Result<Self> {
+        if cast.header.width == 0 || cast.header.height == 0 {
+            bail!("Recording has an empty terminal size");
+        }
+        if config.frame_rate <= 0.0 || config.width == 0 || config.height == 0 {
+            bail!("Rendering needs a positive frame rate and frame size");
+        }
+
+        let idle_limit = config
+            .idle_time_limit
+            .or_else(|| cast.header.idle_time_limit.map(Duration::from_secs_f64));
+        let event_times = compress_idle(&cast, idle_limit);
+        let parser = vt100::Parser::new(cast.header.height, cast.header.width, 0);
+
+        Ok(Self {
+            config,
+            cast,
+            event_times,
+            parser,
+            next_event: 0,
+            font: monospace_font(),
+        })
+    }
+
+    /// Length of the rendered video, after idle compression
+    pub fn duration(&self) -> Duration {
+        self.event_times.last().copied().unwrap_or(Duration::ZERO)
+    }
+
+    pub fn frame_count(&self) -> usize {
+        (self.duration().as_secs_f64() * self.config.frame_rate).ceil() as usize + 1
+    }
+
+    pub fn render_frame(&mut self, index: usize) -> Result<VideoFrame> {
+        self.render_at(Duration::from_secs_f64(index as f64 / self.config.frame_rate))
+    }
+
+    /// Render the screen at `timestamp` on the rendered (compressed) timeline
+    ///
+    /// Sequential calls only feed new output to the emulator; seeking
+    /// backwards replays the recording from the start.
+    pub fn render_at(&mut self, timestamp: Duration) -> Result<VideoFrame> {
+        self.advance_to(timestamp);
+
+        let config = &self.config;
+        let palette = &config.palette;
+        let screen = self.parser.screen();
+        let (rows, cols) = screen.size();
+
+        let mut frame = solid_frame(config.width, config.height, palette.background, timestamp);
+        let mut canvas = Canvas::new(&mut frame)?;
+
+        // Fit the grid into the frame at the largest font size that fits
+        let advance_per_px = text_width(&self.font, "M", 100.0) / 100.0;
+        let usable_width = config.width as f32 - config.padding * 2.0;
+        let usable_height = config.height as f32 - config.padding * 2.0;
+        let font_size = (usable_width / (cols as f32 * advance_per_px))
+            .min(usable_height / (rows as f32 * config.line_height))
+            .max(1.0);
+
+        let cell_width = advance_per_px * font_size;
+        let cell_height = font_size * config.line_height;
+        let (ascent, descent) = line_metrics(&self.font, font_size);
+        let baseline_offset = (cell_height - (ascent - descent)) / 2.0 + ascent;
+
+        // Center the grid when the aspect ratios differ
+        let left = (config.width as f32 - cell_width * cols as f32) / 2.0;
+        let top = (config.height as f32 - cell_height * rows as f32) / 2.0;
+
+        for row in 0..rows {
+            for col in 0..cols {
+                let Some(cell) = screen.cell(row, col) else {
+                    continue;
+                };
+                if cell.is_wide_continuation() {
+                    continue;
+                }
+
+                let mut foreground = palette.resolve(cell.fgcolor(), palette.foreground, cell.bold());
+                let mut background = palette.resolve(cell.bgcolor(), palette.background, false);
+                if cell.inverse() {
+                    std::mem::swap(&mut foreground, &mut background);
+                }
+
+                let x = left + col as f32 * cell_width;
+                let y = top + row as f32 * cell_height;
+                let width = if cell.is_wide() { cell_width * 2.0 } else { cell_width };
+
+                if background != palette.background {
+                    // Whole-pixel edges so neighbouring cells leave no seams
+                    let (x0, y0) = (x.round(), y.round());
+                    let (x1, y1) = ((x + width).round(), (y + cell_height).round());
+                    canvas.fill_rect(x0, y0, x1 - x0, y1 - y0, background);
+                }
+
+                let contents = cell.contents();
+                if !contents.is_empty() && contents != " " {
+                    canvas.draw_text(&self.font, &contents, x, y + baseline_offset, font_size, foreground);
+                }
+                if cell.underline() {
+                    canvas.fill_rect(x, y + baseline_offset + 2.0, width, (font_size / 14.0).max(1.0), foreground);
+                }
+            }
+        }
+
+        if config.show_cursor && !screen.hide_cursor() {
+            let (row, col) = screen.cursor_position();
+            canvas.fill_rect(
+                left + col as f32 * cell_width,
+                top + row as f32 * cell_height,
+                cell_width,
+                cell_height,
+                palette.cursor.faded(0.6),
+            );
+        }
+
+        Ok(frame)
+    }
+
+    /// Plain-text screen contents at `timestamp`, useful for docs and search
+    pub fn text_at(&mut self, timestamp: Duration) -> String {
+        self.advance_to(timestamp);
+        self.parser.screen().contents()
+    }
+
+    /// Marker events with their rendered timestamps, e.g. for chapters
+    pub fn markers(&self) -> Vec<(Duration, String)> {
+        self.cast
+            .events
+            .iter()
+            .zip(&self.event_times)
+            .filter(|(event, _)| event.kind == AsciicastEventKind::Marker)
+            .map(|(event, &time)| (time, event.data.clone()))
+            .collect()
+    }
+
+    fn advance_to(&mut self, timestamp: Duration) {
+        let target = self.event_times.partition_point(|&t| t <= timestamp);
+        if target < self.next_event {
+            self.parser = vt100::Parser::new(self.cast.header.height, self.cast.header.width, 0);
+            self.next_event = 0;
+        }
+
+        for event in &self.cast.events[self.next_event..target] {
+            match event.kind {
+                AsciicastEventKind::Output => self.parser.process(event.data.as_bytes()),
+                AsciicastEventKind::Resize => {
+                    if let Some((cols, rows)) = event.resize_size() {
+                        self.parser.set_size(rows, cols);
+                    }
+                }
+                AsciicastEventKind::Input | AsciicastEventKind::Marker => {}
+            }
+        }
+        self.next_event = target;
+    }
+}
+
+/// Event times with every gap capped at `limit`
+fn compress_idle(cast: &Asciicast, limit: Option<Duration>) -> Vec<Duration> {
+    let mut times = Vec::with_capacity(cast.events.len());
+    let mut previous_source = Duration::ZERO;
+    let mut current = Duration::ZERO;
+
+    for event in &cast.events {
+        let source = event.timestamp().max(previous_source);
+        let gap = source - previous_source;
+        current += limit.map_or(gap, |limit| gap.min(limit));
+        previous_source = source;
+        times.push(current);
+    }
+
+    times
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terminal_recording::{AsciicastEvent, AsciicastHeader};
+
+    fn cast() -> Asciicast {
+        let mut cast = Asciicast::new(AsciicastHeader::new(20, 4));
+        cast.events = vec![
+            AsciicastEvent::new(Duration::from_millis(100), AsciicastEventKind::Output, "$ ls\r\n"),
+            AsciicastEvent::new(Duration::from_millis(200), AsciicastEventKind::Output, "\u{1b}[31mred\u{1b}[0m\r\n"),
+            AsciicastEvent::new(Duration::from_secs(30), AsciicastEventKind::Output, "done"),
+        ];
+        cast
+    }
+
+    fn small_config() -> TerminalRenderConfig {
+        TerminalRenderConfig {
+            width: 320,
+            height: 120,
+            padding: 8.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_idle_time_is_compressed() {
+        let renderer = TerminalRenderer::new(cast(), small_config()).unwrap();
+        assert_eq!(renderer.duration(), Duration::from_millis(2200));
+    }
+
+    #[test]
+    fn test_screen_state_follows_time_and_seeks_back() {
+        let mut renderer = TerminalRenderer::new(cast(), small_config()).unwrap();
+
+        assert!(renderer.text_at(Duration::from_secs(5)).contains("done"));
+        let early = renderer.text_at(Duration::from_millis(150));
+        assert!(early.contains("$ ls"));
+        assert!(!early.contains("red"));
+    }
+
+    #[test]
+    fn test_colored_output_rendered() {
+        let mut renderer = TerminalRenderer::new(cast(), small_config()).unwrap();
+        let frame = renderer.render_at(Duration::from_millis(300)).unwrap();
+        let red = renderer.config.palette.ansi[1];
+
+        // Anti-aliased red glyph pixels lean strongly red
+        let has_red = frame
+            .data
+            .chunks(3)
+            .any(|px| px[0] as i32 - px[1] as i32 > 60 && px[0] as i32 - px[2] as i32 > 40 && px[0] > red.r / 2);
+        assert!(has_red);
+        assert_eq!((frame.width, frame.height), (320, 120));
+    }
+}
@@ -0,0 +1,468 @@
+//! Edit timeline and cut-aware audio rendering for DailyDoco Pro
+//!
+//! Turns the segments picked by the clip selector into an ordered timeline and
+//! renders the recorded audio across every cut without clicks: each cut is
+//! snapped to a zero-crossing or low-energy point, blended with a short
+//! equal-power crossfade, and may lead (J-cut) or trail (L-cut) the picture.
+
+use std::f32::consts::FRAC_PI_2;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use anyhow::{bail, Result};
+
+use aegnt_27::audio::AudioData;
+
+use crate::intelligent_clip_selector::VideoSegment;
+
+/// Offset of the audio cut relative to the picture cut
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum AudioOffset {
+    /// Audio and picture cut together
+    #[default]
+    None,
+    /// Incoming audio starts this long before the picture cut
+    JCut(Duration),
+    /// Outgoing audio continues this long after the picture cut
+    LCut(Duration),
+}
+
+/// How a clip is joined to the clip before it
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CutTransition {
+    /// Crossfade length, `None` uses the renderer default
+    pub crossfade: Option<Duration>,
+    pub audio_offset: AudioOffset,
+}
+
+/// A range of the source recording placed on the timeline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelineClip {
+    pub source_start: Duration,
+    pub source_end: Duration,
+    /// Transition into this clip (ignored for the first clip)
+    pub transition: CutTransition,
+}
+
+impl TimelineClip {
+    pub fn new(source_start: Duration, source_end: Duration) -> Self {
+        Self {
+            source_start,
+            source_end,
+            transition: CutTransition::default(),
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.source_end.saturating_sub(self.source_start)
+    }
+}
+
+impl From<&VideoSegment> for TimelineClip {
+    fn from(segment: &VideoSegment) -> Self {
+        TimelineClip::new(segment.start_time, segment.end_time)
+    }
+}
+
+/// Ordered list of clips cut together from one recording
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Timeline {
+    pub clips: Vec<TimelineClip>,
+}
+
+impl Timeline {
+    pub fn new(clips: Vec<TimelineClip>) -> Self {
+        Self { clips }
+    }
+
+    /// Build a timeline from selected segments, in the order given
+    pub fn from_segments(segments: &[VideoSegment]) -> Self {
+        Self::new(segments.iter().map(TimelineClip::from).collect())
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.clips.iter().map(|clip| clip.duration()).sum()
+    }
+
+    /// Timeline positions of every cut between consecutive clips
+    pub fn cut_points(&self) -> Vec<Duration> {
+        let mut position = Duration::ZERO;
+        let mut cuts = Vec::with_capacity(self.clips.len().saturating_sub(1));
+
+        for clip in self.clips.iter().take(self.clips.len().saturating_sub(1)) {
+            position += clip.duration();
+            cuts.push(position);
+        }
+
+        cuts
+    }
+}
+
+/// Strategy for moving a cut to a quieter sample
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum CutSnapMode {
+    /// Keep cuts exactly where the picture cuts
+    Off,
+    /// Move to the nearest sign change of both sides
+    ZeroCrossing,
+    /// Move to the quietest short window of both sides
+    LowEnergy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioCutConfig {
+    /// Default equal-power crossfade length at every cut
+    pub crossfade: Duration,
+    pub snap_mode: CutSnapMode,
+    /// How far a cut may move while snapping, in either direction
+    pub snap_window: Duration,
+    /// Window used to measure energy in `CutSnapMode::LowEnergy`
+    pub energy_window: Duration,
+}
+
+impl Default for AudioCutConfig {
+    fn default() -> Self {
+        Self {
+            crossfade: Duration::from_millis(10),
+            snap_mode: CutSnapMode::ZeroCrossing,
+            snap_window: Duration::from_millis(5),
+            energy_window: Duration::from_millis(2),
+        }
+    }
+}
+
+/// Source frame range for one clip's audio after offsets and snapping
+#[derive(Debug, Clone, Copy)]
+struct AudioSpan {
+    start: usize,
+    end: usize,
+}
+
+/// Renders the audio of a timeline from its source recording
+pub struct TimelineAudioRenderer {
+    config: AudioCutConfig,
+}
+
+impl TimelineAudioRenderer {
+    pub fn new(config: AudioCutConfig) -> Self {
+        Self { config }
+    }
+
+    /// Render the timeline's audio with click-free cuts
+    ///
+    /// Snapping moves both sides of a cut by the same amount, so the rendered
+    /// audio keeps the exact length of the picture and never drifts.
+    pub fn render(&self, timeline: &Timeline, source: &AudioData) -> Result<AudioData> {
+        if timeline.clips.is_empty() {
+            bail!("Cannot render audio for an empty timeline");
+        }
+        if source.channels == 0 {
+            bail!("Source audio has no channels");
+        }
+
+        let channels = source.channels as usize;
+        let total_frames = source.frame_count();
+        let spans = self.resolve_spans(timeline, source)?;
+
+        let mut output: Vec<f32> = Vec::with_capacity(
+            spans.iter().map(|span| span.end - span.start).sum::<usize>() * channels,
+        );
+
+        for (i, span) in spans.iter().enumerate() {
+            if i == 0 {
+                output.extend_from_slice(&source.samples[span.start * channels..span.end * channels]);
+                continue;
+            }
+
+            let previous = spans[i - 1];
+            let crossfade = timeline.clips[i].transition.crossfade.unwrap_or(self.config.crossfade);
+            let fade_frames = self.frames_for(crossfade, source.sample_rate);
+
+            // Both sides need handle audio beyond the cut for a true crossfade
+            let half = (fade_frames / 2)
+                .min(total_frames - previous.end)
+                .min(span.start)
+                .min((previous.end - previous.start) / 2)
+                .min((span.end - span.start) / 2);
+
+            if half == 0 {
+                self.dip_join(&mut output, &source.samples[span.start * channels..span.end * channels], fade_frames, channels);
+                continue;
+            }
+
+            // Replace the tail of the output with the overlapped region
+            let overlap = half * 2;
+            output.truncate(output.len() - half * channels);
+
+            let outgoing = &source.samples[(previous.end - half) * channels..(previous.end + half) * channels];
+            let incoming = &source.samples[(span.start - half) * channels..(span.start + half) * channels];
+
+            for frame in 0..overlap {
+                let t = (frame as f32 + 0.5) / overlap as f32;
+                let (gain_out, gain_in) = equal_power_gains(t);
+                for ch in 0..channels {
+                    let idx = frame * channels + ch;
+                    output.push(outgoing[idx] * gain_out + incoming[idx] * gain_in);
+                }
+            }
+
+            output.extend_from_slice(&source.samples[(span.start + half) * channels..span.end * channels]);
+        }
+
+        Ok(AudioData::new(output, source.sample_rate, source.channels))
+    }
+
+    /// Work out the source range of each clip's audio
+    fn resolve_spans(&self, timeline: &Timeline, source: &AudioData) -> Result<Vec<AudioSpan>> {
+        let total_frames = source.frame_count();
+        let sample_rate = source.sample_rate;
+
+        let mut spans: Vec<AudioSpan> = timeline
+            .clips
+            .iter()
+            .map(|clip| AudioSpan {
+                start: self.frames_for(clip.source_start, sample_rate).min(total_frames),
+                end: self.frames_for(clip.source_end, sample_rate).min(total_frames),
+            })
+            .collect();
+
+        for (i, span) in spans.iter().enumerate() {
+            if span.end <= span.start {
+                bail!("Clip {} has no audio in the source recording", i);
+            }
+        }
+
+        for i in 1..spans.len() {
+            let (before, after) = spans.split_at_mut(i);
+            let outgoing = &mut before[i - 1];
+            let incoming = &mut after[0];
+
+            // J/L cuts slide the audio cut; both sides move together
+            let shift = match timeline.clips[i].transition.audio_offset {
+                AudioOffset::None => 0,
+                AudioOffset::JCut(lead) => {
+                    -(self.frames_for(lead, sample_rate)
+                        .min(incoming.start)
+                        .min(outgoing.end - outgoing.start - 1) as isize)
+                }
+                AudioOffset::LCut(trail) => {
+                    self.frames_for(trail, sample_rate)
+                        .min(total_frames - outgoing.end)
+                        .min(incoming.end - incoming.start - 1) as isize
+                }
+            };
+            shift_cut(outgoing, incoming, shift);
+
+            let snap = self.find_snap_offset(source, outgoing, incoming);
+            shift_cut(outgoing, incoming, snap);
+        }
+
+        Ok(spans)
+    }
+
+    /// Find a shift that lands both sides of a cut on a quiet sample
+    fn find_snap_offset(&self, source: &AudioData, outgoing: &AudioSpan, incoming: &AudioSpan) -> isize {
+        let window = self.frames_for(self.config.snap_window, source.sample_rate) as isize;
+        if window == 0 || self.config.snap_mode == CutSnapMode::Off {
+            return 0;
+        }
+
+        let total_frames = source.frame_count() as isize;
+
+        // Keep at least one frame in each clip and stay inside the source
+        let min_shift = (-window)
+            .max(outgoing.start as isize + 1 - outgoing.end as isize)
+            .max(-(incoming.start as isize));
+        let max_shift = window
+            .min(incoming.end as isize - 1 - incoming.start as isize)
+            .min(total_frames - outgoing.end as isize);
+
+        if min_shift > max_shift {
+            return 0;
+        }
+
+        let energy_frames = self.frames_for(self.config.energy_window, source.sample_rate).max(1);
+        let mut best_shift: isize = 0;
+        let mut best_cost = f32::INFINITY;
+
+        for shift in min_shift..=max_shift {
+            let out_frame = (outgoing.end as isize + shift) as usize;
+            let in_frame = (incoming.start as isize + shift) as usize;
+
+            let cost = match self.config.snap_mode {
+                CutSnapMode::ZeroCrossing => {
+                    zero_crossing_cost(source, out_frame) + zero_crossing_cost(source, in_frame)
+                }
+                CutSnapMode::LowEnergy => {
+                    window_energy(source, out_frame, energy_frames) + window_energy(source, in_frame, energy_frames)
+                }
+                CutSnapMode::Off => 0.0,
+            };
+
+            // Prefer the smallest move when costs tie
+            if cost < best_cost || (cost == best_cost && shift.abs() < best_shift.abs()) {
+                best_cost = cost;
+                best_shift = shift;
+            }
+        }
+
+        best_shift
+    }
+
+    /// Fallback join without handle audio: fade out, then fade in
+    fn dip_join(&self, output: &mut Vec<f32>, incoming: &[f32], fade_frames: usize, channels: usize) {
+        let output_frames = output.len() / channels;
+        let incoming_frames = incoming.len() / channels;
+        let half = (fade_frames / 2).min(output_frames).min(incoming_frames);
+
+        let tail_start = output_frames - half;
+        for frame in 0..half {
+            let (gain, _) = equal_power_gains((frame as f32 + 0.5) / half as f32);
+            for ch in 0..channels {
+                output[(tail_start + frame) * channels + ch] *= gain;
+            }
+        }
+
+        for frame in 0..incoming_frames {
+            let gain = if frame < half {
+                equal_power_gains((frame as f32 + 0.5) / half as f32).1
+            } else {
+                1.0
+            };
+            for ch in 0..channels {
+                output.push(incoming[frame * channels + ch] * gain);
+            }
+        }
+    }
+
+    fn frames_for(&self, duration: Duration, sample_rate: u32) -> usize {
+        (duration.as_secs_f64() * sample_rate as f64).round() as usize
+    }
+}
+
+impl Default for TimelineAudioRenderer {
+    fn default() -> Self {
+        Self::new(AudioCutConfig::default())
+    }
+}
+
+/// Gains for the outgoing and incoming side at position `t` (0.0 to 1.0)
+fn equal_power_gains(t: f32) -> (f32, f32) {
+    let angle = t.clamp(0.0, 1.0) * FRAC_PI_2;
+    (angle.cos(), angle.sin())
+}
+
+fn shift_cut(outgoing: &mut AudioSpan, incoming: &mut AudioSpan, shift: isize) {
+    outgoing.end = (outgoing.end as isize + shift) as usize;
+    incoming.start = (incoming.start as isize + shift) as usize;
+}
+
+/// Zero when the signal changes sign at `frame`, otherwise its magnitude
+fn zero_crossing_cost(source: &AudioData, frame: usize) -> f32 {
+    let current = mono_sample(source, frame);
+    if frame == 0 {
+        return current.abs();
+    }
+
+    let previous = mono_sample(source, frame - 1);
+    if previous == 0.0 || current == 0.0 || previous.signum() != current.signum() {
+        0.0
+    } else {
+        current.abs()
+    }
+}
+
+/// Mean power of the mono mix around `frame`
+fn window_energy(source: &AudioData, frame: usize, window: usize) -> f32 {
+    let total_frames = source.frame_count();
+    let start = frame.saturating_sub(window / 2);
+    let end = (start + window).min(total_frames);
+    if end <= start {
+        return 0.0;
+    }
+
+    (start..end).map(|f| mono_sample(source, f).powi(2)).sum::<f32>() / (end - start) as f32
+}
+
+fn mono_sample(source: &AudioData, frame: usize) -> f32 {
+    let channels = source.channels as usize;
+    if frame >= source.frame_count() {
+        return 0.0;
+    }
+
+    source.samples[frame * channels..(frame + 1) * channels].iter().sum::<f32>() / channels as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(seconds: f64, frequency: f32, sample_rate: u32) -> AudioData {
+        let frames = (seconds * sample_rate as f64) as usize;
+        let samples = (0..frames)
+            .map(|i| (2.0 * std::f32::consts::PI * frequency * i as f32 / sample_rate as f32).sin() * 0.5)
+            .collect();
+        AudioData::new(samples, sample_rate, 1)
+    }
+
+    #[test]
+    fn test_rendered_length_matches_timeline() {
+        let source = sine(4.0, 440.0, 48000);
+        let timeline = Timeline::new(vec![
+            TimelineClip::new(Duration::from_millis(100), Duration::from_millis(1100)),
+            TimelineClip::new(Duration::from_millis(2000), Duration::from_millis(2500)),
+        ]);
+
+        let audio = TimelineAudioRenderer::default().render(&timeline, &source).unwrap();
+        assert_eq!(audio.frame_count(), 72000);
+    }
+
+    #[test]
+    fn test_cut_has_no_discontinuity() {
+        let source = sine(3.0, 220.0, 48000);
+        // Cut between opposite phases of the wave to provoke a click
+        let timeline = Timeline::new(vec![
+            TimelineClip::new(Duration::ZERO, Duration::from_micros(1_001_136)),
+            TimelineClip::new(Duration::from_micros(2_003_409), Duration::from_secs(3)),
+        ]);
+
+        let audio = TimelineAudioRenderer::default().render(&timeline, &source).unwrap();
+        let max_step = audio
+            .samples
+            .windows(2)
+            .map(|w| (w[1] - w[0]).abs())
+            .fold(0.0f32, f32::max);
+
+        // A 220 Hz sine at 0.5 amplitude moves at most ~0.015 per sample
+        assert!(max_step < 0.03, "max step {}", max_step);
+    }
+
+    #[test]
+    fn test_j_and_l_cuts_keep_length() {
+        let source = sine(4.0, 440.0, 48000);
+        let mut second = TimelineClip::new(Duration::from_secs(2), Duration::from_secs(3));
+        second.transition.audio_offset = AudioOffset::JCut(Duration::from_millis(250));
+        let mut third = TimelineClip::new(Duration::from_millis(500), Duration::from_millis(1500));
+        third.transition.audio_offset = AudioOffset::LCut(Duration::from_millis(250));
+
+        let timeline = Timeline::new(vec![
+            TimelineClip::new(Duration::ZERO, Duration::from_millis(400)),
+            second,
+            third,
+        ]);
+
+        let audio = TimelineAudioRenderer::default().render(&timeline, &source).unwrap();
+        assert_eq!(audio.frame_count(), (timeline.duration().as_secs_f64() * 48000.0) as usize);
+    }
+
+    #[test]
+    fn test_cut_points() {
+        let timeline = Timeline::new(vec![
+            TimelineClip::new(Duration::from_secs(10), Duration::from_secs(20)),
+            TimelineClip::new(Duration::from_secs(40), Duration::from_secs(45)),
+            TimelineClip::new(Duration::from_secs(50), Duration::from_secs(60)),
+        ]);
+
+        assert_eq!(timeline.cut_points(), vec![Duration::from_secs(10), Duration::from_secs(15)]);
+        assert_eq!(timeline.duration(), Duration::from_secs(25));
+    }
+}
@@ -8,6 +8,7 @@ use std::time::Duration;
 use serde::{Deserialize, Serialize};
 // use rand::prelude::*;
 
+use crate::audio_loudness::LoudnessMeasurement;
 use crate::error::{Aegnt27Error, AudioError, Result};
 use crate::utils::{timing, random::HumanRng, validation};
 
@@ -213,6 +214,9 @@ pub struct AudioMetadata {
     
     /// Signal-to-noise ratio estimate
     pub snr_estimate: Option<f32>,
+    
+    /// EBU R128 loudness, once measured
+    pub loudness: Option<LoudnessMeasurement>,
 }
 
 /// Humanized audio with enhanced characteristics
@@ -0,0 +1,395 @@
+//! Sample-rate, channel-layout and sample-format conversion for audio data
+//!
+//! Recordings mix sources that disagree on format, such as a 48 kHz mono
+//! microphone and 44.1 kHz stereo system audio. These methods bring
+//! `AudioData` to a common rate (band-limited sinc resampling through
+//! `rubato`) and channel layout (ITU-R BS.775 downmix coefficients, or any
+//! custom matrix), so tracks can be combined sample by sample.
+
+use rubato::{Resampler, SincFixedIn, SincInterpolationParameters, SincInterpolationType, WindowFunction};
+
+use crate::audio::AudioData;
+use crate::audio_io::{dequantize, quantize};
+use crate::error::{Aegnt27Error, AudioError, Result};
+
+/// Frames fed to the resampler per call
+const RESAMPLE_CHUNK: usize = 1024;
+
+/// Gain for center and surround channels folded into front left/right
+const MINUS_3DB: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+/// Speaker arrangement implied by a channel count
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelLayout {
+    /// One channel
+    Mono,
+    /// Left, right
+    Stereo,
+    /// Left, right, center, LFE, left surround, right surround (WAV/SMPTE order)
+    Surround51,
+    /// Any other count, with no known speaker positions
+    Discrete(u8),
+}
+
+impl ChannelLayout {
+    /// The usual layout for a channel count
+    pub fn from_channels(channels: u8) -> Self {
+        match channels {
+            1 => ChannelLayout::Mono,
+            2 => ChannelLayout::Stereo,
+            6 => ChannelLayout::Surround51,
+            n => ChannelLayout::Discrete(n),
+        }
+    }
+
+    /// Number of channels
+    pub fn channels(self) -> u8 {
+        match self {
+            ChannelLayout::Mono => 1,
+            ChannelLayout::Stereo => 2,
+            ChannelLayout::Surround51 => 6,
+            ChannelLayout::Discrete(n) => n,
+        }
+    }
+}
+
+/// Gains from each input channel to each output channel
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelMatrix {
+    /// One row per output channel, one gain per input channel
+    pub rows: Vec<Vec<f32>>,
+}
+
+impl ChannelMatrix {
+    /// Builds a matrix from rows of per-input gains
+    pub fn new(rows: Vec<Vec<f32>>) -> Result<Self> {
+        let inputs = rows.first().map(Vec::len).unwrap_or(0);
+        if inputs == 0 || rows.iter().any(|row| row.len() != inputs) || rows.len() > u8::MAX as usize {
+            return Err(invalid_format("Channel matrix rows must be non-empty and the same length"));
+        }
+        Ok(Self { rows })
+    }
+
+    /// Number of input channels
+    pub fn inputs(&self) -> usize {
+        self.rows[0].len()
+    }
+
+    /// Number of output channels
+    pub fn outputs(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Standard mapping between two layouts
+    ///
+    /// Downmixes fold center and surrounds into the fronts at -3 dB, drop
+    /// the LFE, and are scaled so a full-scale input can't clip. Upmixes
+    /// copy mono to both fronts (or the center of 5.1) and leave the other
+    /// speakers silent. Discrete layouts keep the channels they share.
+    pub fn between(from: ChannelLayout, to: ChannelLayout) -> Self {
+        use ChannelLayout::*;
+
+        let downmix_51 = {
+            let scale = 1.0 / (1.0 + 2.0 * MINUS_3DB);
+            let g = MINUS_3DB * scale;
+            vec![
+                vec![scale, 0.0, g, 0.0, g, 0.0],
+                vec![0.0, scale, g, 0.0, 0.0, g],
+            ]
+        };
+
+        let rows = match (from, to) {
+            (Mono, Stereo) => vec![vec![1.0], vec![1.0]],
+            (Stereo, Mono) => vec![vec![0.5, 0.5]],
+            (Surround51, Stereo) => downmix_51,
+            (Surround51, Mono) => {
+                let (left, right) = (&downmix_51[0], &downmix_51[1]);
+                vec![left.iter().zip(right).map(|(l, r)| (l + r) * 0.5).collect()]
+            }
+            (Mono, Surround51) => vec![
+                vec![0.0],
+                vec![0.0],
+                vec![1.0],
+                vec![0.0],
+                vec![0.0],
+                vec![0.0],
+            ],
+            _ => {
+                let (inputs, outputs) = (from.channels().max(1) as usize, to.channels().max(1) as usize);
+                (0..outputs)
+                    .map(|output| (0..inputs).map(|input| if input == output { 1.0 } else { 0.0 }).collect())
+                    .collect()
+            }
+        };
+        Self { rows }
+    }
+}
+
+fn invalid_format(message: impl Into<String>) -> Aegnt27Error {
+    Aegnt27Error::Audio(AudioError::InvalidFormat(message.into()))
+}
+
+impl AudioData {
+    /// Resamples to another rate with a band-limited sinc interpolator
+    pub fn resample(&self, sample_rate: u32) -> Result<Self> {
+        if sample_rate == self.sample_rate {
+            return Ok(self.clone());
+        }
+        if self.sample_rate == 0 || sample_rate == 0 {
+            return Err(invalid_format("Sample rate must be greater than 0"));
+        }
+        self.resample_with_ratio(sample_rate as f64 / self.sample_rate as f64, sample_rate)
+    }
+
+    /// Resamples by an exact ratio of output to input frames and labels the
+    /// result with `sample_rate`; used to correct clock drift, where the
+    /// real input rate differs slightly from the nominal one
+    pub fn resample_with_ratio(&self, ratio: f64, sample_rate: u32) -> Result<Self> {
+        if !(ratio.is_finite() && ratio > 0.0) {
+            return Err(invalid_format(format!("Invalid resampling ratio {}", ratio)));
+        }
+        let channels = self.channels.max(1) as usize;
+        let expected = (self.frame_count() as f64 * ratio).round() as usize;
+        if expected == 0 {
+            return Ok(AudioData::new(Vec::new(), sample_rate, self.channels));
+        }
+
+        let parameters = SincInterpolationParameters {
+            sinc_len: 256,
+            f_cutoff: 0.95,
+            oversampling_factor: 256,
+            interpolation: SincInterpolationType::Cubic,
+            window: WindowFunction::BlackmanHarris2,
+        };
+        let mut resampler = SincFixedIn::<f64>::new(ratio, 1.0, parameters, RESAMPLE_CHUNK, channels)
+            .map_err(|e| Aegnt27Error::Audio(AudioError::ProcessingFailed(format!("Resampler: {}", e))))?;
+
+        let planar = self.to_planar();
+        let frames = self.frame_count();
+        let mut output: Vec<Vec<f64>> = vec![Vec::with_capacity(expected + RESAMPLE_CHUNK); channels];
+        let mut position = 0;
+        // The sinc filter is centred on each output frame, so output is not delayed
+        while output[0].len() < expected {
+            let needed = resampler.input_frames_next();
+            let block = if position + needed <= frames {
+                let input: Vec<&[f64]> = planar.iter().map(|c| &c[position..position + needed]).collect();
+                resampler.process(input.as_slice(), None)
+            } else if position < frames {
+                let input: Vec<&[f64]> = planar.iter().map(|c| &c[position..]).collect();
+                resampler.process_partial(Some(input.as_slice()), None)
+            } else {
+                // Zeros let the filter finish the last frames
+                resampler.process_partial::<&[f64]>(None, None)
+            }
+            .map_err(|e| Aegnt27Error::Audio(AudioError::ProcessingFailed(format!("Resampling: {}", e))))?;
+            position += needed;
+            for (channel, resampled) in output.iter_mut().zip(block) {
+                channel.extend(resampled);
+            }
+        }
+
+        for channel in output.iter_mut() {
+            channel.truncate(expected);
+        }
+        Ok(Self::from_planar(&output, sample_rate))
+    }
+
+    /// Maps channels through a gain matrix
+    pub fn remix(&self, matrix: &ChannelMatrix) -> Result<Self> {
+        let inputs = self.channels.max(1) as usize;
+        if matrix.inputs() != inputs {
+            return Err(invalid_format(format!(
+                "Matrix expects {} input channels, audio has {}",
+                matrix.inputs(),
+                inputs
+            )));
+        }
+
+        let mut samples = Vec::with_capacity(self.frame_count() * matrix.outputs());
+        for frame in self.samples.chunks_exact(inputs) {
+            for row in &matrix.rows {
+                samples.push(row.iter().zip(frame).map(|(gain, sample)| gain * sample).sum());
+            }
+        }
+        Ok(AudioData::new(samples, self.sample_rate, matrix.outputs() as u8))
+    }
+
+    /// Converts to another layout with the standard mapping
+    pub fn to_layout(&self, layout: ChannelLayout) -> Result<Self> {
+        if layout.channels() == self.channels {
+            return Ok(self.clone());
+        }
+        self.remix(&ChannelMatrix::between(ChannelLayout::from_channels(self.channels), layout))
+    }
+
+    /// Resamples and remaps so the audio can be mixed with `sample_rate`/`channels` audio
+    pub fn conform(&self, sample_rate: u32, channels: u8) -> Result<Self> {
+        self.to_layout(ChannelLayout::from_channels(channels))?.resample(sample_rate)
+    }
+
+    /// Adds `other` scaled by `gain`, conforming it first; the result is as
+    /// long as the longer input
+    pub fn mix(&self, other: &AudioData, gain: f32) -> Result<Self> {
+        let other = other.conform(self.sample_rate, self.channels)?;
+        let mut samples = self.samples.clone();
+        if other.samples.len() > samples.len() {
+            samples.resize(other.samples.len(), 0.0);
+        }
+        for (mixed, sample) in samples.iter_mut().zip(&other.samples) {
+            *mixed += sample * gain;
+        }
+        Ok(AudioData::new(samples, self.sample_rate, self.channels))
+    }
+
+    /// One `Vec` per channel
+    pub fn to_planar(&self) -> Vec<Vec<f64>> {
+        let channels = self.channels.max(1) as usize;
+        let mut planar = vec![Vec::with_capacity(self.frame_count()); channels];
+        for frame in self.samples.chunks_exact(channels) {
+            for (channel, &sample) in planar.iter_mut().zip(frame) {
+                channel.push(sample as f64);
+            }
+        }
+        planar
+    }
+
+    /// Interleaves equal-length channels
+    pub fn from_planar(planar: &[Vec<f64>], sample_rate: u32) -> Self {
+        let frames = planar.iter().map(Vec::len).min().unwrap_or(0);
+        let mut samples = Vec::with_capacity(frames * planar.len());
+        for frame in 0..frames {
+            samples.extend(planar.iter().map(|channel| channel[frame] as f32));
+        }
+        AudioData::new(samples, sample_rate, planar.len() as u8)
+    }
+
+    /// Builds audio from signed 16-bit samples
+    pub fn from_i16(samples: &[i16], sample_rate: u32, channels: u8) -> Self {
+        let samples = samples.iter().map(|&s| dequantize(s as i32, 16)).collect();
+        AudioData::new(samples, sample_rate, channels)
+    }
+
+    /// Signed 16-bit samples, rounded and clamped
+    pub fn to_i16(&self) -> Vec<i16> {
+        self.samples.iter().map(|&s| quantize(s, 16) as i16).collect()
+    }
+
+    /// Builds audio from signed integers using the low `bits` bits (8 to 32),
+    /// e.g. 24-bit samples unpacked into `i32`
+    pub fn from_i32(samples: &[i32], bits: u32, sample_rate: u32, channels: u8) -> Result<Self> {
+        check_bits(bits)?;
+        let samples = samples.iter().map(|&s| dequantize(s, bits)).collect();
+        Ok(AudioData::new(samples, sample_rate, channels))
+    }
+
+    /// Signed integers of `bits` bits (8 to 32), rounded and clamped
+    pub fn to_i32(&self, bits: u32) -> Result<Vec<i32>> {
+        check_bits(bits)?;
+        Ok(self.samples.iter().map(|&s| quantize(s, bits)).collect())
+    }
+
+    /// Builds audio from unsigned 8-bit samples centred on 128
+    pub fn from_u8(samples: &[u8], sample_rate: u32, channels: u8) -> Self {
+        let samples = samples.iter().map(|&s| dequantize(s as i32 - 128, 8)).collect();
+        AudioData::new(samples, sample_rate, channels)
+    }
+
+    /// Unsigned 8-bit samples centred on 128
+    pub fn to_u8(&self) -> Vec<u8> {
+        self.samples.iter().map(|&s| (quantize(s, 8) + 128) as u8).collect()
+    }
+}
+
+fn check_bits(bits: u32) -> Result<()> {
+    if !(8..=32).contains(&bits) {
+        return Err(invalid_format(format!("{} bits per sample is not supported", bits)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(frequency: f32, sample_rate: u32, frames: usize, channels: u8) -> AudioData {
+        let samples = (0..frames)
+            .flat_map(|frame| {
+                let t = frame as f32 / sample_rate as f32;
+                let value = (2.0 * std::f32::consts::PI * frequency * t).sin() * 0.5;
+                std::iter::repeat_n(value, channels as usize)
+            })
+            .collect();
+        AudioData::new(samples, sample_rate, channels)
+    }
+
+    #[test]
+    fn test_resampling_keeps_pitch_and_duration() {
+        let audio = sine(1_000.0, 48_000, 48_000, 1);
+        let resampled = audio.resample(44_100).unwrap();
+
+        assert_eq!(resampled.sample_rate, 44_100);
+        assert_eq!(resampled.frame_count(), 44_100);
+        // Away from the edges the output matches the same tone sampled at 44.1 kHz,
+        // to within a fraction of a sample's phase
+        let reference = sine(1_000.0, 44_100, 44_100, 1);
+        let error = resampled.samples[1_000..43_000]
+            .iter()
+            .zip(&reference.samples[1_000..43_000])
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0, f32::max);
+        assert!(error < 0.01, "max error {}", error);
+        let peak = resampled.samples[1_000..43_000].iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!((peak - 0.5).abs() < 1e-3, "peak {}", peak);
+
+        let back = resampled.resample(48_000).unwrap();
+        assert_eq!(back.frame_count(), 48_000);
+        assert!(audio.resample(0).is_err());
+    }
+
+    #[test]
+    fn test_channel_layouts_use_standard_coefficients() {
+        // One frame with a distinct level on each 5.1 channel
+        let surround = AudioData::new(vec![0.4, 0.2, 0.3, 1.0, 0.1, 0.05], 48_000, 6);
+        let stereo = surround.to_layout(ChannelLayout::Stereo).unwrap();
+        let scale = 1.0 / (1.0 + 2.0 * MINUS_3DB);
+        let left = (0.4 + MINUS_3DB * 0.3 + MINUS_3DB * 0.1) * scale;
+        let right = (0.2 + MINUS_3DB * 0.3 + MINUS_3DB * 0.05) * scale;
+        assert!((stereo.samples[0] - left).abs() < 1e-6);
+        assert!((stereo.samples[1] - right).abs() < 1e-6);
+
+        // A full-scale 5.1 frame can't clip after the downmix
+        let loud = AudioData::new(vec![1.0; 6], 48_000, 6).to_layout(ChannelLayout::Stereo).unwrap();
+        assert!(loud.samples.iter().all(|s| s.abs() <= 1.0 + 1e-6));
+
+        let mono = stereo.to_layout(ChannelLayout::Mono).unwrap();
+        assert!((mono.samples[0] - (left + right) / 2.0).abs() < 1e-6);
+        assert_eq!(mono.to_layout(ChannelLayout::Stereo).unwrap().samples, vec![mono.samples[0]; 2]);
+
+        let swap = ChannelMatrix::new(vec![vec![0.0, 1.0], vec![1.0, 0.0]]).unwrap();
+        assert_eq!(AudioData::new(vec![0.1, 0.9], 8_000, 2).remix(&swap).unwrap().samples, vec![0.9, 0.1]);
+        assert!(surround.remix(&swap).is_err());
+        assert!(ChannelMatrix::new(vec![vec![1.0], vec![1.0, 0.0]]).is_err());
+    }
+
+    #[test]
+    fn test_sample_formats_and_mixing_mismatched_sources() {
+        let audio = AudioData::new(vec![-1.0, -0.5, 0.0, 0.5, 0.999], 8_000, 1);
+        assert_eq!(audio.to_i16(), vec![-32_768, -16_384, 0, 16_384, 32_735]);
+        assert_eq!(AudioData::from_i16(&audio.to_i16(), 8_000, 1).to_i16(), audio.to_i16());
+        let packed = audio.to_i32(24).unwrap();
+        assert_eq!(packed[0], -8_388_608);
+        assert_eq!(AudioData::from_i32(&packed, 24, 8_000, 1).unwrap().to_i32(24).unwrap(), packed);
+        assert_eq!(audio.to_u8(), vec![0, 64, 128, 192, 255]);
+        assert!(audio.to_i32(4).is_err());
+
+        // A 48 kHz mono mic mixed with 44.1 kHz stereo system audio
+        let mic = sine(440.0, 48_000, 4_800, 1);
+        let system = sine(660.0, 44_100, 4_410, 2);
+        let mixed = mic.mix(&system, 0.5).unwrap();
+        assert_eq!((mixed.sample_rate, mixed.channels), (48_000, 1));
+        assert_eq!(mixed.frame_count(), 4_800);
+        let conformed = system.conform(48_000, 1).unwrap();
+        let expected = mic.samples[2_000] + 0.5 * conformed.samples[2_000];
+        assert!((mixed.samples[2_000] - expected).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,407 @@
+//! Spectral-gating noise reduction for audio data
+//!
+//! Steady background noise such as laptop fans has a stable spectrum. A
+//! noise profile records the mean and spread of each frequency bin's level
+//! in dB over a stretch of noise only, learned either from a range the
+//! user picks or from the quietest frames of the recording. The denoiser then
+//! runs a short-time Fourier transform over the audio and attenuates every
+//! bin that does not rise clearly above the profile.
+//!
+//! Hard per-bin gating causes "musical noise", isolated bins flickering on
+//! and off. The gain mask is therefore smoothed across neighbouring bins,
+//! and over time it falls slowly but rises at once, so speech onsets survive.
+
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+use serde::{Deserialize, Serialize};
+
+use crate::audio::AudioData;
+use crate::error::{Aegnt27Error, AudioError, Result};
+
+/// Frames advance by a quarter of the FFT size
+const OVERLAP: usize = 4;
+/// Share of the quietest frames taken as noise when no range is given
+const SILENCE_SHARE: f64 = 0.1;
+/// Fewest frames a profile is learned from
+const MIN_PROFILE_FRAMES: usize = 4;
+
+/// Settings of the spectral gate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DenoiseConfig {
+    /// Attenuation of gated bins, dB
+    pub reduction_db: f32,
+    /// Standard deviations above the noise mean a bin must reach to pass
+    pub threshold_sigma: f32,
+    /// How slowly gains fall back after a bin closes, 0 (at once) to 1 (never)
+    pub time_smoothing: f32,
+    /// Bins on either side averaged into each gain
+    pub frequency_smoothing: usize,
+    /// Samples per analysis frame, a power of two
+    pub fft_size: usize,
+}
+
+impl Default for DenoiseConfig {
+    fn default() -> Self {
+        Self {
+            reduction_db: 12.0,
+            threshold_sigma: 2.0,
+            time_smoothing: 0.6,
+            frequency_smoothing: 2,
+            fft_size: 2048,
+        }
+    }
+}
+
+/// Spectrum of the background noise of a recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoiseProfile {
+    /// Rate of the audio the profile was learned from
+    pub sample_rate: u32,
+    /// Frame length the profile was learned with
+    pub fft_size: usize,
+    /// Mean level of each bin, dB
+    pub mean_db: Vec<f32>,
+    /// Standard deviation of each bin's level, dB
+    pub deviation_db: Vec<f32>,
+}
+
+impl NoiseProfile {
+    /// Learns the profile from a stretch of the audio holding only noise
+    pub fn from_range(audio: &AudioData, range: Range<Duration>, fft_size: usize) -> Result<Self> {
+        let stft = Stft::new(fft_size)?;
+        let mono = audio.to_mono();
+        let rate = audio.sample_rate as f64;
+        let start = (range.start.as_secs_f64() * rate) as usize;
+        let end = ((range.end.as_secs_f64() * rate) as usize).min(mono.samples.len());
+        if start >= end {
+            return Err(invalid_format(format!("Noise range {:?} is outside the audio", range)));
+        }
+
+        let spectra = stft.magnitudes(&mono.samples[start..end]);
+        if spectra.len() < MIN_PROFILE_FRAMES {
+            return Err(invalid_format(format!(
+                "Noise range {:?} is too short, it needs at least {} frames of {} samples",
+                range, MIN_PROFILE_FRAMES, fft_size
+            )));
+        }
+        Ok(Self::from_spectra(audio.sample_rate, fft_size, &spectra))
+    }
+
+    /// Learns the profile from the quietest frames of the audio, which in a
+    /// narration are the pauses between sentences
+    pub fn from_silence(audio: &AudioData, fft_size: usize) -> Result<Self> {
+        let stft = Stft::new(fft_size)?;
+        let spectra = stft.magnitudes(&audio.to_mono().samples);
+        if spectra.len() < MIN_PROFILE_FRAMES {
+            return Err(invalid_format("Audio is too short to learn a noise profile from"));
+        }
+
+        let mut by_energy: Vec<(f32, usize)> = spectra
+            .iter()
+            .enumerate()
+            .map(|(index, spectrum)| (spectrum.iter().map(|m| m * m).sum(), index))
+            .collect();
+        by_energy.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let count = ((spectra.len() as f64 * SILENCE_SHARE) as usize).max(MIN_PROFILE_FRAMES);
+        let quietest: Vec<Vec<f32>> = by_energy[..count].iter().map(|&(_, index)| spectra[index].clone()).collect();
+
+        Ok(Self::from_spectra(audio.sample_rate, fft_size, &quietest))
+    }
+
+    fn from_spectra(sample_rate: u32, fft_size: usize, spectra: &[Vec<f32>]) -> Self {
+        let bins = fft_size / 2 + 1;
+        let count = spectra.len() as f32;
+        let levels: Vec<Vec<f32>> = spectra.iter().map(|s| s.iter().map(|&m| to_db(m)).collect()).collect();
+        let mean_db: Vec<f32> = (0..bins).map(|bin| levels.iter().map(|l| l[bin]).sum::<f32>() / count).collect();
+        let deviation_db = (0..bins)
+            .map(|bin| {
+                let variance = levels.iter().map(|l| (l[bin] - mean_db[bin]).powi(2)).sum::<f32>() / count;
+                variance.sqrt()
+            })
+            .collect();
+
+        Self {
+            sample_rate,
+            fft_size,
+            mean_db,
+            deviation_db,
+        }
+    }
+}
+
+/// Removes steady background noise described by a profile
+#[derive(Debug, Clone)]
+pub struct SpectralDenoiser {
+    config: DenoiseConfig,
+    profile: NoiseProfile,
+}
+
+impl SpectralDenoiser {
+    /// Creates a denoiser for noise matching the profile
+    pub fn new(config: DenoiseConfig, profile: NoiseProfile) -> Result<Self> {
+        if profile.fft_size != config.fft_size {
+            return Err(invalid_format(format!(
+                "Noise profile was learned with {}-sample frames, not {}",
+                profile.fft_size, config.fft_size
+            )));
+        }
+        Stft::new(config.fft_size)?;
+        Ok(Self { config, profile })
+    }
+
+    /// The profile being removed
+    pub fn profile(&self) -> &NoiseProfile {
+        &self.profile
+    }
+
+    /// Gates every channel of the audio against the profile; the output's
+    /// loudness is cleared, as the input's measurement no longer holds
+    pub fn process(&self, audio: &AudioData) -> Result<AudioData> {
+        if audio.sample_rate != self.profile.sample_rate {
+            return Err(invalid_format(format!(
+                "Noise profile is for {} Hz audio, not {} Hz",
+                self.profile.sample_rate, audio.sample_rate
+            )));
+        }
+
+        let stft = Stft::new(self.config.fft_size)?;
+        let channels = audio.channels.max(1) as usize;
+        let mut output = audio.clone();
+        for channel in 0..channels {
+            let samples: Vec<f32> = audio.samples.iter().skip(channel).step_by(channels).copied().collect();
+            let cleaned = self.process_channel(&stft, &samples);
+            for (index, sample) in cleaned.into_iter().enumerate() {
+                output.samples[index * channels + channel] = sample;
+            }
+        }
+        output.metadata.loudness = None;
+        Ok(output)
+    }
+
+    fn process_channel(&self, stft: &Stft, samples: &[f32]) -> Vec<f32> {
+        let floor = 10f32.powf(-self.config.reduction_db.max(0.0) / 20.0);
+        let smoothing = self.config.time_smoothing.clamp(0.0, 1.0);
+        let bins = self.profile.mean_db.len();
+        let reach = self.config.frequency_smoothing;
+        let thresholds: Vec<f32> = self
+            .profile
+            .mean_db
+            .iter()
+            .zip(&self.profile.deviation_db)
+            .map(|(mean, deviation)| to_magnitude(mean + self.config.threshold_sigma * deviation))
+            .collect();
+        let mut previous = vec![1.0f32; bins];
+
+        stft.filter(samples, |spectrum| {
+            let gates: Vec<f32> = (0..bins)
+                .map(|bin| if spectrum[bin].norm() > thresholds[bin] { 1.0 } else { floor })
+                .collect();
+
+            for bin in 0..bins {
+                let neighbours = &gates[bin.saturating_sub(reach)..(bin + reach + 1).min(bins)];
+                let target = neighbours.iter().sum::<f32>() / neighbours.len() as f32;
+                let gain = if target >= previous[bin] {
+                    target
+                } else {
+                    previous[bin] * smoothing + target * (1.0 - smoothing)
+                };
+                previous[bin] = gain;
+                spectrum[bin] *= gain;
+            }
+            // Keep the spectrum Hermitian so the output stays real
+            let size = spectrum.len();
+            for bin in 1..size - bins + 1 {
+                spectrum[size - bin] = spectrum[bin].conj();
+            }
+        })
+    }
+}
+
+/// Short-time Fourier transform with a periodic Hann window and 75% overlap
+struct Stft {
+    size: usize,
+    window: Vec<f32>,
+    forward: Arc<dyn Fft<f32>>,
+    inverse: Arc<dyn Fft<f32>>,
+}
+
+impl Stft {
+    fn new(size: usize) -> Result<Self> {
+        if size < 64 || !size.is_power_of_two() {
+            return Err(invalid_format(format!("FFT size {} must be a power of two of at least 64", size)));
+        }
+        let window = (0..size)
+            .map(|n| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * n as f32 / size as f32).cos())
+            .collect();
+        let mut planner = FftPlanner::new();
+        Ok(Self {
+            size,
+            window,
+            forward: planner.plan_fft_forward(size),
+            inverse: planner.plan_fft_inverse(size),
+        })
+    }
+
+    fn hop(&self) -> usize {
+        self.size / OVERLAP
+    }
+
+    fn spectrum(&self, frame: &[f32]) -> Vec<Complex<f32>> {
+        let mut buffer: Vec<Complex<f32>> =
+            frame.iter().zip(&self.window).map(|(s, w)| Complex::new(s * w, 0.0)).collect();
+        self.forward.process(&mut buffer);
+        buffer
+    }
+
+    /// Magnitudes of the non-negative frequency bins of each whole frame
+    fn magnitudes(&self, samples: &[f32]) -> Vec<Vec<f32>> {
+        if samples.len() < self.size {
+            return Vec::new();
+        }
+        (0..=samples.len() - self.size)
+            .step_by(self.hop())
+            .map(|start| {
+                let spectrum = self.spectrum(&samples[start..start + self.size]);
+                spectrum[..self.size / 2 + 1].iter().map(|c| c.norm()).collect()
+            })
+            .collect()
+    }
+
+    /// Runs `modify` on every frame's spectrum and overlap-adds the result
+    /// back to a signal of the same length
+    fn filter(&self, samples: &[f32], mut modify: impl FnMut(&mut [Complex<f32>])) -> Vec<f32> {
+        // Pad a whole frame on both sides so every output sample gets full overlap
+        let mut padded = vec![0.0; self.size];
+        padded.extend_from_slice(samples);
+        padded.resize(samples.len() + 2 * self.size, 0.0);
+
+        // The squared Hann window overlapped at a quarter hop sums to 3/2
+        let scale = 1.0 / (self.size as f32 * 1.5);
+        let mut output = vec![0.0f32; padded.len()];
+        for start in (0..=padded.len() - self.size).step_by(self.hop()) {
+            let mut spectrum = self.spectrum(&padded[start..start + self.size]);
+            modify(&mut spectrum);
+            self.inverse.process(&mut spectrum);
+            for (n, value) in spectrum.iter().enumerate() {
+                output[start + n] += value.re * self.window[n] * scale;
+            }
+        }
+
+        output.drain(..self.size);
+        output.truncate(samples.len());
+        output
+    }
+}
+
+/// Levels are floored well below any real noise so digital silence stays finite
+fn to_db(magnitude: f32) -> f32 {
+    20.0 * magnitude.max(1e-10).log10()
+}
+
+fn to_magnitude(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+fn invalid_format(message: impl Into<String>) -> Aegnt27Error {
+    Aegnt27Error::Audio(AudioError::InvalidFormat(message.into()))
+}
+
+impl AudioData {
+    /// Learns the noise from the quietest frames and gates it out
+    pub fn denoise(&self, config: &DenoiseConfig) -> Result<AudioData> {
+        let profile = NoiseProfile::from_silence(self, config.fft_size)?;
+        SpectralDenoiser::new(config.clone(), profile)?.process(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic white noise in [-amplitude, amplitude]
+    fn noise(frames: usize, amplitude: f32, seed: u64) -> Vec<f32> {
+        let mut state = seed;
+        (0..frames)
+            .map(|_| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                amplitude * ((state >> 40) as f32 / (1u64 << 23) as f32 - 1.0)
+            })
+            .collect()
+    }
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    /// Noise throughout, with a tone in the middle second of three
+    fn narration() -> AudioData {
+        let mut samples = noise(48_000 * 3, 0.02, 7);
+        for (n, sample) in samples[48_000..96_000].iter_mut().enumerate() {
+            *sample += 0.3 * (2.0 * std::f32::consts::PI * 440.0 * n as f32 / 48_000.0).sin();
+        }
+        AudioData::new(samples, 48_000, 1)
+    }
+
+    #[test]
+    fn test_gates_noise_learned_from_silence_and_keeps_tone() {
+        let mut audio = narration();
+        audio.analyze_loudness().unwrap();
+        let config = DenoiseConfig::default();
+        let cleaned = audio.denoise(&config).unwrap();
+        assert_eq!(cleaned.samples.len(), audio.samples.len());
+        assert!(cleaned.metadata.loudness.is_none());
+
+        // Noise-only stretch drops by most of the reduction
+        let before = rms(&audio.samples[2_000..44_000]);
+        let after = rms(&cleaned.samples[2_000..44_000]);
+        let reduction = 20.0 * (before / after).log10();
+        assert!(reduction > config.reduction_db - 3.0, "only {} dB", reduction);
+
+        // Tone level is kept within a dB
+        let before = rms(&audio.samples[52_000..92_000]);
+        let after = rms(&cleaned.samples[52_000..92_000]);
+        assert!((20.0 * (before / after).log10()).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_profile_from_range_is_checked() {
+        let audio = narration();
+        let profile =
+            NoiseProfile::from_range(&audio, Duration::from_millis(2_100)..Duration::from_secs(3), 2048).unwrap();
+        assert_eq!(profile.mean_db.len(), 1025);
+        assert!(profile.mean_db.iter().zip(&profile.deviation_db).all(|(m, d)| m.is_finite() && *d > 0.0));
+
+        assert!(NoiseProfile::from_range(&audio, Duration::from_secs(4)..Duration::from_secs(5), 2048).is_err());
+        assert!(NoiseProfile::from_range(&audio, Duration::ZERO..Duration::from_millis(20), 2048).is_err());
+        assert!(NoiseProfile::from_range(&audio, Duration::ZERO..Duration::from_secs(1), 1000).is_err());
+
+        let config = DenoiseConfig {
+            fft_size: 1024,
+            ..DenoiseConfig::default()
+        };
+        assert!(SpectralDenoiser::new(config, profile.clone()).is_err());
+
+        let resampled = AudioData::new(audio.samples.clone(), 44_100, 1);
+        let denoiser = SpectralDenoiser::new(DenoiseConfig::default(), profile).unwrap();
+        assert!(denoiser.process(&resampled).is_err());
+    }
+
+    #[test]
+    fn test_zero_reduction_reconstructs_the_input() {
+        let mono = narration();
+        let samples = mono.samples.iter().zip(noise(mono.samples.len(), 0.1, 11)).flat_map(|(a, b)| [*a, b]).collect();
+        let stereo = AudioData::new(samples, 48_000, 2);
+        let config = DenoiseConfig {
+            reduction_db: 0.0,
+            ..DenoiseConfig::default()
+        };
+
+        let output = stereo.denoise(&config).unwrap();
+        let error = stereo.samples.iter().zip(&output.samples).fold(0.0f32, |m, (a, b)| m.max((a - b).abs()));
+        assert!(error < 1e-4, "max error {}", error);
+    }
+}
@@ -0,0 +1,821 @@
+//! WAV and FLAC import/export for audio data
+//!
+//! Narration is often recorded or cleaned up in other tools, so `AudioData`
+//! can be read from and written to WAV (16/24/32-bit integer and 32-bit
+//! float, through `hound`) and FLAC (16/24-bit; decoded with `claxon`,
+//! encoded here with FLAC's fixed predictors and Rice-coded residuals).
+//!
+//! Long recordings don't have to fit in memory: the stream readers hand out
+//! blocks of frames and the stream writers accept audio a piece at a time,
+//! filling in the stream length when they're finalized.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::audio::AudioData;
+use crate::error::{Aegnt27Error, AudioError, Result};
+
+/// Frames per FLAC frame; a common choice that suits 44.1 and 48 kHz audio
+const FLAC_BLOCK_SIZE: usize = 4096;
+const FLAC_MAX_FIXED_ORDER: usize = 4;
+const FLAC_MAX_PARTITION_ORDER: u32 = 8;
+const STREAMINFO_LENGTH: u32 = 34;
+
+/// How samples are stored in an exported file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SampleEncoding {
+    /// 16-bit signed integer
+    Int16,
+    /// 24-bit signed integer
+    Int24,
+    /// 32-bit signed integer (WAV only)
+    Int32,
+    /// 32-bit IEEE float (WAV only)
+    Float32,
+}
+
+impl SampleEncoding {
+    /// Bits per sample
+    pub fn bits(self) -> u16 {
+        match self {
+            SampleEncoding::Int16 => 16,
+            SampleEncoding::Int24 => 24,
+            SampleEncoding::Int32 | SampleEncoding::Float32 => 32,
+        }
+    }
+}
+
+fn invalid_format(message: impl Into<String>) -> Aegnt27Error {
+    Aegnt27Error::Audio(AudioError::InvalidFormat(message.into()))
+}
+
+fn unsupported(message: impl Into<String>) -> Aegnt27Error {
+    Aegnt27Error::Audio(AudioError::UnsupportedCodec(message.into()))
+}
+
+fn wav_error(error: hound::Error) -> Aegnt27Error {
+    match error {
+        hound::Error::IoError(e) => e.into(),
+        other => invalid_format(format!("WAV: {}", other)),
+    }
+}
+
+fn flac_error(error: claxon::Error) -> Aegnt27Error {
+    match error {
+        claxon::Error::IoError(e) => e.into(),
+        other => invalid_format(format!("FLAC: {}", other)),
+    }
+}
+
+/// Scale a normalised sample to a signed integer of `bits` bits
+pub(crate) fn quantize(sample: f32, bits: u32) -> i32 {
+    let full_scale = (1i64 << (bits - 1)) as f64;
+    let value = (sample.clamp(-1.0, 1.0) as f64 * full_scale).round();
+    value.clamp(-full_scale, full_scale - 1.0) as i32
+}
+
+pub(crate) fn dequantize(sample: i32, bits: u32) -> f32 {
+    (sample as f64 / (1i64 << (bits - 1)) as f64) as f32
+}
+
+impl AudioData {
+    /// Reads a whole WAV stream
+    pub fn from_wav<R: Read>(reader: R) -> Result<Self> {
+        WavStreamReader::new(reader)?.read_to_end()
+    }
+
+    /// Writes the audio as a WAV stream
+    pub fn to_wav<W: Write + Seek>(&self, writer: W, encoding: SampleEncoding) -> Result<()> {
+        let mut stream = WavStreamWriter::new(writer, self.sample_rate, self.channels, encoding)?;
+        stream.write(self)?;
+        stream.finalize()
+    }
+
+    /// Reads a whole FLAC stream
+    pub fn from_flac<R: Read>(reader: R) -> Result<Self> {
+        FlacStreamReader::new(reader)?.read_to_end()
+    }
+
+    /// Writes the audio as a FLAC stream (16 or 24-bit)
+    pub fn to_flac<W: Write + Seek>(&self, writer: W, encoding: SampleEncoding) -> Result<()> {
+        let mut stream = FlacStreamWriter::new(writer, self.sample_rate, self.channels, encoding)?;
+        stream.write(self)?;
+        stream.finalize().map(|_| ())
+    }
+
+    /// Loads a `.wav` or `.flac` file
+    pub fn load(path: &Path) -> Result<Self> {
+        let file = BufReader::new(File::open(path)?);
+        match extension(path).as_str() {
+            "wav" | "wave" => Self::from_wav(file),
+            "flac" => Self::from_flac(file),
+            other => Err(unsupported(format!("Unknown audio file type '.{}'", other))),
+        }
+    }
+
+    /// Saves to a `.wav` or `.flac` file
+    pub fn save(&self, path: &Path, encoding: SampleEncoding) -> Result<()> {
+        let kind = extension(path);
+        if !matches!(kind.as_str(), "wav" | "wave" | "flac") {
+            return Err(unsupported(format!("Unknown audio file type '.{}'", kind)));
+        }
+        let file = BufWriter::new(File::create(path)?);
+        if kind == "flac" {
+            self.to_flac(file, encoding)
+        } else {
+            self.to_wav(file, encoding)
+        }
+    }
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+}
+
+/// Reads a WAV stream a block of frames at a time
+pub struct WavStreamReader<R: Read> {
+    reader: hound::WavReader<R>,
+    spec: hound::WavSpec,
+}
+
+impl WavStreamReader<BufReader<File>> {
+    /// Opens a WAV file
+    pub fn open(path: &Path) -> Result<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> WavStreamReader<R> {
+    /// Reads the WAV header
+    pub fn new(reader: R) -> Result<Self> {
+        let reader = hound::WavReader::new(reader).map_err(wav_error)?;
+        let spec = reader.spec();
+        if spec.channels == 0 || spec.channels > u8::MAX as u16 {
+            return Err(invalid_format(format!("WAV with {} channels", spec.channels)));
+        }
+        Ok(Self { reader, spec })
+    }
+
+    /// Sample rate in Hz
+    pub fn sample_rate(&self) -> u32 {
+        self.spec.sample_rate
+    }
+
+    /// Number of channels
+    pub fn channels(&self) -> u8 {
+        self.spec.channels as u8
+    }
+
+    /// Frames in the whole stream
+    pub fn total_frames(&self) -> u64 {
+        self.reader.duration() as u64
+    }
+
+    /// Reads up to `max_frames` frames, `None` at the end of the stream
+    pub fn read_block(&mut self, max_frames: usize) -> Result<Option<AudioData>> {
+        let wanted = max_frames.max(1) * self.spec.channels as usize;
+        let samples: Vec<f32> = match self.spec.sample_format {
+            hound::SampleFormat::Float => self
+                .reader
+                .samples::<f32>()
+                .take(wanted)
+                .collect::<std::result::Result<_, _>>()
+                .map_err(wav_error)?,
+            hound::SampleFormat::Int => {
+                let bits = self.spec.bits_per_sample as u32;
+                self.reader
+                    .samples::<i32>()
+                    .take(wanted)
+                    .map(|s| s.map(|s| dequantize(s, bits)))
+                    .collect::<std::result::Result<_, _>>()
+                    .map_err(wav_error)?
+            }
+        };
+
+        if samples.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(AudioData::new(samples, self.sample_rate(), self.channels())))
+    }
+
+    /// Reads the rest of the stream into one buffer
+    pub fn read_to_end(&mut self) -> Result<AudioData> {
+        let remaining = self.reader.len() as usize / self.spec.channels as usize;
+        Ok(self
+            .read_block(remaining)?
+            .unwrap_or_else(|| AudioData::new(Vec::new(), self.sample_rate(), self.channels())))
+    }
+}
+
+/// Writes a WAV stream a piece at a time
+pub struct WavStreamWriter<W: Write + Seek> {
+    writer: hound::WavWriter<W>,
+    encoding: SampleEncoding,
+    sample_rate: u32,
+    channels: u8,
+}
+
+impl WavStreamWriter<BufWriter<File>> {
+    /// Creates a WAV file
+    pub fn create(path: &Path, sample_rate: u32, channels: u8, encoding: SampleEncoding) -> Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?), sample_rate, channels, encoding)
+    }
+}
+
+impl<W: Write + Seek> WavStreamWriter<W> {
+    /// Writes the WAV header; the lengths are filled in by `finalize`
+    pub fn new(writer: W, sample_rate: u32, channels: u8, encoding: SampleEncoding) -> Result<Self> {
+        if channels == 0 {
+            return Err(invalid_format("Channel count must be greater than 0"));
+        }
+        let spec = hound::WavSpec {
+            channels: channels as u16,
+            sample_rate,
+            bits_per_sample: encoding.bits(),
+            sample_format: match encoding {
+                SampleEncoding::Float32 => hound::SampleFormat::Float,
+                _ => hound::SampleFormat::Int,
+            },
+        };
+        let writer = hound::WavWriter::new(writer, spec).map_err(wav_error)?;
+        Ok(Self {
+            writer,
+            encoding,
+            sample_rate,
+            channels,
+        })
+    }
+
+    /// Appends audio, which must match the stream's rate and channel count
+    pub fn write(&mut self, audio: &AudioData) -> Result<()> {
+        check_format(audio, self.sample_rate, self.channels)?;
+        for &sample in &audio.samples {
+            match self.encoding {
+                SampleEncoding::Float32 => self.writer.write_sample(sample),
+                SampleEncoding::Int16 => self.writer.write_sample(quantize(sample, 16) as i16),
+                SampleEncoding::Int24 => self.writer.write_sample(quantize(sample, 24)),
+                SampleEncoding::Int32 => self.writer.write_sample(quantize(sample, 32)),
+            }
+            .map_err(wav_error)?;
+        }
+        Ok(())
+    }
+
+    /// Fills in the header lengths and flushes
+    pub fn finalize(self) -> Result<()> {
+        self.writer.finalize().map_err(wav_error)
+    }
+}
+
+fn check_format(audio: &AudioData, sample_rate: u32, channels: u8) -> Result<()> {
+    if audio.sample_rate != sample_rate || audio.channels != channels {
+        return Err(invalid_format(format!(
+            "Expected {} Hz with {} channels, got {} Hz with {}",
+            sample_rate, channels, audio.sample_rate, audio.channels
+        )));
+    }
+    Ok(())
+}
+
+/// Reads a FLAC stream a block of frames at a time
+pub struct FlacStreamReader<R: Read> {
+    reader: claxon::FlacReader<R>,
+    info: claxon::metadata::StreamInfo,
+    /// Decoded interleaved samples not handed out yet
+    pending: Vec<f32>,
+    buffer: Vec<i32>,
+    finished: bool,
+}
+
+impl FlacStreamReader<BufReader<File>> {
+    /// Opens a FLAC file
+    pub fn open(path: &Path) -> Result<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> FlacStreamReader<R> {
+    /// Reads the FLAC metadata
+    pub fn new(reader: R) -> Result<Self> {
+        let reader = claxon::FlacReader::new(reader).map_err(flac_error)?;
+        let info = reader.streaminfo();
+        if info.channels == 0 || info.channels > 8 {
+            return Err(invalid_format(format!("FLAC with {} channels", info.channels)));
+        }
+        Ok(Self {
+            reader,
+            info,
+            pending: Vec::new(),
+            buffer: Vec::new(),
+            finished: false,
+        })
+    }
+
+    /// Sample rate in Hz
+    pub fn sample_rate(&self) -> u32 {
+        self.info.sample_rate
+    }
+
+    /// Number of channels
+    pub fn channels(&self) -> u8 {
+        self.info.channels as u8
+    }
+
+    /// Frames in the whole stream, if the encoder recorded it
+    pub fn total_frames(&self) -> Option<u64> {
+        self.info.samples
+    }
+
+    /// Reads up to `max_frames` frames, `None` at the end of the stream
+    pub fn read_block(&mut self, max_frames: usize) -> Result<Option<AudioData>> {
+        let channels = self.info.channels as usize;
+        let wanted = max_frames.max(1) * channels;
+
+        while self.pending.len() < wanted && !self.finished {
+            let buffer = std::mem::take(&mut self.buffer);
+            match self.reader.blocks().read_next_or_eof(buffer).map_err(flac_error)? {
+                Some(block) => {
+                    let bits = self.info.bits_per_sample;
+                    for frame in 0..block.duration() {
+                        for channel in 0..block.channels() {
+                            self.pending.push(dequantize(block.sample(channel, frame), bits));
+                        }
+                    }
+                    self.buffer = block.into_buffer();
+                }
+                None => self.finished = true,
+            }
+        }
+
+        if self.pending.is_empty() {
+            return Ok(None);
+        }
+        let take = wanted.min(self.pending.len());
+        let samples: Vec<f32> = self.pending.drain(..take).collect();
+        Ok(Some(AudioData::new(samples, self.sample_rate(), self.channels())))
+    }
+
+    /// Reads the rest of the stream into one buffer
+    pub fn read_to_end(&mut self) -> Result<AudioData> {
+        let mut samples = Vec::new();
+        while let Some(block) = self.read_block(FLAC_BLOCK_SIZE * 16)? {
+            samples.extend_from_slice(&block.samples);
+        }
+        Ok(AudioData::new(samples, self.sample_rate(), self.channels()))
+    }
+}
+
+/// Encodes a FLAC stream a piece at a time
+pub struct FlacStreamWriter<W: Write + Seek> {
+    writer: W,
+    sample_rate: u32,
+    channels: u8,
+    bits: u32,
+    /// Quantized interleaved samples waiting for a full block
+    pending: Vec<i32>,
+    streaminfo_offset: u64,
+    frame_number: u64,
+    total_frames: u64,
+    min_frame_size: u32,
+    max_frame_size: u32,
+}
+
+impl FlacStreamWriter<BufWriter<File>> {
+    /// Creates a FLAC file
+    pub fn create(path: &Path, sample_rate: u32, channels: u8, encoding: SampleEncoding) -> Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?), sample_rate, channels, encoding)
+    }
+}
+
+impl<W: Write + Seek> FlacStreamWriter<W> {
+    /// Writes the stream header; the length is filled in by `finalize`
+    pub fn new(mut writer: W, sample_rate: u32, channels: u8, encoding: SampleEncoding) -> Result<Self> {
+        let bits = match encoding {
+            SampleEncoding::Int16 | SampleEncoding::Int24 => encoding.bits() as u32,
+            other => return Err(unsupported(format!("FLAC can't store {:?} samples", other))),
+        };
+        if channels == 0 || channels > 8 {
+            return Err(invalid_format(format!("FLAC supports 1 to 8 channels, not {}", channels)));
+        }
+        if sample_rate == 0 || sample_rate >= 1 << 20 {
+            return Err(invalid_format(format!("FLAC can't store a {} Hz sample rate", sample_rate)));
+        }
+
+        writer.write_all(b"fLaC")?;
+        // Last-metadata-block flag, block type 0 (STREAMINFO), length
+        writer.write_all(&(0x8000_0000 | STREAMINFO_LENGTH).to_be_bytes())?;
+        let streaminfo_offset = writer.stream_position()?;
+
+        let mut stream = Self {
+            writer,
+            sample_rate,
+            channels,
+            bits,
+            pending: Vec::new(),
+            streaminfo_offset,
+            frame_number: 0,
+            total_frames: 0,
+            min_frame_size: 0,
+            max_frame_size: 0,
+        };
+        let streaminfo = stream.streaminfo();
+        stream.writer.write_all(&streaminfo)?;
+        Ok(stream)
+    }
+
+    /// Appends audio, which must match the stream's rate and channel count
+    pub fn write(&mut self, audio: &AudioData) -> Result<()> {
+        check_format(audio, self.sample_rate, self.channels)?;
+        self.pending.extend(audio.samples.iter().map(|&s| quantize(s, self.bits)));
+
+        let block_len = FLAC_BLOCK_SIZE * self.channels as usize;
+        let mut start = 0;
+        while self.pending.len() - start >= block_len {
+            let block = &self.pending[start..start + block_len];
+            let frame = encode_frame(block, self.channels, self.bits, self.frame_number);
+            self.write_frame(&frame, FLAC_BLOCK_SIZE)?;
+            start += block_len;
+        }
+        self.pending.drain(..start);
+        Ok(())
+    }
+
+    /// Encodes the last partial block, fills in the stream length and
+    /// returns the underlying writer
+    pub fn finalize(mut self) -> Result<W> {
+        if !self.pending.is_empty() {
+            let frames = self.pending.len() / self.channels as usize;
+            let frame = encode_frame(&self.pending, self.channels, self.bits, self.frame_number);
+            self.write_frame(&frame, frames)?;
+            self.pending.clear();
+        }
+
+        let end = self.writer.stream_position()?;
+        self.writer.seek(SeekFrom::Start(self.streaminfo_offset))?;
+        let streaminfo = self.streaminfo();
+        self.writer.write_all(&streaminfo)?;
+        self.writer.seek(SeekFrom::Start(end))?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write_frame(&mut self, frame: &[u8], frames: usize) -> Result<()> {
+        self.writer.write_all(frame)?;
+        let size = frame.len() as u32;
+        self.min_frame_size = if self.frame_number == 0 { size } else { self.min_frame_size.min(size) };
+        self.max_frame_size = self.max_frame_size.max(size);
+        self.frame_number += 1;
+        self.total_frames += frames as u64;
+        Ok(())
+    }
+
+    fn streaminfo(&self) -> [u8; STREAMINFO_LENGTH as usize] {
+        let mut info = [0u8; STREAMINFO_LENGTH as usize];
+        info[0..2].copy_from_slice(&(FLAC_BLOCK_SIZE as u16).to_be_bytes());
+        info[2..4].copy_from_slice(&(FLAC_BLOCK_SIZE as u16).to_be_bytes());
+        info[4..7].copy_from_slice(&self.min_frame_size.to_be_bytes()[1..]);
+        info[7..10].copy_from_slice(&self.max_frame_size.to_be_bytes()[1..]);
+        let packed = (self.sample_rate as u64) << 44
+            | ((self.channels as u64 - 1) << 41)
+            | ((self.bits as u64 - 1) << 36)
+            | (self.total_frames & 0xF_FFFF_FFFF);
+        info[10..18].copy_from_slice(&packed.to_be_bytes());
+        // The MD5 of the audio is optional; all zeros means it wasn't computed
+        info
+    }
+}
+
+/// Packs values MSB-first into bytes
+struct BitWriter {
+    bytes: Vec<u8>,
+    accumulator: u64,
+    used: u32,
+}
+
+impl BitWriter {
+    fn new(bytes: Vec<u8>) -> Self {
+        Self {
+            bytes,
+            accumulator: 0,
+            used: 0,
+        }
+    }
+
+    /// Writes the low `bits` bits of `value`, at most 32
+    fn write(&mut self, value: u64, bits: u32) {
+        if bits == 0 {
+            return;
+        }
+        self.accumulator = (self.accumulator << bits) | (value & ((1u64 << bits) - 1));
+        self.used += bits;
+        while self.used >= 8 {
+            self.used -= 8;
+            self.bytes.push((self.accumulator >> self.used) as u8);
+        }
+        self.accumulator &= (1u64 << self.used) - 1;
+    }
+
+    fn write_signed(&mut self, value: i64, bits: u32) {
+        self.write(value as u64, bits);
+    }
+
+    /// `quotient` zeros followed by a one
+    fn write_unary(&mut self, mut quotient: u64) {
+        while quotient >= 32 {
+            self.write(0, 32);
+            quotient -= 32;
+        }
+        self.write(1, quotient as u32 + 1);
+    }
+
+    fn into_bytes(mut self) -> Vec<u8> {
+        if self.used > 0 {
+            self.write(0, 8 - self.used);
+        }
+        self.bytes
+    }
+}
+
+fn crc8(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |mut crc, &byte| {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 { (crc << 1) ^ 0x07 } else { crc << 1 };
+        }
+        crc
+    })
+}
+
+fn crc16(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0u16, |mut crc, &byte| {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x8005 } else { crc << 1 };
+        }
+        crc
+    })
+}
+
+/// FLAC's UTF-8-style variable-length integer, used for frame numbers
+fn utf8_number(value: u64) -> Vec<u8> {
+    if value < 0x80 {
+        return vec![value as u8];
+    }
+    let count: u32 = match value {
+        0..=0x7FF => 2,
+        0x800..=0xFFFF => 3,
+        0x1_0000..=0x1F_FFFF => 4,
+        0x20_0000..=0x3FF_FFFF => 5,
+        _ => 6,
+    };
+    let mut bytes = vec![(0xFFu8 << (8 - count)) | (value >> (6 * (count - 1))) as u8];
+    for index in (0..count - 1).rev() {
+        bytes.push(0x80 | ((value >> (6 * index)) & 0x3F) as u8);
+    }
+    bytes
+}
+
+/// Encode one frame from interleaved samples, each channel independently
+fn encode_frame(interleaved: &[i32], channels: u8, bits: u32, frame_number: u64) -> Vec<u8> {
+    let channels = channels as usize;
+    let block_size = interleaved.len() / channels;
+
+    let mut header = vec![0xFF, 0xF8];
+    // Block size stored as a 16-bit value after the frame number; sample rate from STREAMINFO
+    header.push(0b0111_0000);
+    let size_code = if bits == 24 { 0b110 } else { 0b100 };
+    header.push(((channels as u8 - 1) << 4) | (size_code << 1));
+    header.extend(utf8_number(frame_number));
+    header.extend_from_slice(&(block_size as u16 - 1).to_be_bytes());
+    header.push(crc8(&header));
+
+    let mut writer = BitWriter::new(header);
+    let mut channel = Vec::with_capacity(block_size);
+    for index in 0..channels {
+        channel.clear();
+        channel.extend(interleaved.iter().skip(index).step_by(channels).map(|&s| s as i64));
+        encode_subframe(&mut writer, &channel, bits);
+    }
+
+    let mut frame = writer.into_bytes();
+    let crc = crc16(&frame);
+    frame.extend_from_slice(&crc.to_be_bytes());
+    frame
+}
+
+/// Residual of FLAC's fixed polynomial predictor of the given order, zigzag-folded
+fn fixed_residual(samples: &[i64], order: usize) -> Vec<u64> {
+    samples
+        .windows(order + 1)
+        .map(|w| {
+            let residual = match order {
+                0 => w[0],
+                1 => w[1] - w[0],
+                2 => w[2] - 2 * w[1] + w[0],
+                3 => w[3] - 3 * w[2] + 3 * w[1] - w[0],
+                _ => w[4] - 4 * w[3] + 6 * w[2] - 4 * w[1] + w[0],
+            };
+            ((residual << 1) ^ (residual >> 63)) as u64
+        })
+        .collect()
+}
+
+/// Best Rice parameter and its estimated cost in bits for `count` values summing to `sum`
+fn rice_cost(sum: u64, count: usize) -> (u32, u64) {
+    (0..=30u32)
+        .map(|k| (k, count as u64 * (k as u64 + 1) + (sum >> k)))
+        .min_by_key(|&(_, bits)| bits)
+        .unwrap_or((0, u64::MAX))
+}
+
+/// Chosen residual partitioning: order and one Rice parameter per partition
+struct Partitioning {
+    order: u32,
+    parameters: Vec<u32>,
+    bits: u64,
+}
+
+fn partition_residual(residual: &[u64], block_size: usize, predictor_order: usize) -> Partitioning {
+    let mut best: Option<Partitioning> = None;
+    for order in 0..=FLAC_MAX_PARTITION_ORDER {
+        let partitions = 1usize << order;
+        if !block_size.is_multiple_of(partitions) || block_size / partitions <= predictor_order {
+            break;
+        }
+        let length = block_size / partitions;
+
+        let mut parameters = Vec::with_capacity(partitions);
+        let mut bits = 0;
+        let mut start = 0;
+        for partition in 0..partitions {
+            let count = if partition == 0 { length - predictor_order } else { length };
+            let sum = residual[start..start + count].iter().sum();
+            let (parameter, cost) = rice_cost(sum, count);
+            parameters.push(parameter);
+            bits += cost + 5;
+            start += count;
+        }
+        if best.as_ref().is_none_or(|b| bits < b.bits) {
+            best = Some(Partitioning { order, parameters, bits });
+        }
+    }
+    best.unwrap_or(Partitioning {
+        order: 0,
+        parameters: vec![0],
+        bits: u64::MAX,
+    })
+}
+
+fn encode_subframe(writer: &mut BitWriter, samples: &[i64], bits: u32) {
+    // Header: zero pad bit, six type bits, "wasted bits" flag
+    if samples.iter().all(|&s| s == samples[0]) {
+        writer.write(0b0000_0000, 8);
+        writer.write_signed(samples[0], bits);
+        return;
+    }
+
+    let verbatim_bits = samples.len() as u64 * bits as u64;
+    let best = (0..=FLAC_MAX_FIXED_ORDER.min(samples.len() - 1))
+        .map(|order| {
+            let residual = fixed_residual(samples, order);
+            let partitioning = partition_residual(&residual, samples.len(), order);
+            (order, residual, partitioning)
+        })
+        .min_by_key(|(order, _, partitioning)| partitioning.bits.saturating_add(*order as u64 * bits as u64));
+
+    let Some((order, residual, partitioning)) = best.filter(|(_, _, p)| p.bits < verbatim_bits) else {
+        writer.write(0b0000_0010, 8);
+        for &sample in samples {
+            writer.write_signed(sample, bits);
+        }
+        return;
+    };
+
+    writer.write(0b0001_0000 | (order as u64) << 1, 8);
+    for &sample in &samples[..order] {
+        writer.write_signed(sample, bits);
+    }
+
+    // Parameters above 14 need the 5-bit parameter variant
+    let wide = partitioning.parameters.iter().any(|&p| p > 14);
+    writer.write(if wide { 0b01 } else { 0b00 }, 2);
+    writer.write(partitioning.order as u64, 4);
+
+    let length = samples.len() >> partitioning.order;
+    let mut start = 0;
+    for (partition, &parameter) in partitioning.parameters.iter().enumerate() {
+        let count = if partition == 0 { length - order } else { length };
+        writer.write(parameter as u64, if wide { 5 } else { 4 });
+        for &value in &residual[start..start + count] {
+            writer.write_unary(value >> parameter);
+            writer.write(value, parameter);
+        }
+        start += count;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn test_signal(frames: usize, channels: u8) -> AudioData {
+        let mut samples = Vec::with_capacity(frames * channels as usize);
+        for frame in 0..frames {
+            let t = frame as f32 / 48_000.0;
+            for channel in 0..channels {
+                let tone = (2.0 * std::f32::consts::PI * (220.0 + 110.0 * channel as f32) * t).sin() * 0.5;
+                let hiss = (((frame * 7_919 + channel as usize * 104_729) % 1_000) as f32 / 1_000.0 - 0.5) * 0.01;
+                samples.push(tone + hiss);
+            }
+        }
+        AudioData::new(samples, 48_000, channels)
+    }
+
+    #[test]
+    fn test_wav_round_trip_in_every_encoding() {
+        let audio = test_signal(1_000, 2);
+        for (encoding, tolerance) in [
+            (SampleEncoding::Int16, 1.0 / 32_768.0),
+            (SampleEncoding::Int24, 1.0 / 8_388_608.0),
+            (SampleEncoding::Int32, 1e-7),
+            (SampleEncoding::Float32, 0.0),
+        ] {
+            let mut file = Cursor::new(Vec::new());
+            audio.to_wav(&mut file, encoding).unwrap();
+            file.set_position(0);
+
+            let decoded = AudioData::from_wav(file).unwrap();
+            assert_eq!(decoded.sample_rate, 48_000);
+            assert_eq!(decoded.channels, 2);
+            assert_eq!(decoded.samples.len(), audio.samples.len());
+            let error = audio
+                .samples
+                .iter()
+                .zip(&decoded.samples)
+                .map(|(a, b)| (a - b).abs())
+                .fold(0.0, f32::max);
+            assert!(error <= tolerance, "{:?}: error {}", encoding, error);
+        }
+    }
+
+    #[test]
+    fn test_flac_round_trip_is_lossless_after_quantization() {
+        // Two full blocks and a partial one
+        let audio = test_signal(FLAC_BLOCK_SIZE * 2 + 1_234, 2);
+        for encoding in [SampleEncoding::Int16, SampleEncoding::Int24] {
+            let mut file = Cursor::new(Vec::new());
+            audio.to_flac(&mut file, encoding).unwrap();
+            let encoded = file.into_inner();
+            let raw = audio.samples.len() * encoding.bits() as usize / 8;
+            assert!(encoded.len() < raw, "{:?}: {} bytes vs {} raw", encoding, encoded.len(), raw);
+
+            let decoded = AudioData::from_flac(Cursor::new(encoded)).unwrap();
+            assert_eq!(decoded.samples.len(), audio.samples.len());
+            let bits = encoding.bits() as u32;
+            for (original, decoded) in audio.samples.iter().zip(&decoded.samples) {
+                assert_eq!(quantize(*original, bits), quantize(*decoded, bits));
+            }
+        }
+
+        let mut file = Cursor::new(Vec::new());
+        assert!(audio.to_flac(&mut file, SampleEncoding::Float32).is_err());
+    }
+
+    #[test]
+    fn test_streaming_writers_and_readers_work_in_blocks() {
+        let audio = test_signal(10_000, 1);
+        let mut flac = FlacStreamWriter::new(Cursor::new(Vec::new()), 48_000, 1, SampleEncoding::Int16).unwrap();
+        let mut wav = WavStreamWriter::new(Cursor::new(Vec::new()), 48_000, 1, SampleEncoding::Int16).unwrap();
+        for piece in audio.samples.chunks(3_000) {
+            let piece = AudioData::new(piece.to_vec(), 48_000, 1);
+            flac.write(&piece).unwrap();
+            wav.write(&piece).unwrap();
+        }
+        assert!(wav.write(&AudioData::new(vec![0.0; 4], 44_100, 1)).is_err());
+        let flac = flac.finalize().unwrap().into_inner();
+
+        let mut reader = FlacStreamReader::new(Cursor::new(flac)).unwrap();
+        assert_eq!(reader.total_frames(), Some(10_000));
+        let mut sizes = Vec::new();
+        while let Some(block) = reader.read_block(4_000).unwrap() {
+            sizes.push(block.frame_count());
+        }
+        assert_eq!(sizes, vec![4_000, 4_000, 2_000]);
+
+        let mut file = Cursor::new(Vec::new());
+        audio.to_wav(&mut file, SampleEncoding::Int16).unwrap();
+        file.set_position(0);
+        let mut reader = WavStreamReader::new(file).unwrap();
+        assert_eq!(reader.total_frames(), 10_000);
+        assert_eq!(reader.read_block(6_000).unwrap().unwrap().frame_count(), 6_000);
+        assert_eq!(reader.read_to_end().unwrap().frame_count(), 4_000);
+        assert!(reader.read_block(1).unwrap().is_none());
+    }
+}
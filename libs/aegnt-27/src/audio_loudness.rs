@@ -0,0 +1,514 @@
+//! EBU R128 loudness measurement and normalisation for audio data
+//!
+//! Loudness follows ITU-R BS.1770-4: each channel is K-weighted (a high
+//! shelf modelling the head plus a high-pass), mean squares are summed with
+//! the surround channels weighted up and the LFE left out, and the result is
+//! expressed in LUFS. Momentary loudness uses 400 ms windows, short-term
+//! 3 s windows, both every 100 ms. Integrated loudness gates out blocks
+//! below -70 LUFS and then those 10 LU below the remaining mean; loudness
+//! range (EBU Tech 3342) is the spread between the 10th and 95th percentile
+//! of gated short-term values. True peak is read from a 4x oversampled
+//! signal.
+//!
+//! The normaliser applies the gain that brings integrated loudness to a
+//! platform's target, with a lookahead limiter keeping true peaks under the
+//! platform's ceiling.
+
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+use crate::audio::AudioData;
+use crate::error::{Aegnt27Error, AudioError, Result};
+
+const BLOCK_SECONDS: f64 = 0.1;
+const MOMENTARY_BLOCKS: usize = 4;
+const SHORT_TERM_BLOCKS: usize = 30;
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const RELATIVE_GATE_LU: f64 = -10.0;
+const LRA_RELATIVE_GATE_LU: f64 = -20.0;
+const OVERSAMPLING: usize = 4;
+const TRUE_PEAK_TAPS: usize = 48;
+/// Below this rate the signal is oversampled for true peak
+const TRUE_PEAK_MAX_RATE: u32 = 96_000;
+const NORMALIZE_PASSES: usize = 4;
+const NORMALIZE_TOLERANCE_LU: f64 = 0.05;
+
+/// Loudness of a piece of audio
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LoudnessMeasurement {
+    /// Gated loudness over the whole audio, LUFS
+    pub integrated_lufs: f64,
+    /// Loudest 400 ms window, LUFS
+    pub momentary_max_lufs: f64,
+    /// Loudest 3 s window, LUFS
+    pub short_term_max_lufs: f64,
+    /// Spread of short-term loudness, LU
+    pub loudness_range_lu: f64,
+    /// Highest inter-sample peak, dBTP
+    pub true_peak_dbtp: f64,
+}
+
+impl LoudnessMeasurement {
+    /// Whether the audio is too quiet for a gated measurement
+    pub fn is_silent(&self) -> bool {
+        !self.integrated_lufs.is_finite()
+    }
+}
+
+/// Loudness targets of common destinations
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum LoudnessTarget {
+    /// YouTube and most streaming video: -14 LUFS, -1 dBTP
+    YouTube,
+    /// Spoken-word podcasts: -16 LUFS, -1 dBTP
+    Podcast,
+    /// EBU R128 broadcast: -23 LUFS, -1 dBTP
+    Broadcast,
+    /// Any other target
+    Custom {
+        /// Integrated loudness to reach, LUFS
+        integrated_lufs: f64,
+        /// Highest allowed true peak, dBTP
+        true_peak_dbtp: f64,
+    },
+}
+
+impl LoudnessTarget {
+    /// Integrated loudness to reach, LUFS
+    pub fn integrated_lufs(&self) -> f64 {
+        match self {
+            LoudnessTarget::YouTube => -14.0,
+            LoudnessTarget::Podcast => -16.0,
+            LoudnessTarget::Broadcast => -23.0,
+            LoudnessTarget::Custom { integrated_lufs, .. } => *integrated_lufs,
+        }
+    }
+
+    /// Highest allowed true peak, dBTP
+    pub fn true_peak_dbtp(&self) -> f64 {
+        match self {
+            LoudnessTarget::Custom { true_peak_dbtp, .. } => *true_peak_dbtp,
+            _ => -1.0,
+        }
+    }
+}
+
+/// Second-order IIR section in direct form I
+#[derive(Debug, Clone, Copy)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    x: [f64; 2],
+    y: [f64; 2],
+}
+
+impl Biquad {
+    fn new(b: [f64; 3], a: [f64; 2]) -> Self {
+        Self {
+            b,
+            a,
+            x: [0.0; 2],
+            y: [0.0; 2],
+        }
+    }
+
+    fn process(&mut self, input: f64) -> f64 {
+        let output = self.b[0] * input + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[0] * self.y[0]
+            - self.a[1] * self.y[1];
+        self.x = [input, self.x[0]];
+        self.y = [output, self.y[0]];
+        output
+    }
+}
+
+/// BS.1770 K-weighting for any sample rate: the head-effect shelf, then the
+/// RLB high-pass
+fn k_weighting(sample_rate: u32) -> [Biquad; 2] {
+    let fs = sample_rate as f64;
+
+    let (f0, gain_db, q) = (1_681.974_450_955_533, 3.999_843_853_973_347, 0.707_175_236_955_419_6);
+    let k = (PI * f0 / fs).tan();
+    let vh = 10f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.499_666_774_154_541_6);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad::new(
+        [(vh + vb * k / q + k * k) / a0, 2.0 * (k * k - vh) / a0, (vh - vb * k / q + k * k) / a0],
+        [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    );
+
+    let (f0, q) = (38.135_470_876_024_44, 0.500_327_037_323_877_3);
+    let k = (PI * f0 / fs).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad::new([1.0, -2.0, 1.0], [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0]);
+
+    [shelf, high_pass]
+}
+
+/// BS.1770 channel weights; in 5.1 the LFE is ignored and surrounds count +1.5 dB
+fn channel_weight(channels: usize, channel: usize) -> f64 {
+    match (channels, channel) {
+        (6, 3) => 0.0,
+        (6, 4) | (6, 5) => 1.41,
+        _ => 1.0,
+    }
+}
+
+fn to_lufs(power: f64) -> f64 {
+    if power > 0.0 {
+        -0.691 + 10.0 * power.log10()
+    } else {
+        f64::NEG_INFINITY
+    }
+}
+
+fn invalid_format(message: impl Into<String>) -> Aegnt27Error {
+    Aegnt27Error::Audio(AudioError::InvalidFormat(message.into()))
+}
+
+/// Weighted power of each 100 ms block
+fn block_powers(audio: &AudioData) -> Vec<f64> {
+    let channels = audio.channels.max(1) as usize;
+    let block_frames = ((audio.sample_rate as f64 * BLOCK_SECONDS).round() as usize).max(1);
+    let mut filters: Vec<[Biquad; 2]> = (0..channels).map(|_| k_weighting(audio.sample_rate)).collect();
+
+    let mut powers = Vec::with_capacity(audio.frame_count() / block_frames);
+    let mut sums = vec![0.0; channels];
+    for (index, frame) in audio.samples.chunks_exact(channels).enumerate() {
+        for (channel, &sample) in frame.iter().enumerate() {
+            let [shelf, high_pass] = &mut filters[channel];
+            let weighted = high_pass.process(shelf.process(sample as f64));
+            sums[channel] += weighted * weighted;
+        }
+        if (index + 1) % block_frames == 0 {
+            let power = sums
+                .iter()
+                .enumerate()
+                .map(|(channel, sum)| channel_weight(channels, channel) * sum / block_frames as f64)
+                .sum();
+            powers.push(power);
+            sums.iter_mut().for_each(|sum| *sum = 0.0);
+        }
+    }
+    powers
+}
+
+/// Mean power of every run of `length` consecutive blocks
+fn window_powers(blocks: &[f64], length: usize) -> Vec<f64> {
+    if blocks.len() < length {
+        return Vec::new();
+    }
+    blocks.windows(length).map(|w| w.iter().sum::<f64>() / length as f64).collect()
+}
+
+fn gated_mean(powers: &[f64], relative_gate_lu: f64) -> Option<(f64, Vec<f64>)> {
+    let absolute: Vec<f64> = powers.iter().copied().filter(|&p| to_lufs(p) > ABSOLUTE_GATE_LUFS).collect();
+    if absolute.is_empty() {
+        return None;
+    }
+    let threshold = to_lufs(absolute.iter().sum::<f64>() / absolute.len() as f64) + relative_gate_lu;
+    let gated: Vec<f64> = absolute.into_iter().filter(|&p| to_lufs(p) > threshold).collect();
+    if gated.is_empty() {
+        return None;
+    }
+    let mean = gated.iter().sum::<f64>() / gated.len() as f64;
+    Some((mean, gated))
+}
+
+fn loudness_range(short_term: &[f64]) -> f64 {
+    let Some((_, gated)) = gated_mean(short_term, LRA_RELATIVE_GATE_LU) else {
+        return 0.0;
+    };
+    let mut levels: Vec<f64> = gated.into_iter().map(to_lufs).collect();
+    levels.sort_by(f64::total_cmp);
+    let percentile = |p: f64| levels[((levels.len() - 1) as f64 * p).round() as usize];
+    percentile(0.95) - percentile(0.10)
+}
+
+/// Windowed-sinc interpolation filter, one row of taps per oversampled phase
+fn true_peak_phases() -> Vec<Vec<f64>> {
+    let center = (TRUE_PEAK_TAPS - 1) as f64 / 2.0;
+    let taps: Vec<f64> = (0..TRUE_PEAK_TAPS)
+        .map(|n| {
+            let x = (n as f64 - center) / OVERSAMPLING as f64;
+            let sinc = if x == 0.0 { 1.0 } else { (PI * x).sin() / (PI * x) };
+            let window = 0.5 - 0.5 * (2.0 * PI * (n as f64 + 0.5) / TRUE_PEAK_TAPS as f64).cos();
+            sinc * window
+        })
+        .collect();
+    (0..OVERSAMPLING)
+        .map(|phase| taps.iter().skip(phase).step_by(OVERSAMPLING).copied().collect())
+        .collect()
+}
+
+/// Largest absolute value of the 4x oversampled signal, linear
+fn true_peak(audio: &AudioData) -> f64 {
+    let channels = audio.channels.max(1) as usize;
+    let sample_peak = audio.samples.iter().fold(0.0f64, |peak, &s| peak.max((s as f64).abs()));
+    if audio.sample_rate >= TRUE_PEAK_MAX_RATE {
+        return sample_peak;
+    }
+
+    let phases = true_peak_phases();
+    let history_len = phases[0].len();
+    let mut peak = sample_peak;
+    for channel in 0..channels {
+        let mut history: VecDeque<f64> = VecDeque::from(vec![0.0; history_len]);
+        for frame in audio.samples.chunks_exact(channels) {
+            history.pop_back();
+            history.push_front(frame[channel] as f64);
+            for taps in &phases {
+                let value: f64 = taps.iter().zip(&history).map(|(t, x)| t * x).sum();
+                peak = peak.max(value.abs());
+            }
+        }
+    }
+    peak
+}
+
+fn to_db(linear: f64) -> f64 {
+    if linear > 0.0 {
+        20.0 * linear.log10()
+    } else {
+        f64::NEG_INFINITY
+    }
+}
+
+fn scale(audio: &mut AudioData, gain_db: f64) {
+    let gain = 10f64.powf(gain_db / 20.0) as f32;
+    audio.samples.iter_mut().for_each(|s| *s *= gain);
+}
+
+impl AudioData {
+    /// Momentary loudness (400 ms windows) every 100 ms, LUFS
+    pub fn momentary_loudness(&self) -> Vec<f64> {
+        window_powers(&block_powers(self), MOMENTARY_BLOCKS).into_iter().map(to_lufs).collect()
+    }
+
+    /// Short-term loudness (3 s windows) every 100 ms, LUFS
+    pub fn short_term_loudness(&self) -> Vec<f64> {
+        window_powers(&block_powers(self), SHORT_TERM_BLOCKS).into_iter().map(to_lufs).collect()
+    }
+
+    /// Measures loudness without changing the audio
+    pub fn measure_loudness(&self) -> Result<LoudnessMeasurement> {
+        if self.channels == 0 || self.sample_rate == 0 {
+            return Err(invalid_format("Audio needs a sample rate and at least one channel"));
+        }
+
+        let blocks = block_powers(self);
+        let momentary = window_powers(&blocks, MOMENTARY_BLOCKS);
+        let short_term = window_powers(&blocks, SHORT_TERM_BLOCKS);
+        let max_lufs = |powers: &[f64]| powers.iter().copied().fold(f64::NEG_INFINITY, |m, p| m.max(to_lufs(p)));
+
+        Ok(LoudnessMeasurement {
+            integrated_lufs: gated_mean(&momentary, RELATIVE_GATE_LU)
+                .map(|(mean, _)| to_lufs(mean))
+                .unwrap_or(f64::NEG_INFINITY),
+            momentary_max_lufs: max_lufs(&momentary),
+            short_term_max_lufs: max_lufs(&short_term),
+            loudness_range_lu: loudness_range(&short_term),
+            true_peak_dbtp: to_db(true_peak(self)),
+        })
+    }
+
+    /// Measures loudness and keeps the result in the metadata
+    pub fn analyze_loudness(&mut self) -> Result<LoudnessMeasurement> {
+        let measurement = self.measure_loudness()?;
+        self.metadata.loudness = Some(measurement);
+        Ok(measurement)
+    }
+}
+
+/// Brings audio to a loudness target
+#[derive(Debug, Clone)]
+pub struct LoudnessNormalizer {
+    target: LoudnessTarget,
+    /// The limiter starts reducing gain this long before a peak
+    lookahead_seconds: f64,
+    /// Time for the limiter to recover most of the way after a peak
+    release_seconds: f64,
+    /// Largest gain applied to very quiet audio, dB
+    max_gain_db: f64,
+}
+
+impl LoudnessNormalizer {
+    /// Creates a normaliser for a target
+    pub fn new(target: LoudnessTarget) -> Self {
+        Self {
+            target,
+            lookahead_seconds: 0.005,
+            release_seconds: 0.1,
+            max_gain_db: 30.0,
+        }
+    }
+
+    /// The target being normalised to
+    pub fn target(&self) -> LoudnessTarget {
+        self.target
+    }
+
+    /// Gain to the target and limit peaks; the result carries its own
+    /// measurement. The input is always measured afresh, as a stored
+    /// measurement may predate a transform that changed the samples.
+    pub fn normalize(&self, audio: &AudioData) -> Result<AudioData> {
+        let measurement = audio.measure_loudness()?;
+        let mut output = audio.clone();
+        if measurement.is_silent() {
+            output.metadata.loudness = Some(measurement);
+            return Ok(output);
+        }
+
+        // Limiting takes some loudness with it, so gain and limit again until
+        // the target holds. Sample peaks sit below true peaks, so the limiter
+        // leaves some headroom and any overshoot left is trimmed at the end
+        let ceiling = 10f64.powf((self.target.true_peak_dbtp() - 0.5) / 20.0) as f32;
+        let mut result = measurement;
+        let mut applied_db = 0.0;
+        for _ in 0..NORMALIZE_PASSES {
+            let gain_db = (self.target.integrated_lufs() - result.integrated_lufs).min(self.max_gain_db - applied_db);
+            if gain_db.abs() < NORMALIZE_TOLERANCE_LU {
+                break;
+            }
+            applied_db += gain_db;
+            scale(&mut output, gain_db);
+            if result.true_peak_dbtp + gain_db > self.target.true_peak_dbtp() {
+                self.limit(&mut output, ceiling);
+            }
+            result = output.measure_loudness()?;
+        }
+
+        let overshoot = result.true_peak_dbtp - self.target.true_peak_dbtp();
+        if overshoot > 0.0 {
+            scale(&mut output, -overshoot);
+            result = output.measure_loudness()?;
+        }
+
+        output.metadata.loudness = Some(result);
+        Ok(output)
+    }
+
+    /// Lookahead peak limiter: gain ramps down before each peak and recovers exponentially after
+    fn limit(&self, audio: &mut AudioData, ceiling: f32) {
+        let channels = audio.channels.max(1) as usize;
+        let frames = audio.frame_count();
+        let lookahead = ((audio.sample_rate as f64 * self.lookahead_seconds) as usize).max(1);
+        let release = 1.0 - (-1.0 / (audio.sample_rate as f64 * self.release_seconds)).exp() as f32;
+
+        let required: Vec<f32> = audio
+            .samples
+            .chunks_exact(channels)
+            .map(|frame| {
+                let peak = frame.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+                if peak > ceiling {
+                    ceiling / peak
+                } else {
+                    1.0
+                }
+            })
+            .collect();
+
+        // Reach each reduction by the time its peak arrives, ramping over the lookahead
+        let mut gains = required.clone();
+        for index in (0..frames.saturating_sub(1)).rev() {
+            gains[index] = gains[index].min(gains[index + 1] + 1.0 / lookahead as f32);
+        }
+        let mut previous = 1.0f32;
+        for (gain, required) in gains.iter_mut().zip(&required) {
+            let released = previous + (1.0 - previous) * release;
+            *gain = gain.min(released).min(*required);
+            previous = *gain;
+        }
+
+        for (frame, gain) in audio.samples.chunks_exact_mut(channels).zip(&gains) {
+            frame.iter_mut().for_each(|s| *s *= gain);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(frequency: f64, amplitude_db: f64, seconds: f64, sample_rate: u32, channels: u8) -> AudioData {
+        let amplitude = 10f64.powf(amplitude_db / 20.0);
+        let frames = (seconds * sample_rate as f64) as usize;
+        let samples = (0..frames)
+            .flat_map(|n| {
+                let value = (amplitude * (2.0 * PI * frequency * n as f64 / sample_rate as f64).sin()) as f32;
+                std::iter::repeat_n(value, channels as usize)
+            })
+            .collect();
+        AudioData::new(samples, sample_rate, channels)
+    }
+
+    fn concat(parts: &[AudioData]) -> AudioData {
+        let samples = parts.iter().flat_map(|p| p.samples.iter().copied()).collect();
+        AudioData::new(samples, parts[0].sample_rate, parts[0].channels)
+    }
+
+    #[test]
+    fn test_measures_ebu_reference_signals() {
+        // EBU Tech 3341 case 1: stereo 1 kHz at -23 dBFS reads -23 LUFS
+        let reference = tone(1_000.0, -23.0, 20.0, 48_000, 2);
+        let measurement = reference.measure_loudness().unwrap();
+        assert!((measurement.integrated_lufs + 23.0).abs() < 0.1, "{:?}", measurement);
+        assert!((measurement.momentary_max_lufs + 23.0).abs() < 0.1);
+        assert!((measurement.short_term_max_lufs + 23.0).abs() < 0.1);
+        assert!(measurement.loudness_range_lu < 0.1);
+
+        // Silence is gated out of the integrated value
+        let with_silence = concat(&[reference.clone(), tone(1_000.0, -120.0, 20.0, 48_000, 2)]);
+        let measurement = with_silence.measure_loudness().unwrap();
+        assert!((measurement.integrated_lufs + 23.0).abs() < 0.1);
+
+        // EBU Tech 3342 case 1: 20 s at -20 then 20 s at -30 gives 10 LU of range
+        let range = concat(&[tone(1_000.0, -20.0, 20.0, 48_000, 2), tone(1_000.0, -30.0, 20.0, 48_000, 2)]);
+        let measurement = range.measure_loudness().unwrap();
+        assert!((measurement.loudness_range_lu - 10.0).abs() < 1.0, "{:?}", measurement);
+
+        assert!(AudioData::new(vec![0.0; 48_000], 48_000, 1).measure_loudness().unwrap().is_silent());
+    }
+
+    #[test]
+    fn test_true_peak_finds_peaks_between_samples() {
+        // A quarter-rate tone at 45 degrees never samples its crest
+        let samples: Vec<f32> = (0..48_000)
+            .map(|n| (0.5 * (PI / 2.0 * n as f64 + PI / 4.0).sin()) as f32)
+            .collect();
+        let audio = AudioData::new(samples, 48_000, 1);
+        let sample_peak = audio.samples.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!((to_db(sample_peak as f64) - to_db(0.5 * std::f64::consts::FRAC_1_SQRT_2)).abs() < 0.01);
+
+        let measurement = audio.measure_loudness().unwrap();
+        assert!((measurement.true_peak_dbtp - to_db(0.5)).abs() < 0.5, "{:?}", measurement);
+    }
+
+    #[test]
+    fn test_normalizes_to_platform_targets_under_the_peak_ceiling() {
+        // Quiet tone with a loud click every second that full gain would clip
+        let mut audio = tone(300.0, -32.0, 12.0, 48_000, 1);
+        for second in 0..12 {
+            audio.samples[second * 48_000 + 100..second * 48_000 + 103].copy_from_slice(&[0.6, -0.9, 0.6]);
+        }
+        let before = audio.analyze_loudness().unwrap();
+        assert_eq!(audio.metadata.loudness, Some(before));
+
+        for target in [LoudnessTarget::YouTube, LoudnessTarget::Podcast] {
+            let normalized = LoudnessNormalizer::new(target).normalize(&audio).unwrap();
+            let after = normalized.metadata.loudness.unwrap();
+            assert!((after.integrated_lufs - target.integrated_lufs()).abs() < 0.5, "{:?}: {:?}", target, after);
+            assert!(after.true_peak_dbtp <= target.true_peak_dbtp() + 1e-6, "{:?}: {:?}", target, after);
+            assert_eq!(normalized.frame_count(), audio.frame_count());
+        }
+
+        // A measurement left over from before an edit is not trusted
+        let mut edited = audio.clone();
+        edited.metadata.loudness = Some(AudioData::new(vec![0.0; 48_000], 48_000, 1).measure_loudness().unwrap());
+        let normalized = LoudnessNormalizer::new(LoudnessTarget::YouTube).normalize(&edited).unwrap();
+        let after = normalized.metadata.loudness.unwrap();
+        assert!((after.integrated_lufs - LoudnessTarget::YouTube.integrated_lufs()).abs() < 0.5, "{:?}", after);
+    }
+}
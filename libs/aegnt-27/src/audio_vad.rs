@@ -0,0 +1,365 @@
+//! Voice activity detection for narration audio
+//!
+//! The audio is split into short frames and each is judged on two cues:
+//! its energy above the recording's noise floor (estimated from the quietest
+//! frames), and spectral flux, how much of the energy across frequency
+//! bands is newly rising. Speech is both louder than the floor and constantly
+//! changing, while fans, hum and other steady noise may be loud but barely
+//! move, so requiring both keeps background noise out of the speech segments.
+//!
+//! Frame decisions are then smoothed: speech is held for a short hangover so
+//! word endings are not clipped, and speech or silence runs too short to be
+//! real are absorbed by their neighbours.
+
+use std::time::Duration;
+
+use rustfft::num_complex::Complex;
+use rustfft::FftPlanner;
+use serde::{Deserialize, Serialize};
+
+use crate::audio::AudioData;
+use crate::error::{Aegnt27Error, AudioError, Result};
+
+/// Share of the quietest frames the noise floor is read from
+const NOISE_FLOOR_PERCENTILE: f64 = 0.1;
+/// Number of log-spaced bands spectral flux is measured over
+const FLUX_BANDS: usize = 24;
+const FLUX_LOW_HZ: f32 = 80.0;
+const FLUX_HIGH_HZ: f32 = 8_000.0;
+
+/// Settings of the voice activity detector
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VadConfig {
+    /// Length of each analysis frame
+    pub frame: Duration,
+    /// How far above the noise floor a frame must be to count as speech, dB
+    pub energy_margin_db: f32,
+    /// Frames quieter than this are silence whatever the floor, dBFS
+    pub silence_floor_dbfs: f32,
+    /// Share of a frame's band power that must be newly rising, averaged
+    /// over the flux window, for the frame to count as speech
+    pub flux_threshold: f32,
+    /// Window over which flux is averaged
+    pub flux_window: Duration,
+    /// Speech is held this long after its last speech frame
+    pub hangover: Duration,
+    /// Shorter speech runs are treated as silence
+    pub min_speech: Duration,
+    /// Shorter silences between speech are treated as speech
+    pub min_silence: Duration,
+}
+
+impl Default for VadConfig {
+    fn default() -> Self {
+        Self {
+            frame: Duration::from_millis(20),
+            energy_margin_db: 9.0,
+            silence_floor_dbfs: -60.0,
+            flux_threshold: 0.12,
+            flux_window: Duration::from_millis(200),
+            hangover: Duration::from_millis(150),
+            min_speech: Duration::from_millis(120),
+            min_silence: Duration::from_millis(200),
+        }
+    }
+}
+
+/// Whether a stretch of audio holds speech
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VoiceActivity {
+    /// Someone is talking
+    Speech,
+    /// Pause, breath or background noise only
+    Silence,
+}
+
+/// Run of frames with the same activity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActivitySegment {
+    /// Start, from the beginning of the audio
+    pub start: Duration,
+    /// End, from the beginning of the audio
+    pub end: Duration,
+    /// What the stretch holds
+    pub activity: VoiceActivity,
+}
+
+impl ActivitySegment {
+    /// Length of the segment
+    pub fn duration(&self) -> Duration {
+        self.end.saturating_sub(self.start)
+    }
+
+    /// Whether the segment holds speech
+    pub fn is_speech(&self) -> bool {
+        self.activity == VoiceActivity::Speech
+    }
+}
+
+/// Splits narration into speech and silence
+#[derive(Debug, Clone)]
+pub struct VoiceActivityDetector {
+    config: VadConfig,
+}
+
+impl VoiceActivityDetector {
+    /// Creates a detector
+    pub fn new(config: VadConfig) -> Self {
+        Self { config }
+    }
+
+    /// Alternating speech and silence segments covering the whole audio
+    pub fn detect(&self, audio: &AudioData) -> Result<Vec<ActivitySegment>> {
+        let frame_len = (audio.sample_rate as f64 * self.config.frame.as_secs_f64()) as usize;
+        if audio.channels == 0 || frame_len < 16 {
+            return Err(Aegnt27Error::Audio(AudioError::InvalidFormat(format!(
+                "Frames of {:?} are too short at {} Hz",
+                self.config.frame, audio.sample_rate
+            ))));
+        }
+
+        let mono = audio.to_mono();
+        let (energies, fluxes) = frame_features(&mono.samples, audio.sample_rate, frame_len);
+        if energies.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut sorted = energies.clone();
+        sorted.sort_by(f32::total_cmp);
+        let noise_floor = sorted[((sorted.len() - 1) as f64 * NOISE_FLOOR_PERCENTILE) as usize];
+        let threshold = (noise_floor + self.config.energy_margin_db).max(self.config.silence_floor_dbfs);
+
+        let frames_in = |d: Duration| (d.as_secs_f64() / self.config.frame.as_secs_f64()).round() as usize;
+        let flux = moving_average(&fluxes, frames_in(self.config.flux_window).max(1));
+        let mut speech: Vec<bool> = energies
+            .iter()
+            .zip(&flux)
+            .map(|(&energy, &flux)| energy > threshold && flux > self.config.flux_threshold)
+            .collect();
+
+        hold(&mut speech, frames_in(self.config.hangover));
+        absorb_runs(&mut speech, false, frames_in(self.config.min_silence));
+        absorb_runs(&mut speech, true, frames_in(self.config.min_speech));
+
+        Ok(self.segments(&speech, audio.duration))
+    }
+
+    fn segments(&self, speech: &[bool], duration: Duration) -> Vec<ActivitySegment> {
+        let mut segments: Vec<ActivitySegment> = Vec::new();
+        for (index, &is_speech) in speech.iter().enumerate() {
+            let activity = if is_speech { VoiceActivity::Speech } else { VoiceActivity::Silence };
+            let start = self.config.frame * index as u32;
+            match segments.last_mut() {
+                Some(last) if last.activity == activity => last.end = start + self.config.frame,
+                _ => segments.push(ActivitySegment {
+                    start,
+                    end: start + self.config.frame,
+                    activity,
+                }),
+            }
+        }
+        // The partial frame at the end belongs to the last segment
+        if let Some(last) = segments.last_mut() {
+            last.end = last.end.max(duration);
+        }
+        segments
+    }
+}
+
+impl AudioData {
+    /// Speech and silence segments of the audio
+    pub fn detect_voice_activity(&self, config: &VadConfig) -> Result<Vec<ActivitySegment>> {
+        VoiceActivityDetector::new(config.clone()).detect(self)
+    }
+}
+
+/// Energy in dBFS and spectral flux of every whole frame
+fn frame_features(samples: &[f32], sample_rate: u32, frame_len: usize) -> (Vec<f32>, Vec<f32>) {
+    let fft_size = frame_len.next_power_of_two();
+    let fft = FftPlanner::<f32>::new().plan_fft_forward(fft_size);
+    let window: Vec<f32> = (0..frame_len)
+        .map(|n| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * n as f32 / frame_len as f32).cos())
+        .collect();
+
+    // Log-spaced band edges in bins, each band at least one bin wide
+    let bin_hz = sample_rate as f32 / fft_size as f32;
+    let high = FLUX_HIGH_HZ.min(sample_rate as f32 / 2.0);
+    let edges: Vec<usize> = (0..=FLUX_BANDS)
+        .map(|band| {
+            let hz = FLUX_LOW_HZ * (high / FLUX_LOW_HZ).powf(band as f32 / FLUX_BANDS as f32);
+            ((hz / bin_hz) as usize + band).min(fft_size / 2)
+        })
+        .collect();
+
+    let mut energies = Vec::with_capacity(samples.len() / frame_len);
+    let mut fluxes = Vec::with_capacity(samples.len() / frame_len);
+    let mut previous: Option<Vec<f32>> = None;
+    for frame in samples.chunks_exact(frame_len) {
+        let mean_square = frame.iter().map(|s| s * s).sum::<f32>() / frame_len as f32;
+        energies.push(10.0 * mean_square.max(1e-12).log10());
+
+        let mut buffer: Vec<Complex<f32>> = frame.iter().zip(&window).map(|(s, w)| Complex::new(s * w, 0.0)).collect();
+        buffer.resize(fft_size, Complex::new(0.0, 0.0));
+        fft.process(&mut buffer);
+        let bands: Vec<f32> = edges
+            .windows(2)
+            .map(|edge| {
+                buffer[edge[0]..edge[1].max(edge[0] + 1)].iter().map(|c| c.norm_sqr()).sum::<f32>()
+            })
+            .collect();
+
+        // Rising band power as a share of the frame's power, so the
+        // loudest components decide and quiet flickering bands count little
+        let flux = match &previous {
+            Some(previous) => {
+                let rise: f32 = bands.iter().zip(previous).map(|(now, before)| (now - before).max(0.0)).sum();
+                rise / bands.iter().sum::<f32>().max(1e-12)
+            }
+            None => 0.0,
+        };
+        fluxes.push(flux);
+        previous = Some(bands);
+    }
+    (energies, fluxes)
+}
+
+/// Centred moving average
+fn moving_average(values: &[f32], width: usize) -> Vec<f32> {
+    let half = width / 2;
+    (0..values.len())
+        .map(|index| {
+            let window = &values[index.saturating_sub(half)..(index + half + 1).min(values.len())];
+            window.iter().sum::<f32>() / window.len() as f32
+        })
+        .collect()
+}
+
+/// Keeps speech on for `frames` after each speech frame
+fn hold(speech: &mut [bool], frames: usize) {
+    let mut remaining = 0;
+    for is_speech in speech.iter_mut() {
+        if *is_speech {
+            remaining = frames;
+        } else if remaining > 0 {
+            *is_speech = true;
+            remaining -= 1;
+        }
+    }
+}
+
+/// Flips runs of `value` shorter than `frames` that sit between runs of the
+/// other value, except at the very start or end
+fn absorb_runs(speech: &mut [bool], value: bool, frames: usize) {
+    let mut start = 0;
+    while start < speech.len() {
+        let end = start + speech[start..].iter().take_while(|&&s| s == speech[start]).count();
+        if speech[start] == value && end - start < frames && start > 0 && end < speech.len() {
+            speech[start..end].iter_mut().for_each(|s| *s = !value);
+        }
+        start = end;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16_000;
+
+    fn noise(samples: &mut [f32], amplitude: f32) {
+        let mut state = 17u32;
+        for sample in samples.iter_mut() {
+            state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+            *sample += amplitude * (state as f32 / u32::MAX as f32 * 2.0 - 1.0);
+        }
+    }
+
+    /// Syllable-like bursts: a voiced tone whose pitch and level move at about 4 Hz
+    fn speak(samples: &mut [f32], from: f32, to: f32) {
+        let range = (from * RATE as f32) as usize..(to * RATE as f32) as usize;
+        let mut phase = 0.0f32;
+        for n in range.clone() {
+            let t = (n - range.start) as f32 / RATE as f32;
+            let pitch = 140.0 + 40.0 * (2.0 * std::f32::consts::PI * 3.0 * t).sin();
+            phase += 2.0 * std::f32::consts::PI * pitch / RATE as f32;
+            let envelope = (std::f32::consts::PI * 4.0 * t).sin().abs();
+            samples[n] += 0.3 * envelope * (phase.sin() + 0.5 * (2.0 * phase).sin() + 0.25 * (3.0 * phase).sin());
+        }
+    }
+
+    fn narration() -> AudioData {
+        let mut samples = vec![0.0; RATE as usize * 10];
+        noise(&mut samples, 0.003);
+        speak(&mut samples, 1.0, 3.0);
+        speak(&mut samples, 6.0, 8.5);
+        AudioData::new(samples, RATE, 1)
+    }
+
+    fn speech(segments: &[ActivitySegment]) -> Vec<ActivitySegment> {
+        segments.iter().copied().filter(ActivitySegment::is_speech).collect()
+    }
+
+    #[test]
+    fn test_finds_speech_between_pauses() {
+        let audio = narration();
+        let segments = audio.detect_voice_activity(&VadConfig::default()).unwrap();
+
+        assert_eq!(segments.first().unwrap().start, Duration::ZERO);
+        assert_eq!(segments.last().unwrap().end, audio.duration);
+        assert!(segments.windows(2).all(|pair| pair[0].end == pair[1].start && pair[0].activity != pair[1].activity));
+
+        let speech = speech(&segments);
+        assert_eq!(speech.len(), 2, "{:?}", segments);
+        let near = |a: Duration, b: f64| (a.as_secs_f64() - b).abs() < 0.25;
+        assert!(near(speech[0].start, 1.0) && near(speech[0].end, 3.0), "{:?}", speech);
+        assert!(near(speech[1].start, 6.0) && near(speech[1].end, 8.5), "{:?}", speech);
+    }
+
+    #[test]
+    fn test_steady_noise_is_not_speech() {
+        // A fan with a harmonic hum switches on after a quiet second and a
+        // half; past its onset it is well above the floor, but steady
+        let mut samples: Vec<f32> = (0..RATE as usize * 4)
+            .map(|n| {
+                let t = n as f32 / RATE as f32;
+                let hum = 0.05 * (2.0 * std::f32::consts::PI * 120.0 * t).sin()
+                    + 0.025 * (2.0 * std::f32::consts::PI * 240.0 * t).sin();
+                if t < 1.5 { 0.0 } else { hum }
+            })
+            .collect();
+        noise(&mut samples[..RATE as usize * 3 / 2], 0.002);
+        noise(&mut samples[RATE as usize * 3 / 2..], 0.02);
+        let fan = AudioData::new(samples.clone(), RATE, 1);
+        let segments = fan.detect_voice_activity(&VadConfig::default()).unwrap();
+        let after_onset = |segment: &&ActivitySegment| segment.end > Duration::from_secs(2);
+        assert_eq!(speech(&segments).iter().filter(after_onset).count(), 0, "{:?}", segments);
+
+        // Speech over the running fan is still found
+        speak(&mut samples, 2.5, 3.5);
+        let segments = AudioData::new(samples, RATE, 1).detect_voice_activity(&VadConfig::default()).unwrap();
+        let speech: Vec<_> = speech(&segments).into_iter().filter(|s| s.end > Duration::from_secs(2)).collect();
+        assert_eq!(speech.len(), 1, "{:?}", segments);
+        assert!((speech[0].start.as_secs_f64() - 2.5).abs() < 0.25, "{:?}", speech);
+    }
+
+    #[test]
+    fn test_smoothing_merges_short_gaps_and_drops_blips() {
+        let mut speech_frames = vec![false, true, true, true, false, true, true, true, false, false, false, false];
+        absorb_runs(&mut speech_frames, false, 2);
+        assert_eq!(speech_frames[1..8], [true; 7]);
+
+        let mut blip = vec![false, false, true, false, false, false];
+        absorb_runs(&mut blip, true, 2);
+        assert!(blip.iter().all(|s| !s));
+
+        let mut tail = vec![true, false, false, false];
+        hold(&mut tail, 2);
+        assert_eq!(tail, vec![true, true, true, false]);
+
+        let config = VadConfig {
+            frame: Duration::from_micros(500),
+            ..VadConfig::default()
+        };
+        assert!(narration().detect_voice_activity(&config).is_err());
+    }
+}
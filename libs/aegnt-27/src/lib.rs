@@ -68,6 +68,26 @@ pub mod typing;
 #[cfg_attr(docsrs, doc(cfg(feature = "audio")))]
 pub mod audio;
 
+#[cfg(feature = "audio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "audio")))]
+pub mod audio_io;
+
+#[cfg(feature = "audio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "audio")))]
+pub mod audio_convert;
+
+#[cfg(feature = "audio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "audio")))]
+pub mod audio_loudness;
+
+#[cfg(feature = "audio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "audio")))]
+pub mod audio_denoise;
+
+#[cfg(feature = "audio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "audio")))]
+pub mod audio_vad;
+
 #[cfg(feature = "visual")]
 #[cfg_attr(docsrs, doc(cfg(feature = "visual")))]
 pub mod visual;
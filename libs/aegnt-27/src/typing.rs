@@ -43,7 +43,7 @@ pub struct TypingConfig {
     /// Enable keyboard layout specific timing
     pub enable_layout_timing: bool,
     
-    /// Keyboard layout ("qwerty", "dvorak", "colemak")
+    /// Keyboard layout ("qwerty", "dvorak", "colemak", "azerty", "qwertz")
     pub keyboard_layout: String,
     
     /// Enable autocorrection simulation
@@ -608,12 +608,86 @@ impl TypingHumanizer {
     }
 }
 
-/// Keyboard layout information for realistic typo generation
+/// Keyboard layout information for realistic typo generation and key labels
 #[derive(Debug, Clone)]
 pub struct KeyboardLayout {
     name: String,
     key_positions: HashMap<char, (i32, i32)>,
     adjacent_keys: HashMap<char, Vec<char>>,
+    key_labels: HashMap<u16, String>,
+}
+
+/// Unshifted characters of the main key block for one layout
+struct LayoutRows {
+    number: &'static str,
+    top: &'static str,
+    home: &'static str,
+    bottom: &'static str,
+    grave: char,
+    backslash: char,
+    /// Extra key left of Z on ISO keyboards
+    iso: Option<char>,
+}
+
+// Keycodes are Linux evdev codes, which match PC set-1 scan codes for the
+// main block. Each row is laid out left to right from its first keycode.
+const NUMBER_ROW_START: u16 = 2;
+const TOP_ROW_START: u16 = 16;
+const HOME_ROW_START: u16 = 30;
+const BOTTOM_ROW_START: u16 = 44;
+const GRAVE_KEY: u16 = 41;
+const BACKSLASH_KEY: u16 = 43;
+const ISO_KEY: u16 = 86;
+const SPACE_KEY: u16 = 57;
+
+/// Keys whose label does not depend on the layout
+const NAMED_KEYS: &[(u16, &str)] = &[
+    (1, "Esc"),
+    (14, "Backspace"),
+    (15, "Tab"),
+    (28, "Enter"),
+    (29, "Ctrl"),
+    (42, "Shift"),
+    (54, "Shift"),
+    (56, "Alt"),
+    (57, "Space"),
+    (58, "CapsLock"),
+    (59, "F1"),
+    (60, "F2"),
+    (61, "F3"),
+    (62, "F4"),
+    (63, "F5"),
+    (64, "F6"),
+    (65, "F7"),
+    (66, "F8"),
+    (67, "F9"),
+    (68, "F10"),
+    (87, "F11"),
+    (88, "F12"),
+    (97, "Ctrl"),
+    (100, "AltGr"),
+    (102, "Home"),
+    (103, "Up"),
+    (104, "PgUp"),
+    (105, "Left"),
+    (106, "Right"),
+    (107, "End"),
+    (108, "Down"),
+    (109, "PgDn"),
+    (110, "Insert"),
+    (111, "Delete"),
+    (125, "Super"),
+    (126, "Super"),
+];
+
+/// Key cap label of a character: upper case where that is still one
+/// character, so `ß` stays `ß` rather than becoming `SS`
+fn key_cap(ch: char) -> String {
+    let mut upper = ch.to_uppercase();
+    match (upper.next(), upper.next()) {
+        (Some(single), None) => single.to_string(),
+        _ => ch.to_string(),
+    }
 }
 
 impl KeyboardLayout {
@@ -622,6 +696,8 @@ impl KeyboardLayout {
             "qwerty" => Ok(Self::qwerty_layout()),
             "dvorak" => Ok(Self::dvorak_layout()),
             "colemak" => Ok(Self::colemak_layout()),
+            "azerty" => Ok(Self::azerty_layout()),
+            "qwertz" => Ok(Self::qwertz_layout()),
             _ => Err(Aegnt27Error::Typing(TypingError::UnsupportedLayout(
                 layout_name.to_string(),
             ))),
@@ -629,25 +705,98 @@ impl KeyboardLayout {
     }
     
     fn qwerty_layout() -> Self {
+        Self::from_rows("qwerty", LayoutRows {
+            number: "1234567890-=",
+            top: "qwertyuiop[]",
+            home: "asdfghjkl;'",
+            bottom: "zxcvbnm,./",
+            grave: '`',
+            backslash: '\\',
+            iso: None,
+        })
+    }
+    
+    fn dvorak_layout() -> Self {
+        Self::from_rows("dvorak", LayoutRows {
+            number: "1234567890[]",
+            top: "',.pyfgcrl/=",
+            home: "aoeuidhtns-",
+            bottom: ";qjkxbmwvz",
+            grave: '`',
+            backslash: '\\',
+            iso: None,
+        })
+    }
+    
+    fn colemak_layout() -> Self {
+        Self::from_rows("colemak", LayoutRows {
+            number: "1234567890-=",
+            top: "qwfpgjluy;[]",
+            home: "arstdhneio'",
+            bottom: "zxcvbkm,./",
+            grave: '`',
+            backslash: '\\',
+            iso: None,
+        })
+    }
+    
+    fn azerty_layout() -> Self {
+        // French AZERTY
+        Self::from_rows("azerty", LayoutRows {
+            number: "&é\"'(-è_çà)=",
+            top: "azertyuiop^$",
+            home: "qsdfghjklmù",
+            bottom: "wxcvbn,;:!",
+            grave: '²',
+            backslash: '*',
+            iso: Some('<'),
+        })
+    }
+    
+    fn qwertz_layout() -> Self {
+        // German QWERTZ
+        Self::from_rows("qwertz", LayoutRows {
+            number: "1234567890ß´",
+            top: "qwertzuiopü+",
+            home: "asdfghjklöä",
+            bottom: "yxcvbnm,.-",
+            grave: '^',
+            backslash: '#',
+            iso: Some('<'),
+        })
+    }
+    
+    fn from_rows(name: &str, rows: LayoutRows) -> Self {
         let mut key_positions: HashMap<char, (i32, i32)> = HashMap::new();
         let mut adjacent_keys = HashMap::new();
+        let mut key_labels: HashMap<u16, String> = NAMED_KEYS
+            .iter()
+            .map(|&(code, label)| (code, label.to_string()))
+            .collect();
         
-        // Row 1
-        let row1 = "qwertyuiop";
-        for (i, ch) in row1.chars().enumerate() {
-            key_positions.insert(ch, (i as i32, 0));
+        for (i, ch) in rows.number.chars().enumerate() {
+            key_labels.insert(NUMBER_ROW_START + i as u16, ch.to_string());
         }
         
-        // Row 2
-        let row2 = "asdfghjkl";
-        for (i, ch) in row2.chars().enumerate() {
-            key_positions.insert(ch, (i as i32, 1));
+        // Letter rows also drive typo adjacency: row 0 top, 1 home, 2 bottom
+        let letter_rows = [
+            (rows.top, TOP_ROW_START),
+            (rows.home, HOME_ROW_START),
+            (rows.bottom, BOTTOM_ROW_START),
+        ];
+        for (row, &(chars, first_code)) in letter_rows.iter().enumerate() {
+            for (i, ch) in chars.chars().enumerate() {
+                key_labels.insert(first_code + i as u16, key_cap(ch));
+                if ch.is_alphabetic() {
+                    key_positions.insert(ch, (i as i32, row as i32));
+                }
+            }
         }
         
-        // Row 3
-        let row3 = "zxcvbnm";
-        for (i, ch) in row3.chars().enumerate() {
-            key_positions.insert(ch, (i as i32, 2));
+        key_labels.insert(GRAVE_KEY, rows.grave.to_string());
+        key_labels.insert(BACKSLASH_KEY, rows.backslash.to_string());
+        if let Some(iso) = rows.iso {
+            key_labels.insert(ISO_KEY, iso.to_string());
         }
         
         // Generate adjacent keys based on positions
@@ -667,30 +816,46 @@ impl KeyboardLayout {
         }
         
         Self {
-            name: "qwerty".to_string(),
+            name: name.to_string(),
             key_positions,
             adjacent_keys,
+            key_labels,
         }
     }
     
-    fn dvorak_layout() -> Self {
-        // Simplified Dvorak layout implementation
-        let mut layout = Self::qwerty_layout();
-        layout.name = "dvorak".to_string();
-        // Would need full Dvorak key mapping here
-        layout
+    pub fn name(&self) -> &str {
+        &self.name
     }
     
-    fn colemak_layout() -> Self {
-        // Simplified Colemak layout implementation
-        let mut layout = Self::qwerty_layout();
-        layout.name = "colemak".to_string();
-        // Would need full Colemak key mapping here
-        layout
+    /// Label Super and Alt the way a Mac keyboard prints them, for
+    /// recordings made on macOS; keycodes stay evdev / PC scan codes
+    pub fn with_mac_modifiers(mut self) -> Self {
+        for code in [125, 126] {
+            self.key_labels.insert(code, "Cmd".to_string());
+        }
+        self.key_labels.insert(56, "Option".to_string());
+        self
+    }
+    
+    /// Display label for a physical keycode, e.g. `"P"` or `"Ctrl"`
+    pub fn key_label(&self, keycode: u16) -> Option<&str> {
+        self.key_labels.get(&keycode).map(String::as_str)
+    }
+    
+    /// Whether the key types a character when pressed without modifiers
+    pub fn is_character_key(&self, keycode: u16) -> bool {
+        keycode == SPACE_KEY
+            || (NUMBER_ROW_START..NUMBER_ROW_START + 12).contains(&keycode)
+            || (TOP_ROW_START..TOP_ROW_START + 12).contains(&keycode)
+            || (HOME_ROW_START..HOME_ROW_START + 11).contains(&keycode)
+            || (BOTTOM_ROW_START..BOTTOM_ROW_START + 10).contains(&keycode)
+            || keycode == GRAVE_KEY
+            || keycode == BACKSLASH_KEY
+            || (keycode == ISO_KEY && self.key_labels.contains_key(&ISO_KEY))
     }
     
     pub fn get_adjacent_keys(&self, ch: char) -> Option<&Vec<char>> {
-        self.adjacent_keys.get(&ch.to_ascii_lowercase())
+        self.adjacent_keys.get(&ch.to_lowercase().next().unwrap_or(ch))
     }
     
     pub fn get_timing_modifier(&self, ch: char) -> f64 {
@@ -793,6 +958,48 @@ mod tests {
         assert!(!adjacent.unwrap().is_empty());
     }
     
+    #[test]
+    fn test_keyboard_layout_labels() {
+        // Keycode 16 is the key right of Tab, 44 the key right of left Shift
+        let qwerty = KeyboardLayout::new("qwerty").unwrap();
+        assert_eq!(qwerty.key_label(16), Some("Q"));
+        assert_eq!(qwerty.key_label(29), Some("Ctrl"));
+        
+        let azerty = KeyboardLayout::new("AZERTY").unwrap();
+        assert_eq!(azerty.key_label(16), Some("A"));
+        assert_eq!(azerty.key_label(44), Some("W"));
+        
+        let qwertz = KeyboardLayout::new("qwertz").unwrap();
+        assert_eq!(qwertz.key_label(21), Some("Z"));
+        assert_eq!(qwertz.key_label(44), Some("Y"));
+        
+        let dvorak = KeyboardLayout::new("dvorak").unwrap();
+        assert_eq!(dvorak.key_label(25), Some("L"));
+        
+        let colemak = KeyboardLayout::new("colemak").unwrap();
+        assert_eq!(colemak.key_label(31), Some("R"));
+        
+        assert!(qwerty.is_character_key(16));
+        assert!(!qwerty.is_character_key(29));
+        assert!(!qwerty.is_character_key(86));
+        assert!(qwertz.is_character_key(86));
+        
+        // Labels don't depend on the platform the crate was built for
+        assert_eq!(qwerty.key_label(125), Some("Super"));
+        assert_eq!(qwerty.key_label(56), Some("Alt"));
+        let mac = KeyboardLayout::new("qwerty").unwrap().with_mac_modifiers();
+        assert_eq!(mac.key_label(125), Some("Cmd"));
+        assert_eq!(mac.key_label(56), Some("Option"));
+        assert_eq!(key_cap('ß'), "ß");
+        assert_eq!(key_cap('ü'), "Ü");
+        assert_eq!(qwertz.key_label(12), Some("ß"));
+        
+        // Layout labels leave the existing typing timing alone
+        assert_eq!(qwerty.get_timing_modifier('a'), 0.9);
+        assert_eq!(qwerty.get_timing_modifier('g'), 1.2);
+        assert_eq!(qwerty.get_timing_modifier('A'), 1.2);
+    }
+    
     #[test]
     fn test_typing_sequence_analysis() {
         let keystrokes = vec![